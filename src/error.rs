@@ -8,9 +8,11 @@ use std::{
 };
 
 use self::OrionError::*;
+use crate::lexer::tokens::Location;
+use crate::sema::ty::Primitive;
 use colored::*;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum OrionError {
     // GENERAL ERRORS
     /// Unimplemented feature
@@ -25,7 +27,355 @@ pub enum OrionError {
     /// Unclosed multiline comment. e.g. `/* this is a comment without a close.`
     UnclosedMultilineComment,
     // PARSER ERRORS
-    // ...
+    /// A token that doesn't fit the grammar. e.g. `let = 5;`
+    UnexpectedToken {
+        expected: String,
+        found: String,
+        loc: Location,
+    },
+    /// A number literal that isn't a valid number. e.g. `1.2.3`
+    InvalidNumber { literal: String, loc: Location },
+    /// Expressions nested deeper than the parser supports. e.g. `((((...))))`
+    NestingTooDeep { loc: Location },
+    /// An unknown escape sequence in a string or character. e.g. `"\q"`
+    InvalidEscape { escape: String, loc: Location },
+    /// A character literal not holding exactly one character. e.g. `'ab'`
+    InvalidCharLiteral { literal: String, loc: Location },
+    /// An operator writing to something that can't be written to. e.g. `5++`
+    NotAssignable { operator: String, loc: Location },
+    /// A C-style cast, which Orion doesn't have. e.g. `(u64)*a`
+    CStyleCast { ty: String, loc: Location },
+    /// `<-` used as an assignment or glued to a negative number, which
+    /// Orion doesn't have an operator for. e.g. `x <- 1` or `x<-1`
+    ArrowAssignment { loc: Location },
+    // SEMANTIC ERRORS
+    /// A name defined twice in the same place. e.g. two `struct Point`
+    DuplicateDefinition { name: String, loc: Location },
+    /// A field declared or initialized twice. e.g. `Point { x: 1, x: 2 }`
+    DuplicateField {
+        structure: String,
+        field: String,
+        loc: Location,
+    },
+    /// A type name that doesn't refer to any type. e.g. `let a : Nope = 1;`
+    UnknownType { name: String, loc: Location },
+    /// A struct literal naming something that isn't a struct. e.g. `u8 { }`
+    NotAStruct { name: String, loc: Location },
+    /// A field that the type doesn't have. e.g. `point.z`
+    UnknownField {
+        ty: String,
+        field: String,
+        loc: Location,
+    },
+    /// A struct literal leaving out fields. e.g. `Point { x: 1 }`
+    MissingFields {
+        structure: String,
+        fields: Vec<String>,
+        loc: Location,
+    },
+    /// A struct containing itself, which would make it infinitely large.
+    /// e.g. `struct List { next : List }`
+    RecursiveStruct { name: String, loc: Location },
+    /// A type without a known size where one is needed. e.g. a `str` field
+    Unsized { ty: String, loc: Location },
+    /// An array length that isn't a whole number. e.g. `[u8; 1.5]`
+    InvalidArrayLength { loc: Location },
+    /// A value of the wrong type. e.g. `let a : bool = 'a';`
+    MismatchedTypes {
+        expected: String,
+        found: String,
+        /// What the expected type comes from, like the annotation of a
+        /// variable or the type of a parameter, if it is written somewhere
+        origin: Option<Location>,
+        loc: Location,
+    },
+    /// An operator applied to a value of a type it doesn't apply to. e.g.
+    /// `true + false`
+    InvalidOperand {
+        operator: String,
+        ty: String,
+        takes: String,
+        loc: Location,
+    },
+    /// A number used where one of a type that doesn't hold all its values
+    /// is expected, with the cast converting it. e.g. `b + a` for an `i32`
+    /// `b` and a `u32` `a`
+    NumberConversion {
+        expected: Primitive,
+        found: Primitive,
+        /// The value converted to the type expected, like `a as i32`
+        cast: String,
+        origin: Option<Location>,
+        loc: Location,
+    },
+    /// A number literal outside the values of the integer type it is given
+    /// for. e.g. `let a : u8 = 300;`
+    LiteralOutOfRange {
+        literal: String,
+        ty: String,
+        min: i128,
+        max: i128,
+        loc: Location,
+    },
+    /// A float literal too large for the float type it is given for, which
+    /// would make it infinite. e.g. a 40 digit number given for an `f32`
+    FloatOutOfRange {
+        literal: String,
+        ty: String,
+        loc: Location,
+    },
+    /// An array literal of the wrong length. e.g. `let a : [u8; 2] = [1];`
+    ArrayLengthMismatch {
+        expected: u64,
+        found: u64,
+        loc: Location,
+    },
+    /// Indexing or slicing something that isn't an array or a slice. e.g.
+    /// `true[0]`
+    NotIndexable { ty: String, loc: Location },
+    /// Asking the length of something without one. e.g. `len(1)`
+    NoLength { ty: String, loc: Location },
+    /// A variant declared twice in an enum. e.g. `enum E { A, A }`
+    DuplicateVariant {
+        enumeration: String,
+        variant: String,
+        loc: Location,
+    },
+    /// A path naming something that isn't an enum. e.g. `u8::A`
+    NotAnEnum { name: String, loc: Location },
+    /// A variant the enum doesn't have. e.g. `Shape::Triangle`
+    UnknownVariant {
+        enumeration: String,
+        variant: String,
+        loc: Location,
+    },
+    /// A variant pattern with the wrong number of fields. e.g.
+    /// `Shape::Rect(w)`
+    PatternArity {
+        variant: String,
+        expected: usize,
+        found: usize,
+        loc: Location,
+    },
+    /// A `match` that some values fall through, with patterns they match.
+    /// e.g. `match s { Shape::Circle(r) => r }`
+    NonExhaustiveMatch { missing: Vec<String>, loc: Location },
+    /// A pattern only matching values that earlier arms already match.
+    /// e.g. the second arm of `match b { _ => 1, true => 2 }`
+    UnreachablePattern { loc: Location },
+    /// A call given the wrong number of arguments. e.g. `f(1, 2)` for
+    /// `func f(a: u8)`
+    ArgumentCount {
+        callee: String,
+        expected: usize,
+        found: usize,
+        loc: Location,
+    },
+    /// A call of a value that isn't a function. e.g. `5(1)`
+    NotCallable { ty: String, loc: Location },
+    /// A generic type given the wrong number of type arguments. e.g.
+    /// `Pair<u8>` for `struct Pair<A, B>`
+    TypeArguments {
+        name: String,
+        expected: usize,
+        found: usize,
+        loc: Location,
+    },
+    /// A type parameter that nothing tells the type of where a generic
+    /// function or struct is used. e.g. `let a := zero();` for
+    /// `func zero<T>() : T`
+    UninferredType {
+        parameter: String,
+        item: String,
+        loc: Location,
+    },
+    /// A generic function needing ever more instances of itself, with
+    /// larger and larger type arguments. e.g. `f(&x)` in the body of
+    /// `func f<T>(x : T)`
+    InstantiationTooDeep { function: String, loc: Location },
+    /// A closure parameter without a type where nothing tells it. e.g.
+    /// `let f := |x| = x;`
+    UntypedParameter { name: String, loc: Location },
+    /// A closure capturing a variable by reference returned from where the
+    /// variable is. e.g. `return || = { count++; };`
+    EscapingClosure { name: String, loc: Location },
+    /// A write to a variable, parameter or function that isn't declared
+    /// `mut`, with where it is declared if known. e.g. `x = 2;` after
+    /// `let x := 1;`
+    ImmutableAssignment {
+        name: String,
+        declared: Option<Location>,
+        loc: Location,
+    },
+    /// A write through a reference that isn't `&mut`. e.g. `*r = 2;` for
+    /// `r : &u8`
+    ImmutableReference { ty: String, loc: Location },
+    /// A function or closure with a result that can reach the end of its
+    /// body without returning one, with the name of the function. e.g.
+    /// `func f(a : bool) : u8 = { if a { return 1; } }`
+    MissingReturn {
+        function: Option<String>,
+        ty: String,
+        loc: Location,
+    },
+    /// A top-level variable declared without an initializer, which the
+    /// functions could read before it is assigned. e.g. `let x : u8;`
+    UninitializedGlobal { name: String, loc: Location },
+    // TRAIT ERRORS
+    /// A name used as a trait that doesn't refer to one. e.g. `impl Nope
+    /// for Point`
+    UnknownTrait { name: String, loc: Location },
+    /// A trait implemented for a type that can't have methods. e.g. `impl
+    /// Show for u8`
+    InvalidImpl { ty: String, loc: Location },
+    /// A built-in trait implemented by the program. e.g. `impl Ord for
+    /// Point`
+    BuiltinImpl { trait_name: String, loc: Location },
+    /// A trait implemented twice for the same type
+    ConflictingImpl {
+        trait_name: String,
+        ty: String,
+        first: Location,
+        loc: Location,
+    },
+    /// An implementation leaving out methods of its trait
+    MissingMethods {
+        trait_name: String,
+        ty: String,
+        methods: Vec<String>,
+        loc: Location,
+    },
+    /// A method in an implementation that its trait doesn't declare
+    UnknownTraitMethod {
+        trait_name: String,
+        method: String,
+        loc: Location,
+    },
+    /// A type used where a trait is required, without implementing it. e.g.
+    /// `show(1 as u8)` for `func show<T : Show>(x : T)`
+    MissingImpl {
+        trait_name: String,
+        ty: String,
+        loc: Location,
+    },
+    /// A method call that no trait of the receiver provides. e.g.
+    /// `point.nope()`
+    UnknownMethod {
+        ty: String,
+        method: String,
+        loc: Location,
+    },
+    /// A method call that two traits of the receiver provide
+    AmbiguousMethod {
+        ty: String,
+        method: String,
+        traits: (String, String),
+        loc: Location,
+    },
+    // MODULE ERRORS
+    /// A module whose file can't be read. e.g. `mod math;` without a
+    /// `math.ori`
+    ModuleNotFound {
+        module: String,
+        file: String,
+        loc: Location,
+    },
+    /// Modules whose top-level variables are initialized with each other's
+    /// items, directly or through other modules. e.g. `let x := b::f();` in
+    /// `a` while `let y := a::g();` is in `b`, or the root module `main`
+    /// declaring `mod main;`
+    ModuleCycle { modules: Vec<String>, loc: Location },
+    /// A path naming something a module doesn't have. e.g. `math::nope`
+    UnknownItem {
+        module: String,
+        item: String,
+        loc: Location,
+    },
+    /// A path naming an item of another module that isn't `pub`. e.g.
+    /// `math::helper` without a `pub func helper`
+    PrivateItem {
+        module: String,
+        item: String,
+        loc: Location,
+    },
+    /// An import naming something inside what isn't a module. e.g.
+    /// `import shapes::Shape::Circle;`
+    NotAModule { name: String, loc: Location },
+    /// An error in one of the files of a program split across files
+    InFile {
+        file: String,
+        error: Box<OrionError>,
+    },
+    // COMPILE-TIME ERRORS
+    /// An error evaluating a `$` expression or the condition of a `$if`,
+    /// with where the evaluation started. e.g. `$(1 / 0)`
+    CompileTime {
+        error: Box<OrionError>,
+        loc: Location,
+    },
+    /// Compile-time evaluation taking more steps than it may, likely because
+    /// it never ends. e.g. `$(forever())`
+    TooManySteps { limit: usize, loc: Location },
+    /// A `$` expression whose value can't be written as a literal. e.g.
+    /// `$(&x)`
+    NotConstant { kind: String, loc: Location },
+    // RUNTIME ERRORS
+    /// An index past the end of an array or slice. e.g. `[1, 2][2]`
+    IndexOutOfBounds {
+        index: String,
+        length: usize,
+        loc: Location,
+    },
+    /// A slice reaching past the end of what is sliced, or ending before it
+    /// starts. e.g. `[1, 2][1..3]`
+    SliceOutOfBounds {
+        start: String,
+        end: String,
+        length: usize,
+        loc: Location,
+    },
+    /// A name used without being defined. e.g. `let a := b;` without a `b`.
+    /// Reported by the semantic checks, and when running code they didn't
+    /// check, like the code evaluated while compiling.
+    UndefinedName { name: String, loc: Location },
+    /// A variable read before it is assigned, with where a path leading to
+    /// the read without assigning it starts when some path does assign it.
+    /// e.g. `let x : u8; if a { x = 1; } return x;`. Reported by the
+    /// semantic checks, and when running code they didn't check.
+    Uninitialized {
+        name: String,
+        missing: Option<Location>,
+        loc: Location,
+    },
+    /// Running a program without a `main` function
+    MissingMain,
+    /// Dividing by zero, or taking a remainder of it. e.g. `1 % 0`
+    DivisionByZero { loc: Location },
+    /// Integer arithmetic whose result its type doesn't hold, or a shift by
+    /// its width or more, when overflow is checked. e.g. `x + 1` for a `u8`
+    /// `x` of 255. Reported by the semantic checks for arithmetic on number
+    /// literals, and when running.
+    IntegerOverflow {
+        operation: String,
+        ty: String,
+        loc: Location,
+    },
+    /// Calls nested deeper than the interpreter supports, usually endless
+    /// recursion. e.g. `func f() : = { f(); }`
+    RecursionTooDeep { loc: Location },
+    /// An array made with more elements than the interpreter holds. e.g.
+    /// `[0; 1000000000]`
+    ArrayTooLong {
+        length: String,
+        limit: usize,
+        loc: Location,
+    },
+    /// A value that none of the arms of a `match` match
+    NoMatchingArm { loc: Location },
+    // WARNINGS
+    /// A statement that no path through its function reaches. e.g. `x = 1;`
+    /// after `return;`
+    UnreachableCode { loc: Location },
 }
 
 /// Implementing the error trait for Orion's custom error
@@ -47,6 +397,344 @@ impl OrionError {
             LexerError => "there was an error in lexing.".to_string(),
             UnterminatedQuote => "There was an unterminated quote.".to_string(),
             UnclosedMultilineComment => "A multiline comment was unclosed.".to_string(),
+            UnexpectedToken {
+                expected,
+                found,
+                loc,
+            } => format!("expected {expected}, found {found} at {loc}."),
+            InvalidNumber { literal, loc } => format!("invalid number `{literal}` at {loc}."),
+            NestingTooDeep { loc } => format!("expression nested too deeply at {loc}."),
+            InvalidEscape { escape, loc } => format!("invalid escape `{escape}` at {loc}."),
+            InvalidCharLiteral { literal, loc } => {
+                format!("character literal {literal} must hold exactly one character at {loc}.")
+            }
+            NotAssignable { operator, loc } => {
+                format!("`{operator}` can only be applied to a variable, a field or an array element at {loc}.")
+            }
+            CStyleCast { ty, loc } => format!(
+                "`({ty})` followed by a value looks like a C-style cast at {loc}, write `value as {ty}` instead."
+            ),
+            ArrowAssignment { loc } => format!(
+                "`<-` is not an operator at {loc}, write `place = value` to assign or `a < -b` to compare."
+            ),
+            DuplicateDefinition { name, loc } => {
+                format!("`{name}` is defined more than once at {loc}.")
+            }
+            DuplicateField {
+                structure,
+                field,
+                loc,
+            } => format!("field `{field}` of `{structure}` appears more than once at {loc}."),
+            UnknownType { name, loc } => format!("unknown type `{name}` at {loc}."),
+            NotAStruct { name, loc } => format!("`{name}` is not a struct at {loc}."),
+            UnknownField { ty, field, loc } => format!("`{ty}` has no field `{field}` at {loc}."),
+            MissingFields {
+                structure,
+                fields,
+                loc,
+            } => {
+                let fields: Vec<String> = fields.iter().map(|field| format!("`{field}`")).collect();
+                format!("missing {} in `{structure}` at {loc}.", fields.join(", "))
+            }
+            RecursiveStruct { name, loc } => format!(
+                "`{name}` contains itself and would be infinitely large at {loc}, put it behind a reference like `&{name}`."
+            ),
+            Unsized { ty, loc } => format!(
+                "the size of `{ty}` isn't known at compile time at {loc}, put it behind a reference like `&{ty}`."
+            ),
+            InvalidArrayLength { loc } => {
+                format!("array lengths must be whole numbers at {loc}.")
+            }
+            MismatchedTypes {
+                expected,
+                found,
+                origin: Some(origin),
+                loc,
+            } => format!("expected `{expected}` because of {origin}, found `{found}` at {loc}."),
+            MismatchedTypes {
+                expected,
+                found,
+                origin: None,
+                loc,
+            } => format!("expected `{expected}`, found `{found}` at {loc}."),
+            InvalidOperand {
+                operator,
+                ty,
+                takes,
+                loc,
+            } => format!("`{operator}` can't be applied to `{ty}` at {loc}, only to {takes}."),
+            NumberConversion {
+                expected,
+                found,
+                cast,
+                origin: Some(origin),
+                loc,
+            } => format!(
+                "expected `{expected}` because of {origin}, found `{found}` at {loc}, which has values `{expected}` doesn't: write `{cast}` to convert it."
+            ),
+            NumberConversion {
+                expected,
+                found,
+                cast,
+                origin: None,
+                loc,
+            } => format!(
+                "expected `{expected}`, found `{found}` at {loc}, which has values `{expected}` doesn't: write `{cast}` to convert it."
+            ),
+            LiteralOutOfRange {
+                literal,
+                ty,
+                min,
+                max,
+                loc,
+            } => format!(
+                "`{literal}` doesn't fit in `{ty}`, whose values go from {min} to {max} at {loc}."
+            ),
+            FloatOutOfRange { literal, ty, loc } => format!(
+                "`{literal}` doesn't fit in `{ty}` at {loc}, where it would be infinite."
+            ),
+            ArrayLengthMismatch {
+                expected,
+                found,
+                loc,
+            } => format!(
+                "expected an array of {expected} elements, found one of {found} at {loc}."
+            ),
+            NotIndexable { ty, loc } => format!("`{ty}` can't be indexed at {loc}."),
+            NoLength { ty, loc } => format!("`{ty}` doesn't have a length at {loc}."),
+            DuplicateVariant {
+                enumeration,
+                variant,
+                loc,
+            } => format!("variant `{variant}` of `{enumeration}` appears more than once at {loc}."),
+            NotAnEnum { name, loc } => format!("`{name}` is not an enum at {loc}."),
+            UnknownVariant {
+                enumeration,
+                variant,
+                loc,
+            } => format!("`{enumeration}` has no variant `{variant}` at {loc}."),
+            PatternArity {
+                variant,
+                expected,
+                found,
+                loc,
+            } => format!(
+                "`{variant}` has {expected} fields, but the pattern has {found} at {loc}."
+            ),
+            NonExhaustiveMatch { missing, loc } => {
+                // A few examples are enough to show what is missing
+                const SHOWN: usize = 3;
+
+                let mut patterns: Vec<String> = missing
+                    .iter()
+                    .take(SHOWN)
+                    .map(|pattern| format!("`{pattern}`"))
+                    .collect();
+
+                if missing.len() > SHOWN {
+                    patterns.push(format!("{} more", missing.len() - SHOWN));
+                }
+
+                format!("`match` doesn't cover {} at {loc}.", patterns.join(", "))
+            }
+            UnreachablePattern { loc } => format!(
+                "unreachable pattern at {loc}, the arms before it match everything it does."
+            ),
+            ArgumentCount {
+                callee,
+                expected,
+                found,
+                loc,
+            } => format!(
+                "`{callee}` takes {expected} arguments, but {found} were given at {loc}."
+            ),
+            NotCallable { ty, loc } => {
+                format!("a value of type `{ty}` can't be called at {loc}, it isn't a function.")
+            }
+            TypeArguments {
+                name,
+                expected,
+                found,
+                loc,
+            } => format!(
+                "`{name}` has {expected} type parameters, but {found} type arguments were given at {loc}."
+            ),
+            UninferredType {
+                parameter,
+                item,
+                loc,
+            } => format!(
+                "can't infer the type `{parameter}` of `{item}` at {loc}, give a type to the variable it is assigned to."
+            ),
+            InstantiationTooDeep { function, loc } => format!(
+                "instantiating `{function}` never ends at {loc}, it needs instances of itself with ever larger type arguments."
+            ),
+            UntypedParameter { name, loc } => format!(
+                "can't infer the type of the closure parameter `{name}` at {loc}, write it as `{name} : T`."
+            ),
+            EscapingClosure { name, loc } => format!(
+                "the closure at {loc} can't be returned, it captures `{name}` by reference and would outlive it."
+            ),
+            ImmutableAssignment {
+                name,
+                declared: Some(declared),
+                loc,
+            } => format!(
+                "`{name}` can't be assigned to at {loc}, it is declared without `mut` at {declared}."
+            ),
+            ImmutableAssignment {
+                name,
+                declared: None,
+                loc,
+            } => format!("`{name}` can't be assigned to at {loc}, it isn't declared `mut`."),
+            ImmutableReference { ty, loc } => format!(
+                "can't write through `{ty}` at {loc}, it isn't a `&mut` reference."
+            ),
+            MissingReturn {
+                function: Some(function),
+                ty,
+                loc,
+            } => format!(
+                "`{function}` must return `{ty}`, but can reach the end of its body at {loc}."
+            ),
+            UninitializedGlobal { name, loc } => format!(
+                "top-level variable `{name}` needs an initializer at {loc}, as functions can read it at any time."
+            ),
+            MissingReturn {
+                function: None,
+                ty,
+                loc,
+            } => format!(
+                "the closure must return `{ty}`, but can reach the end of its body at {loc}."
+            ),
+            UnknownTrait { name, loc } => format!("unknown trait `{name}` at {loc}."),
+            InvalidImpl { ty, loc } => format!(
+                "`{ty}` can't implement a trait at {loc}, only structs and enums without type parameters can."
+            ),
+            BuiltinImpl { trait_name, loc } => format!(
+                "`{trait_name}` can't be implemented at {loc}, it is built in and only numbers and characters implement it."
+            ),
+            ConflictingImpl {
+                trait_name,
+                ty,
+                first,
+                loc,
+            } => format!(
+                "conflicting implementations of `{trait_name}` for `{ty}` at {loc}, it is first implemented at {first}."
+            ),
+            MissingMethods {
+                trait_name,
+                ty,
+                methods,
+                loc,
+            } => {
+                let methods: Vec<String> =
+                    methods.iter().map(|method| format!("`{method}`")).collect();
+
+                format!(
+                    "missing {} in the implementation of `{trait_name}` for `{ty}` at {loc}.",
+                    methods.join(", ")
+                )
+            }
+            UnknownTraitMethod {
+                trait_name,
+                method,
+                loc,
+            } => format!("`{method}` is not a method of `{trait_name}` at {loc}."),
+            MissingImpl {
+                trait_name,
+                ty,
+                loc,
+            } => format!("`{ty}` doesn't implement `{trait_name}` at {loc}."),
+            UnknownMethod { ty, method, loc } => {
+                format!("`{ty}` has no method `{method}` at {loc}.")
+            }
+            AmbiguousMethod {
+                ty,
+                method,
+                traits: (first, second),
+                loc,
+            } => format!(
+                "`{ty}` has a method `{method}` from both `{first}` and `{second}` at {loc}."
+            ),
+            ModuleNotFound { module, file, loc } => {
+                format!("no file `{file}` for module `{module}` at {loc}.")
+            }
+            ModuleCycle { modules, loc } => {
+                let modules: Vec<String> =
+                    modules.iter().map(|module| format!("`{module}`")).collect();
+                format!(
+                    "modules depend on each other in a cycle, {} at {loc}.",
+                    modules.join(" -> ")
+                )
+            }
+            UnknownItem { module, item, loc } => {
+                format!("module `{module}` has no item `{item}` at {loc}.")
+            }
+            PrivateItem { module, item, loc } => format!(
+                "`{item}` is private to module `{module}` at {loc}, declare it `pub` to use it here."
+            ),
+            NotAModule { name, loc } => format!("`{name}` is not a module at {loc}."),
+            InFile { file, error } => format!("{file}: {}", error.message()),
+            CompileTime { error, loc } => format!(
+                "{}, while evaluating at compile time from {loc}.",
+                error.message().trim_end_matches('.')
+            ),
+            TooManySteps { limit, loc } => format!(
+                "evaluation took more than {limit} steps at {loc}."
+            ),
+            NotConstant { kind, loc } => format!(
+                "a `{kind}` value can't be the result of compile-time evaluation at {loc}, only numbers, bools, characters, strings, arrays, structs and enums can."
+            ),
+            IndexOutOfBounds { index, length, loc } => format!(
+                "index {index} is out of bounds for a length of {length} at {loc}."
+            ),
+            SliceOutOfBounds {
+                start,
+                end,
+                length,
+                loc,
+            } => format!(
+                "slice {start}..{end} is out of bounds for a length of {length} at {loc}."
+            ),
+            UndefinedName { name, loc } => format!("`{name}` is not defined at {loc}."),
+            Uninitialized {
+                name,
+                missing: Some(missing),
+                loc,
+            } => format!(
+                "`{name}` is read at {loc} before it is assigned on every path, the one through {missing} doesn't assign it."
+            ),
+            Uninitialized {
+                name,
+                missing: None,
+                loc,
+            } => format!("`{name}` is read at {loc} before it is assigned."),
+            MissingMain => "there is no `main` function to run.".to_string(),
+            DivisionByZero { loc } => format!("division by zero at {loc}."),
+            IntegerOverflow { operation, ty, loc } => {
+                format!("`{operation}` overflows `{ty}` at {loc}.")
+            }
+            RecursionTooDeep { loc } => format!("calls nested too deeply at {loc}."),
+            ArrayTooLong { length, limit, loc } => format!(
+                "an array of {length} elements is too long at {loc}, arrays can have at most {limit}."
+            ),
+            NoMatchingArm { loc } => {
+                format!("no arm of the `match` matches the value at {loc}.")
+            }
+            UnreachableCode { loc } => {
+                format!("unreachable statement at {loc}, every path before it returns.")
+            }
+        }
+    }
+
+    /// Whether this is a warning, which doesn't stop the program from
+    /// compiling
+    pub fn is_warning(&self) -> bool {
+        match self {
+            InFile { error, .. } => error.is_warning(),
+            UnreachableCode { .. } => true,
+            _ => false,
         }
     }
 }
@@ -54,7 +742,11 @@ impl OrionError {
 impl Display for OrionError {
     /// Display the error in a useful and colorful format
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        let prefix = "[ERROR]".red().bold();
+        let prefix = if self.is_warning() {
+            "[WARNING]".yellow().bold()
+        } else {
+            "[ERROR]".red().bold()
+        };
 
         write!(f, "{prefix}: {}", self.message())
     }
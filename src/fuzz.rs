@@ -0,0 +1,1042 @@
+//! Random program generation for fuzzing the lexer and parser.
+//!
+//! [`Generator`] produces programs from the grammar the parser implements,
+//! with random whitespace and comments between the tokens, so every program
+//! it generates is valid. [`Generator::corrupted_program`] then breaks such a
+//! program by deleting, duplicating and inserting pieces of text, which
+//! exercises the error paths. Generation is fully determined by the seed, so
+//! a failure can always be reproduced.
+
+/// A small xorshift pseudo-random number generator. Not suitable for anything
+/// but testing.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // xorshift gets stuck on zero
+        Self(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A number in `0..n`
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+
+    /// True one in `n` times
+    pub fn one_in(&mut self, n: u64) -> bool {
+        self.below(n) == 0
+    }
+
+    pub fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len() as u64) as usize]
+    }
+}
+
+const OPERATORS: &[&str] = &[
+    "+", "-", "*", "/", "%", "&", "|", "^", "<<", ">>", "==", "!=", "<", "<=", ">", ">=",
+];
+
+/// Text inserted when corrupting a program: every symbol the lexer knows,
+/// keywords, unterminated literals and comments, and characters it doesn't
+/// know at all.
+const NOISE: &[&str] = &[
+    "(",
+    ")",
+    "[",
+    "]",
+    "{",
+    "}",
+    "=",
+    ":",
+    ":=",
+    ";",
+    "$",
+    ",",
+    "->",
+    "=>",
+    "<-",
+    "..",
+    ".",
+    "~",
+    "::",
+    "+",
+    "++",
+    "-",
+    "--",
+    "*",
+    "/",
+    "%",
+    "&",
+    "|",
+    "^",
+    "!",
+    "+=",
+    "|=",
+    "<<=",
+    ">>=",
+    "let",
+    "mut",
+    "return",
+    "as",
+    "func",
+    "if",
+    "else",
+    "struct",
+    "enum",
+    "match",
+    "mod",
+    "import",
+    "pub",
+    "trait",
+    "impl",
+    "for",
+    "self",
+    "_",
+    "<>",
+    ">>",
+    "(u8)",
+    "1.2.3",
+    "1__0",
+    "\"",
+    "\"str\"",
+    "//",
+    "/*",
+    "*/",
+    "@",
+    "#",
+    "`",
+    "\\",
+    "\u{e9}",
+    "\u{1f680}",
+    "\u{a0}",
+    "\0",
+    " ",
+    "\n",
+    "\t",
+];
+
+pub struct Generator {
+    pub rng: Rng,
+    /// How deeply expressions may nest
+    pub max_depth: u32,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng::new(seed),
+            max_depth: 6,
+        }
+    }
+
+    /// A syntactically valid program
+    pub fn program(&mut self) -> String {
+        let mut out = String::new();
+
+        for _ in 0..self.rng.below(6) {
+            self.trivia(&mut out);
+            self.statement(&mut out);
+        }
+
+        self.trivia(&mut out);
+        out
+    }
+
+    /// A valid program with random damage done to it
+    pub fn corrupted_program(&mut self) -> String {
+        let mut chars: Vec<char> = self.program().chars().collect();
+
+        for _ in 0..self.rng.below(4) + 1 {
+            let at = self.rng.below(chars.len() as u64 + 1) as usize;
+
+            match self.rng.below(4) {
+                // Delete a few characters
+                0 => {
+                    let end = (at + self.rng.below(4) as usize + 1).min(chars.len());
+                    chars.drain(at..end);
+                }
+                // Duplicate a piece of the program
+                1 => {
+                    let end = (at + self.rng.below(8) as usize).min(chars.len());
+                    let piece: Vec<char> = chars[at..end].to_vec();
+                    chars.splice(at..at, piece);
+                }
+                // Cut the program short
+                2 => chars.truncate(at),
+                // Insert noise
+                _ => {
+                    let noise = self.rng.pick(NOISE);
+                    chars.splice(at..at, noise.chars());
+                }
+            }
+        }
+
+        chars.into_iter().collect()
+    }
+
+    fn statement(&mut self, out: &mut String) {
+        match self.rng.below(9) {
+            0..=2 => {
+                if self.rng.one_in(3) {
+                    token(out, "pub");
+                    self.space(out);
+                }
+
+                match self.rng.below(4) {
+                    0 => self.function(out, false),
+                    1 => self.structure(out),
+                    2 => self.trait_declaration(out),
+                    _ => self.enumeration(out),
+                }
+            }
+            3 if self.rng.one_in(2) => self.implementation(out),
+            3 => self.module(out),
+            4 => self.import(out),
+            _ => self.let_statement(out),
+        }
+    }
+
+    /// `mod name;`, possibly `pub`
+    fn module(&mut self, out: &mut String) {
+        if self.rng.one_in(3) {
+            token(out, "pub");
+            self.space(out);
+        }
+
+        token(out, "mod");
+        self.space(out);
+        self.identifier(out);
+        self.trivia(out);
+        token(out, ";");
+    }
+
+    /// `import path::to::name;`
+    fn import(&mut self, out: &mut String) {
+        token(out, "import");
+        self.space(out);
+        self.identifier(out);
+
+        for _ in 0..self.rng.below(3) {
+            self.trivia(out);
+            token(out, "::");
+            self.trivia(out);
+            self.identifier(out);
+        }
+
+        self.trivia(out);
+        token(out, ";");
+    }
+
+    /// `struct Name { field : T, ... }`
+    fn structure(&mut self, out: &mut String) {
+        token(out, "struct");
+        self.space(out);
+        self.identifier(out);
+        self.trivia(out);
+        self.generics(out, false);
+        token(out, "{");
+
+        for i in 0..self.rng.below(4) {
+            if i > 0 {
+                token(out, ",");
+            }
+
+            self.trivia(out);
+            self.identifier(out);
+            self.trivia(out);
+            token(out, ":");
+            self.trivia(out);
+            self.ty(out, 2, true);
+        }
+
+        self.trivia(out);
+        token(out, "}");
+    }
+
+    /// `<A, B>` after the name of a function or struct, or nothing. The
+    /// type parameters of a function can have bounds, like `A : Show + Eq`.
+    fn generics(&mut self, out: &mut String, bounds: bool) {
+        if self.rng.one_in(2) {
+            return;
+        }
+
+        token(out, "<");
+
+        for i in 0..self.rng.below(3) + 1 {
+            if i > 0 {
+                token(out, ",");
+                self.trivia(out);
+            }
+
+            self.identifier(out);
+
+            if bounds && self.rng.one_in(2) {
+                self.trivia(out);
+                token(out, ":");
+
+                for i in 0..self.rng.below(2) + 1 {
+                    if i > 0 {
+                        self.trivia(out);
+                        token(out, "+");
+                    }
+
+                    self.trivia(out);
+                    self.identifier(out);
+                }
+            }
+        }
+
+        token(out, ">");
+        self.trivia(out);
+    }
+
+    /// `enum Name { Variant(T, U), Other, ... }`
+    fn enumeration(&mut self, out: &mut String) {
+        token(out, "enum");
+        self.space(out);
+        self.identifier(out);
+        self.trivia(out);
+        token(out, "{");
+
+        for i in 0..self.rng.below(4) {
+            if i > 0 {
+                token(out, ",");
+            }
+
+            self.trivia(out);
+            self.identifier(out);
+
+            if self.rng.one_in(2) {
+                self.trivia(out);
+                token(out, "(");
+
+                for i in 0..self.rng.below(3) + 1 {
+                    if i > 0 {
+                        token(out, ",");
+                        self.trivia(out);
+                    }
+
+                    self.ty(out, 2, true);
+                }
+
+                token(out, ")");
+            }
+        }
+
+        self.trivia(out);
+        token(out, "}");
+    }
+
+    fn let_statement(&mut self, out: &mut String) {
+        token(out, "let");
+        self.space(out);
+
+        if self.rng.one_in(3) {
+            token(out, "mut");
+            self.space(out);
+        }
+
+        self.identifier(out);
+        self.trivia(out);
+
+        if self.rng.one_in(3) {
+            token(out, ":");
+            self.trivia(out);
+            self.ty(out, self.max_depth / 2, true);
+            self.trivia(out);
+            token(out, "=");
+        } else {
+            token(out, if self.rng.one_in(2) { "=" } else { ":=" });
+        }
+
+        self.trivia(out);
+        self.expression(out, self.max_depth);
+        self.trivia(out);
+        token(out, ";");
+    }
+
+    /// `trait Name { func method(self, a : T) : R; ... }`
+    fn trait_declaration(&mut self, out: &mut String) {
+        token(out, "trait");
+        self.space(out);
+        self.identifier(out);
+        self.trivia(out);
+        token(out, "{");
+
+        for _ in 0..self.rng.below(3) {
+            self.trivia(out);
+            token(out, "func");
+            self.space(out);
+            self.identifier(out);
+            self.trivia(out);
+            self.parameters(out, true);
+
+            if self.rng.one_in(2) {
+                token(out, ":");
+                self.trivia(out);
+                self.ty(out, 2, true);
+                self.trivia(out);
+            }
+
+            token(out, ";");
+        }
+
+        self.trivia(out);
+        token(out, "}");
+    }
+
+    /// `impl Trait for Type { func method(self) : R = { ... } ... }`
+    fn implementation(&mut self, out: &mut String) {
+        token(out, "impl");
+        self.space(out);
+        self.identifier(out);
+        self.space(out);
+        token(out, "for");
+        self.space(out);
+        self.ty(out, 2, true);
+        self.trivia(out);
+        token(out, "{");
+
+        for _ in 0..self.rng.below(3) {
+            self.trivia(out);
+            self.function(out, true);
+        }
+
+        self.trivia(out);
+        token(out, "}");
+    }
+
+    /// A function, or a method taking `self` first, which has no type
+    /// parameters
+    fn function(&mut self, out: &mut String, method: bool) {
+        token(out, "func");
+        self.space(out);
+        self.identifier(out);
+        self.trivia(out);
+
+        if !method {
+            self.generics(out, true);
+        }
+
+        self.parameters(out, method);
+
+        if self.rng.one_in(3) {
+            token(out, "=");
+        } else {
+            token(out, ":");
+            self.trivia(out);
+
+            if !self.rng.one_in(4) {
+                self.ty(out, 2, true);
+                self.trivia(out);
+            }
+
+            token(out, "=");
+        }
+
+        self.trivia(out);
+        self.block(out, 2);
+    }
+
+    /// `(a : T, b : U)`, starting with `self` for a method
+    fn parameters(&mut self, out: &mut String, method: bool) {
+        token(out, "(");
+
+        if method {
+            self.trivia(out);
+            token(out, "self");
+        }
+
+        for i in 0..self.rng.below(4) {
+            if i > 0 || method {
+                token(out, ",");
+            }
+
+            self.trivia(out);
+            self.identifier(out);
+            self.trivia(out);
+            token(out, ":");
+            self.trivia(out);
+            self.ty(out, 2, true);
+        }
+
+        token(out, ")");
+        self.trivia(out);
+    }
+
+    fn block(&mut self, out: &mut String, depth: u32) {
+        token(out, "{");
+
+        for _ in 0..self.rng.below(4) {
+            self.trivia(out);
+            self.block_statement(out, depth);
+        }
+
+        self.trivia(out);
+        token(out, "}");
+    }
+
+    /// A statement inside a function
+    fn block_statement(&mut self, out: &mut String, depth: u32) {
+        match self.rng.below(7) {
+            0 => {
+                token(out, "return");
+
+                if self.rng.one_in(2) {
+                    self.space(out);
+                    self.expression(out, self.max_depth / 2);
+                }
+
+                self.trivia(out);
+                token(out, ";");
+            }
+            1 if depth > 0 => {
+                let comptime = self.rng.one_in(4);
+                self.if_statement(out, depth - 1, comptime);
+            }
+            2 if depth > 0 => self.block(out, depth - 1),
+            3 => {
+                self.call(out, self.max_depth / 2);
+                self.trivia(out);
+                token(out, ";");
+            }
+            4 => {
+                self.place(out);
+                self.trivia(out);
+
+                // The operators before the comparisons have compound
+                // assignments, like `+=`
+                match self.rng.one_in(3) {
+                    true => token(out, &format!("{}=", self.rng.pick(&OPERATORS[..10]))),
+                    false => token(out, "="),
+                }
+
+                self.trivia(out);
+                self.expression(out, self.max_depth / 2);
+                self.trivia(out);
+                token(out, ";");
+            }
+            // A `match` on its own, which needs no `;`
+            5 if depth > 0 => self.match_expression(out, depth - 1),
+            _ => self.let_statement(out),
+        }
+    }
+
+    /// An `if`, or a `$if` if `comptime` is set
+    fn if_statement(&mut self, out: &mut String, depth: u32, comptime: bool) {
+        if comptime {
+            token(out, "$");
+            self.trivia(out);
+        }
+
+        token(out, "if");
+        self.space(out);
+        self.expression(out, self.max_depth / 2);
+        self.trivia(out);
+        self.block(out, depth);
+
+        if self.rng.one_in(2) {
+            self.trivia(out);
+            token(out, "else");
+            self.space(out);
+
+            if depth > 0 && self.rng.one_in(2) {
+                self.if_statement(out, depth - 1, comptime);
+            } else {
+                self.block(out, depth);
+            }
+        }
+    }
+
+    /// `f(a, b)`
+    fn call(&mut self, out: &mut String, depth: u32) {
+        self.identifier(out);
+        self.trivia(out);
+        token(out, "(");
+
+        for i in 0..self.rng.below(3) {
+            if i > 0 {
+                token(out, ",");
+                self.trivia(out);
+            }
+
+            self.expression(out, depth.saturating_sub(1));
+        }
+
+        token(out, ")");
+    }
+
+    /// Something that can be assigned to: `a`, `*a`, `a.b.c` or `a[i].b`
+    fn place(&mut self, out: &mut String) {
+        if self.rng.one_in(4) {
+            token(out, "*");
+            self.trivia(out);
+        }
+
+        self.identifier(out);
+
+        for _ in 0..self.rng.below(3) {
+            self.trivia(out);
+
+            if self.rng.one_in(2) {
+                token(out, ".");
+                self.trivia(out);
+                self.identifier(out);
+            } else {
+                token(out, "[");
+                self.expression(out, 2);
+                token(out, "]");
+            }
+        }
+    }
+
+    /// `[a, b]` or `[value; length]`
+    fn array(&mut self, out: &mut String, depth: u32) {
+        token(out, "[");
+        self.trivia(out);
+
+        if self.rng.one_in(3) {
+            self.expression(out, depth);
+            self.trivia(out);
+            token(out, ";");
+            self.trivia(out);
+            self.number(out);
+        } else {
+            for i in 0..self.rng.below(4) {
+                if i > 0 {
+                    token(out, ",");
+                    self.trivia(out);
+                }
+
+                self.expression(out, depth);
+            }
+        }
+
+        self.trivia(out);
+        token(out, "]");
+    }
+
+    /// `a[i]`, or `a[start..end]` where either bound may be left out
+    fn index(&mut self, out: &mut String, depth: u32) {
+        self.identifier(out);
+        self.trivia(out);
+        token(out, "[");
+
+        if self.rng.one_in(2) {
+            self.expression(out, depth);
+        } else {
+            if self.rng.one_in(2) {
+                self.expression(out, depth);
+            }
+
+            token(out, "..");
+
+            if self.rng.one_in(2) {
+                self.expression(out, depth);
+            }
+        }
+
+        token(out, "]");
+    }
+
+    /// `Name { field: value, ... }`, in parentheses so that it can also be
+    /// used in the condition of an `if`
+    fn struct_literal(&mut self, out: &mut String, depth: u32) {
+        token(out, "(");
+        self.identifier(out);
+        self.trivia(out);
+        token(out, "{");
+
+        for i in 0..self.rng.below(4) {
+            if i > 0 {
+                token(out, ",");
+            }
+
+            self.trivia(out);
+            self.identifier(out);
+            self.trivia(out);
+            token(out, ":");
+            self.trivia(out);
+            self.expression(out, depth);
+        }
+
+        self.trivia(out);
+        token(out, "}");
+        token(out, ")");
+    }
+
+    /// `match scrutinee { pattern => value, pattern => { ... } }`
+    fn match_expression(&mut self, out: &mut String, depth: u32) {
+        token(out, "match");
+        self.space(out);
+        self.expression(out, depth);
+        self.trivia(out);
+        token(out, "{");
+
+        let arms = self.rng.below(4);
+
+        for i in 0..arms {
+            self.trivia(out);
+            self.pattern(out, 2);
+            self.trivia(out);
+            token(out, "=>");
+            self.trivia(out);
+
+            if self.rng.one_in(3) {
+                self.block(out, depth.min(1));
+
+                if self.rng.one_in(2) {
+                    token(out, ",");
+                }
+            } else {
+                self.expression(out, depth);
+
+                // The comma after the last arm is optional
+                if i + 1 < arms || self.rng.one_in(2) {
+                    token(out, ",");
+                }
+            }
+        }
+
+        self.trivia(out);
+        token(out, "}");
+    }
+
+    /// `|a : T, b| -> R = value`, the body being an expression or a block
+    /// `$(value)` or `${ ... }`
+    fn comptime(&mut self, out: &mut String, depth: u32) {
+        token(out, "$");
+        self.trivia(out);
+
+        if self.rng.one_in(3) {
+            self.block(out, 0);
+        } else {
+            token(out, "(");
+            self.trivia(out);
+            self.expression(out, depth);
+            self.trivia(out);
+            token(out, ")");
+        }
+    }
+
+    fn closure(&mut self, out: &mut String, depth: u32) {
+        token(out, "|");
+
+        for i in 0..self.rng.below(3) {
+            if i > 0 {
+                token(out, ",");
+                self.trivia(out);
+            }
+
+            self.identifier(out);
+
+            if self.rng.one_in(2) {
+                self.trivia(out);
+                token(out, ":");
+                self.trivia(out);
+                self.ty(out, 2, true);
+            }
+        }
+
+        token(out, "|");
+        self.trivia(out);
+
+        if self.rng.one_in(3) {
+            token(out, "->");
+            self.trivia(out);
+            self.ty(out, 2, true);
+            self.trivia(out);
+        }
+
+        token(out, "=");
+        self.trivia(out);
+
+        if self.rng.one_in(3) {
+            self.block(out, depth.min(1));
+        } else {
+            self.expression(out, depth);
+        }
+    }
+
+    fn pattern(&mut self, out: &mut String, depth: u32) {
+        match self.rng.below(7) {
+            0 => token(out, "_"),
+            1 => self.identifier(out),
+            2 => {
+                if self.rng.one_in(3) {
+                    token(out, "-");
+                }
+
+                self.number(out);
+            }
+            3 => token(out, self.rng.pick(&["true", "false", "'a'", "'\\n'"])),
+            _ => {
+                self.path(out);
+
+                if depth > 0 && self.rng.one_in(2) {
+                    token(out, "(");
+
+                    for i in 0..self.rng.below(3) + 1 {
+                        if i > 0 {
+                            token(out, ",");
+                            self.trivia(out);
+                        }
+
+                        self.pattern(out, depth - 1);
+                    }
+
+                    token(out, ")");
+                }
+            }
+        }
+    }
+
+    /// `Enum::Variant`
+    fn path(&mut self, out: &mut String) {
+        self.identifier(out);
+        token(out, "::");
+        self.identifier(out);
+    }
+
+    fn expression(&mut self, out: &mut String, depth: u32) {
+        if depth == 0 || self.rng.one_in(3) {
+            return self.atom(out);
+        }
+
+        match self.rng.below(18) {
+            0 => {
+                token(out, "(");
+                self.trivia(out);
+                self.expression(out, depth - 1);
+                self.trivia(out);
+                token(out, ")");
+            }
+            1 => {
+                token(out, self.rng.pick(&["-", "!", "~", "&", "*"]));
+                self.trivia(out);
+                self.expression(out, depth - 1);
+            }
+            // Increments and decrements only apply to variables
+            2 => {
+                token(out, self.rng.pick(&["++", "--"]));
+                self.trivia(out);
+                self.identifier(out);
+            }
+            3 => {
+                self.identifier(out);
+                self.trivia(out);
+                token(out, self.rng.pick(&["++", "--"]));
+            }
+            4 => {
+                self.expression(out, depth - 1);
+                self.space(out);
+                token(out, "as");
+                self.space(out);
+                self.ty(out, 2, false);
+            }
+            5 => self.call(out, depth - 1),
+            6 => self.struct_literal(out, depth - 1),
+            8 => self.array(out, depth - 1),
+            9 => self.index(out, depth - 1),
+            10 => self.path(out),
+            11 => self.match_expression(out, depth - 1),
+            7 => {
+                self.call(out, depth - 1);
+                self.trivia(out);
+                token(out, ".");
+                self.trivia(out);
+                self.identifier(out);
+            }
+            12 => {
+                self.identifier(out);
+                self.trivia(out);
+                token(out, ".");
+                self.trivia(out);
+                self.call(out, depth - 1);
+            }
+            13 => self.closure(out, depth - 1),
+            14 => self.comptime(out, depth - 1),
+            _ => {
+                self.expression(out, depth - 1);
+                self.trivia(out);
+                token(out, self.rng.pick(OPERATORS));
+                self.trivia(out);
+                self.expression(out, depth - 1);
+            }
+        }
+    }
+
+    fn atom(&mut self, out: &mut String) {
+        match self.rng.below(6) {
+            0 | 1 => self.number(out),
+            2 | 3 => self.identifier(out),
+            4 => token(out, self.rng.pick(&["true", "false"])),
+            _ => {
+                let literal = self.rng.pick(&[
+                    "\"\"",
+                    "\"text\"",
+                    "\"\\\"quoted\\\"\\n\"",
+                    "'a'",
+                    "'\\''",
+                    "'\\t'",
+                    "'\u{1f680}'",
+                ]);
+                token(out, literal);
+            }
+        }
+    }
+
+    /// A type. Without `bare_generics` generic arguments are written with
+    /// `::<`, as needed after `as`.
+    fn ty(&mut self, out: &mut String, depth: u32, bare_generics: bool) {
+        if depth == 0 || self.rng.one_in(2) {
+            return token(
+                out,
+                self.rng.pick(&["u8", "i64", "f32", "bool", "char", "str"]),
+            );
+        }
+
+        match self.rng.below(7) {
+            0 => {
+                token(out, "&");
+                self.trivia(out);
+                self.ty(out, depth - 1, bare_generics);
+            }
+            1 => {
+                token(out, "[");
+                self.ty(out, depth - 1, true);
+
+                if self.rng.one_in(2) {
+                    token(out, ";");
+                    self.trivia(out);
+                    self.number(out);
+                }
+
+                token(out, "]");
+            }
+            2 => {
+                token(out, "(");
+
+                for i in 0..self.rng.below(4) {
+                    if i > 0 {
+                        token(out, ",");
+                        self.trivia(out);
+                    }
+
+                    self.ty(out, depth - 1, true);
+                }
+
+                token(out, ")");
+            }
+            3 => {
+                token(out, "func");
+                token(out, "(");
+                self.ty(out, depth - 1, true);
+                token(out, ")");
+
+                if self.rng.one_in(2) {
+                    self.trivia(out);
+                    token(out, "->");
+                    self.trivia(out);
+                    self.ty(out, depth - 1, bare_generics);
+                }
+            }
+            _ => {
+                self.identifier(out);
+
+                if self.rng.one_in(2) {
+                    token(out, "::");
+                    self.identifier(out);
+                }
+
+                if !bare_generics {
+                    token(out, "::");
+                }
+
+                token(out, "<");
+                self.ty(out, depth - 1, true);
+
+                if self.rng.one_in(2) {
+                    token(out, ",");
+                    self.trivia(out);
+                    self.ty(out, depth - 1, true);
+                }
+
+                token(out, ">");
+            }
+        }
+    }
+
+    /// Identifiers never start like a keyword, so they can't be one
+    fn identifier(&mut self, out: &mut String) {
+        const FIRST: &[char] = &['a', 'b', 'x', 'y', 'z', '_', '\u{e9}'];
+        const REST: &[char] = &['a', 'e', 't', '_', '0', '9'];
+
+        let mut ident = String::from(self.rng.pick(FIRST));
+
+        for _ in 0..self.rng.below(6) {
+            ident.push(self.rng.pick(REST));
+        }
+
+        token(out, &ident);
+    }
+
+    fn number(&mut self, out: &mut String) {
+        let mut number = self.rng.below(1_000_000).to_string();
+
+        if self.rng.one_in(4) {
+            number.push('_');
+            number.push_str(&self.rng.below(1_000).to_string());
+        }
+
+        if self.rng.one_in(3) {
+            number.push('.');
+            number.push_str(&self.rng.below(1_000).to_string());
+        }
+
+        token(out, &number);
+    }
+
+    /// Whitespace that can't be left out, between a keyword and a name
+    fn space(&mut self, out: &mut String) {
+        out.push(' ');
+        self.trivia(out);
+    }
+
+    /// Optional whitespace and comments
+    fn trivia(&mut self, out: &mut String) {
+        match self.rng.below(8) {
+            0..=2 => {}
+            3 => out.push('\n'),
+            4 => out.push_str("\t "),
+            5 => out.push_str(" // comment\n"),
+            6 => token(out, "/* comment */"),
+            _ => out.push(' '),
+        }
+    }
+}
+
+/// Append a token, separated by a space from what comes before if the two
+/// would otherwise run together into different tokens, like `-` and `-`
+/// becoming `--`.
+fn token(out: &mut String, text: &str) {
+    const SYMBOLS: &str = "+-*/%<>=!:.&|^~";
+
+    let word = |chr: char| chr.is_alphanumeric() || chr == '_';
+
+    if let (Some(last), Some(first)) = (out.chars().last(), text.chars().next()) {
+        if (SYMBOLS.contains(last) && SYMBOLS.contains(first)) || (word(last) && word(first)) {
+            out.push(' ');
+        }
+    }
+
+    out.push_str(text);
+}
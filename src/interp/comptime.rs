@@ -0,0 +1,414 @@
+//! Compile-time evaluation, running the `$` code of a program.
+//!
+//! `$if` is conditional compilation. Its condition is evaluated as the file
+//! it is in is loaded, and the statements of the branch taken replace it,
+//! as if they were written in its place. The other branches are dropped
+//! without being checked, so they can hold code for other targets. Nothing
+//! of the program is known yet then, so a condition can only use the
+//! settings of the compilation, like `target_os`.
+//!
+//! `$(value)` and `${ ... return value; }` are evaluated once the names of
+//! the program are resolved, and replaced by a literal of their value. They
+//! can call the functions of the program, and use the settings and the
+//! top-level variables initialized with `$`. Those are evaluated first, in
+//! the order the modules run in, as other top-level variables only have a
+//! value when the program runs.
+//!
+//! The interpreter evaluates the `$` code, with a limit on the steps it
+//! takes so that code that never ends doesn't keep compiling from ending.
+//! An error points at the code that failed, which may be in a function the
+//! `$` code called, and says where the evaluation started.
+use crate::error::OrionError;
+use crate::interp::value::Value;
+use crate::interp::{Interpreter, Overflow, Unwind, MAX_CALL_DEPTH};
+use crate::lexer::tokens::Span;
+use crate::module::Module;
+use crate::parser::ast::*;
+use crate::parser::visit::{walk_expression, walk_expression_mut, Visitor, VisitorMut};
+use crate::sema::ty::Primitive;
+use std::collections::HashMap;
+
+/// How many steps evaluating one `$` expression or `$if` condition takes
+/// at most, unless configured otherwise
+pub const MAX_STEPS: usize = 1_000_000;
+
+/// The settings of a compilation, which `$` code can use
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// The settings by name, `target_os` and `target_arch` and those given
+    /// with [`Config::set`]
+    pub settings: Vec<(String, Setting)>,
+    /// How many expressions evaluating one `$` expression or `$if`
+    /// condition may evaluate
+    pub max_steps: usize,
+    /// How deeply calls may nest in `$` code and when the program runs
+    pub max_depth: usize,
+    /// What integer arithmetic does when it overflows, in `$` code and when
+    /// the program runs
+    pub overflow: Overflow,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Setting {
+    Flag(bool),
+    Text(String),
+}
+
+/// The target the compiler itself runs on
+impl Default for Config {
+    fn default() -> Self {
+        let text = |value: &str| Setting::Text(value.to_string());
+
+        Self {
+            settings: vec![
+                ("target_os".to_string(), text(std::env::consts::OS)),
+                ("target_arch".to_string(), text(std::env::consts::ARCH)),
+            ],
+            max_steps: MAX_STEPS,
+            max_depth: MAX_CALL_DEPTH,
+            overflow: Overflow::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Add a setting written `name=value`, or `name` for a flag that is
+    /// set. The values `true` and `false` are flags as well. A setting
+    /// replaces an earlier one of the same name.
+    pub fn set(&mut self, setting: &str) {
+        let (name, value) = match setting.split_once('=') {
+            Some((name, "true")) => (name, Setting::Flag(true)),
+            Some((name, "false")) => (name, Setting::Flag(false)),
+            Some((name, value)) => (name, Setting::Text(value.to_string())),
+            None => (setting, Setting::Flag(true)),
+        };
+
+        self.settings.retain(|(other, _)| other != name);
+        self.settings.push((name.to_string(), value));
+    }
+}
+
+/// Replace the `$if`s of a program by the statements of the branches they
+/// take, `source` being the text it was parsed from. Returns the errors
+/// evaluating the conditions, the `$if`s that failed being dropped.
+pub fn select(program: &mut Program, source: &str, config: &Config) -> Vec<OrionError> {
+    let mut selector = Selector {
+        source,
+        config,
+        errors: vec![],
+    };
+
+    selector.statements(program);
+    selector.errors
+}
+
+/// Evaluate the `$` expressions of every module, replacing them by
+/// literals of their values. The names of the modules must be resolved and
+/// the modules ordered by [`crate::module::resolve()`]. Returns the errors
+/// found, with the file they were found in.
+pub fn evaluate(modules: &mut [Module], config: &Config) -> Vec<OrionError> {
+    let (literals, errors) = {
+        let mut evaluator = Evaluator {
+            interpreter: Interpreter::compiling("", config),
+            literals: modules.iter().map(|_| HashMap::new()).collect(),
+            errors: vec![],
+        };
+
+        evaluator.modules(modules);
+        (evaluator.literals, evaluator.errors)
+    };
+
+    for (module, literals) in modules.iter_mut().zip(literals) {
+        Replacer(literals).visit_program_mut(&mut module.program);
+    }
+
+    errors
+}
+
+impl<'ast> Interpreter<'ast> {
+    /// An interpreter for `$` code, which knows the settings and takes a
+    /// limited number of steps
+    fn compiling(source: &'ast str, config: &'ast Config) -> Self {
+        let mut interpreter = Self::new(source)
+            .with_overflow(config.overflow)
+            .with_max_depth(config.max_depth);
+        interpreter.max_steps = Some(config.max_steps);
+
+        for (name, setting) in &config.settings {
+            let value = match setting {
+                Setting::Flag(value) => Value::Bool(*value),
+                Setting::Text(text) => Value::Str(text),
+            };
+
+            interpreter.globals.insert(name, value.slot());
+        }
+
+        interpreter
+    }
+
+    /// Evaluate `$` code, which runs like the body of a function without
+    /// parameters. An error is reported as found while evaluating the code
+    /// at `start`.
+    fn evaluate(&mut self, code: &'ast Expression, start: Span) -> Result<Value<'ast>, OrionError> {
+        self.steps = 0;
+
+        match self.expression(code) {
+            Ok(value) | Err(Unwind::Return(value)) => Ok(value),
+            Err(Unwind::Error(error)) => Err(OrionError::CompileTime {
+                error,
+                loc: self.loc(start),
+            }),
+        }
+    }
+}
+
+struct Selector<'a> {
+    source: &'a str,
+    config: &'a Config,
+    errors: Vec<OrionError>,
+}
+
+impl Selector<'_> {
+    fn statements(&mut self, statements: &mut Vec<Statement>) {
+        for stmt in std::mem::take(statements) {
+            self.select(stmt, statements);
+        }
+    }
+
+    /// Add a statement to `selected`, or the statements of the branch it
+    /// takes if it is a `$if`
+    fn select(&mut self, stmt: Statement, selected: &mut Vec<Statement>) {
+        let Statement { kind, span } = stmt;
+
+        let StatementKind::ComptimeIf {
+            condition,
+            then,
+            otherwise,
+        } = kind
+        else {
+            let mut stmt = Statement { kind, span };
+            self.visit_statement_mut(&mut stmt);
+            selected.push(stmt);
+            return;
+        };
+
+        let taken = match self.condition(&condition, span) {
+            Ok(true) => Some(Statement {
+                span: then.span,
+                kind: StatementKind::Block(then),
+            }),
+            Ok(false) => otherwise.map(|otherwise| *otherwise),
+            Err(error) => {
+                self.errors.push(error);
+                None
+            }
+        };
+
+        match taken {
+            Some(Statement {
+                kind: StatementKind::Block(block),
+                ..
+            }) => {
+                for stmt in block.statements {
+                    self.select(stmt, selected);
+                }
+            }
+            // `else $if`
+            Some(stmt) => self.select(stmt, selected),
+            None => {}
+        }
+    }
+
+    fn condition(&self, condition: &Expression, start: Span) -> Result<bool, OrionError> {
+        let mut interpreter = Interpreter::compiling(self.source, self.config);
+
+        match interpreter.evaluate(condition, start)? {
+            Value::Bool(value) => Ok(value),
+            value => Err(interpreter.mismatch("bool", &value, condition.span)),
+        }
+    }
+}
+
+impl VisitorMut for Selector<'_> {
+    fn visit_block_mut(&mut self, block: &mut Block) {
+        self.statements(&mut block.statements);
+    }
+}
+
+struct Evaluator<'ast> {
+    interpreter: Interpreter<'ast>,
+    /// The literals replacing the `$` expressions of every module, by where
+    /// the expressions start
+    literals: Vec<HashMap<usize, Expression>>,
+    errors: Vec<OrionError>,
+}
+
+impl<'ast> Evaluator<'ast> {
+    fn modules(&mut self, modules: &'ast [Module]) {
+        self.interpreter.sources = modules
+            .iter()
+            .map(|module| (module.source.as_str(), Some(module.file.as_path())))
+            .collect();
+
+        self.interpreter.declare_modules(modules);
+
+        // The top-level variables first, as any `$` code can use them
+        for (index, module) in modules.iter().enumerate() {
+            self.interpreter.module = index;
+
+            for (name, code, span) in module.program.iter().filter_map(constant) {
+                if let Some(value) = self.constant(code, span) {
+                    self.interpreter.declare(name, value);
+                }
+            }
+        }
+
+        for (index, module) in modules.iter().enumerate() {
+            self.interpreter.module = index;
+
+            for stmt in &module.program {
+                if constant(stmt).is_none() {
+                    self.visit_statement(stmt);
+                }
+            }
+        }
+    }
+
+    /// Evaluate `$` code starting at `span`, keeping a literal of its value
+    fn constant(&mut self, code: &'ast Expression, span: Span) -> Option<Value<'ast>> {
+        let interpreter = &mut self.interpreter;
+
+        let evaluated = interpreter.evaluate(code, span).and_then(|value| {
+            let literal = literal(&value, span).map_err(|kind| OrionError::NotConstant {
+                kind: kind.to_string(),
+                loc: interpreter.loc(span),
+            })?;
+
+            Ok((value, literal))
+        });
+
+        match evaluated {
+            Ok((value, literal)) => {
+                self.literals[interpreter.module].insert(span.start, literal);
+                Some(value)
+            }
+            Err(error) => {
+                self.errors.push(interpreter.in_file(error));
+                None
+            }
+        }
+    }
+}
+
+impl<'ast> Visitor<'ast> for Evaluator<'ast> {
+    fn visit_expression(&mut self, expr: &'ast Expression) {
+        match &expr.kind {
+            // The `$` code inside is evaluated along with it
+            ExpressionKind::Comptime(code) => {
+                self.constant(code, expr.span);
+            }
+            _ => walk_expression(self, expr),
+        }
+    }
+}
+
+/// A top-level variable initialized with `$`, with the code evaluated and
+/// where the `$` starts
+fn constant(stmt: &Statement) -> Option<(&str, &Expression, Span)> {
+    match &stmt.kind {
+        StatementKind::Let {
+            name,
+            initial: Some(initial),
+            ..
+        } => match &initial.kind {
+            ExpressionKind::Comptime(code) => Some((name, code, initial.span)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// A number literal, written after a `-` if it is negative
+fn number(num: Number, span: Span) -> ExpressionKind {
+    if num.is_negative() {
+        ExpressionKind::Unary {
+            op: UnaryOperator::Neg,
+            operand: Box::new(Expression::new(ExpressionKind::Number(-num), span)),
+        }
+    } else {
+        ExpressionKind::Number(num)
+    }
+}
+
+/// A literal of a value, or the kind of the part of it that can't be one
+fn literal(value: &Value, span: Span) -> Result<Expression, &'static str> {
+    let kind = match value {
+        Value::Integer(num, _) => number(Number::Integer(*num), span),
+        // An `f32` is written as the shortest number reading back as it
+        Value::Float(num, Some(Primitive::F32)) => {
+            let num = (*num as f32).to_string().parse().unwrap_or(*num);
+            number(Number::Float(num), span)
+        }
+        Value::Float(num, _) => number(Number::Float(*num), span),
+        Value::Bool(value) => ExpressionKind::Bool(*value),
+        Value::Char(chr) => ExpressionKind::Char(*chr),
+        Value::Str(string) => ExpressionKind::String(string.to_string()),
+        Value::Array(elements) => ExpressionKind::Array(
+            elements
+                .iter()
+                .map(|element| literal(element, span))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Struct(name, fields) => ExpressionKind::StructLiteral {
+            name: name.to_string(),
+            fields: fields
+                .iter()
+                .map(|(name, value)| {
+                    Ok(FieldInit {
+                        name: name.to_string(),
+                        value: literal(value, span)?,
+                        span,
+                    })
+                })
+                .collect::<Result<_, _>>()?,
+        },
+        Value::Variant(enumeration, variant, fields) => {
+            let mut path: Vec<String> = enumeration.split("::").map(str::to_string).collect();
+            path.push(variant.to_string());
+
+            let path = Expression::new(ExpressionKind::Path(path), span);
+
+            if fields.is_empty() {
+                return Ok(path);
+            }
+
+            ExpressionKind::Call {
+                callee: Box::new(path),
+                arguments: fields
+                    .iter()
+                    .map(|field| literal(field, span))
+                    .collect::<Result<_, _>>()?,
+            }
+        }
+        value => return Err(value.kind()),
+    };
+
+    Ok(Expression::new(kind, span))
+}
+
+/// Replaces the `$` expressions of a module by the literals of their values
+struct Replacer(HashMap<usize, Expression>);
+
+impl VisitorMut for Replacer {
+    fn visit_expression_mut(&mut self, expr: &mut Expression) {
+        let literal = match expr.kind {
+            ExpressionKind::Comptime(_) => self.0.remove(&expr.span.start),
+            _ => None,
+        };
+
+        match literal {
+            Some(literal) => *expr = literal,
+            None => walk_expression_mut(self, expr),
+        }
+    }
+}
@@ -0,0 +1,1573 @@
+//! A tree-walking interpreter, running a program straight from its AST.
+//!
+//! This is the execution backend until there is a code generator. It
+//! relies on the semantic checks for what they cover, and reports what only
+//! shows up while running, like an index out of bounds, pointing at the
+//! expression that failed.
+//!
+//! Whole numbers are held exactly and floats as `f64`. Arithmetic on two
+//! whole numbers gives a whole number, dividing rounding towards zero, and
+//! a float otherwise. A whole number given for a variable, a parameter, a
+//! field or a result declared as a float becomes one, as does one assigned
+//! to a variable holding a float. A float given for an `f32` or cast to
+//! one is rounded to it, as is the result of arithmetic on `f32` values.
+//!
+//! A whole number given for an integer type, or cast to one, is a value of
+//! that type, and arithmetic with it is done in it: a number literal added
+//! to a `u8` makes a `u8`. A result the type doesn't hold overflows, which
+//! stops with an error, wraps around or saturates as [`Overflow`] says, as
+//! does a number given for an integer type that isn't one of its values.
+//! The builtins like `wrapping_add` and `saturating_add` do what their
+//! names say whatever the mode.
+//!
+//! It also evaluates the `$` code of a program while compiling it, see
+//! [`comptime`].
+pub mod comptime;
+pub mod value;
+
+use crate::error::OrionError;
+use crate::interp::value::{Environment, Place, Slot, Value};
+use crate::lexer::tokens::{Location, Span};
+use crate::module::{in_file, Module};
+use crate::parser::ast::*;
+use crate::parser::visit::{walk_statement, Visitor};
+use crate::sema::closures::{captures, CaptureMode};
+use crate::sema::ty::Primitive;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::rc::Rc;
+
+/// How deeply calls may nest unless told otherwise. Every call recurses
+/// through the interpreter, so without a limit endless recursion would
+/// overflow the stack.
+pub const MAX_CALL_DEPTH: usize = 10_000;
+
+/// The stack a call may take up: the large frames of a debug build, for a
+/// call made a few expressions deep in the body of its caller
+const CALL_STACK_SIZE: usize = 128 << 10;
+
+/// The stack the thread running an interpreter needs for calls to nest
+/// `max_depth` deep, the default 2 MiB of a thread being enough for about
+/// ten of them. Most of it is never touched, so it costs address space
+/// rather than memory.
+pub fn stack_size(max_depth: usize) -> usize {
+    (max_depth + 64).saturating_mul(CALL_STACK_SIZE)
+}
+
+/// How many elements an array made by `[value; length]` may have, so that
+/// a large length is an error rather than an allocation that takes all the
+/// memory there is
+const MAX_ARRAY_LENGTH: usize = 1 << 20;
+
+type Scope<'ast> = HashMap<&'ast str, Slot<'ast>>;
+
+/// Why running stopped before reaching the end of what was run. A `return`
+/// unwinds like an error does, up to the call it returns from, as it may be
+/// in a block inside an expression. Errors are boxed so they don't make
+/// the result of everything that runs larger, calls nesting deeply.
+enum Unwind<'ast> {
+    Error(Box<OrionError>),
+    Return(Value<'ast>),
+}
+
+impl From<OrionError> for Unwind<'_> {
+    fn from(error: OrionError) -> Self {
+        Unwind::Error(Box::new(error))
+    }
+}
+
+type Run<'ast, T> = Result<T, Unwind<'ast>>;
+
+/// What integer arithmetic does when its result isn't a value of its type,
+/// and what a number given for an integer type does when it isn't one.
+/// Checked unless the program is run with another mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Overflow {
+    /// Stop with an error pointing at the arithmetic
+    #[default]
+    Checked,
+    /// Wrap around to the value of the type with the same low bits
+    Wrapping,
+    /// Give the value of the type closest to the result, its smallest or
+    /// largest
+    Saturating,
+}
+
+pub struct Interpreter<'ast> {
+    /// The text of every module run, and its file when the program is split
+    /// across files
+    sources: Vec<(&'ast str, Option<&'ast Path>)>,
+    /// The module whose code is running
+    module: usize,
+    /// The module every function is declared in, when there are several
+    functions: HashMap<Declared<'ast>, usize>,
+    /// The variables and functions declared at the top level
+    globals: Scope<'ast>,
+    /// The enums declared anywhere in the program, by name
+    enums: HashMap<&'ast str, &'ast Enum>,
+    /// The structs declared anywhere in the program, by name
+    structs: HashMap<&'ast str, &'ast Struct>,
+    /// The methods of the impls, by the struct or enum they are for and
+    /// their name
+    methods: HashMap<(String, &'ast str), &'ast Function>,
+    /// The scopes of the function being run, innermost scope last
+    scopes: Vec<Scope<'ast>>,
+    /// How deeply calls are nested, and how deeply they may be
+    depth: usize,
+    max_depth: usize,
+    /// How many expressions were evaluated, and how many may be. Only
+    /// compile-time evaluation is limited.
+    steps: usize,
+    max_steps: Option<usize>,
+    overflow: Overflow,
+}
+
+/// An interpreter without a source, for [`Interpreter::run_modules`] which
+/// takes the text of every module from the modules
+impl Default for Interpreter<'_> {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+impl<'ast> Interpreter<'ast> {
+    /// `source` is the text the program was parsed from, to point runtime
+    /// errors at their line and column
+    pub fn new(source: &'ast str) -> Self {
+        Self {
+            sources: vec![(source, None)],
+            module: 0,
+            functions: HashMap::new(),
+            globals: HashMap::new(),
+            enums: HashMap::new(),
+            structs: HashMap::new(),
+            methods: HashMap::new(),
+            scopes: vec![],
+            depth: 0,
+            max_depth: MAX_CALL_DEPTH,
+            steps: 0,
+            max_steps: None,
+            overflow: Overflow::default(),
+        }
+    }
+
+    /// An interpreter doing what `overflow` says when integer arithmetic
+    /// overflows
+    pub fn with_overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// An interpreter letting calls nest `max_depth` deep, which needs a
+    /// thread with a [`stack_size`] for it
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Run the top level of a program and then its `main` function,
+    /// returning what `main` returned
+    pub fn run(&mut self, program: &'ast Program) -> Result<Value<'ast>, OrionError> {
+        let mut collector = TypeCollector(&mut self.enums, &mut self.structs);
+        collector.visit_program(program);
+
+        self.declare_functions(program);
+        self.declare_methods(program);
+
+        for stmt in program {
+            match self.statement(stmt) {
+                Ok(()) => {}
+                Err(Unwind::Return(value)) => return Ok(value),
+                Err(Unwind::Error(error)) => return Err(*error),
+            }
+        }
+
+        self.main()
+    }
+
+    /// Run a program split across modules, ordered as
+    /// [`crate::module::resolve()`] left them: the top level of every module
+    /// runs after the modules its top-level variables use, and then the
+    /// `main` function of the root module. Runtime errors say which file
+    /// they happened in.
+    pub fn run_modules(&mut self, modules: &'ast [Module]) -> Result<Value<'ast>, OrionError> {
+        self.sources = modules
+            .iter()
+            .map(|module| (module.source.as_str(), Some(module.file.as_path())))
+            .collect();
+
+        self.declare_modules(modules);
+
+        for (index, module) in modules.iter().enumerate() {
+            self.module = index;
+
+            for stmt in &module.program {
+                match self.statement(stmt) {
+                    Ok(()) => {}
+                    Err(Unwind::Return(value)) => return Ok(value),
+                    Err(Unwind::Error(error)) => return Err(self.in_file(*error)),
+                }
+            }
+        }
+
+        self.main()
+    }
+
+    /// Declare the structs, enums, functions and methods of every module
+    fn declare_modules(&mut self, modules: &'ast [Module]) {
+        for (index, module) in modules.iter().enumerate() {
+            let mut collector = TypeCollector(&mut self.enums, &mut self.structs);
+            collector.visit_program(&module.program);
+
+            let mut collector = FunctionCollector(&mut self.functions, index);
+            collector.visit_program(&module.program);
+
+            self.declare_functions(&module.program);
+            self.declare_methods(&module.program);
+        }
+    }
+
+    fn main(&mut self) -> Result<Value<'ast>, OrionError> {
+        let main = self.globals.get("main").map(|slot| slot.borrow().clone());
+
+        match main {
+            Some(Value::Function(main)) if main.parameters.is_empty() => {
+                self.module = self.module_of(main);
+
+                match self.call(main, vec![], main.body.span) {
+                    Ok(value) | Err(Unwind::Return(value)) => Ok(value),
+                    Err(Unwind::Error(error)) => Err(self.in_file(*error)),
+                }
+            }
+            _ => Err(OrionError::MissingMain),
+        }
+    }
+
+    fn loc(&self, span: Span) -> Location {
+        let (source, _) = self.sources[self.module];
+        let start = span.start.min(source.len());
+        Location::from_input(&source[..start])
+    }
+
+    fn module_of(&self, function: &'ast Function) -> usize {
+        let key = Declared(function);
+        self.functions.get(&key).copied().unwrap_or(self.module)
+    }
+
+    /// An error found in the running module, saying which file it is in
+    fn in_file(&self, error: OrionError) -> OrionError {
+        match self.sources[self.module] {
+            (_, Some(file)) => in_file(file, error),
+            (_, None) => error,
+        }
+    }
+
+    fn declare(&mut self, name: &'ast str, value: Value<'ast>) {
+        let scope = self.scopes.last_mut().unwrap_or(&mut self.globals);
+        scope.insert(name, value.slot());
+    }
+
+    /// Functions can be called before they are declared, so they are known
+    /// as soon as the statements around them start running
+    fn declare_functions(&mut self, statements: &'ast [Statement]) {
+        for stmt in statements {
+            if let StatementKind::Function(function) = &stmt.kind {
+                self.declare(&function.name, Value::Function(function));
+            }
+        }
+    }
+
+    /// The methods of the impls, which are only declared at the top level.
+    /// A type has at most one method of a name, the semantic checks make
+    /// sure of it.
+    fn declare_methods(&mut self, program: &'ast Program) {
+        for stmt in program {
+            let StatementKind::Impl(implementation) = &stmt.kind else {
+                continue;
+            };
+
+            let TypeKind::Named(path) = &implementation.ty.kind else {
+                continue;
+            };
+
+            for method in &implementation.methods {
+                if let StatementKind::Function(function) = &method.kind {
+                    let key = (path.segments.join("::"), function.name.as_str());
+                    self.methods.insert(key, function);
+                }
+            }
+        }
+    }
+
+    fn lookup(&self, name: &str, span: Span) -> Result<Slot<'ast>, OrionError> {
+        self.scopes
+            .iter()
+            .rev()
+            .chain([&self.globals])
+            .find_map(|scope| scope.get(name))
+            .cloned()
+            .ok_or_else(|| OrionError::UndefinedName {
+                name: name.to_string(),
+                loc: self.loc(span),
+            })
+    }
+
+    fn call(
+        &mut self,
+        function: &'ast Function,
+        arguments: Vec<Value<'ast>>,
+        span: Span,
+    ) -> Run<'ast, Value<'ast>> {
+        let mut parameters = HashMap::new();
+
+        for (parameter, argument) in function.parameters.iter().zip(arguments) {
+            let argument = self.convert(argument, &parameter.ty, span)?;
+            parameters.insert(parameter.name.as_str(), argument.slot());
+        }
+
+        let module = self.module_of(function);
+
+        let returns = function.result.as_ref();
+
+        self.enter(vec![parameters], module, returns, span, |interpreter| {
+            interpreter.block(&function.body)?;
+            Ok(Value::Unit)
+        })
+    }
+
+    /// Call a closure, which sees the variables it captured
+    fn call_closure(
+        &mut self,
+        closure: &'ast Closure,
+        environment: &Environment<'ast>,
+        arguments: Vec<Value<'ast>>,
+        span: Span,
+    ) -> Run<'ast, Value<'ast>> {
+        let captures = environment
+            .captures
+            .iter()
+            .map(|(name, slot)| (*name, slot.clone()))
+            .collect();
+
+        let mut parameters = HashMap::new();
+
+        for (parameter, argument) in closure.parameters.iter().zip(arguments) {
+            let argument = match &parameter.ty {
+                Some(ty) => self.convert(argument, ty, span)?,
+                None => argument,
+            };
+
+            parameters.insert(parameter.name.as_str(), argument.slot());
+        }
+
+        self.enter(
+            vec![captures, parameters],
+            environment.module,
+            closure.result.as_deref(),
+            span,
+            |interpreter| interpreter.expression(&closure.body),
+        )
+    }
+
+    /// Run the body of a function or a closure in `module`, with `scopes`
+    /// in place of the caller's, returning what it returns as a value of
+    /// the type it `returns`
+    fn enter(
+        &mut self,
+        scopes: Vec<Scope<'ast>>,
+        callee: usize,
+        returns: Option<&Type>,
+        span: Span,
+        body: impl FnOnce(&mut Self) -> Run<'ast, Value<'ast>>,
+    ) -> Run<'ast, Value<'ast>> {
+        if self.depth >= self.max_depth {
+            return Err(OrionError::RecursionTooDeep {
+                loc: self.loc(span),
+            }
+            .into());
+        }
+
+        // The caller's variables are out of reach while the function runs
+        let caller = std::mem::replace(&mut self.scopes, scopes);
+        let module = std::mem::replace(&mut self.module, callee);
+        self.depth += 1;
+        let result = body(self);
+        self.depth -= 1;
+        self.scopes = caller;
+
+        // Errors leaving a module are marked with its file before the
+        // locations of the caller's module apply
+        let result = match result {
+            Err(Unwind::Error(error)) if self.module != module => {
+                Err(Unwind::Error(Box::new(self.in_file(*error))))
+            }
+            result => result,
+        };
+        self.module = module;
+
+        match (result, returns) {
+            (Ok(value) | Err(Unwind::Return(value)), Some(ty)) => self.convert(value, ty, span),
+            (Ok(value) | Err(Unwind::Return(value)), None) => Ok(value),
+            (result, _) => result,
+        }
+    }
+
+    fn block(&mut self, block: &'ast Block) -> Run<'ast, ()> {
+        self.scopes.push(HashMap::new());
+        self.declare_functions(&block.statements);
+
+        let result = block
+            .statements
+            .iter()
+            .try_for_each(|stmt| self.statement(stmt));
+
+        self.scopes.pop();
+        result
+    }
+
+    fn statement(&mut self, stmt: &'ast Statement) -> Run<'ast, ()> {
+        match &stmt.kind {
+            StatementKind::Let {
+                name, ty, initial, ..
+            } => {
+                let value = match (initial, ty) {
+                    (Some(initial), ty) => {
+                        let value = self.expression(initial)?;
+
+                        match ty {
+                            Some(ty) => self.convert(value, ty, initial.span)?,
+                            None => value,
+                        }
+                    }
+                    (None, Some(ty)) => Value::Uninitialized(ty),
+                    (None, None) => unreachable!("a variable without a value has a type"),
+                };
+
+                self.declare(name, value);
+            }
+            // Declared when the block around them started, or before
+            // running
+            StatementKind::Function(_) | StatementKind::Struct(_) | StatementKind::Enum(_) => {}
+            StatementKind::Trait(_) | StatementKind::Impl(_) => {}
+            // Resolved when the modules were loaded
+            StatementKind::Module(_) | StatementKind::Import(_) => {}
+            StatementKind::Return(value) => {
+                let value = match value {
+                    Some(value) => self.expression(value)?,
+                    None => Value::Unit,
+                };
+
+                return Err(Unwind::Return(value));
+            }
+            // Only a program that wasn't loaded with `crate::module::load`
+            // still has its `$if`s, which run like an `if` then
+            StatementKind::If {
+                condition,
+                then,
+                otherwise,
+            }
+            | StatementKind::ComptimeIf {
+                condition,
+                then,
+                otherwise,
+            } => {
+                if self.condition(condition)? {
+                    return self.block(then);
+                } else if let Some(otherwise) = otherwise {
+                    return self.statement(otherwise);
+                }
+            }
+            StatementKind::Block(block) => return self.block(block),
+            StatementKind::Expression(expression) => {
+                self.expression(expression)?;
+            }
+            StatementKind::Assign {
+                place,
+                operator,
+                value,
+            } => self.assign(place, *operator, value, stmt.span)?,
+        }
+
+        Ok(())
+    }
+
+    /// Write a value to a place, or apply a compound assignment to it
+    fn assign(
+        &mut self,
+        place: &'ast Expression,
+        operator: Option<BinaryOperator>,
+        value: &'ast Expression,
+        span: Span,
+    ) -> Run<'ast, ()> {
+        let at = value.span;
+        let value = self.expression(value)?;
+        let written = self.place(place)?;
+        let old = written.read();
+
+        let value = match operator {
+            Some(op) => self.operate(op, (old, place.span), (value, at), span)?,
+            // A float variable stays one when given a whole number, and a
+            // variable of an integer type holds a value of it
+            None => match (old, value) {
+                (Value::Uninitialized(ty), value) => self.convert(value, ty, at)?,
+                (Value::Float(_, ty), Value::Integer(num, _)) => Value::float(num as f64, ty),
+                (Value::Float(_, ty), Value::Float(num, None)) => Value::float(num, ty),
+                (Value::Integer(_, Some(ty)), Value::Integer(num, _)) => {
+                    self.of_type(num, ty, at)?
+                }
+                (_, value) => value,
+            },
+        };
+
+        written.write(value);
+        Ok(())
+    }
+
+    fn condition(&mut self, condition: &'ast Expression) -> Run<'ast, bool> {
+        match self.expression(condition)? {
+            Value::Bool(value) => Ok(value),
+            value => Err(self.mismatch("bool", &value, condition.span).into()),
+        }
+    }
+
+    fn mismatch(&self, expected: &str, found: &Value, span: Span) -> OrionError {
+        OrionError::MismatchedTypes {
+            expected: expected.to_string(),
+            found: found.kind().to_string(),
+            origin: None,
+            loc: self.loc(span),
+        }
+    }
+
+    /// The value of an expression giving a number, like an index, as a
+    /// float
+    /// A whole number, like an index or a length, read exactly
+    fn integer_value(&mut self, expression: &'ast Expression) -> Run<'ast, i128> {
+        match self.expression(expression)? {
+            Value::Integer(num, _) => Ok(num),
+            value => Err(self.mismatch("integer", &value, expression.span).into()),
+        }
+    }
+
+    fn expression(&mut self, expression: &'ast Expression) -> Run<'ast, Value<'ast>> {
+        let span = expression.span;
+        self.steps += 1;
+
+        if let Some(limit) = self.max_steps.filter(|&limit| self.steps > limit) {
+            return Err(OrionError::TooManySteps {
+                limit,
+                loc: self.loc(span),
+            }
+            .into());
+        }
+
+        let value = match &expression.kind {
+            ExpressionKind::Number(Number::Integer(num)) => Value::Integer(*num, None),
+            ExpressionKind::Number(Number::Float(num)) => Value::Float(*num, None),
+            ExpressionKind::Bool(value) => Value::Bool(*value),
+            ExpressionKind::String(string) => Value::Str(string),
+            ExpressionKind::Char(chr) => Value::Char(*chr),
+            ExpressionKind::Identifier(name) => match self.place(expression)?.read() {
+                Value::Uninitialized(_) => {
+                    return Err(OrionError::Uninitialized {
+                        name: name.clone(),
+                        missing: None,
+                        loc: self.loc(span),
+                    }
+                    .into())
+                }
+                value => value,
+            },
+            ExpressionKind::Field { .. } | ExpressionKind::Index { .. } => {
+                self.place(expression)?.read()
+            }
+            ExpressionKind::Unary { op, operand } => self.unary(*op, operand)?,
+            ExpressionKind::Binary { op, lhs, rhs } => self.binary(*op, lhs, rhs, span)?,
+            ExpressionKind::Cast { expression, ty } => {
+                let value = self.expression(expression)?;
+                cast(value, ty)
+            }
+            ExpressionKind::Call { callee, arguments } => {
+                if let ExpressionKind::Identifier(name) = &callee.kind {
+                    if is_builtin_function(name) && self.lookup(name, span).is_err() {
+                        return self.builtin(name, arguments, span);
+                    }
+                }
+
+                let count = arguments.len();
+
+                match self.expression(callee)? {
+                    Value::Function(function) => {
+                        let name = function.name.clone();
+                        self.arity(name, function.parameters.len(), count, span)?;
+
+                        let arguments = self.values(arguments)?;
+                        self.call(function, arguments, span)?
+                    }
+                    Value::Closure(closure, environment) => {
+                        let name = "closure".to_string();
+                        self.arity(name, closure.parameters.len(), count, span)?;
+
+                        let arguments = self.values(arguments)?;
+                        self.call_closure(closure, &environment, arguments, span)?
+                    }
+                    Value::Constructor(enumeration, variant) => {
+                        let name = format!("{enumeration}::{}", variant.name);
+                        self.arity(name, variant.fields.len(), count, span)?;
+
+                        let values = self.values(arguments)?;
+                        let values = self.converted(values, &variant.fields, span)?;
+                        Value::Variant(enumeration, &variant.name, values)
+                    }
+                    value => return Err(self.mismatch("function", &value, callee.span).into()),
+                }
+            }
+            ExpressionKind::MethodCall {
+                receiver,
+                method,
+                arguments,
+            } => self.method_call(receiver, method, arguments, span)?,
+            ExpressionKind::StructLiteral { name, fields } => self.struct_literal(name, fields)?,
+            ExpressionKind::Array(elements) => Value::Array(self.values(elements)?),
+            ExpressionKind::ArrayRepeat { value, length } => {
+                let value = self.expression(value)?;
+                let length = self.integer_value(length)?;
+
+                if length < 0 {
+                    return Err(OrionError::InvalidArrayLength {
+                        loc: self.loc(span),
+                    }
+                    .into());
+                }
+
+                let length = match usize::try_from(length) {
+                    Ok(length) if length <= MAX_ARRAY_LENGTH => length,
+                    _ => {
+                        return Err(OrionError::ArrayTooLong {
+                            length: length.to_string(),
+                            limit: MAX_ARRAY_LENGTH,
+                            loc: self.loc(span),
+                        }
+                        .into())
+                    }
+                };
+
+                Value::Array(vec![value; length])
+            }
+            ExpressionKind::Slice {
+                expression,
+                start,
+                end,
+            } => self.slice(expression, start.as_deref(), end.as_deref(), span)?,
+            ExpressionKind::Path(path) => {
+                let (variant, enumeration) = path.split_last().expect("paths aren't empty");
+
+                let found = self
+                    .enums
+                    .get(enumeration.join("::").as_str())
+                    .and_then(|def| {
+                        let variant = def.variants.iter().find(|other| other.name == *variant)?;
+                        Some((def.name.as_str(), variant))
+                    });
+
+                match found {
+                    Some((enumeration, variant)) if variant.fields.is_empty() => {
+                        Value::Variant(enumeration, &variant.name, vec![])
+                    }
+                    Some((enumeration, variant)) => Value::Constructor(enumeration, variant),
+                    None => {
+                        return Err(OrionError::UndefinedName {
+                            name: path.join("::"),
+                            loc: self.loc(span),
+                        }
+                        .into())
+                    }
+                }
+            }
+            ExpressionKind::Match { scrutinee, arms } => {
+                // Like fields, enums are matched through references
+                let value = self.deref(scrutinee)?.read();
+
+                let matched = arms.iter().find_map(|arm| {
+                    let mut bindings = HashMap::new();
+                    matches(&arm.pattern, &value, &mut bindings).then_some((arm, bindings))
+                });
+
+                let Some((arm, bindings)) = matched else {
+                    return Err(OrionError::NoMatchingArm {
+                        loc: self.loc(span),
+                    }
+                    .into());
+                };
+
+                self.scopes.push(bindings);
+                let value = self.expression(&arm.body);
+                self.scopes.pop();
+
+                value?
+            }
+            ExpressionKind::Block(block) => {
+                self.block(block)?;
+                Value::Unit
+            }
+            ExpressionKind::Closure(closure) => self.closure(closure),
+            // Likewise only left in a program whose `$` code wasn't
+            // evaluated when compiling it
+            ExpressionKind::Comptime(inner) => match self.expression(inner) {
+                Err(Unwind::Return(value)) => value,
+                value => value?,
+            },
+        };
+
+        Ok(value)
+    }
+
+    /// Make a struct, its fields given the number types they are declared
+    /// with
+    fn struct_literal(
+        &mut self,
+        name: &'ast str,
+        fields: &'ast [FieldInit],
+    ) -> Run<'ast, Value<'ast>> {
+        let declared = self.structs.get(name).copied();
+
+        let fields = fields
+            .iter()
+            .map(|field| {
+                let value = self.expression(&field.value)?;
+
+                let ty = declared.and_then(|declared| {
+                    let found = declared.fields.iter().find(|f| f.name == field.name);
+                    found.map(|declared| &declared.ty)
+                });
+
+                let value = match ty {
+                    Some(ty) => self.convert(value, ty, field.value.span)?,
+                    None => value,
+                };
+
+                Ok((field.name.as_str(), value))
+            })
+            .collect::<Run<_>>()?;
+
+        Ok(Value::Struct(name, fields))
+    }
+
+    /// Slice an array or a slice, checking the range is in bounds
+    fn slice(
+        &mut self,
+        expression: &'ast Expression,
+        start: Option<&'ast Expression>,
+        end: Option<&'ast Expression>,
+        span: Span,
+    ) -> Run<'ast, Value<'ast>> {
+        let (place, offset, length) = self.elements(expression)?;
+
+        let start = match start {
+            Some(start) => self.integer_value(start)?,
+            None => 0,
+        };
+
+        let end = match end {
+            Some(end) => self.integer_value(end)?,
+            None => length as i128,
+        };
+
+        match (usize::try_from(start), usize::try_from(end)) {
+            (Ok(start), Ok(end)) if start <= end && end <= length => {
+                Ok(Value::Slice(place, offset + start, offset + end))
+            }
+            _ => Err(OrionError::SliceOutOfBounds {
+                start: start.to_string(),
+                end: end.to_string(),
+                length,
+                loc: self.loc(span),
+            }
+            .into()),
+        }
+    }
+
+    /// Make a closure, capturing the variables of the functions it is in
+    /// that it uses. The variables of the top level are reached without
+    /// capturing them.
+    fn closure(&self, closure: &'ast Closure) -> Value<'ast> {
+        let is_local = |name: &str| self.scopes.iter().any(|scope| scope.contains_key(name));
+        let mut captured = vec![];
+
+        for capture in captures(closure, is_local) {
+            let (&name, slot) = self
+                .scopes
+                .iter()
+                .rev()
+                .find_map(|scope| scope.get_key_value(capture.name.as_str()))
+                .expect("captured variables are local");
+
+            let slot = match capture.mode {
+                CaptureMode::Value => slot.borrow().clone().slot(),
+                CaptureMode::Reference | CaptureMode::Mutable => slot.clone(),
+            };
+
+            captured.push((name, slot));
+        }
+
+        let environment = Environment {
+            module: self.module,
+            captures: captured,
+        };
+
+        Value::Closure(closure, Rc::new(environment))
+    }
+
+    /// Call a method, found by the struct or enum the receiver is, through
+    /// references. The receiver is passed as `self`.
+    fn method_call(
+        &mut self,
+        receiver: &'ast Expression,
+        method: &str,
+        arguments: &'ast [Expression],
+        span: Span,
+    ) -> Run<'ast, Value<'ast>> {
+        let value = self.deref(receiver)?.read();
+
+        let found = match &value {
+            Value::Struct(name, _) | Value::Variant(name, ..) => {
+                self.methods.get(&(name.to_string(), method))
+            }
+            _ => None,
+        };
+
+        let Some(&function) = found else {
+            return Err(OrionError::UnknownMethod {
+                ty: value.kind().to_string(),
+                method: method.to_string(),
+                loc: self.loc(span),
+            }
+            .into());
+        };
+
+        // `self` is the receiver, the arguments are for the other
+        // parameters
+        self.arity(
+            method.to_string(),
+            function.parameters.len() - 1,
+            arguments.len(),
+            span,
+        )?;
+
+        let mut values = vec![value];
+        values.extend(self.values(arguments)?);
+        self.call(function, values, span)
+    }
+
+    /// Check that a call at `span` gives as many arguments as the function
+    /// `callee` has parameters
+    fn arity(&self, callee: String, expected: usize, found: usize, span: Span) -> Run<'ast, ()> {
+        if expected == found {
+            return Ok(());
+        }
+
+        Err(OrionError::ArgumentCount {
+            callee,
+            expected,
+            found,
+            loc: self.loc(span),
+        }
+        .into())
+    }
+
+    /// Evaluate expressions in order, like the arguments of a call
+    fn values(&mut self, expressions: &'ast [Expression]) -> Run<'ast, Vec<Value<'ast>>> {
+        expressions
+            .iter()
+            .map(|expression| self.expression(expression))
+            .collect()
+    }
+
+    /// The place an expression refers to. Values that aren't in a variable,
+    /// like the result of a call, are put in a slot of their own.
+    fn place(&mut self, expression: &'ast Expression) -> Run<'ast, Place<'ast>> {
+        let place = match &expression.kind {
+            ExpressionKind::Identifier(name) => Place::new(self.lookup(name, expression.span)?),
+            ExpressionKind::Field {
+                expression: inner,
+                field,
+            } => {
+                let place = self.deref(inner)?;
+
+                let index = match place.read() {
+                    Value::Struct(_, fields) => fields.iter().position(|(name, _)| name == field),
+                    _ => None,
+                };
+
+                match index {
+                    Some(index) => place.step(index),
+                    None => {
+                        return Err(OrionError::UnknownField {
+                            ty: place.read().kind().to_string(),
+                            field: field.clone(),
+                            loc: self.loc(expression.span),
+                        }
+                        .into())
+                    }
+                }
+            }
+            ExpressionKind::Index {
+                expression: inner,
+                index,
+            } => {
+                let (place, offset, length) = self.elements(inner)?;
+                let index = self.integer_value(index)?;
+
+                // Every access is checked, even ones that look fine
+                match usize::try_from(index) {
+                    Ok(index) if index < length => place.step(offset + index),
+                    _ => {
+                        return Err(OrionError::IndexOutOfBounds {
+                            index: index.to_string(),
+                            length,
+                            loc: self.loc(expression.span),
+                        }
+                        .into())
+                    }
+                }
+            }
+            ExpressionKind::Unary {
+                op: UnaryOperator::Deref,
+                operand,
+            } => match self.expression(operand)? {
+                Value::Ref(place) => place,
+                value => return Err(self.mismatch("reference", &value, operand.span).into()),
+            },
+            _ => Place::new(self.expression(expression)?.slot()),
+        };
+
+        Ok(place)
+    }
+
+    /// The place of an expression, following any references
+    fn deref(&mut self, expression: &'ast Expression) -> Run<'ast, Place<'ast>> {
+        let mut place = self.place(expression)?;
+
+        while let Value::Ref(referenced) = place.read() {
+            place = referenced;
+        }
+
+        Ok(place)
+    }
+
+    /// The array an indexed or sliced expression refers to, with the range
+    /// of its elements that can be used: all of them for an array, a part
+    /// for a slice
+    fn elements(&mut self, expression: &'ast Expression) -> Run<'ast, (Place<'ast>, usize, usize)> {
+        let place = self.deref(expression)?;
+
+        match place.read() {
+            Value::Array(elements) => Ok((place, 0, elements.len())),
+            Value::Slice(array, start, end) => Ok((array, start, end - start)),
+            value => Err(OrionError::NotIndexable {
+                ty: value.kind().to_string(),
+                loc: self.loc(expression.span),
+            }
+            .into()),
+        }
+    }
+
+    /// Call a builtin function
+    fn builtin(
+        &mut self,
+        name: &str,
+        arguments: &'ast [Expression],
+        span: Span,
+    ) -> Run<'ast, Value<'ast>> {
+        match builtin_operator(name) {
+            Some(op) => self.overflowing(name, op, arguments, span),
+            None => self.len(arguments, span),
+        }
+    }
+
+    /// The built in `len`
+    fn len(&mut self, arguments: &'ast [Expression], span: Span) -> Run<'ast, Value<'ast>> {
+        let [argument] = arguments else {
+            return Err(OrionError::UndefinedName {
+                name: "len".to_string(),
+                loc: self.loc(span),
+            }
+            .into());
+        };
+
+        let length = match self.deref(argument)?.read() {
+            Value::Str(string) => string.len(),
+            Value::Array(elements) => elements.len(),
+            Value::Slice(_, start, end) => end - start,
+            value => {
+                return Err(OrionError::NoLength {
+                    ty: value.kind().to_string(),
+                    loc: self.loc(argument.span),
+                }
+                .into())
+            }
+        };
+
+        Ok(Value::Integer(length as i128, Some(Primitive::Usize)))
+    }
+
+    /// A builtin like `wrapping_add`, applying an operator to two integers
+    /// with overflow wrapping around or saturating
+    fn overflowing(
+        &mut self,
+        name: &str,
+        op: BinaryOperator,
+        arguments: &'ast [Expression],
+        span: Span,
+    ) -> Run<'ast, Value<'ast>> {
+        let [lhs, rhs] = arguments else {
+            return Err(OrionError::UndefinedName {
+                name: name.to_string(),
+                loc: self.loc(span),
+            }
+            .into());
+        };
+
+        let overflow = if name.starts_with("wrapping_") {
+            Overflow::Wrapping
+        } else {
+            Overflow::Saturating
+        };
+
+        match (self.expression(lhs)?, self.expression(rhs)?) {
+            (Value::Integer(a, a_ty), Value::Integer(b, b_ty)) => {
+                self.integer(op, (a, a_ty), (b, b_ty), overflow, span)
+            }
+            (Value::Integer(..), value) => Err(self.mismatch("integer", &value, rhs.span).into()),
+            (value, _) => Err(self.mismatch("integer", &value, lhs.span).into()),
+        }
+    }
+
+    fn unary(&mut self, op: UnaryOperator, operand: &'ast Expression) -> Run<'ast, Value<'ast>> {
+        let value = match op {
+            UnaryOperator::Neg => match self.expression(operand)? {
+                Value::Integer(num, ty) => {
+                    let (neg, wrapped) = num.overflowing_neg();
+                    let above = if wrapped {
+                        Some(true)
+                    } else {
+                        outside(neg, ty)
+                    };
+
+                    match fit(neg, above, ty, self.overflow) {
+                        Some(neg) => Value::Integer(neg, ty),
+                        None => {
+                            let operation = format!("-({num})");
+                            return Err(self.overflowed(operation, ty, operand.span).into());
+                        }
+                    }
+                }
+                Value::Float(num, ty) => Value::Float(-num, ty),
+                value => return Err(self.mismatch("number", &value, operand.span).into()),
+            },
+            UnaryOperator::Not => Value::Bool(!self.condition(operand)?),
+            UnaryOperator::BitNot => match self.expression(operand)? {
+                Value::Integer(num, ty) => Value::Integer(ty.map_or(!num, |ty| ty.wrap(!num)), ty),
+                Value::Float(num, ty) => Value::float(!(num as i64) as f64, ty),
+                value => return Err(self.mismatch("number", &value, operand.span).into()),
+            },
+            UnaryOperator::Ref | UnaryOperator::RefMut => Value::Ref(self.place(operand)?),
+            UnaryOperator::Deref => match self.expression(operand)? {
+                Value::Ref(place) => place.read(),
+                value => return Err(self.mismatch("reference", &value, operand.span).into()),
+            },
+            UnaryOperator::PreIncrement
+            | UnaryOperator::PreDecrement
+            | UnaryOperator::PostIncrement
+            | UnaryOperator::PostDecrement => {
+                let place = self.place(operand)?;
+                let step = match op {
+                    UnaryOperator::PreIncrement | UnaryOperator::PostIncrement => {
+                        BinaryOperator::Add
+                    }
+                    _ => BinaryOperator::Sub,
+                };
+
+                let (old, new) = match place.read() {
+                    Value::Integer(num, ty) => {
+                        let one = (1, None);
+                        let new =
+                            self.integer(step, (num, ty), one, self.overflow, operand.span)?;
+                        (Value::Integer(num, ty), new)
+                    }
+                    Value::Float(num, ty) => (
+                        Value::Float(num, ty),
+                        Value::float(arithmetic(step, num, 1.0), ty),
+                    ),
+                    value => return Err(self.mismatch("number", &value, operand.span).into()),
+                };
+
+                place.write(new.clone());
+
+                if op.is_postfix() {
+                    old
+                } else {
+                    new
+                }
+            }
+        };
+
+        Ok(value)
+    }
+
+    fn binary(
+        &mut self,
+        op: BinaryOperator,
+        lhs: &'ast Expression,
+        rhs: &'ast Expression,
+        span: Span,
+    ) -> Run<'ast, Value<'ast>> {
+        let lhs_value = self.expression(lhs)?;
+        let rhs_value = self.expression(rhs)?;
+
+        self.operate(op, (lhs_value, lhs.span), (rhs_value, rhs.span), span)
+    }
+
+    /// Apply a binary operator to the values of its operands, given with
+    /// where they are
+    fn operate(
+        &self,
+        op: BinaryOperator,
+        (lhs_value, lhs): (Value<'ast>, Span),
+        (rhs_value, rhs): (Value<'ast>, Span),
+        span: Span,
+    ) -> Run<'ast, Value<'ast>> {
+        let value = match op {
+            BinaryOperator::Eq => Value::Bool(lhs_value == rhs_value),
+            BinaryOperator::NotEq => Value::Bool(lhs_value != rhs_value),
+            BinaryOperator::Less
+            | BinaryOperator::LessEq
+            | BinaryOperator::Greater
+            | BinaryOperator::GreaterEq => {
+                let ordering = match (&lhs_value, &rhs_value) {
+                    (Value::Integer(a, _), Value::Integer(b, _)) => Some(a.cmp(b)),
+                    (
+                        Value::Integer(..) | Value::Float(..),
+                        Value::Integer(..) | Value::Float(..),
+                    ) => float(&lhs_value).partial_cmp(&float(&rhs_value)),
+                    (Value::Char(a), Value::Char(b)) => Some(a.cmp(b)),
+                    _ => return Err(self.mismatch("number", &lhs_value, lhs).into()),
+                };
+
+                let Some(ordering) = ordering else {
+                    return Ok(Value::Bool(false));
+                };
+
+                Value::Bool(match op {
+                    BinaryOperator::Less => ordering.is_lt(),
+                    BinaryOperator::LessEq => ordering.is_le(),
+                    BinaryOperator::Greater => ordering.is_gt(),
+                    _ => ordering.is_ge(),
+                })
+            }
+            // The bitwise operators apply to bools too, without
+            // short-circuiting
+            BinaryOperator::BitAnd | BinaryOperator::BitOr | BinaryOperator::BitXor
+                if matches!((&lhs_value, &rhs_value), (Value::Bool(_), Value::Bool(_))) =>
+            {
+                let (Value::Bool(a), Value::Bool(b)) = (lhs_value, rhs_value) else {
+                    unreachable!("both are bools")
+                };
+
+                Value::Bool(match op {
+                    BinaryOperator::BitAnd => a & b,
+                    BinaryOperator::BitOr => a | b,
+                    _ => a ^ b,
+                })
+            }
+            _ => {
+                match (&lhs_value, &rhs_value) {
+                    (
+                        Value::Integer(..) | Value::Float(..),
+                        Value::Integer(..) | Value::Float(..),
+                    ) => {}
+                    (Value::Integer(..) | Value::Float(..), value) => {
+                        return Err(self.mismatch("number", value, rhs).into())
+                    }
+                    (value, _) => return Err(self.mismatch("number", value, lhs).into()),
+                }
+
+                if matches!(op, BinaryOperator::Div | BinaryOperator::Rem)
+                    && float(&rhs_value) == 0.0
+                {
+                    return Err(OrionError::DivisionByZero {
+                        loc: self.loc(span),
+                    }
+                    .into());
+                }
+
+                match (lhs_value, rhs_value) {
+                    (Value::Integer(a, a_ty), Value::Integer(b, b_ty)) => {
+                        self.integer(op, (a, a_ty), (b, b_ty), self.overflow, span)?
+                    }
+                    (a, b) => {
+                        let num = arithmetic(op, float(&a), float(&b));
+                        Value::float(num, float_type(&a, &b))
+                    }
+                }
+            }
+        };
+
+        Ok(value)
+    }
+
+    /// Apply an arithmetic or bitwise operator to whole numbers, which is
+    /// of the integer type of either, the one the other widens to when both
+    /// have one, and of the type of the left one for a shift. Division
+    /// rounds towards zero. A result that isn't a value of the type does
+    /// what `overflow` says, as does a shift by its width or more: checked
+    /// it is an error, saturating the result is clamped to the values of
+    /// the type, and wrapping the amount wraps around.
+    fn integer(
+        &self,
+        op: BinaryOperator,
+        (a, a_ty): (i128, Option<Primitive>),
+        (b, b_ty): (i128, Option<Primitive>),
+        overflow: Overflow,
+        span: Span,
+    ) -> Run<'ast, Value<'ast>> {
+        let operation = || format!("{a} {} {b}", op.symbol());
+
+        if let BinaryOperator::Shl | BinaryOperator::Shr = op {
+            let bits = a_ty.and_then(|ty| ty.bits()).unwrap_or(128) as i128;
+
+            if overflow == Overflow::Checked && !(0..bits).contains(&b) {
+                return Err(self.overflowed(operation(), a_ty, span).into());
+            }
+
+            if overflow == Overflow::Saturating {
+                return Ok(Value::Integer(saturating_shift(op, a, b, a_ty), a_ty));
+            }
+
+            let amount = b.rem_euclid(bits) as u32;
+            let num = match op {
+                BinaryOperator::Shl => a << amount,
+                _ => a >> amount,
+            };
+
+            return Ok(Value::Integer(a_ty.map_or(num, |ty| ty.wrap(num)), a_ty));
+        }
+
+        let ty = match (a_ty, b_ty) {
+            (Some(a), Some(b)) if a.widens_to(b) => Some(b),
+            (a, b) => a.or(b),
+        };
+
+        let (num, wrapped) = match op {
+            BinaryOperator::Add => a.overflowing_add(b),
+            BinaryOperator::Sub => a.overflowing_sub(b),
+            BinaryOperator::Mul => a.overflowing_mul(b),
+            BinaryOperator::Div => a.overflowing_div(b),
+            BinaryOperator::Rem => (a.wrapping_rem(b), false),
+            BinaryOperator::BitAnd => (a & b, false),
+            BinaryOperator::BitOr => (a | b, false),
+            _ => (a ^ b, false),
+        };
+
+        // Past the values of an `i128`, the signs of the operands tell on
+        // which side the result is
+        let above = if wrapped {
+            Some(match op {
+                BinaryOperator::Add => b > 0,
+                BinaryOperator::Sub => b < 0,
+                BinaryOperator::Mul => (a < 0) == (b < 0),
+                _ => true,
+            })
+        } else {
+            outside(num, ty)
+        };
+
+        match fit(num, above, ty, overflow) {
+            Some(num) => Ok(Value::Integer(num, ty)),
+            None => Err(self.overflowed(operation(), ty, span).into()),
+        }
+    }
+
+    fn overflowed(&self, operation: String, ty: Option<Primitive>, span: Span) -> OrionError {
+        OrionError::IntegerOverflow {
+            operation,
+            ty: ty.map_or("number".to_string(), |ty| ty.to_string()),
+            loc: self.loc(span),
+        }
+    }
+
+    /// Give a value the number type it is declared with at `span`: a number
+    /// given for a float type becomes a value of it, rounded for an `f32`,
+    /// and a whole number given for an integer type a value of it, in
+    /// arrays too
+    fn convert(&self, value: Value<'ast>, ty: &Type, span: Span) -> Run<'ast, Value<'ast>> {
+        let value = match (value, &ty.kind) {
+            (Value::Integer(num, found), _) => match ty.name().and_then(Primitive::from_name) {
+                Some(primitive @ (Primitive::F32 | Primitive::F64)) => {
+                    Value::float(num as f64, Some(primitive))
+                }
+                Some(primitive) if primitive.is_integer() => self.of_type(num, primitive, span)?,
+                _ => Value::Integer(num, found),
+            },
+            (Value::Float(num, found), _) => match ty.name().and_then(Primitive::from_name) {
+                Some(primitive @ (Primitive::F32 | Primitive::F64)) => {
+                    Value::float(num, Some(primitive))
+                }
+                _ => Value::Float(num, found),
+            },
+            (Value::Array(elements), TypeKind::Array { element, .. }) => {
+                let elements = elements
+                    .into_iter()
+                    .map(|value| self.convert(value, element, span))
+                    .collect::<Run<_>>()?;
+
+                Value::Array(elements)
+            }
+            (value, _) => value,
+        };
+
+        Ok(value)
+    }
+
+    /// Make a whole number given for the integer type `ty` at `span` a
+    /// value of it
+    fn of_type(&self, num: i128, ty: Primitive, span: Span) -> Run<'ast, Value<'ast>> {
+        let ty = Some(ty);
+
+        match fit(num, outside(num, ty), ty, self.overflow) {
+            Some(num) => Ok(Value::Integer(num, ty)),
+            None => Err(self.overflowed(num.to_string(), ty, span).into()),
+        }
+    }
+
+    /// Give values the number types they are declared with, in order
+    fn converted(
+        &self,
+        values: Vec<Value<'ast>>,
+        types: &[Type],
+        span: Span,
+    ) -> Run<'ast, Vec<Value<'ast>>> {
+        values
+            .into_iter()
+            .zip(types)
+            .map(|(value, ty)| self.convert(value, ty, span))
+            .collect()
+    }
+}
+
+/// The value of a number as a float
+fn float(value: &Value) -> f64 {
+    match value {
+        Value::Integer(num, _) => *num as f64,
+        Value::Float(num, _) => *num,
+        _ => unreachable!("only numbers are floats"),
+    }
+}
+
+/// The float type of arithmetic on two numbers, one of them a float: the
+/// type of either, `f64` when one of them is
+fn float_type(a: &Value, b: &Value) -> Option<Primitive> {
+    let ty = |value: &Value| match value {
+        Value::Float(_, ty) => *ty,
+        _ => None,
+    };
+
+    match (ty(a), ty(b)) {
+        (Some(Primitive::F64), _) | (_, Some(Primitive::F64)) => Some(Primitive::F64),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Apply an arithmetic or bitwise operator to floats, the bitwise ones
+/// working on the bits of their whole part
+fn arithmetic(op: BinaryOperator, a: f64, b: f64) -> f64 {
+    let (x, y) = (a as i64, b as i64);
+
+    match op {
+        BinaryOperator::Add => a + b,
+        BinaryOperator::Sub => a - b,
+        BinaryOperator::Mul => a * b,
+        BinaryOperator::Div => a / b,
+        BinaryOperator::Rem => a % b,
+        BinaryOperator::BitAnd => (x & y) as f64,
+        BinaryOperator::BitOr => (x | y) as f64,
+        BinaryOperator::BitXor => (x ^ y) as f64,
+        BinaryOperator::Shl => x.wrapping_shl(y as u32) as f64,
+        _ => x.wrapping_shr(y as u32) as f64,
+    }
+}
+
+/// Whether a value matches a pattern, adding the names the pattern binds
+/// to `bindings` as it goes
+fn matches<'ast>(pattern: &'ast Pattern, value: &Value<'ast>, bindings: &mut Scope<'ast>) -> bool {
+    match (&pattern.kind, value) {
+        (PatternKind::Wildcard, _) => true,
+        (PatternKind::Binding(name), value) => {
+            bindings.insert(name, value.clone().slot());
+            true
+        }
+        (PatternKind::Number(Number::Integer(expected)), value) => {
+            *value == Value::Integer(*expected, None)
+        }
+        (PatternKind::Number(Number::Float(expected)), value) => {
+            *value == Value::Float(*expected, None)
+        }
+        (PatternKind::Bool(expected), Value::Bool(value)) => value == expected,
+        (PatternKind::Char(expected), Value::Char(chr)) => chr == expected,
+        (PatternKind::Variant { path, fields }, Value::Variant(enumeration, variant, values)) => {
+            let (name, path) = path.split_last().expect("paths aren't empty");
+
+            path.join("::") == *enumeration
+                && name == variant
+                && fields.len() == values.len()
+                && fields
+                    .iter()
+                    .zip(values)
+                    .all(|(field, value)| matches(field, value, bindings))
+        }
+        _ => false,
+    }
+}
+
+/// Finds the enum and struct declarations in a program
+struct TypeCollector<'a, 'ast>(
+    &'a mut HashMap<&'ast str, &'ast Enum>,
+    &'a mut HashMap<&'ast str, &'ast Struct>,
+);
+
+impl<'ast> Visitor<'ast> for TypeCollector<'_, 'ast> {
+    fn visit_statement(&mut self, stmt: &'ast Statement) {
+        match &stmt.kind {
+            StatementKind::Enum(enumeration) => {
+                self.0.insert(&enumeration.name, enumeration);
+            }
+            StatementKind::Struct(structure) => {
+                self.1.insert(&structure.name, structure);
+            }
+            _ => {}
+        }
+
+        walk_statement(self, stmt);
+    }
+}
+
+/// A function declared in the program. Two functions can be written the
+/// same way in different places, so they are told apart by the declaration
+/// they are, not by how they are written.
+#[derive(Clone, Copy)]
+struct Declared<'ast>(&'ast Function);
+
+impl PartialEq for Declared<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.0, other.0)
+    }
+}
+
+impl Eq for Declared<'_> {}
+
+impl Hash for Declared<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::ptr::hash(self.0, state);
+    }
+}
+
+/// Finds the functions declared in a module, nested ones included
+struct FunctionCollector<'a, 'ast>(&'a mut HashMap<Declared<'ast>, usize>, usize);
+
+impl<'ast> Visitor<'ast> for FunctionCollector<'_, 'ast> {
+    fn visit_statement(&mut self, stmt: &'ast Statement) {
+        if let StatementKind::Function(function) = &stmt.kind {
+            self.0.insert(Declared(function), self.1);
+        }
+
+        walk_statement(self, stmt);
+    }
+}
+
+/// Convert a value with `as`. Numbers become whole when converted to an
+/// integer type, wrapping around to a value of it.
+fn cast<'ast>(value: Value<'ast>, ty: &Type) -> Value<'ast> {
+    let Some(primitive) = ty.name().and_then(Primitive::from_name) else {
+        return value;
+    };
+
+    let whole = match (&value, primitive) {
+        (Value::Integer(num, _), Primitive::F32 | Primitive::F64) => {
+            return Value::float(*num as f64, Some(primitive))
+        }
+        (Value::Float(num, _), Primitive::F32 | Primitive::F64) => {
+            return Value::float(*num, Some(primitive))
+        }
+        (Value::Integer(num, _), Primitive::Char) => {
+            let chr = u32::try_from(*num).ok().and_then(char::from_u32);
+            return Value::Char(chr.unwrap_or(char::REPLACEMENT_CHARACTER));
+        }
+        (Value::Float(num, _), Primitive::Char) => {
+            let chr = char::from_u32(*num as u32);
+            return Value::Char(chr.unwrap_or(char::REPLACEMENT_CHARACTER));
+        }
+        (Value::Integer(num, _), _) => *num,
+        (Value::Float(num, _), _) => *num as i128,
+        (Value::Char(chr), _) => *chr as i128,
+        (Value::Bool(value), _) => *value as i128,
+        _ => return value,
+    };
+
+    if primitive.is_integer() {
+        Value::Integer(primitive.wrap(whole), Some(primitive))
+    } else {
+        value
+    }
+}
+
+/// Bring a whole number that isn't a value of the integer type `ty`, or
+/// of an `i128` without one, back to one of its values, `above` saying
+/// whether it is above them or below. `None` when overflow is checked.
+fn fit(num: i128, above: Option<bool>, ty: Option<Primitive>, overflow: Overflow) -> Option<i128> {
+    let Some(above) = above else {
+        return Some(num);
+    };
+
+    let (min, max) = range(ty);
+
+    match overflow {
+        Overflow::Checked => None,
+        Overflow::Wrapping => Some(ty.map_or(num, |ty| ty.wrap(num))),
+        Overflow::Saturating if above => Some(max),
+        Overflow::Saturating => Some(min),
+    }
+}
+
+/// Shift a whole number by any amount, a negative one shifting by nothing.
+/// Shifting left multiplies by a power of two, giving the largest or the
+/// smallest value of the integer type `ty` when the result is past them,
+/// and shifting right by its width or more only leaves the sign.
+fn saturating_shift(op: BinaryOperator, num: i128, amount: i128, ty: Option<Primitive>) -> i128 {
+    let amount = amount.clamp(0, 127) as u32;
+
+    let (shifted, above) = match op {
+        BinaryOperator::Shl if num == 0 => (0, None),
+        BinaryOperator::Shl => match num.checked_mul(1 << amount.min(126)) {
+            Some(shifted) if amount < 127 => (shifted, outside(shifted, ty)),
+            _ => (num, Some(num > 0)),
+        },
+        _ => (num >> amount, None),
+    };
+
+    fit(shifted, above, ty, Overflow::Saturating).unwrap_or(shifted)
+}
+
+/// The smallest and the largest value of the integer type `ty`, or of an
+/// `i128` without one
+fn range(ty: Option<Primitive>) -> (i128, i128) {
+    ty.and_then(|ty| ty.range())
+        .unwrap_or((i128::MIN, i128::MAX))
+}
+
+/// Whether a whole number is above the values of the integer type `ty` or
+/// below them, `None` when it is one of them
+fn outside(num: i128, ty: Option<Primitive>) -> Option<bool> {
+    let (min, max) = range(ty);
+    (num < min || num > max).then_some(num > max)
+}
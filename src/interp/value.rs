@@ -0,0 +1,276 @@
+//! The values a running program works with.
+//!
+//! Values live in slots, one for every variable. A reference is a path from
+//! a slot down through the fields and elements of the value in it, so
+//! writing through a reference changes the variable it refers to.
+use crate::parser::ast::{Closure, Function, Type, Variant};
+use crate::sema::ty::Primitive;
+use std::cell::RefCell;
+use std::fmt::{Display, Formatter, Result};
+use std::rc::Rc;
+
+/// Where a variable's value is stored
+pub type Slot<'ast> = Rc<RefCell<Value<'ast>>>;
+
+#[derive(Debug, Clone)]
+pub enum Value<'ast> {
+    /// A whole number, exact over the values of every integer type, and
+    /// the integer type it is a value of once it has one: the type of what
+    /// it is given for, the type it is cast to or the type of the number
+    /// it is added to. A number literal has none.
+    Integer(i128, Option<Primitive>),
+    /// A float, and the float type it is a value of once it has one, the
+    /// same way. An `f32` is held rounded to one.
+    Float(f64, Option<Primitive>),
+    Bool(bool),
+    Char(char),
+    /// A string literal, which is a reference to the text of the program
+    Str(&'ast str),
+    /// What functions returning nothing return
+    Unit,
+    /// The name of the struct and its fields in the order they were
+    /// initialized
+    Struct(&'ast str, Vec<(&'ast str, Value<'ast>)>),
+    Array(Vec<Value<'ast>>),
+    Ref(Place<'ast>),
+    /// The elements `start..end` of the array at a place
+    Slice(Place<'ast>, usize, usize),
+    Function(&'ast Function),
+    /// A closure with the variables it captured
+    Closure(&'ast Closure, Rc<Environment<'ast>>),
+    /// The name of the enum, the name of the variant and its fields
+    Variant(&'ast str, &'ast str, Vec<Value<'ast>>),
+    /// A variant with fields, which is called like a function to make a
+    /// value of its enum
+    Constructor(&'ast str, &'ast Variant),
+    /// What a variable declared without an initializer holds until it is
+    /// assigned, with the type it is declared with
+    Uninitialized(&'ast Type),
+}
+
+impl<'ast> Value<'ast> {
+    pub fn slot(self) -> Slot<'ast> {
+        Rc::new(RefCell::new(self))
+    }
+
+    /// A float of the float type `ty`, rounded to the nearest `f32` for one
+    pub fn float(num: f64, ty: Option<Primitive>) -> Self {
+        match ty {
+            Some(Primitive::F32) => Value::Float(num as f32 as f64, ty),
+            _ => Value::Float(num, ty),
+        }
+    }
+
+    /// What kind of value this is, for errors
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Value::Integer(..) | Value::Float(..) => "number",
+            Value::Bool(_) => "bool",
+            Value::Char(_) => "char",
+            Value::Str(_) => "&str",
+            Value::Unit => "()",
+            Value::Struct(..) => "struct",
+            Value::Array(_) => "array",
+            Value::Ref(_) => "reference",
+            Value::Slice(..) => "slice",
+            Value::Function(_) | Value::Closure(..) | Value::Constructor(..) => "function",
+            Value::Variant(..) => "enum",
+            Value::Uninitialized(_) => "uninitialized",
+        }
+    }
+}
+
+/// A whole number equals the float of the same value, like the numbers of
+/// `1 == 1.0`, and the whole numbers of other types of the same value
+impl PartialEq for Value<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Integer(a, _), Value::Integer(b, _)) => a == b,
+            (Value::Float(a, _), Value::Float(b, _)) => a == b,
+            (Value::Integer(a, _), Value::Float(b, _))
+            | (Value::Float(b, _), Value::Integer(a, _)) => *a as f64 == *b,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Char(a), Value::Char(b)) => a == b,
+            (Value::Str(a), Value::Str(b)) => a == b,
+            (Value::Unit, Value::Unit) => true,
+            (Value::Struct(a, a_fields), Value::Struct(b, b_fields)) => {
+                a == b && a_fields == b_fields
+            }
+            (Value::Array(a), Value::Array(b)) => a == b,
+            (Value::Ref(a), Value::Ref(b)) => a == b,
+            (Value::Slice(a, a_start, a_end), Value::Slice(b, b_start, b_end)) => {
+                a == b && a_start == b_start && a_end == b_end
+            }
+            (Value::Function(a), Value::Function(b)) => a == b,
+            (Value::Closure(a, a_environment), Value::Closure(b, b_environment)) => {
+                a == b && a_environment == b_environment
+            }
+            (Value::Variant(a, a_variant, a_fields), Value::Variant(b, b_variant, b_fields)) => {
+                a == b && a_variant == b_variant && a_fields == b_fields
+            }
+            (Value::Constructor(a, a_variant), Value::Constructor(b, b_variant)) => {
+                a == b && a_variant == b_variant
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The variables a closure captured when it was made. A variable captured
+/// by value has a slot of its own, one captured by reference shares the
+/// slot of the variable.
+pub struct Environment<'ast> {
+    /// The module the closure was made in
+    pub module: usize,
+    pub captures: Vec<(&'ast str, Slot<'ast>)>,
+}
+
+/// Two closures are equal if they were made at the same time, a variable
+/// captured by reference could hold the closure itself
+impl PartialEq for Environment<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl std::fmt::Debug for Environment<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let names: Vec<&str> = self.captures.iter().map(|(name, _)| *name).collect();
+        f.debug_struct("Environment")
+            .field("module", &self.module)
+            .field("captures", &names)
+            .finish()
+    }
+}
+
+/// A place in memory: a slot, and the position of a field or an element in
+/// it for every step
+#[derive(Debug, Clone)]
+pub struct Place<'ast> {
+    pub slot: Slot<'ast>,
+    pub path: Vec<usize>,
+}
+
+impl<'ast> Place<'ast> {
+    pub fn new(slot: Slot<'ast>) -> Self {
+        Self { slot, path: vec![] }
+    }
+
+    /// The place of a field or an element of the value at this place
+    pub fn step(&self, index: usize) -> Self {
+        let mut path = self.path.clone();
+        path.push(index);
+
+        Self {
+            slot: self.slot.clone(),
+            path,
+        }
+    }
+
+    pub fn read(&self) -> Value<'ast> {
+        let value = self.slot.borrow();
+        let mut value = &*value;
+
+        for &index in &self.path {
+            value = match value {
+                Value::Struct(_, fields) => &fields[index].1,
+                Value::Array(elements) => &elements[index],
+                _ => unreachable!("a path only leads through structs and arrays"),
+            };
+        }
+
+        value.clone()
+    }
+
+    pub fn write(&self, new: Value<'ast>) {
+        let mut value = self.slot.borrow_mut();
+        let mut value = &mut *value;
+
+        for &index in &self.path {
+            value = match value {
+                Value::Struct(_, fields) => &mut fields[index].1,
+                Value::Array(elements) => &mut elements[index],
+                _ => unreachable!("a path only leads through structs and arrays"),
+            };
+        }
+
+        *value = new;
+    }
+}
+
+/// Two references are equal if they refer to the same place
+impl PartialEq for Place<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.slot, &other.slot) && self.path == other.path
+    }
+}
+
+impl Display for Value<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Value::Integer(num, _) => write!(f, "{num}"),
+            // Written as the shortest number reading back as the same `f32`
+            Value::Float(num, Some(Primitive::F32)) => write!(f, "{}", *num as f32),
+            Value::Float(num, _) => write!(f, "{num}"),
+            Value::Bool(value) => write!(f, "{value}"),
+            Value::Char(chr) => write!(f, "{chr:?}"),
+            Value::Str(string) => write!(f, "{string:?}"),
+            Value::Unit => write!(f, "()"),
+            Value::Struct(name, fields) => {
+                write!(f, "{name} {{")?;
+
+                for (i, (field, value)) in fields.iter().enumerate() {
+                    let separator = if i > 0 { "," } else { "" };
+                    write!(f, "{separator} {field}: {value}")?;
+                }
+
+                write!(f, " }}")
+            }
+            Value::Array(elements) => write_elements(f, elements),
+            Value::Ref(place) => write!(f, "&{}", place.read()),
+            Value::Slice(place, start, end) => match place.read() {
+                Value::Array(elements) => {
+                    write!(f, "&")?;
+                    write_elements(f, &elements[*start..*end])
+                }
+                _ => unreachable!("only arrays are sliced"),
+            },
+            Value::Function(function) => write!(f, "func {}", function.name),
+            Value::Closure(..) => write!(f, "closure"),
+            Value::Variant(enumeration, variant, fields) => {
+                write!(f, "{enumeration}::{variant}")?;
+
+                if !fields.is_empty() {
+                    write!(f, "(")?;
+
+                    for (i, field) in fields.iter().enumerate() {
+                        let separator = if i > 0 { ", " } else { "" };
+                        write!(f, "{separator}{field}")?;
+                    }
+
+                    write!(f, ")")?;
+                }
+
+                Ok(())
+            }
+            Value::Constructor(enumeration, variant) => {
+                write!(f, "func {enumeration}::{}", variant.name)
+            }
+            Value::Uninitialized(_) => write!(f, "uninitialized"),
+        }
+    }
+}
+
+fn write_elements(f: &mut Formatter<'_>, elements: &[Value]) -> Result {
+    write!(f, "[")?;
+
+    for (i, element) in elements.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+
+        write!(f, "{element}")?;
+    }
+
+    write!(f, "]")
+}
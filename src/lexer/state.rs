@@ -1,5 +1,6 @@
 //! Lexer for the Orion compiler
 use crate::lexer::tokens::Location;
+use crate::lexer::tokens::Span;
 use crate::lexer::tokens::Token;
 use crate::lexer::tokens::TokenKind;
 use std::iter::Peekable;
@@ -18,6 +19,11 @@ pub struct Lexer<'a> {
     c: char,
     // the index of the previous character
     ci: usize,
+    // the line the previous character is on, and the index that line
+    // starts at. Kept up to date while scanning, since computing a location
+    // from the input before it would take time proportional to its length.
+    line: usize,
+    line_start: usize,
 
     // true if and only if lexing encountered an error. this hopefully will
     // be replaced by using Results, but unsure how to best handle that
@@ -33,6 +39,8 @@ impl<'a> Lexer<'a> {
             // null character
             c: '\x00',
             ci: 0,
+            line: 1,
+            line_start: 0,
             error: false,
         };
 
@@ -42,7 +50,14 @@ impl<'a> Lexer<'a> {
         lex
     }
 
+    /// The text being lexed
+    pub fn input(&self) -> &'a str {
+        self.input
+    }
+
     fn scan_char(&mut self) {
+        let newline = self.c == '\n';
+
         // Check if the iterator has something, if so, scan the next character
         if let Some((index, chr)) = self.iter.next() {
             // Update the index
@@ -56,21 +71,38 @@ impl<'a> Lexer<'a> {
             // And set the current character to a null character
             self.c = '\x00';
         }
+
+        if newline {
+            self.line += 1;
+            self.line_start = self.ci;
+        }
+    }
+
+    /// The location of the current character
+    fn location(&self) -> Location {
+        Location::new(self.line, self.ci - self.line_start + 1)
     }
 
     pub fn next_token(&mut self) -> Token<'a> {
         // Skip everything at the beginning that we don't need. Spaces, tabs,
         // etc..
         self.skip_nontokens();
+
+        // Everything consumed while scanning the token is part of its span
+        let start = self.ci;
+        let mut token = self.scan_token();
+        token.span = Span::new(start, self.ci);
+
+        token
+    }
+
+    fn scan_token(&mut self) -> Token<'a> {
         // Get the current location of the token
-        let loc = Location::from_input(&self.input[..self.ci]);
+        let loc = self.location();
 
         // If we are at the end, return EOF token
         if self.is_at_end() {
-            return Token {
-                kind: TokenKind::Eof,
-                loc,
-            };
+            return Token::new(TokenKind::Eof, loc);
         }
 
         // Otherwise, match against different symbols
@@ -78,6 +110,9 @@ impl<'a> Lexer<'a> {
             '"' => {
                 return self.scan_quote();
             }
+            '\'' => {
+                return self.scan_char_literal();
+            }
             '(' => TokenKind::LPar,
             ')' => TokenKind::RPar,
             '[' => TokenKind::LBracket,
@@ -88,16 +123,10 @@ impl<'a> Lexer<'a> {
                 if let Some((_, chr)) = self.iter.peek() {
                     if *chr == '=' {
                         self.skip_n(2);
-                        return Token {
-                            kind: TokenKind::UntypedAssignment,
-                            loc,
-                        };
+                        return Token::new(TokenKind::UntypedAssignment, loc);
                     } else if *chr == ':' {
                         self.skip_n(2);
-                        return Token {
-                            kind: TokenKind::ColonColon,
-                            loc,
-                        };
+                        return Token::new(TokenKind::ColonColon, loc);
                     }
                 }
 
@@ -110,16 +139,13 @@ impl<'a> Lexer<'a> {
                 if let Some((_, chr)) = self.iter.peek() {
                     if *chr == '>' {
                         self.skip_n(2);
-                        return Token {
-                            kind: TokenKind::RightArrow,
-                            loc,
-                        };
+                        return Token::new(TokenKind::RightArrow, loc);
                     } else if *chr == '-' {
                         self.skip_n(2);
-                        return Token {
-                            kind: TokenKind::Decrement,
-                            loc,
-                        };
+                        return Token::new(TokenKind::Decrement, loc);
+                    } else if *chr == '=' {
+                        self.skip_n(2);
+                        return Token::new(TokenKind::MinusEq, loc);
                     }
                 }
 
@@ -129,22 +155,19 @@ impl<'a> Lexer<'a> {
                 if let Some((_, chr)) = self.iter.peek() {
                     if *chr == '-' {
                         self.skip_n(2);
-                        return Token {
-                            kind: TokenKind::LeftArrow,
-                            loc,
-                        };
+                        return Token::new(TokenKind::LeftArrow, loc);
                     } else if *chr == '<' {
                         self.skip_n(2);
-                        return Token {
-                            kind: TokenKind::LesserLesser,
-                            loc,
-                        };
+
+                        if self.c == '=' {
+                            self.scan_char();
+                            return Token::new(TokenKind::LesserLesserEq, loc);
+                        }
+
+                        return Token::new(TokenKind::LesserLesser, loc);
                     } else if *chr == '=' {
                         self.skip_n(2);
-                        return Token {
-                            kind: TokenKind::LesserEq,
-                            loc,
-                        };
+                        return Token::new(TokenKind::LesserEq, loc);
                     }
                 }
 
@@ -154,16 +177,16 @@ impl<'a> Lexer<'a> {
                 if let Some((_, chr)) = self.iter.peek() {
                     if *chr == '>' {
                         self.skip_n(2);
-                        return Token {
-                            kind: TokenKind::GreaterGreater,
-                            loc,
-                        };
+
+                        if self.c == '=' {
+                            self.scan_char();
+                            return Token::new(TokenKind::GreaterGreaterEq, loc);
+                        }
+
+                        return Token::new(TokenKind::GreaterGreater, loc);
                     } else if *chr == '=' {
                         self.skip_n(2);
-                        return Token {
-                            kind: TokenKind::GreaterEq,
-                            loc,
-                        };
+                        return Token::new(TokenKind::GreaterEq, loc);
                     }
                 }
 
@@ -173,10 +196,7 @@ impl<'a> Lexer<'a> {
                 if let Some((_, chr)) = self.iter.peek() {
                     if *chr == '.' {
                         self.skip_n(2);
-                        return Token {
-                            kind: TokenKind::DotDot,
-                            loc,
-                        };
+                        return Token::new(TokenKind::DotDot, loc);
                     }
                 }
 
@@ -187,16 +207,16 @@ impl<'a> Lexer<'a> {
                 if let Some((_, chr)) = self.iter.peek() {
                     if *chr == '+' {
                         self.skip_n(2);
-                        return Token {
-                            kind: TokenKind::Increment,
-                            loc,
-                        };
+                        return Token::new(TokenKind::Increment, loc);
+                    } else if *chr == '=' {
+                        self.skip_n(2);
+                        return Token::new(TokenKind::PlusEq, loc);
                     }
                 }
 
                 TokenKind::Plus
             }
-            '*' => TokenKind::Star,
+            '*' => self.compound(TokenKind::Star, TokenKind::StarEq),
             '/' => {
                 if let Some((_, chr)) = self.iter.peek() {
                     if *chr == '/' {
@@ -205,23 +225,23 @@ impl<'a> Lexer<'a> {
                     } else if *chr == '*' {
                         self.skip_n(2);
                         return self.scan_multiline_comment();
+                    } else if *chr == '=' {
+                        self.skip_n(2);
+                        return Token::new(TokenKind::SlashEq, loc);
                     }
                 }
 
                 TokenKind::Slash
             }
-            '%' => TokenKind::Percent,
-            '&' => TokenKind::Ampersand,
-            '|' => TokenKind::Bar,
-            '^' => TokenKind::Hat,
+            '%' => self.compound(TokenKind::Percent, TokenKind::PercentEq),
+            '&' => self.compound(TokenKind::Ampersand, TokenKind::AmpersandEq),
+            '|' => self.compound(TokenKind::Bar, TokenKind::BarEq),
+            '^' => self.compound(TokenKind::Hat, TokenKind::HatEq),
             '!' => {
                 if let Some((_, chr)) = self.iter.peek() {
                     if *chr == '=' {
                         self.skip_n(2);
-                        return Token {
-                            kind: TokenKind::BangEq,
-                            loc,
-                        };
+                        return Token::new(TokenKind::BangEq, loc);
                     }
                 }
 
@@ -231,10 +251,10 @@ impl<'a> Lexer<'a> {
                 if let Some((_, chr)) = self.iter.peek() {
                     if *chr == '=' {
                         self.skip_n(2);
-                        return Token {
-                            kind: TokenKind::EqEq,
-                            loc,
-                        };
+                        return Token::new(TokenKind::EqEq, loc);
+                    } else if *chr == '>' {
+                        self.skip_n(2);
+                        return Token::new(TokenKind::FatArrow, loc);
                     }
                 }
 
@@ -246,7 +266,7 @@ impl<'a> Lexer<'a> {
         // If we got something, create a token at the position and the given
         // TokenKind
         if kind != TokenKind::Error {
-            let token = Token { kind, loc };
+            let token = Token::new(kind, loc);
 
             self.scan_char();
 
@@ -260,15 +280,18 @@ impl<'a> Lexer<'a> {
         } else if self.c.is_ascii_digit() {
             self.scan_number()
         // Otherwise, we have encountered an error and must return an error
-        // token
+        // token. The character is consumed, so lexing can carry on after it.
         } else {
-            self.error_token()
+            let token = self.error_token();
+            self.scan_char();
+
+            token
         }
     }
 
     fn scan_identifier(&mut self) -> Token<'a> {
         let startpos = self.ci;
-        let loc = Location::from_input(&self.input[..startpos]);
+        let loc = self.location();
 
         // Identifiers allow alphabetic characters, underscores, and numerics.
         // We can do this check without also checking that the first character
@@ -283,50 +306,112 @@ impl<'a> Lexer<'a> {
 
         // Checks for hard keywords. Mostly unimplemented for now.
         if input == "let" {
-            return Token {
-                kind: TokenKind::Let,
-                loc,
-            };
+            return Token::new(TokenKind::Let, loc);
         }
 
         if input == "return" {
-            return Token {
-                kind: TokenKind::Keyword(input),
-                loc,
-            };
+            return Token::new(TokenKind::Return, loc);
+        }
+
+        if input == "true" {
+            return Token::new(TokenKind::True, loc);
+        }
+
+        if input == "false" {
+            return Token::new(TokenKind::False, loc);
+        }
+
+        if input == "as" {
+            return Token::new(TokenKind::As, loc);
+        }
+
+        if input == "func" {
+            return Token::new(TokenKind::Func, loc);
+        }
+
+        if input == "if" {
+            return Token::new(TokenKind::If, loc);
+        }
+
+        if input == "else" {
+            return Token::new(TokenKind::Else, loc);
+        }
+
+        if input == "struct" {
+            return Token::new(TokenKind::Struct, loc);
+        }
+
+        if input == "enum" {
+            return Token::new(TokenKind::Enum, loc);
         }
 
-        Token {
-            kind: TokenKind::Identifier(input),
-            loc,
+        if input == "match" {
+            return Token::new(TokenKind::Match, loc);
         }
+
+        if input == "mod" {
+            return Token::new(TokenKind::Mod, loc);
+        }
+
+        if input == "import" {
+            return Token::new(TokenKind::Import, loc);
+        }
+
+        if input == "pub" {
+            return Token::new(TokenKind::Pub, loc);
+        }
+
+        if input == "trait" {
+            return Token::new(TokenKind::Trait, loc);
+        }
+
+        if input == "impl" {
+            return Token::new(TokenKind::Impl, loc);
+        }
+
+        if input == "for" {
+            return Token::new(TokenKind::For, loc);
+        }
+
+        if input == "mut" {
+            return Token::new(TokenKind::Mut, loc);
+        }
+
+        Token::new(TokenKind::Identifier(input), loc)
     }
 
     fn scan_number(&mut self) -> Token<'a> {
         // Get the starting point of the token for use in the Location
         let startpos = self.ci;
-        let loc = Location::from_input(&self.input[..startpos]);
+        let loc = self.location();
 
         // TOOD: Check and account for various bases (oct, dec, hex)
         while self.c.is_ascii_digit() || self.c == '_' || self.c == '.' {
+            // `1..2` is a range from 1 to 2, not a number
+            if self.c == '.' && matches!(self.iter.peek(), Some((_, '.'))) {
+                break;
+            }
+
             self.scan_char();
         }
 
-        Token {
-            kind: TokenKind::Number(&self.input[startpos..self.ci]),
-            loc,
-        }
+        Token::new(TokenKind::Number(&self.input[startpos..self.ci]), loc)
     }
 
     fn scan_quote(&mut self) -> Token<'a> {
         // Get the starting point of the token for use in the Location
         let startpos = self.ci;
-        let loc = Location::from_input(&self.input[..startpos]);
+        let loc = self.location();
 
         // consume leading quote
         self.scan_char();
 
         while !self.is_at_end() && self.c != '"' {
+            // An escaped quote doesn't end the string
+            if self.c == '\\' {
+                self.scan_char();
+            }
+
             self.scan_char();
         }
 
@@ -337,33 +422,55 @@ impl<'a> Lexer<'a> {
             // consume trailing quote
             self.scan_char();
 
-            Token {
-                kind: TokenKind::Quote(&self.input[startpos..self.ci]),
-                loc,
+            Token::new(TokenKind::Quote(&self.input[startpos..self.ci]), loc)
+        }
+    }
+
+    fn scan_char_literal(&mut self) -> Token<'a> {
+        // Get the starting point of the token for use in the Location
+        let startpos = self.ci;
+        let loc = self.location();
+
+        // consume leading quote
+        self.scan_char();
+
+        // A character literal doesn't span lines. Whether it holds exactly one
+        // character is up to the parser, which can give a better error.
+        while !self.is_at_end() && self.c != '\'' && self.c != '\n' {
+            if self.c == '\\' {
+                self.scan_char();
             }
+
+            self.scan_char();
+        }
+
+        if self.c != '\'' {
+            self.error_token()
+        } else {
+            // consume trailing quote
+            self.scan_char();
+
+            Token::new(TokenKind::Char(&self.input[startpos..self.ci]), loc)
         }
     }
 
     fn scan_single_line_comment(&mut self) -> Token<'a> {
         // Get the starting point of the token for use in the Location
         let startpos = self.ci;
-        let loc = Location::from_input(&self.input[..self.ci]);
+        let loc = self.location();
 
         // Scan until the end of the line
         while !self.is_at_end() && self.c != '\n' {
             self.scan_char();
         }
 
-        Token {
-            kind: TokenKind::Comment(&self.input[startpos..self.ci]),
-            loc,
-        }
+        Token::new(TokenKind::Comment(&self.input[startpos..self.ci]), loc)
     }
 
     fn scan_multiline_comment(&mut self) -> Token<'a> {
         // Get the starting point of the token for use in the Location
         let startpos = self.ci;
-        let loc = Location::from_input(&self.input[..startpos]);
+        let loc = self.location();
         // A variable to keep track whether the comment was closed
         let mut closed = false;
 
@@ -386,10 +493,10 @@ impl<'a> Lexer<'a> {
 
         // If it's closed, return the Token, otherwise, return an error
         if closed {
-            Token {
-                kind: TokenKind::Comment(&self.input[startpos..(self.ci - 2)]),
+            Token::new(
+                TokenKind::Comment(&self.input[startpos..(self.ci - 2)]),
                 loc,
-            }
+            )
         } else {
             self.error_token()
         }
@@ -400,12 +507,9 @@ impl<'a> Lexer<'a> {
         // Update to lexer to denote an error
         self.error = true;
         // Get the current location
-        let loc = Location::from_input(&self.input[..self.ci]);
+        let loc = self.location();
 
-        Token {
-            kind: TokenKind::Error,
-            loc,
-        }
+        Token::new(TokenKind::Error, loc)
     }
 
     /// Check if the lexer is at the end of it's input
@@ -421,10 +525,23 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    /// The operator `kind` of the current character, or its compound
+    /// assignment `assign` if an `=` follows, as in `*=`. The last character
+    /// of either is scanned past like that of any one-character token.
+    fn compound(&mut self, kind: TokenKind<'a>, assign: TokenKind<'a>) -> TokenKind<'a> {
+        match self.iter.peek() {
+            Some((_, '=')) => {
+                self.scan_char();
+                assign
+            }
+            _ => kind,
+        }
+    }
+
     /// When scanning through multi-charactered tokens, sometimes it's useful
     /// to skip two at once.
     fn skip_n(&mut self, many: usize) {
-        for i in 0..many {
+        for _ in 0..many {
             self.scan_char();
         }
     }
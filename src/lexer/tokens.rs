@@ -1,7 +1,7 @@
 //! Relevent structures and methods for the Tokens as part of lexical analysis
 use std::fmt::{Debug, Display, Formatter, Result};
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub enum TokenKind<'tok> {
     LPar,              // (
     RPar,              // )
@@ -16,6 +16,7 @@ pub enum TokenKind<'tok> {
     Dollar,            // $
     Comma,             // ,
     RightArrow,        // ->
+    FatArrow,          // =>
     LeftArrow,         // <-
     DotDot,            // ..
     Dot,               // .
@@ -28,10 +29,27 @@ pub enum TokenKind<'tok> {
     Keyword(&'tok str),
     Number(&'tok str),
     Quote(&'tok str),
+    Char(&'tok str),
 
     // Keywords
     Let,
     Return,
+    True,
+    False,
+    As,
+    Func,
+    If,
+    Else,
+    Struct,
+    Enum,
+    Match,
+    Mod,
+    Import,
+    Pub,
+    Trait,
+    Impl,
+    For,
+    Mut,
 
     // Operators
     Plus,           // +
@@ -54,17 +72,38 @@ pub enum TokenKind<'tok> {
     BangEq,         // !=
     Bang,           // !
 
+    // Compound assignments
+    PlusEq,           // +=
+    MinusEq,          // -=
+    StarEq,           // *=
+    SlashEq,          // /=
+    PercentEq,        // %=
+    AmpersandEq,      // &=
+    BarEq,            // |=
+    HatEq,            // ^=
+    LesserLesserEq,   // <<=
+    GreaterGreaterEq, // >>=
+
     Eof,
     Error,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Location(usize, usize);
 
 impl Location {
-    fn new(line: usize, col: usize) -> Self {
+    pub fn new(line: usize, col: usize) -> Self {
         Self(line, col)
     }
 
+    pub fn line(&self) -> usize {
+        self.0
+    }
+
+    pub fn column(&self) -> usize {
+        self.1
+    }
+
     pub fn from_input(input: &str) -> Self {
         let lines: usize = input.chars().filter(|&ch| ch == '\n').count();
 
@@ -73,7 +112,7 @@ impl Location {
             None => input.len(),
         };
 
-        Self(lines + 1, cols + 1)
+        Self::new(lines + 1, cols + 1)
     }
 }
 
@@ -83,14 +122,47 @@ impl Display for Location {
     }
 }
 
+/// A region of the input, as byte offsets. `start` is inclusive and `end`
+/// exclusive, so the text of a span is `&input[span.start..span.end]`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// The smallest span covering both `self` and `other`
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+#[derive(Clone)]
 pub struct Token<'tok> {
     pub kind: TokenKind<'tok>,
     pub loc: Location,
+    pub span: Span,
 }
 
 impl<'tok> Token<'tok> {
+    /// Create a token. The span is filled in by the lexer once the whole
+    /// token has been scanned.
     pub fn new(kind: TokenKind<'tok>, loc: Location) -> Self {
-        Self { kind, loc }
+        Self {
+            kind,
+            loc,
+            span: Span::default(),
+        }
     }
 
     /// The token's length
@@ -103,7 +175,13 @@ impl<'tok> Token<'tok> {
 
 impl Display for Token<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        match &self.kind {
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl Display for TokenKind<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
             TokenKind::LPar => write!(f, "("),
             TokenKind::RPar => write!(f, ")"),
             TokenKind::LBracket => write!(f, "["),
@@ -118,6 +196,7 @@ impl Display for Token<'_> {
             TokenKind::Dollar => write!(f, "$"),
             TokenKind::Comma => write!(f, ","),
             TokenKind::RightArrow => write!(f, "->"),
+            TokenKind::FatArrow => write!(f, "=>"),
             TokenKind::LeftArrow => write!(f, "<-"),
             TokenKind::DotDot => write!(f, ".."),
             TokenKind::Dot => write!(f, "."),
@@ -129,6 +208,26 @@ impl Display for Token<'_> {
             TokenKind::Keyword(str) => write!(f, "{str}"),
             TokenKind::Number(str) => write!(f, "{str}"),
             TokenKind::Quote(str) => write!(f, "{str}"),
+            TokenKind::Char(str) => write!(f, "{str}"),
+
+            TokenKind::Let => write!(f, "let"),
+            TokenKind::Return => write!(f, "return"),
+            TokenKind::True => write!(f, "true"),
+            TokenKind::False => write!(f, "false"),
+            TokenKind::As => write!(f, "as"),
+            TokenKind::Func => write!(f, "func"),
+            TokenKind::If => write!(f, "if"),
+            TokenKind::Else => write!(f, "else"),
+            TokenKind::Struct => write!(f, "struct"),
+            TokenKind::Enum => write!(f, "enum"),
+            TokenKind::Match => write!(f, "match"),
+            TokenKind::Mod => write!(f, "mod"),
+            TokenKind::Import => write!(f, "import"),
+            TokenKind::Pub => write!(f, "pub"),
+            TokenKind::Trait => write!(f, "trait"),
+            TokenKind::Impl => write!(f, "impl"),
+            TokenKind::For => write!(f, "for"),
+            TokenKind::Mut => write!(f, "mut"),
 
             TokenKind::Plus => write!(f, "+"),
             TokenKind::Increment => write!(f, "++"),
@@ -148,7 +247,20 @@ impl Display for Token<'_> {
             TokenKind::Lesser => write!(f, "<"),
             TokenKind::BangEq => write!(f, "!="),
             TokenKind::Bang => write!(f, "!"),
-            _ => write!(f, "not implemented"),
+
+            TokenKind::PlusEq => write!(f, "+="),
+            TokenKind::MinusEq => write!(f, "-="),
+            TokenKind::StarEq => write!(f, "*="),
+            TokenKind::SlashEq => write!(f, "/="),
+            TokenKind::PercentEq => write!(f, "%="),
+            TokenKind::AmpersandEq => write!(f, "&="),
+            TokenKind::BarEq => write!(f, "|="),
+            TokenKind::HatEq => write!(f, "^="),
+            TokenKind::LesserLesserEq => write!(f, "<<="),
+            TokenKind::GreaterGreaterEq => write!(f, ">>="),
+
+            TokenKind::Eof => write!(f, "end of file"),
+            TokenKind::Error => write!(f, "error"),
         }
     }
 }
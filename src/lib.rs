@@ -1,6 +1,12 @@
 pub mod error;
+#[cfg(any(test, feature = "fuzz"))]
+pub mod fuzz;
+pub mod interp;
 pub mod lexer;
+pub mod module;
 pub mod parser;
+pub mod sema;
+pub mod syntax;
 
 use crate::error::*;
 use crate::lexer::state::*;
@@ -1,7 +1,12 @@
-use clap::Parser as ClapParser;
+use clap::{Parser as ClapParser, ValueEnum};
 use colored::*;
 use orion::error::OrionError;
+use orion::interp::comptime::{self, Config};
+use orion::interp::{self, Interpreter, Overflow};
 use orion::lexer::state::Lexer;
+use orion::module;
+use orion::parser::ast::Program;
+use orion::parser::dump;
 use orion::parser::state::Parser;
 use std::path::PathBuf;
 
@@ -17,21 +22,92 @@ struct Args {
 
     #[arg(short, long)]
     verbose: bool,
+
+    /// Print an intermediate representation of the program
+    #[arg(long, value_enum)]
+    emit: Option<Emit>,
+    /// The format used by `--emit ast`
+    #[arg(long, value_enum, default_value_t = AstFormat::Tree)]
+    ast_format: AstFormat,
+    /// Run the program's `main` function with the interpreter
+    #[arg(short, long)]
+    run: bool,
+    /// A setting `$` code can use, `name=value` or `name` for a flag that is
+    /// set
+    #[arg(long = "cfg", value_name = "SETTING")]
+    settings: Vec<String>,
+    /// How many steps evaluating a `$` expression or `$if` condition may take
+    #[arg(long, default_value_t = comptime::MAX_STEPS)]
+    max_steps: usize,
+    /// How deeply calls may nest, in `$` code and when running
+    #[arg(long, default_value_t = interp::MAX_CALL_DEPTH)]
+    max_depth: usize,
+    /// What integer arithmetic does when it overflows, in `$` code and when
+    /// running. Checked unless another mode is given.
+    #[arg(long, value_enum)]
+    overflow: Option<OverflowMode>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Emit {
+    /// The parsed program, as written, before names are resolved and comptime
+    /// expressions evaluated
+    Ast,
+    /// The lossless concrete syntax tree, including comments and whitespace
+    Cst,
+    /// The instances of generic functions the program needs, one per line
+    Instances,
+    /// The closures lowered to functions, with what they capture, one per
+    /// line
+    Closures,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum OverflowMode {
+    /// Stop with an error pointing at the arithmetic
+    Checked,
+    /// Wrap around to the value with the same low bits
+    Wrapping,
+    /// Give the smallest or largest value of the type
+    Saturating,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum AstFormat {
+    /// Indented tree with the span of every node
+    Tree,
+    /// Compact S-expression, without spans
+    Sexpr,
+    /// JSON, for external tools
+    Json,
 }
 
 fn main() -> Result<(), OrionError> {
-    let contents;
     let args = Args::parse();
+
+    // Calls recurse through the interpreter, in `$` code too, so compiling
+    // runs on a thread with a stack for as many of them as may nest
+    let compiler = std::thread::Builder::new()
+        .stack_size(interp::stack_size(args.max_depth))
+        .spawn(move || compile(args))?;
+
+    compiler
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
+
+fn compile(args: Args) -> Result<(), OrionError> {
+    let contents;
     let prefix = "[Orion]".purple().bold();
 
     // Check if a path was supplied
-    match args.file {
+    let file = match args.file {
         Some(file) => {
             // File was passed
             match std::fs::read_to_string(&file) {
                 Ok(content) => {
                     contents = content;
-                    println!("{prefix} {file:?}");
+                    eprintln!("{prefix} {file:?}");
                 }
                 Err(error) => {
                     println!("{}", OrionError::from(error));
@@ -40,6 +116,8 @@ fn main() -> Result<(), OrionError> {
                     std::process::exit(-1);
                 }
             }
+
+            file
         }
         None => {
             // File was not passed. Eventually I'd like to implement a REPL, however
@@ -47,9 +125,10 @@ fn main() -> Result<(), OrionError> {
             // to lex/parse/etc..
             contents = std::fs::read_to_string("examples/main.ori").unwrap();
 
-            println!("{prefix} \"examples/main.ori\"");
+            eprintln!("{prefix} \"examples/main.ori\"");
+            PathBuf::from("examples/main.ori")
         }
-    }
+    };
 
     let mut lexer = Lexer::new(&contents);
 
@@ -57,10 +136,147 @@ fn main() -> Result<(), OrionError> {
         orion::print_tokens(&mut lexer, args.verbose)?;
     }
 
-    let mut parser = Parser::new(lexer);
-    let mut stmts = parser.parse();
+    let overflow = match args.overflow {
+        Some(OverflowMode::Checked) => Overflow::Checked,
+        Some(OverflowMode::Wrapping) => Overflow::Wrapping,
+        Some(OverflowMode::Saturating) => Overflow::Saturating,
+        None => Overflow::default(),
+    };
+
+    let mut config = Config {
+        max_steps: args.max_steps,
+        max_depth: args.max_depth,
+        overflow,
+        ..Config::default()
+    };
+
+    for setting in &args.settings {
+        config.set(setting);
+    }
+
+    // The entry file and the files of the modules it declares
+    let read = |file: &std::path::Path| std::fs::read_to_string(file);
+
+    let mut modules = match module::load(&file, contents.clone(), &config, read) {
+        Ok(modules) => modules,
+        Err(errors) => {
+            // The concrete syntax tree is made of code that doesn't parse too
+            if let Some(Emit::Cst) = args.emit {
+                print_cst(&contents);
+            }
+
+            for error in errors {
+                println!("{error}");
+            }
+
+            std::process::exit(-1);
+        }
+    };
+
+    // The trees are printed as they were written, before the names in them
+    // are resolved and their comptime expressions evaluated, for every
+    // module of the program, each after the file it is in
+    let trees = matches!(args.emit, Some(Emit::Ast | Emit::Cst));
+
+    if let Some(emit @ (Emit::Ast | Emit::Cst)) = args.emit {
+        for module in &modules {
+            if modules.len() > 1 {
+                eprintln!("{prefix} {:?}", module.file);
+            }
+
+            match emit {
+                Emit::Cst => print_cst(&module.source),
+                _ => print_ast(&module.program, args.ast_format),
+            }
+        }
+    }
+
+    let mut errors = module::resolve(&mut modules);
+    let mut instances = Vec::new();
+    let mut closures = Vec::new();
+
+    if errors.is_empty() {
+        errors = comptime::evaluate(&mut modules, &config);
+    }
+
+    // The errors resolving and evaluating the trees find are reported after
+    // them, whatever the semantic checks would find
+    if trees {
+        for error in &errors {
+            println!("{error}");
+        }
+
+        if !errors.is_empty() {
+            std::process::exit(-1);
+        }
+
+        return Ok(());
+    }
+
+    if errors.is_empty() {
+        let analysis = orion::sema::analyze_modules(&modules);
+
+        for warning in analysis.warnings {
+            println!("{warning}");
+        }
+
+        errors = analysis.errors;
+        instances = analysis.instances;
+        closures = analysis.closures;
+    }
+
+    if !errors.is_empty() {
+        for error in errors {
+            println!("{error}");
+        }
+
+        std::process::exit(-1);
+    }
+
+    if let Some(Emit::Instances) = args.emit {
+        for instance in instances {
+            println!("{instance}");
+        }
+    } else if let Some(Emit::Closures) = args.emit {
+        for closure in closures {
+            println!("{closure}");
+        }
+    } else if args.run {
+        let prefix = "[Orion - Interpreter]".purple().bold();
 
-    println!("{stmts:?}");
+        let mut interpreter = Interpreter::default()
+            .with_overflow(config.overflow)
+            .with_max_depth(config.max_depth);
+
+        match interpreter.run_modules(&modules) {
+            Ok(value) => println!("{prefix} main returned {value}"),
+            Err(error) => {
+                println!("{error}");
+                std::process::exit(-1);
+            }
+        }
+    } else {
+        let prefix = "[Orion - Parser]".purple().bold();
+        let statements: usize = modules.iter().map(|module| module.program.len()).sum();
+        println!("{prefix} parsed {statements} statements");
+    }
 
     Ok(())
 }
+
+/// Print the concrete syntax tree of a file. Errors parsing it are the ones
+/// loading it reports.
+fn print_cst(source: &str) {
+    let (root, _) = Parser::new(Lexer::new(source)).syntax();
+    print!("{root:?}");
+}
+
+fn print_ast(program: &Program, format: AstFormat) {
+    let node = dump::program_node(program);
+
+    match format {
+        AstFormat::Tree => print!("{}", dump::to_tree(&node)),
+        AstFormat::Sexpr => println!("{}", dump::to_sexpr(&node)),
+        AstFormat::Json => println!("{}", dump::to_json(&node)),
+    }
+}
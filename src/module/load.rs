@@ -0,0 +1,131 @@
+//! Reading the files of a program, following its `mod` declarations.
+use crate::error::OrionError;
+use crate::interp::comptime::{self, Config};
+use crate::lexer::state::Lexer;
+use crate::lexer::tokens::{Location, Span};
+use crate::module::{in_file, Module};
+use crate::parser::ast::StatementKind;
+use crate::parser::state::Parser;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Parse the entry file of a program, whose text is `source`, and the
+/// files of every module it leads to, which are read with `read`. The root
+/// module comes first, and every module comes before the modules it
+/// declares. The `$if`s of every file take their branches as it is parsed,
+/// so they can declare modules as well.
+///
+/// A file that can't be read or parsed is reported, and the modules it
+/// would have declared are left out. The errors of every file are returned
+/// if there are any.
+pub fn load(
+    entry: &Path,
+    source: String,
+    config: &Config,
+    read: impl FnMut(&Path) -> io::Result<String>,
+) -> Result<Vec<Module>, Vec<OrionError>> {
+    let mut loader = Loader {
+        directory: entry.parent().map(Path::to_path_buf).unwrap_or_default(),
+        modules: vec![],
+        errors: vec![],
+        config,
+        read,
+    };
+
+    loader.module(vec![], entry.to_path_buf(), source);
+
+    match loader.errors.is_empty() {
+        true => Ok(loader.modules),
+        false => Err(loader.errors),
+    }
+}
+
+struct Loader<'a, F> {
+    /// The directory of the entry file, where the file of every module is
+    /// looked for
+    directory: PathBuf,
+    modules: Vec<Module>,
+    errors: Vec<OrionError>,
+    config: &'a Config,
+    read: F,
+}
+
+impl<F: FnMut(&Path) -> io::Result<String>> Loader<'_, F> {
+    fn module(&mut self, path: Vec<String>, file: PathBuf, source: String) {
+        let mut program = match Parser::new(Lexer::new(&source)).parse() {
+            Ok(program) => program,
+            Err(error) => {
+                self.errors.push(in_file(&file, error));
+                return;
+            }
+        };
+
+        let errors = comptime::select(&mut program, &source, self.config);
+        self.errors
+            .extend(errors.into_iter().map(|error| in_file(&file, error)));
+
+        let children: Vec<(String, Span)> = program
+            .iter()
+            .filter_map(|stmt| match &stmt.kind {
+                StatementKind::Module(module) => Some((module.name.clone(), stmt.span)),
+                _ => None,
+            })
+            .collect();
+
+        let index = self.modules.len();
+        self.modules.push(Module {
+            path,
+            file,
+            source,
+            program,
+        });
+
+        for (name, span) in children {
+            let parent = &self.modules[index];
+            let path = [parent.path.clone(), vec![name]].concat();
+            let file = self.file(&path);
+            let loc = Location::from_input(&parent.source[..span.start]);
+
+            // Declared twice, which is reported once the names are resolved
+            if self.modules.iter().any(|module| module.path == path) {
+                continue;
+            }
+
+            // Only the parent of a module declares it, so the one file that
+            // can be reached again is the entry file, by `mod main;` in
+            // `main.ori`
+            if file == self.modules[0].file {
+                let error = OrionError::ModuleCycle {
+                    modules: vec![parent.name(), parent.name()],
+                    loc,
+                };
+
+                self.errors.push(parent.in_file(error));
+                continue;
+            }
+
+            match (self.read)(&file) {
+                Ok(source) => self.module(path, file, source),
+                Err(_) => {
+                    let error = OrionError::ModuleNotFound {
+                        module: path.join("::"),
+                        file: file.display().to_string(),
+                        loc,
+                    };
+
+                    let error = self.modules[index].in_file(error);
+                    self.errors.push(error);
+                }
+            }
+        }
+    }
+
+    /// The file of the module with the given path
+    fn file(&self, path: &[String]) -> PathBuf {
+        let mut file = self.directory.clone();
+        file.extend(path);
+        file.set_extension("ori");
+
+        file
+    }
+}
@@ -0,0 +1,76 @@
+//! Programs split across files.
+//!
+//! A program starts at its entry file, which holds the root module. `mod
+//! name;` declares a module whose items are in `name.ori`: next to the
+//! entry file for a module declared by the root, and in a directory named
+//! after the parent module otherwise, so `mod shapes;` in `math.ori` is
+//! `math/shapes.ori`. [`load()`] reads and parses every file of a program and
+//! [`resolve()`] finds what the names and paths in each of them refer to.
+//!
+//! Once resolved, the items of a module other than the root are renamed to
+//! their full path, like `math::add`, and every use of them refers to them
+//! by that name. The modules can then be checked and run side by side, as
+//! if they were a single program.
+pub mod load;
+pub mod resolve;
+
+pub use load::load;
+pub use resolve::resolve;
+
+use crate::error::OrionError;
+use crate::parser::ast::Program;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub struct Module {
+    /// The names of the modules leading to this one from the root, ending
+    /// with its own name. Empty for the root module.
+    pub path: Vec<String>,
+    /// The file the module was read from
+    pub file: PathBuf,
+    pub source: String,
+    pub program: Program,
+}
+
+impl Module {
+    pub fn is_root(&self) -> bool {
+        self.path.is_empty()
+    }
+
+    /// The module as it is named in errors, its path like `math::shapes`.
+    /// The root module is named after its file, `main` for `main.ori`.
+    pub fn name(&self) -> String {
+        match self.is_root() {
+            true => self
+                .file
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            false => self.path.join("::"),
+        }
+    }
+
+    /// The full name of an item declared in the module. The items of the
+    /// root module keep their names.
+    pub fn qualify(&self, name: &str) -> String {
+        match self.is_root() {
+            true => name.to_string(),
+            false => format!("{}::{name}", self.path.join("::")),
+        }
+    }
+
+    /// An error found in the module, saying which file it is in
+    pub fn in_file(&self, error: OrionError) -> OrionError {
+        in_file(&self.file, error)
+    }
+}
+
+pub(crate) fn in_file(file: &std::path::Path, error: OrionError) -> OrionError {
+    match error {
+        OrionError::InFile { .. } => error,
+        error => OrionError::InFile {
+            file: file.display().to_string(),
+            error: Box::new(error),
+        },
+    }
+}
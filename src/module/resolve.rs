@@ -0,0 +1,608 @@
+//! Finding what the names and paths of a program refer to, across its
+//! modules.
+//!
+//! The items of a module are its functions, structs, enums, traits and
+//! top-level variables, and the modules it declares. An item is private to its
+//! module unless it is declared `pub`: only the module and the modules
+//! inside it can use it. `import` makes an item usable by its name, the
+//! path after it starting from the root module. Other paths start from what
+//! a module can name by itself, its items and what it imports.
+//!
+//! A module whose top-level variables are initialized with the items of
+//! another one depends on it, as the other module's variables may need to
+//! be set first. Modules are ordered after the modules they depend on, so
+//! their initializers can't depend on each other in a cycle. The functions
+//! of modules can call each other freely.
+use crate::error::OrionError;
+use crate::lexer::tokens::Span;
+use crate::module::Module;
+use crate::parser::ast::*;
+use crate::parser::visit::{
+    walk_block_mut, walk_expression_mut, walk_statement_mut, walk_type_mut, VisitorMut,
+};
+use crate::sema::Diagnostics;
+use std::collections::{HashMap, HashSet};
+
+/// Resolve the names and paths of every module, renaming the items of the
+/// modules other than the root to their full path, and order the modules
+/// after the modules they depend on. Returns the errors found, with the
+/// file they were found in.
+pub fn resolve(modules: &mut Vec<Module>) -> Vec<OrionError> {
+    let mut errors = vec![];
+    let mut namespace = Namespace::new(modules, &mut errors);
+
+    for (index, module) in modules.iter_mut().enumerate() {
+        let Module {
+            source, program, ..
+        } = module;
+        let mut diagnostics = Diagnostics::new(source);
+
+        let mut renamer = Renamer {
+            namespace: &mut namespace,
+            module: index,
+            locals: vec![],
+            generics: vec![],
+            initializer: false,
+            diagnostics: &mut diagnostics,
+        };
+
+        renamer.visit_program_mut(program);
+
+        let found = diagnostics.into_errors();
+        errors.extend(found.into_iter().map(|error| module.in_file(error)));
+    }
+
+    let order = namespace.order(modules, &mut errors);
+    let mut unordered: Vec<Option<Module>> = modules.drain(..).map(Some).collect();
+    modules.extend(
+        order
+            .into_iter()
+            .filter_map(|index| unordered[index].take()),
+    );
+
+    errors
+}
+
+/// What a name refers to
+#[derive(Debug, Clone)]
+enum Target {
+    Module(usize),
+    /// A function, struct, enum, trait or top-level variable, by the module
+    /// it is declared in and its full name
+    Item(usize, String),
+}
+
+struct Entry {
+    target: Target,
+    public: bool,
+}
+
+/// The items and imports of every module
+struct Namespace {
+    paths: Vec<Vec<String>>,
+    names: Vec<String>,
+    items: Vec<HashMap<String, Entry>>,
+    imports: Vec<HashMap<String, Target>>,
+    /// The modules every module depends on, with where its initializers
+    /// first use them
+    dependencies: Vec<Vec<(usize, Span)>>,
+}
+
+impl Namespace {
+    fn new(modules: &[Module], errors: &mut Vec<OrionError>) -> Self {
+        let mut namespace = Self {
+            paths: modules.iter().map(|module| module.path.clone()).collect(),
+            names: modules.iter().map(Module::name).collect(),
+            items: vec![],
+            imports: vec![],
+            dependencies: vec![vec![]; modules.len()],
+        };
+
+        for (index, module) in modules.iter().enumerate() {
+            let items = namespace.items(index, module, errors);
+            namespace.items.push(items);
+        }
+
+        for (index, module) in modules.iter().enumerate() {
+            let imports = namespace.imports(index, module, errors);
+            namespace.imports.push(imports);
+        }
+
+        namespace
+    }
+
+    fn items(
+        &self,
+        index: usize,
+        module: &Module,
+        errors: &mut Vec<OrionError>,
+    ) -> HashMap<String, Entry> {
+        let diagnostics = Diagnostics::new(&module.source);
+        let mut items: HashMap<String, Entry> = HashMap::new();
+
+        for stmt in &module.program {
+            let (name, public) = match &stmt.kind {
+                StatementKind::Function(function) => (&function.name, function.public),
+                StatementKind::Struct(structure) => (&structure.name, structure.public),
+                StatementKind::Enum(enumeration) => (&enumeration.name, enumeration.public),
+                StatementKind::Trait(declared) => (&declared.name, declared.public),
+                StatementKind::Let { name, .. } => (name, false),
+                StatementKind::Module(declared) => (&declared.name, declared.public),
+                _ => continue,
+            };
+
+            let target = match &stmt.kind {
+                StatementKind::Module(declared) => {
+                    let path = [module.path.clone(), vec![declared.name.clone()]].concat();
+
+                    match self.paths.iter().position(|other| *other == path) {
+                        Some(index) => Target::Module(index),
+                        None => continue,
+                    }
+                }
+                _ => Target::Item(index, module.qualify(name)),
+            };
+
+            // The other passes report structs, enums and functions declared
+            // twice, and a variable can be declared again
+            if let Some(other) = items.get(name) {
+                if matches!(target, Target::Module(_)) || matches!(other.target, Target::Module(_))
+                {
+                    let error = OrionError::DuplicateDefinition {
+                        name: name.clone(),
+                        loc: diagnostics.loc(stmt.span),
+                    };
+
+                    errors.push(module.in_file(error));
+                }
+
+                continue;
+            }
+
+            items.insert(name.clone(), Entry { target, public });
+        }
+
+        items
+    }
+
+    fn imports(
+        &mut self,
+        index: usize,
+        module: &Module,
+        errors: &mut Vec<OrionError>,
+    ) -> HashMap<String, Target> {
+        let mut diagnostics = Diagnostics::new(&module.source);
+        let mut imports: HashMap<String, Target> = HashMap::new();
+
+        for stmt in &module.program {
+            let StatementKind::Import(path) = &stmt.kind else {
+                continue;
+            };
+
+            // Imports start from the root module, which is loaded first
+            let root = Target::Module(0);
+            let followed = self.follow(index, root, path, 0, stmt.span, &mut diagnostics);
+
+            let Some((target, used)) = followed else {
+                continue;
+            };
+
+            if used < path.len() {
+                diagnostics.report(OrionError::NotAModule {
+                    name: path[..used].join("::"),
+                    loc: diagnostics.loc(stmt.span),
+                });
+
+                continue;
+            }
+
+            let name = &path[path.len() - 1];
+
+            if self.items[index].contains_key(name) || imports.contains_key(name) {
+                diagnostics.report(OrionError::DuplicateDefinition {
+                    name: name.clone(),
+                    loc: diagnostics.loc(stmt.span),
+                });
+
+                continue;
+            }
+
+            imports.insert(name.clone(), target);
+        }
+
+        let found = diagnostics.into_errors();
+        errors.extend(found.into_iter().map(|error| module.in_file(error)));
+
+        imports
+    }
+
+    /// Follow a path from `from` through the modules it names, starting at
+    /// `start` with the first `used` names of the path already followed to
+    /// get there. Returns what the path names and how many of its names it
+    /// took. The names after an item aren't for this pass to make sense
+    /// of, like the variant after an enum.
+    fn follow(
+        &self,
+        from: usize,
+        start: Target,
+        path: &[String],
+        mut used: usize,
+        span: Span,
+        diagnostics: &mut Diagnostics,
+    ) -> Option<(Target, usize)> {
+        let mut target = start;
+
+        while let (Target::Module(module), Some(name)) = (&target, path.get(used)) {
+            let module = *module;
+
+            let Some(entry) = self.items[module].get(name) else {
+                diagnostics.report(OrionError::UnknownItem {
+                    module: self.names[module].clone(),
+                    item: name.clone(),
+                    loc: diagnostics.loc(span),
+                });
+
+                return None;
+            };
+
+            if !entry.public && !self.paths[from].starts_with(&self.paths[module]) {
+                diagnostics.report(OrionError::PrivateItem {
+                    module: self.names[module].clone(),
+                    item: name.clone(),
+                    loc: diagnostics.loc(span),
+                });
+
+                return None;
+            }
+
+            target = entry.target.clone();
+            used += 1;
+        }
+
+        Some((target, used))
+    }
+
+    /// What a path used in `module` starts with, if its first name is one
+    /// of the module's items or imports
+    fn start(&self, module: usize, name: &str) -> Option<Target> {
+        match self.items[module].get(name) {
+            Some(entry) => Some(entry.target.clone()),
+            None => self.imports[module].get(name).cloned(),
+        }
+    }
+
+    /// Record that `module` uses what `other` declares, at `span`
+    fn depend(&mut self, module: usize, other: usize, span: Span) {
+        let dependencies = &mut self.dependencies[module];
+
+        if other != module && dependencies.iter().all(|(known, _)| *known != other) {
+            dependencies.push((other, span));
+        }
+    }
+
+    /// The modules in an order where every module comes after the modules
+    /// it depends on, reporting the cycles that make that impossible
+    fn order(&self, modules: &[Module], errors: &mut Vec<OrionError>) -> Vec<usize> {
+        let mut order = vec![];
+        let mut visiting = vec![];
+        let mut visited = vec![false; modules.len()];
+
+        for index in 0..modules.len() {
+            self.visit(
+                index,
+                modules,
+                &mut visiting,
+                &mut visited,
+                &mut order,
+                errors,
+            );
+        }
+
+        order
+    }
+
+    fn visit(
+        &self,
+        index: usize,
+        modules: &[Module],
+        visiting: &mut Vec<usize>,
+        visited: &mut [bool],
+        order: &mut Vec<usize>,
+        errors: &mut Vec<OrionError>,
+    ) {
+        if visited[index] {
+            return;
+        }
+
+        visited[index] = true;
+        visiting.push(index);
+
+        for &(other, span) in &self.dependencies[index] {
+            if let Some(start) = visiting.iter().position(|&module| module == other) {
+                let mut cycle: Vec<String> = visiting[start..]
+                    .iter()
+                    .map(|&module| self.names[module].clone())
+                    .collect();
+                cycle.push(self.names[other].clone());
+
+                let diagnostics = Diagnostics::new(&modules[index].source);
+                let error = OrionError::ModuleCycle {
+                    modules: cycle,
+                    loc: diagnostics.loc(span),
+                };
+
+                errors.push(modules[index].in_file(error));
+                continue;
+            }
+
+            self.visit(other, modules, visiting, visited, order, errors);
+        }
+
+        visiting.pop();
+        order.push(index);
+    }
+}
+
+/// Renames the items of a module and the names and paths referring to
+/// items, to their full names
+struct Renamer<'a, 'd> {
+    namespace: &'a mut Namespace,
+    module: usize,
+    /// The names of the variables and functions declared in the function
+    /// being renamed, innermost scope last. They hide the items of the
+    /// module.
+    locals: Vec<HashSet<String>>,
+    /// The type parameters of the generic function or struct being
+    /// renamed, which hide the types of the module
+    generics: Vec<String>,
+    /// Whether the initializer of a top-level variable is being renamed,
+    /// the modules it uses being the ones the module depends on
+    initializer: bool,
+    diagnostics: &'a mut Diagnostics<'d>,
+}
+
+impl Renamer<'_, '_> {
+    fn is_local(&self, name: &str) -> bool {
+        self.locals.iter().any(|scope| scope.contains(name))
+    }
+
+    /// Record that the module depends on `other`, if a top-level variable
+    /// is initialized with its items
+    fn depend(&mut self, other: usize, span: Span) {
+        if self.initializer {
+            self.namespace.depend(self.module, other, span);
+        }
+    }
+
+    /// Rename a declaration at the top level of the module
+    fn declare(&self, name: &mut String) {
+        if !self.locals.is_empty() {
+            return;
+        }
+
+        if let Some(Target::Item(_, full)) = self.namespace.start(self.module, name) {
+            *name = full;
+        }
+    }
+
+    /// Replace the names of `path` naming an item by its full name. Paths
+    /// whose first name isn't known are left alone, for the other passes to
+    /// report.
+    fn path(&mut self, path: &mut Vec<String>, span: Span) {
+        let Some(start) = self.namespace.start(self.module, &path[0]) else {
+            return;
+        };
+
+        let followed = self
+            .namespace
+            .follow(self.module, start, path, 1, span, self.diagnostics);
+
+        if let Some((target, used)) = followed {
+            let (Target::Module(other) | Target::Item(other, _)) = target;
+            self.depend(other, span);
+
+            if let Target::Item(_, full) = target {
+                path.splice(..used, [full]);
+            }
+        }
+    }
+
+    fn pattern(&mut self, pattern: &mut Pattern) {
+        match &mut pattern.kind {
+            PatternKind::Binding(name) => {
+                if let Some(scope) = self.locals.last_mut() {
+                    scope.insert(name.clone());
+                }
+            }
+            PatternKind::Variant { path, fields } => {
+                self.path(path, pattern.span);
+
+                for field in fields {
+                    self.pattern(field);
+                }
+            }
+            PatternKind::Wildcard
+            | PatternKind::Number(_)
+            | PatternKind::Bool(_)
+            | PatternKind::Char(_) => {}
+        }
+    }
+}
+
+impl VisitorMut for Renamer<'_, '_> {
+    fn visit_statement_mut(&mut self, stmt: &mut Statement) {
+        match &mut stmt.kind {
+            StatementKind::Let {
+                name, ty, initial, ..
+            } => {
+                if let Some(ty) = ty {
+                    self.visit_type_mut(ty);
+                }
+
+                if let Some(initial) = initial {
+                    self.initializer = self.locals.is_empty();
+                    self.visit_expression_mut(initial);
+                    self.initializer = false;
+                }
+
+                match self.locals.last_mut() {
+                    Some(scope) => {
+                        scope.insert(name.clone());
+                    }
+                    None => self.declare(name),
+                }
+            }
+            StatementKind::Function(function) => {
+                // A function inside another one was declared when the
+                // block around it started
+                self.declare(&mut function.name);
+                let generics = std::mem::replace(&mut self.generics, function.generic_names());
+
+                for generic in &mut function.generics {
+                    for bound in &mut generic.bounds {
+                        self.path(bound, generic.span);
+                    }
+                }
+
+                for parameter in &mut function.parameters {
+                    self.visit_type_mut(&mut parameter.ty);
+                }
+
+                if let Some(result) = &mut function.result {
+                    self.visit_type_mut(result);
+                }
+
+                let parameters = function
+                    .parameters
+                    .iter()
+                    .map(|parameter| parameter.name.clone())
+                    .collect();
+
+                self.locals.push(parameters);
+                self.visit_block_mut(&mut function.body);
+                self.locals.pop();
+                self.generics = generics;
+            }
+            StatementKind::Struct(structure) => {
+                self.declare(&mut structure.name);
+                let generics = std::mem::replace(&mut self.generics, structure.generic_names());
+                walk_statement_mut(self, stmt);
+                self.generics = generics;
+            }
+            StatementKind::Enum(enumeration) => {
+                self.declare(&mut enumeration.name);
+                walk_statement_mut(self, stmt);
+            }
+            StatementKind::Trait(declared) => {
+                self.declare(&mut declared.name);
+                let generics = std::mem::replace(&mut self.generics, vec!["Self".to_string()]);
+                walk_statement_mut(self, stmt);
+                self.generics = generics;
+            }
+            StatementKind::Impl(implementation) => {
+                self.path(&mut implementation.trait_path, stmt.span);
+                self.visit_type_mut(&mut implementation.ty);
+
+                // The methods belong to the impl rather than the module, so
+                // they keep their names
+                self.locals.push(HashSet::new());
+
+                for method in &mut implementation.methods {
+                    self.visit_statement_mut(method);
+                }
+
+                self.locals.pop();
+            }
+            _ => walk_statement_mut(self, stmt),
+        }
+    }
+
+    fn visit_block_mut(&mut self, block: &mut Block) {
+        let functions = block
+            .statements
+            .iter()
+            .filter_map(|stmt| match &stmt.kind {
+                StatementKind::Function(function) => Some(function.name.clone()),
+                _ => None,
+            })
+            .collect();
+
+        self.locals.push(functions);
+        walk_block_mut(self, block);
+        self.locals.pop();
+    }
+
+    fn visit_expression_mut(&mut self, expr: &mut Expression) {
+        match &mut expr.kind {
+            ExpressionKind::Identifier(name) => {
+                if self.is_local(name) {
+                    return;
+                }
+
+                if let Some(Target::Item(module, full)) = self.namespace.start(self.module, name) {
+                    self.depend(module, expr.span);
+                    *name = full;
+                }
+            }
+            ExpressionKind::Path(path) => {
+                self.path(path, expr.span);
+
+                // A path to a function or a variable is now its full name
+                if path.len() == 1 {
+                    expr.kind = ExpressionKind::Identifier(path.remove(0));
+                }
+            }
+            ExpressionKind::StructLiteral { name, .. } => {
+                let mut path = vec![std::mem::take(name)];
+                self.path(&mut path, expr.span);
+                *name = path.remove(0);
+
+                walk_expression_mut(self, expr);
+            }
+            ExpressionKind::Match { scrutinee, arms } => {
+                self.visit_expression_mut(scrutinee);
+
+                for arm in arms {
+                    self.locals.push(HashSet::new());
+                    self.pattern(&mut arm.pattern);
+                    self.visit_expression_mut(&mut arm.body);
+                    self.locals.pop();
+                }
+            }
+            ExpressionKind::Closure(closure) => {
+                for ty in closure
+                    .parameters
+                    .iter_mut()
+                    .filter_map(|parameter| parameter.ty.as_mut())
+                {
+                    self.visit_type_mut(ty);
+                }
+
+                if let Some(result) = &mut closure.result {
+                    self.visit_type_mut(result);
+                }
+
+                let parameters = closure
+                    .parameters
+                    .iter()
+                    .map(|parameter| parameter.name.clone())
+                    .collect();
+
+                self.locals.push(parameters);
+                self.visit_expression_mut(&mut closure.body);
+                self.locals.pop();
+            }
+            _ => walk_expression_mut(self, expr),
+        }
+    }
+
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        if let TypeKind::Named(path) = &mut ty.kind {
+            if !matches!(path.segments.as_slice(), [name] if self.generics.contains(name)) {
+                self.path(&mut path.segments, ty.span);
+            }
+        }
+
+        walk_type_mut(self, ty);
+    }
+}
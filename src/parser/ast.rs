@@ -1,14 +1,663 @@
-#[derive(Debug)]
-pub enum Statement {
+pub use crate::lexer::tokens::Span;
+
+/// A parsed Orion source file, a list of top-level statements.
+pub type Program = Vec<Statement>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatementKind {
+    /// `let name : T = initial;`, or `let name := initial;` without a type.
+    /// Only a variable declared `let mut name` can be assigned to, but one
+    /// declared without an initializer, `let name : T;`, is assigned once
+    /// before it is read.
     Let {
         name: String,
-        initial: Expression,
+        mutable: bool,
+        ty: Option<Type>,
+        initial: Option<Expression>,
+    },
+    Function(Function),
+    /// `return value;`, the value being optional
+    Return(Option<Expression>),
+    /// `if condition { ... } else { ... }`. The `else` branch is either a
+    /// block or another `if`.
+    If {
+        condition: Expression,
+        then: Block,
+        otherwise: Option<Box<Statement>>,
+    },
+    /// `$if condition { ... } else $if ... else { ... }`, evaluated while
+    /// compiling. The statements of the branch taken replace it, the other
+    /// branches are dropped unchecked.
+    ComptimeIf {
+        condition: Expression,
+        then: Block,
+        otherwise: Option<Box<Statement>>,
+    },
+    Block(Block),
+    /// An expression evaluated for its effects, like a call: `print(x);`
+    Expression(Expression),
+    /// `place = value;`, writing to a variable or a field, or a compound
+    /// assignment like `place += value;` applying `operator` to both
+    Assign {
+        place: Expression,
+        operator: Option<BinaryOperator>,
+        value: Expression,
+    },
+    Struct(Struct),
+    Enum(Enum),
+    /// `mod name;`, declaring the module `name` whose items are in another
+    /// file
+    Module(Module),
+    /// `import path::to::name;`, making what the path names usable by the
+    /// last name of the path
+    Import(Vec<String>),
+    Trait(Trait),
+    Impl(Impl),
+}
+
+/// A module declared by `mod name;`. Its items are in the file `name.ori`
+/// next to the file of the root module, or in a directory named after its
+/// parent modules for a module inside another.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Module {
+    pub name: String,
+    /// Whether it was declared `pub`, which lets other modules use it
+    pub public: bool,
+}
+
+/// `struct Name { field : T, ... }`, or `struct Name<A, B> { ... }` for a
+/// generic struct whose fields can be of the types `A` and `B`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Struct {
+    pub name: String,
+    pub public: bool,
+    /// The type parameters, empty unless the struct is generic. They have
+    /// no bounds.
+    pub generics: Vec<Generic>,
+    pub fields: Vec<Field>,
+}
+
+impl Struct {
+    /// The names of the type parameters
+    pub fn generic_names(&self) -> Vec<String> {
+        self.generics
+            .iter()
+            .map(|generic| generic.name.clone())
+            .collect()
     }
 }
 
-#[derive(Debug)]
-pub enum Expression {
-    Number(f64),
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub name: String,
+    pub ty: Type,
+    pub span: Span,
+}
+
+/// `enum Name { Variant(T, U), Other, ... }`, a value that is one of the
+/// variants, each holding its own fields
+#[derive(Debug, Clone, PartialEq)]
+pub struct Enum {
+    pub name: String,
+    pub public: bool,
+    pub variants: Vec<Variant>,
+}
+
+/// A variant of an enum. A variant without fields is written without
+/// parentheses.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant {
+    pub name: String,
+    pub fields: Vec<Type>,
+    pub span: Span,
+}
+
+/// `func name(a : T, b : U) : R = { ... }`. Leaving out the result type,
+/// as in `func name() : = { ... }`, means the function returns nothing.
+/// A generic function names its type parameters after its name,
+/// `func max<T>(a : T, b : T) : T`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub public: bool,
+    /// The type parameters, empty unless the function is generic
+    pub generics: Vec<Generic>,
+    pub parameters: Vec<Parameter>,
+    pub result: Option<Type>,
+    pub body: Block,
+}
+
+impl Function {
+    /// The names of the type parameters
+    pub fn generic_names(&self) -> Vec<String> {
+        self.generics
+            .iter()
+            .map(|generic| generic.name.clone())
+            .collect()
+    }
+}
+
+/// A type parameter of a generic function, followed by the traits its type
+/// arguments have to implement if there are any: `T : Show + Eq`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Generic {
+    pub name: String,
+    /// The paths to the traits
+    pub bounds: Vec<Vec<String>>,
+    pub span: Span,
+}
+
+/// `trait Name { func method(self, a : T) : R; ... }`, the methods a type
+/// implementing the trait has
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trait {
+    pub name: String,
+    pub public: bool,
+    pub methods: Vec<Method>,
+}
+
+/// A method declared by a trait, without a body. Its first parameter is
+/// `self`, of the type implementing the trait, which the other types of the
+/// method can name `Self`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Method {
+    pub name: String,
+    pub parameters: Vec<Parameter>,
+    pub result: Option<Type>,
+    pub span: Span,
+}
+
+/// `impl Trait for Type { func method(self) : R = { ... } ... }`, the
+/// methods of the trait for a type. They are called like `value.method()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Impl {
+    /// The path to the trait
+    pub trait_path: Vec<String>,
+    pub ty: Type,
+    /// The methods, each a [`StatementKind::Function`] whose first
+    /// parameter is `self`
+    pub methods: Vec<Statement>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    pub name: String,
+    pub ty: Type,
+    pub span: Span,
+}
+
+/// Statements between braces
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub statements: Vec<Statement>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    pub kind: ExpressionKind,
+    pub span: Span,
+}
+
+impl Expression {
+    pub fn new(kind: ExpressionKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+
+    /// Whether the expression names a place in memory that can be written
+    /// to, like a variable, a field or an element of an array
+    pub fn is_place(&self) -> bool {
+        matches!(
+            self.kind,
+            ExpressionKind::Identifier(_)
+                | ExpressionKind::Field { .. }
+                | ExpressionKind::Index { .. }
+                | ExpressionKind::Unary {
+                    op: UnaryOperator::Deref,
+                    ..
+                }
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExpressionKind {
+    Number(Number),
+    Bool(bool),
+    /// A string literal, with its escape sequences resolved
+    String(String),
+    Char(char),
     Identifier(String),
-    Nil,
+    Unary {
+        op: UnaryOperator,
+        operand: Box<Expression>,
+    },
+    Binary {
+        op: BinaryOperator,
+        lhs: Box<Expression>,
+        rhs: Box<Expression>,
+    },
+    /// `expression as T`, converting a value to another type. This is the
+    /// only cast syntax: `(T)x` is not a cast, see the lexer README.
+    Cast {
+        expression: Box<Expression>,
+        ty: Type,
+    },
+    /// `callee(arguments)`
+    Call {
+        callee: Box<Expression>,
+        arguments: Vec<Expression>,
+    },
+    /// `expression.field`
+    Field {
+        expression: Box<Expression>,
+        field: String,
+    },
+    /// `receiver.method(arguments)`, calling a method of a trait the
+    /// receiver implements. A function stored in a field is called as
+    /// `(value.field)(arguments)` instead.
+    MethodCall {
+        receiver: Box<Expression>,
+        method: String,
+        arguments: Vec<Expression>,
+    },
+    /// `Name { field: value, ... }`. Inside the condition of an `if` a
+    /// struct literal has to be put in parentheses, as the `{` would
+    /// otherwise start the block.
+    StructLiteral {
+        name: String,
+        fields: Vec<FieldInit>,
+    },
+    /// `[a, b, c]`
+    Array(Vec<Expression>),
+    /// `[value; length]`, `length` copies of `value`
+    ArrayRepeat {
+        value: Box<Expression>,
+        length: Box<Expression>,
+    },
+    /// `expression[index]`
+    Index {
+        expression: Box<Expression>,
+        index: Box<Expression>,
+    },
+    /// `expression[start..end]`, a reference to the elements from `start`
+    /// up to but not including `end`. Either bound can be left out to slice
+    /// from the start or up to the end.
+    Slice {
+        expression: Box<Expression>,
+        start: Option<Box<Expression>>,
+        end: Option<Box<Expression>>,
+    },
+    /// Names separated by `::`, like the variant `Shape::Circle`
+    Path(Vec<String>),
+    /// `match scrutinee { pattern => value, ... }`, the value of the first
+    /// arm whose pattern matches. Like in an `if` condition, a struct
+    /// literal in the scrutinee has to be put in parentheses.
+    Match {
+        scrutinee: Box<Expression>,
+        arms: Vec<MatchArm>,
+    },
+    /// A block as the value of a `match` arm or a closure, which evaluates
+    /// to `()`
+    Block(Block),
+    /// `|a : T, b| -> R = value`, a function made where it is written
+    Closure(Closure),
+    /// `$(value)` or `${ ... return value; }`, evaluated while compiling and
+    /// replaced by a literal of its value
+    Comptime(Box<Expression>),
+}
+
+/// A closure, which can use the variables of the functions around it. Its
+/// body extends as far as an expression can, so a closure has to be put in
+/// parentheses to be called or used as an operand.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Closure {
+    pub parameters: Vec<ClosureParameter>,
+    /// The type of the result, `-> R`
+    pub result: Option<Box<Type>>,
+    /// An expression, or a block returning the result
+    pub body: Box<Expression>,
+}
+
+/// A parameter of a closure, whose type can be left out where the closure
+/// is given for a parameter of a function type
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClosureParameter {
+    pub name: String,
+    pub ty: Option<Type>,
+    pub span: Span,
+}
+
+/// `pattern => value`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub body: Expression,
+    pub span: Span,
+}
+
+/// The value of a number literal, kept exactly as written. A literal with
+/// a `.` is a float, any other a whole number, which is exact up to the
+/// largest `u64`. A literal has no type of its own, it takes the type of
+/// the number its context expects, see [`crate::sema::check`](mod@crate::sema::check).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Number {
+    Integer(i128),
+    Float(f64),
+}
+
+impl Number {
+    pub fn is_negative(&self) -> bool {
+        match self {
+            Number::Integer(num) => *num < 0,
+            Number::Float(num) => num.is_sign_negative(),
+        }
+    }
+}
+
+impl std::ops::Neg for Number {
+    type Output = Number;
+
+    fn neg(self) -> Number {
+        match self {
+            Number::Integer(num) => Number::Integer(-num),
+            Number::Float(num) => Number::Float(-num),
+        }
+    }
+}
+
+/// Written so that it reads back as the same literal: a whole float keeps
+/// a `.0`
+impl std::fmt::Display for Number {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Number::Integer(num) => write!(f, "{num}"),
+            Number::Float(num) if num.is_finite() && num.fract() == 0.0 => write!(f, "{num:.1}"),
+            Number::Float(num) => write!(f, "{num}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    pub kind: PatternKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatternKind {
+    /// `_`, matching anything
+    Wildcard,
+    /// A name, matching anything and binding it to the name
+    Binding(String),
+    /// A number literal, negative if written after a `-`
+    Number(Number),
+    Bool(bool),
+    Char(char),
+    /// `Shape::Rect(w, h)`, matching a variant and its fields
+    Variant {
+        path: Vec<String>,
+        fields: Vec<Pattern>,
+    },
+}
+
+/// `field: value` in a struct literal
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldInit {
+    pub name: String,
+    pub value: Expression,
+    pub span: Span,
+}
+
+/// How casts bind, tighter than the binary operators and looser than the
+/// prefix ones: `-x as u8 * y` means `((-x) as u8) * y`.
+pub const CAST_PRECEDENCE: u8 = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Type {
+    pub kind: TypeKind,
+    pub span: Span,
+}
+
+impl Type {
+    pub fn new(kind: TypeKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+
+    /// The name of a type referred to by a single name without generic
+    /// arguments, like `u32`
+    pub fn name(&self) -> Option<&str> {
+        match &self.kind {
+            TypeKind::Named(path) if path.segments.len() == 1 && path.arguments.is_empty() => {
+                Some(&path.segments[0])
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeKind {
+    /// A type referred to by its path, like `u32` or `shapes::Pair<u8, u8>`
+    Named(Path),
+    /// `&T`, a reference to a value of type `T`, or `&mut T`, one that can
+    /// be written through
+    Reference { inner: Box<Type>, mutable: bool },
+    /// `[T; N]`, `N` values of type `T`
+    Array {
+        element: Box<Type>,
+        length: Box<Expression>,
+    },
+    /// `[T]`, any number of values of type `T`
+    Slice(Box<Type>),
+    /// `(A, B)`. The empty tuple `()` is the type of functions returning
+    /// nothing, and a single element needs a trailing comma, `(A,)`, as
+    /// `(A)` is just `A`.
+    Tuple(Vec<Type>),
+    /// `func(A, B) -> R`, where `-> R` can be left out for functions
+    /// returning nothing
+    Function {
+        parameters: Vec<Type>,
+        result: Option<Box<Type>>,
+    },
+}
+
+/// Names separated by `::`, followed by generic arguments if the named type
+/// has parameters: `Pair<A, B>`. After `as` the arguments are written
+/// `Pair::<A, B>` instead, since `x as Pair < y` is a comparison.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    pub segments: Vec<String>,
+    pub arguments: Vec<Type>,
+}
+
+/// The types built into the language, which can't be used as values
+pub const PRIMITIVE_TYPES: &[&str] = &[
+    "u8", "u16", "u32", "u64", "usize", "i8", "i16", "i32", "i64", "isize", "f32", "f64", "bool",
+    "char", "str",
+];
+
+pub fn is_primitive_type(name: &str) -> bool {
+    PRIMITIVE_TYPES.contains(&name)
+}
+
+/// The functions built into the language. A function of the same name in
+/// reach takes the place of one.
+pub const BUILTIN_FUNCTIONS: &[&str] = &[
+    "len",
+    "wrapping_add",
+    "wrapping_sub",
+    "wrapping_mul",
+    "saturating_add",
+    "saturating_sub",
+    "saturating_mul",
+];
+
+pub fn is_builtin_function(name: &str) -> bool {
+    BUILTIN_FUNCTIONS.contains(&name)
+}
+
+/// The operator a builtin like `wrapping_add` applies to two integers.
+/// Its overflow wraps around or saturates as its name says, whatever the
+/// compiler is told to do with overflow otherwise.
+pub fn builtin_operator(name: &str) -> Option<BinaryOperator> {
+    let operator = name
+        .strip_prefix("wrapping_")
+        .or_else(|| name.strip_prefix("saturating_"))?;
+
+    match operator {
+        "add" => Some(BinaryOperator::Add),
+        "sub" => Some(BinaryOperator::Sub),
+        "mul" => Some(BinaryOperator::Mul),
+        _ => None,
+    }
+}
+
+/// Unary operators. The prefix operators bind tighter than any binary
+/// operator or cast, and the postfix ones tighter still, so `-x++` means
+/// `-(x++)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOperator {
+    /// `-x`, arithmetic negation of a number
+    Neg,
+    /// `!x`, logical negation of a boolean
+    Not,
+    /// `~x`, flips every bit of an integer
+    BitNot,
+    /// `&x`, a reference to `x`
+    Ref,
+    /// `&mut x`, a reference to `x` that can be written through, so `x` has
+    /// to be a place that can be written to
+    RefMut,
+    /// `*x`, the value the reference `x` refers to. Unlike other unary
+    /// expressions this is a place, so it can be written to.
+    Deref,
+    /// `++x`, adds one to the variable `x` and evaluates to its new value
+    PreIncrement,
+    /// `--x`, subtracts one from the variable `x` and evaluates to its new
+    /// value
+    PreDecrement,
+    /// `x++`, adds one to the variable `x` and evaluates to the value it had
+    /// before
+    PostIncrement,
+    /// `x--`, subtracts one from the variable `x` and evaluates to the value
+    /// it had before
+    PostDecrement,
+}
+
+impl UnaryOperator {
+    pub fn is_postfix(&self) -> bool {
+        matches!(
+            self,
+            UnaryOperator::PostIncrement | UnaryOperator::PostDecrement
+        )
+    }
+
+    /// Whether the operator writes to its operand, or lets it be written to
+    /// like `&mut`, which then has to be a place
+    pub fn is_mutating(&self) -> bool {
+        !matches!(
+            self,
+            UnaryOperator::Neg
+                | UnaryOperator::Not
+                | UnaryOperator::BitNot
+                | UnaryOperator::Ref
+                | UnaryOperator::Deref
+        )
+    }
+
+    /// How tightly the operator binds, on the same scale as
+    /// [`BinaryOperator::precedence`]
+    pub fn precedence(&self) -> u8 {
+        if self.is_postfix() {
+            10
+        } else {
+            9
+        }
+    }
+
+    /// The operator as it is written in source
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            UnaryOperator::Neg => "-",
+            UnaryOperator::Not => "!",
+            UnaryOperator::BitNot => "~",
+            UnaryOperator::Ref => "&",
+            UnaryOperator::RefMut => "&mut",
+            UnaryOperator::Deref => "*",
+            UnaryOperator::PreIncrement | UnaryOperator::PostIncrement => "++",
+            UnaryOperator::PreDecrement | UnaryOperator::PostDecrement => "--",
+        }
+    }
+}
+
+/// Binary operators, all of which are left associative.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOperator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+    Eq,
+    NotEq,
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+}
+
+impl BinaryOperator {
+    /// How tightly the operator binds, higher binds tighter. Unlike C, the
+    /// bitwise operators bind tighter than the comparisons, so
+    /// `a & b == c` means `(a & b) == c`.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            BinaryOperator::Eq
+            | BinaryOperator::NotEq
+            | BinaryOperator::Less
+            | BinaryOperator::LessEq
+            | BinaryOperator::Greater
+            | BinaryOperator::GreaterEq => 1,
+            BinaryOperator::BitOr => 2,
+            BinaryOperator::BitXor => 3,
+            BinaryOperator::BitAnd => 4,
+            BinaryOperator::Shl | BinaryOperator::Shr => 5,
+            BinaryOperator::Add | BinaryOperator::Sub => 6,
+            BinaryOperator::Mul | BinaryOperator::Div | BinaryOperator::Rem => 7,
+        }
+    }
+
+    /// The operator as it is written in source
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOperator::Add => "+",
+            BinaryOperator::Sub => "-",
+            BinaryOperator::Mul => "*",
+            BinaryOperator::Div => "/",
+            BinaryOperator::Rem => "%",
+            BinaryOperator::BitAnd => "&",
+            BinaryOperator::BitOr => "|",
+            BinaryOperator::BitXor => "^",
+            BinaryOperator::Shl => "<<",
+            BinaryOperator::Shr => ">>",
+            BinaryOperator::Eq => "==",
+            BinaryOperator::NotEq => "!=",
+            BinaryOperator::Less => "<",
+            BinaryOperator::LessEq => "<=",
+            BinaryOperator::Greater => ">",
+            BinaryOperator::GreaterEq => ">=",
+        }
+    }
 }
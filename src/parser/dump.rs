@@ -0,0 +1,602 @@
+//! Dumping the AST for humans and tools.
+//!
+//! The AST is first converted into a generic [`Node`] tree, a small
+//! serialization layer which knows nothing about any output format. The
+//! renderers then turn that tree into an indented tree view (with spans), a
+//! compact S-expression, or JSON. Adding a node type to the AST only means
+//! describing it once in this module.
+use crate::parser::ast::*;
+use std::fmt::Write;
+
+/// A generic AST node: its kind, where it came from, its scalar attributes
+/// and its child nodes, in order.
+#[derive(Debug)]
+pub struct Node {
+    pub kind: &'static str,
+    pub span: Option<Span>,
+    pub attributes: Vec<(&'static str, Value)>,
+    pub children: Vec<(&'static str, Child)>,
+}
+
+/// Scalar attribute values
+#[derive(Debug)]
+pub enum Value {
+    /// A name or an operator, printed bare where the format allows it
+    Symbol(String),
+    Number(Number),
+    Bool(bool),
+    /// Text from a string or character literal, always quoted
+    String(String),
+}
+
+#[derive(Debug)]
+pub enum Child {
+    Node(Node),
+    List(Vec<Node>),
+}
+
+impl Node {
+    fn new(kind: &'static str, span: Option<Span>) -> Self {
+        Self {
+            kind,
+            span,
+            attributes: vec![],
+            children: vec![],
+        }
+    }
+
+    fn attribute(mut self, name: &'static str, value: Value) -> Self {
+        self.attributes.push((name, value));
+        self
+    }
+
+    fn child(mut self, name: &'static str, node: Node) -> Self {
+        self.children.push((name, Child::Node(node)));
+        self
+    }
+
+    fn list(mut self, name: &'static str, nodes: Vec<Node>) -> Self {
+        self.children.push((name, Child::List(nodes)));
+        self
+    }
+
+    /// A child that may be missing, in which case it is left out entirely
+    fn optional(self, name: &'static str, node: Option<Node>) -> Self {
+        match node {
+            Some(node) => self.child(name, node),
+            None => self,
+        }
+    }
+}
+
+pub fn program_node(program: &Program) -> Node {
+    Node::new("program", None).list("statements", program.iter().map(statement_node).collect())
+}
+
+/// The type parameters of a generic struct, which have no bounds
+fn generics_nodes(generics: &[Generic]) -> Vec<Node> {
+    generics
+        .iter()
+        .map(|generic| {
+            Node::new("generic", Some(generic.span))
+                .attribute("name", Value::Symbol(generic.name.clone()))
+        })
+        .collect()
+}
+
+fn parameter_nodes(parameters: &[Parameter]) -> Vec<Node> {
+    parameters
+        .iter()
+        .map(|parameter| {
+            Node::new("parameter", Some(parameter.span))
+                .attribute("name", Value::Symbol(parameter.name.clone()))
+                .child("type", type_node(&parameter.ty))
+        })
+        .collect()
+}
+
+pub fn statement_node(stmt: &Statement) -> Node {
+    let span = Some(stmt.span);
+
+    match &stmt.kind {
+        StatementKind::Let {
+            name,
+            mutable,
+            ty,
+            initial,
+        } => Node::new("let", span)
+            .attribute("name", Value::Symbol(name.clone()))
+            .attribute("mutable", Value::Bool(*mutable))
+            .optional("type", ty.as_ref().map(type_node))
+            .optional("initial", initial.as_ref().map(expression_node)),
+        StatementKind::Function(function) => {
+            let generics = function
+                .generics
+                .iter()
+                .map(|generic| {
+                    let bounds = generic.bounds.iter().map(|bound| {
+                        Node::new("bound", None).attribute("path", Value::Symbol(bound.join("::")))
+                    });
+
+                    Node::new("generic", Some(generic.span))
+                        .attribute("name", Value::Symbol(generic.name.clone()))
+                        .list("bounds", bounds.collect())
+                })
+                .collect();
+
+            Node::new("function", span)
+                .attribute("name", Value::Symbol(function.name.clone()))
+                .attribute("public", Value::Bool(function.public))
+                .list("generics", generics)
+                .list("parameters", parameter_nodes(&function.parameters))
+                .optional("result", function.result.as_ref().map(type_node))
+                .child("body", block_node(&function.body))
+        }
+        StatementKind::Return(value) => {
+            Node::new("return", span).optional("value", value.as_ref().map(expression_node))
+        }
+        StatementKind::If {
+            condition,
+            then,
+            otherwise,
+        } => Node::new("if", span)
+            .child("condition", expression_node(condition))
+            .child("then", block_node(then))
+            .optional(
+                "otherwise",
+                otherwise.as_ref().map(|stmt| statement_node(stmt)),
+            ),
+        StatementKind::ComptimeIf {
+            condition,
+            then,
+            otherwise,
+        } => Node::new("comptime_if", span)
+            .child("condition", expression_node(condition))
+            .child("then", block_node(then))
+            .optional(
+                "otherwise",
+                otherwise.as_ref().map(|stmt| statement_node(stmt)),
+            ),
+        StatementKind::Block(block) => block_node(block),
+        StatementKind::Expression(expression) => {
+            Node::new("expression", span).child("expression", expression_node(expression))
+        }
+        StatementKind::Assign {
+            place,
+            operator,
+            value,
+        } => Node::new("assign", span)
+            .attribute(
+                "op",
+                Value::Symbol(operator.map_or("=".to_string(), |op| format!("{}=", op.symbol()))),
+            )
+            .child("place", expression_node(place))
+            .child("value", expression_node(value)),
+        StatementKind::Struct(structure) => {
+            let fields = structure
+                .fields
+                .iter()
+                .map(|field| {
+                    Node::new("field", Some(field.span))
+                        .attribute("name", Value::Symbol(field.name.clone()))
+                        .child("type", type_node(&field.ty))
+                })
+                .collect();
+
+            Node::new("struct", span)
+                .attribute("name", Value::Symbol(structure.name.clone()))
+                .attribute("public", Value::Bool(structure.public))
+                .list("generics", generics_nodes(&structure.generics))
+                .list("fields", fields)
+        }
+        StatementKind::Enum(enumeration) => {
+            let variants = enumeration
+                .variants
+                .iter()
+                .map(|variant| {
+                    Node::new("variant", Some(variant.span))
+                        .attribute("name", Value::Symbol(variant.name.clone()))
+                        .list("fields", variant.fields.iter().map(type_node).collect())
+                })
+                .collect();
+
+            Node::new("enum", span)
+                .attribute("name", Value::Symbol(enumeration.name.clone()))
+                .attribute("public", Value::Bool(enumeration.public))
+                .list("variants", variants)
+        }
+        StatementKind::Module(module) => Node::new("module", span)
+            .attribute("name", Value::Symbol(module.name.clone()))
+            .attribute("public", Value::Bool(module.public)),
+        StatementKind::Import(path) => {
+            Node::new("import", span).attribute("path", Value::Symbol(path.join("::")))
+        }
+        StatementKind::Trait(declaration) => {
+            let methods = declaration
+                .methods
+                .iter()
+                .map(|method| {
+                    Node::new("method", Some(method.span))
+                        .attribute("name", Value::Symbol(method.name.clone()))
+                        .list("parameters", parameter_nodes(&method.parameters))
+                        .optional("result", method.result.as_ref().map(type_node))
+                })
+                .collect();
+
+            Node::new("trait", span)
+                .attribute("name", Value::Symbol(declaration.name.clone()))
+                .attribute("public", Value::Bool(declaration.public))
+                .list("methods", methods)
+        }
+        StatementKind::Impl(implementation) => Node::new("impl", span)
+            .attribute("trait", Value::Symbol(implementation.trait_path.join("::")))
+            .child("type", type_node(&implementation.ty))
+            .list(
+                "methods",
+                implementation.methods.iter().map(statement_node).collect(),
+            ),
+    }
+}
+
+pub fn block_node(block: &Block) -> Node {
+    Node::new("block", Some(block.span)).list(
+        "statements",
+        block.statements.iter().map(statement_node).collect(),
+    )
+}
+
+pub fn expression_node(expr: &Expression) -> Node {
+    let span = Some(expr.span);
+
+    match &expr.kind {
+        ExpressionKind::Number(num) => {
+            Node::new("number", span).attribute("value", Value::Number(*num))
+        }
+        ExpressionKind::Bool(value) => {
+            Node::new("bool", span).attribute("value", Value::Bool(*value))
+        }
+        ExpressionKind::String(string) => {
+            Node::new("string", span).attribute("value", Value::String(string.clone()))
+        }
+        ExpressionKind::Char(chr) => {
+            Node::new("char", span).attribute("value", Value::String(chr.to_string()))
+        }
+        ExpressionKind::Identifier(ident) => {
+            Node::new("identifier", span).attribute("name", Value::Symbol(ident.clone()))
+        }
+        ExpressionKind::Unary { op, operand } => {
+            let kind = if op.is_postfix() { "postfix" } else { "prefix" };
+
+            Node::new(kind, span)
+                .attribute("op", Value::Symbol(op.symbol().to_string()))
+                .child("operand", expression_node(operand))
+        }
+        ExpressionKind::Binary { op, lhs, rhs } => Node::new("binary", span)
+            .attribute("op", Value::Symbol(op.symbol().to_string()))
+            .child("lhs", expression_node(lhs))
+            .child("rhs", expression_node(rhs)),
+        ExpressionKind::Cast { expression, ty } => Node::new("cast", span)
+            .child("expression", expression_node(expression))
+            .child("type", type_node(ty)),
+        ExpressionKind::Call { callee, arguments } => Node::new("call", span)
+            .child("callee", expression_node(callee))
+            .list("arguments", arguments.iter().map(expression_node).collect()),
+        ExpressionKind::Field { expression, field } => Node::new("field_access", span)
+            .attribute("field", Value::Symbol(field.clone()))
+            .child("expression", expression_node(expression)),
+        ExpressionKind::MethodCall {
+            receiver,
+            method,
+            arguments,
+        } => Node::new("method_call", span)
+            .attribute("method", Value::Symbol(method.clone()))
+            .child("receiver", expression_node(receiver))
+            .list("arguments", arguments.iter().map(expression_node).collect()),
+        ExpressionKind::StructLiteral { name, fields } => {
+            let fields = fields
+                .iter()
+                .map(|field| {
+                    Node::new("field_init", Some(field.span))
+                        .attribute("name", Value::Symbol(field.name.clone()))
+                        .child("value", expression_node(&field.value))
+                })
+                .collect();
+
+            Node::new("struct_literal", span)
+                .attribute("name", Value::Symbol(name.clone()))
+                .list("fields", fields)
+        }
+        ExpressionKind::Array(elements) => Node::new("array", span)
+            .list("elements", elements.iter().map(expression_node).collect()),
+        ExpressionKind::ArrayRepeat { value, length } => Node::new("array_repeat", span)
+            .child("value", expression_node(value))
+            .child("length", expression_node(length)),
+        ExpressionKind::Index { expression, index } => Node::new("index", span)
+            .child("expression", expression_node(expression))
+            .child("index", expression_node(index)),
+        ExpressionKind::Slice {
+            expression,
+            start,
+            end,
+        } => {
+            // Which bounds are there, as the S-expression doesn't name them
+            let range = match (start, end) {
+                (Some(_), Some(_)) => "start..end",
+                (Some(_), None) => "start..",
+                (None, Some(_)) => "..end",
+                (None, None) => "..",
+            };
+
+            Node::new("slice", span)
+                .attribute("range", Value::Symbol(range.to_string()))
+                .child("expression", expression_node(expression))
+                .optional("start", start.as_deref().map(expression_node))
+                .optional("end", end.as_deref().map(expression_node))
+        }
+        ExpressionKind::Path(segments) => {
+            Node::new("path", span).attribute("path", Value::Symbol(segments.join("::")))
+        }
+        ExpressionKind::Match { scrutinee, arms } => {
+            let arms = arms
+                .iter()
+                .map(|arm| {
+                    Node::new("arm", Some(arm.span))
+                        .child("pattern", pattern_node(&arm.pattern))
+                        .child("value", expression_node(&arm.body))
+                })
+                .collect();
+
+            Node::new("match", span)
+                .child("scrutinee", expression_node(scrutinee))
+                .list("arms", arms)
+        }
+        ExpressionKind::Block(block) => block_node(block),
+        ExpressionKind::Closure(closure) => {
+            let parameters = closure
+                .parameters
+                .iter()
+                .map(|parameter| {
+                    Node::new("parameter", Some(parameter.span))
+                        .attribute("name", Value::Symbol(parameter.name.clone()))
+                        .optional("type", parameter.ty.as_ref().map(type_node))
+                })
+                .collect();
+
+            Node::new("closure", span)
+                .list("parameters", parameters)
+                .optional("result", closure.result.as_deref().map(type_node))
+                .child("body", expression_node(&closure.body))
+        }
+        ExpressionKind::Comptime(inner) => {
+            Node::new("comptime", span).child("value", expression_node(inner))
+        }
+    }
+}
+
+pub fn pattern_node(pattern: &Pattern) -> Node {
+    let span = Some(pattern.span);
+
+    match &pattern.kind {
+        PatternKind::Wildcard => Node::new("wildcard", span),
+        PatternKind::Binding(name) => {
+            Node::new("binding", span).attribute("name", Value::Symbol(name.clone()))
+        }
+        PatternKind::Number(num) => {
+            Node::new("literal_pattern", span).attribute("value", Value::Number(*num))
+        }
+        PatternKind::Bool(value) => {
+            Node::new("literal_pattern", span).attribute("value", Value::Bool(*value))
+        }
+        PatternKind::Char(chr) => {
+            Node::new("literal_pattern", span).attribute("value", Value::String(chr.to_string()))
+        }
+        PatternKind::Variant { path, fields } => Node::new("variant_pattern", span)
+            .attribute("path", Value::Symbol(path.join("::")))
+            .list("fields", fields.iter().map(pattern_node).collect()),
+    }
+}
+
+pub fn type_node(ty: &Type) -> Node {
+    let span = Some(ty.span);
+
+    match &ty.kind {
+        TypeKind::Named(path) => Node::new("named", span)
+            .attribute("path", Value::Symbol(path.segments.join("::")))
+            .list("arguments", path.arguments.iter().map(type_node).collect()),
+        TypeKind::Reference { inner, mutable } => Node::new("reference", span)
+            .attribute("mutable", Value::Bool(*mutable))
+            .child("to", type_node(inner)),
+        TypeKind::Array { element, length } => Node::new("array", span)
+            .child("element", type_node(element))
+            .child("length", expression_node(length)),
+        TypeKind::Slice(element) => Node::new("slice", span).child("element", type_node(element)),
+        TypeKind::Tuple(elements) => {
+            Node::new("tuple", span).list("elements", elements.iter().map(type_node).collect())
+        }
+        // The arity tells the parameters from the result in an S-expression
+        TypeKind::Function { parameters, result } => Node::new("function_type", span)
+            .attribute(
+                "arity",
+                Value::Number(Number::Integer(parameters.len() as i128)),
+            )
+            .list("parameters", parameters.iter().map(type_node).collect())
+            .optional("result", result.as_deref().map(type_node)),
+    }
+}
+
+/// Indented tree view, one node per line with its span and attributes.
+///
+/// ```text
+/// program
+///   let @ 0..21 name=variable
+///     initial: number @ 15..20 value=12379
+/// ```
+pub fn to_tree(node: &Node) -> String {
+    let mut out = String::new();
+    write_tree(&mut out, node, None, 0);
+
+    out
+}
+
+fn write_tree(out: &mut String, node: &Node, label: Option<&str>, depth: usize) {
+    out.push_str(&"  ".repeat(depth));
+
+    if let Some(label) = label {
+        let _ = write!(out, "{label}: ");
+    }
+
+    out.push_str(node.kind);
+
+    if let Some(span) = node.span {
+        let _ = write!(out, " @ {span}");
+    }
+
+    for (name, value) in &node.attributes {
+        let _ = write!(out, " {name}={}", sexpr_value(value));
+    }
+
+    out.push('\n');
+
+    for (name, child) in &node.children {
+        match child {
+            Child::Node(child) => write_tree(out, child, Some(name), depth + 1),
+            // List elements are labelled by the list itself
+            Child::List(children) => {
+                for child in children {
+                    write_tree(out, child, None, depth + 1);
+                }
+            }
+        }
+    }
+}
+
+/// Compact S-expression without spans, e.g. `(let x (binary + (number 1)
+/// (identifier y)))`. The elements of a list are spliced into their parent,
+/// which keeps snapshots short.
+pub fn to_sexpr(node: &Node) -> String {
+    let mut out = String::new();
+    write_sexpr(&mut out, node);
+
+    out
+}
+
+fn write_sexpr(out: &mut String, node: &Node) {
+    out.push('(');
+    out.push_str(node.kind);
+
+    for (_, value) in &node.attributes {
+        out.push(' ');
+        out.push_str(&sexpr_value(value));
+    }
+
+    for (_, child) in &node.children {
+        match child {
+            Child::Node(child) => {
+                out.push(' ');
+                write_sexpr(out, child);
+            }
+            Child::List(children) => {
+                for child in children {
+                    out.push(' ');
+                    write_sexpr(out, child);
+                }
+            }
+        }
+    }
+
+    out.push(')');
+}
+
+fn sexpr_value(value: &Value) -> String {
+    match value {
+        Value::Symbol(symbol) => symbol.clone(),
+        Value::Number(num) => num.to_string(),
+        Value::Bool(value) => value.to_string(),
+        Value::String(string) => json_string(string),
+    }
+}
+
+/// JSON object per node. Every node has a `kind`, nodes from the source have
+/// a `span` with `start` and `end` byte offsets, and attributes and children
+/// are stored under their names.
+pub fn to_json(node: &Node) -> String {
+    let mut out = String::new();
+    write_json(&mut out, node);
+
+    out
+}
+
+fn write_json(out: &mut String, node: &Node) {
+    let _ = write!(out, "{{\"kind\":{}", json_string(node.kind));
+
+    if let Some(span) = node.span {
+        let _ = write!(
+            out,
+            ",\"span\":{{\"start\":{},\"end\":{}}}",
+            span.start, span.end
+        );
+    }
+
+    for (name, value) in &node.attributes {
+        let _ = write!(out, ",{}:", json_string(name));
+
+        match value {
+            Value::Symbol(symbol) => out.push_str(&json_string(symbol)),
+            Value::Number(num) => out.push_str(&json_number(*num)),
+            Value::Bool(value) => out.push_str(&value.to_string()),
+            Value::String(string) => out.push_str(&json_string(string)),
+        }
+    }
+
+    for (name, child) in &node.children {
+        let _ = write!(out, ",{}:", json_string(name));
+
+        match child {
+            Child::Node(child) => write_json(out, child),
+            Child::List(children) => {
+                out.push('[');
+
+                for (i, child) in children.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+
+                    write_json(out, child);
+                }
+
+                out.push(']');
+            }
+        }
+    }
+
+    out.push('}');
+}
+
+fn json_string(string: &str) -> String {
+    let mut out = String::from('"');
+
+    for chr in string.chars() {
+        match chr {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            chr if (chr as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", chr as u32);
+            }
+            chr => out.push(chr),
+        }
+    }
+
+    out.push('"');
+    out
+}
+
+/// JSON has no representation for NaN or the infinities
+fn json_number(num: Number) -> String {
+    match num {
+        Number::Float(num) if !num.is_finite() => "null".to_string(),
+        num => num.to_string(),
+    }
+}
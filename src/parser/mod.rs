@@ -1,2 +1,6 @@
-pub mod state;
 pub mod ast;
+pub mod dump;
+pub mod printer;
+pub mod state;
+pub mod stream;
+pub mod visit;
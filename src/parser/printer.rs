@@ -0,0 +1,833 @@
+//! Pretty-printer turning an AST back into canonical Orion source.
+//!
+//! The output is meant to be parsed again: parsing the printed form of a
+//! program gives back the same program. Parentheses are only inserted where
+//! the precedence of the operators requires them.
+use crate::parser::ast::*;
+use crate::parser::visit::{walk_expression, Visitor};
+
+/// Print a whole program, one statement per line and blocks indented by four
+/// spaces.
+pub fn print_program(program: &Program) -> String {
+    let mut printer = Printer::default();
+
+    for stmt in program {
+        printer.statement(stmt);
+    }
+
+    printer.out
+}
+
+/// Print a single expression.
+pub fn print_expression(expr: &Expression) -> String {
+    let mut printer = Printer::default();
+    printer.expression(expr, 0);
+
+    printer.out
+}
+
+/// Print a type, as it would be written in an annotation.
+pub fn print_type(ty: &Type) -> String {
+    let mut printer = Printer::default();
+    printer.ty(ty, true);
+
+    printer.out
+}
+
+#[derive(Default)]
+struct Printer {
+    out: String,
+    // How many blocks deep the statement being printed is
+    indent: usize,
+}
+
+impl Printer {
+    fn statement(&mut self, stmt: &Statement) {
+        self.out.push_str(&"    ".repeat(self.indent));
+        self.statement_body(stmt);
+        self.out.push('\n');
+    }
+
+    /// A statement without its indentation and the newline after it
+    fn statement_body(&mut self, stmt: &Statement) {
+        match &stmt.kind {
+            StatementKind::Let {
+                name,
+                mutable,
+                ty,
+                initial,
+            } => {
+                self.out.push_str("let ");
+
+                if *mutable {
+                    self.out.push_str("mut ");
+                }
+
+                self.out.push_str(name);
+
+                match ty {
+                    Some(ty) => {
+                        self.out.push_str(" : ");
+                        self.ty(ty, true);
+                    }
+                    None => self.out.push_str(" :="),
+                }
+
+                if let Some(initial) = initial {
+                    self.out.push_str(if ty.is_some() { " = " } else { " " });
+                    self.expression(initial, 0);
+                }
+
+                self.out.push(';');
+            }
+            StatementKind::Function(function) => self.function(function, false),
+            StatementKind::Return(value) => {
+                self.out.push_str("return");
+
+                if let Some(value) = value {
+                    self.out.push(' ');
+                    self.expression(value, 0);
+                }
+
+                self.out.push(';');
+            }
+            StatementKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                self.out.push_str("if ");
+                self.condition(condition);
+                self.block(then);
+
+                if let Some(otherwise) = otherwise {
+                    self.out.push_str(" else ");
+                    self.statement_body(otherwise);
+                }
+            }
+            StatementKind::ComptimeIf {
+                condition,
+                then,
+                otherwise,
+            } => {
+                self.out.push_str("$if ");
+                self.condition(condition);
+                self.block(then);
+
+                if let Some(otherwise) = otherwise {
+                    self.out.push_str(" else ");
+                    self.statement_body(otherwise);
+                }
+            }
+            StatementKind::Block(block) => self.block(block),
+            // A `match` starting a statement is parsed as a statement of its
+            // own, which doesn't need a `;`. Anything continuing it has to be
+            // in parentheses.
+            StatementKind::Expression(expression) => match expression.kind {
+                ExpressionKind::Match { .. } => self.expression(expression, 0),
+                _ => {
+                    self.leading(expression);
+                    self.out.push(';');
+                }
+            },
+            StatementKind::Assign {
+                place,
+                operator,
+                value,
+            } => {
+                self.leading(place);
+                self.out.push(' ');
+
+                if let Some(operator) = operator {
+                    self.out.push_str(operator.symbol());
+                }
+
+                self.out.push_str("= ");
+                self.expression(value, 0);
+                self.out.push(';');
+            }
+            StatementKind::Struct(structure) => {
+                self.visibility(structure.public);
+                self.out.push_str("struct ");
+                self.out.push_str(&structure.name);
+                self.generics(&structure.generic_names());
+                self.out.push_str(" {\n");
+                self.indent += 1;
+
+                for field in &structure.fields {
+                    self.out.push_str(&"    ".repeat(self.indent));
+                    self.out.push_str(&field.name);
+                    self.out.push_str(" : ");
+                    self.ty(&field.ty, true);
+                    self.out.push_str(",\n");
+                }
+
+                self.indent -= 1;
+                self.out.push_str(&"    ".repeat(self.indent));
+                self.out.push('}');
+            }
+            StatementKind::Enum(enumeration) => {
+                self.visibility(enumeration.public);
+                self.out.push_str("enum ");
+                self.out.push_str(&enumeration.name);
+                self.out.push_str(" {\n");
+                self.indent += 1;
+
+                for variant in &enumeration.variants {
+                    self.out.push_str(&"    ".repeat(self.indent));
+                    self.out.push_str(&variant.name);
+
+                    if !variant.fields.is_empty() {
+                        self.out.push('(');
+                        self.types(&variant.fields);
+                        self.out.push(')');
+                    }
+
+                    self.out.push_str(",\n");
+                }
+
+                self.indent -= 1;
+                self.out.push_str(&"    ".repeat(self.indent));
+                self.out.push('}');
+            }
+            StatementKind::Module(module) => {
+                self.visibility(module.public);
+                self.out.push_str("mod ");
+                self.out.push_str(&module.name);
+                self.out.push(';');
+            }
+            StatementKind::Import(path) => {
+                self.out.push_str("import ");
+                self.out.push_str(&path.join("::"));
+                self.out.push(';');
+            }
+            StatementKind::Trait(declaration) => {
+                self.visibility(declaration.public);
+                self.out.push_str("trait ");
+                self.out.push_str(&declaration.name);
+                self.out.push_str(" {\n");
+                self.indent += 1;
+
+                for method in &declaration.methods {
+                    self.out.push_str(&"    ".repeat(self.indent));
+                    self.out.push_str("func ");
+                    self.out.push_str(&method.name);
+                    self.parameters(&method.parameters, true);
+
+                    if let Some(result) = &method.result {
+                        self.out.push_str(" : ");
+                        self.ty(result, true);
+                    }
+
+                    self.out.push_str(";\n");
+                }
+
+                self.indent -= 1;
+                self.out.push_str(&"    ".repeat(self.indent));
+                self.out.push('}');
+            }
+            StatementKind::Impl(implementation) => {
+                self.out.push_str("impl ");
+                self.out.push_str(&implementation.trait_path.join("::"));
+                self.out.push_str(" for ");
+                self.ty(&implementation.ty, true);
+                self.out.push_str(" {\n");
+                self.indent += 1;
+
+                for method in &implementation.methods {
+                    self.out.push_str(&"    ".repeat(self.indent));
+
+                    if let StatementKind::Function(function) = &method.kind {
+                        self.function(function, true);
+                    }
+
+                    self.out.push('\n');
+                }
+
+                self.indent -= 1;
+                self.out.push_str(&"    ".repeat(self.indent));
+                self.out.push('}');
+            }
+        }
+    }
+
+    /// A function declaration, or a `method` in an `impl`
+    fn function(&mut self, function: &Function, method: bool) {
+        self.visibility(function.public);
+        self.out.push_str("func ");
+        self.out.push_str(&function.name);
+
+        if !function.generics.is_empty() {
+            self.out.push('<');
+
+            for (i, generic) in function.generics.iter().enumerate() {
+                if i > 0 {
+                    self.out.push_str(", ");
+                }
+
+                self.out.push_str(&generic.name);
+
+                for (i, bound) in generic.bounds.iter().enumerate() {
+                    self.out.push_str(if i == 0 { " : " } else { " + " });
+                    self.out.push_str(&bound.join("::"));
+                }
+            }
+
+            self.out.push('>');
+        }
+
+        self.parameters(&function.parameters, method);
+
+        if let Some(result) = &function.result {
+            self.out.push_str(" : ");
+            self.ty(result, true);
+        }
+
+        self.out.push_str(" = ");
+        self.block(&function.body);
+    }
+
+    /// The parameters of a function in parentheses. The first parameter of
+    /// a `method` is `self`, written without its type.
+    fn parameters(&mut self, parameters: &[Parameter], method: bool) {
+        self.out.push('(');
+
+        for (i, parameter) in parameters.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+
+            self.out.push_str(&parameter.name);
+
+            if i > 0 || !method {
+                self.out.push_str(" : ");
+                self.ty(&parameter.ty, true);
+            }
+        }
+
+        self.out.push(')');
+    }
+
+    /// The type parameters of a generic struct
+    fn generics(&mut self, generics: &[String]) {
+        if !generics.is_empty() {
+            self.out.push('<');
+            self.out.push_str(&generics.join(", "));
+            self.out.push('>');
+        }
+    }
+
+    fn visibility(&mut self, public: bool) {
+        if public {
+            self.out.push_str("pub ");
+        }
+    }
+
+    /// Print the condition of an `if` and the space before its block
+    fn condition(&mut self, condition: &Expression) {
+        // A struct literal would end the condition early, but parentheses
+        // allow them again
+        let mut finder = StructLiteralFinder(false);
+        finder.visit_expression(condition);
+
+        if finder.0 {
+            self.out.push('(');
+            self.expression(condition, 0);
+            self.out.push(')');
+        } else {
+            self.expression(condition, 0);
+        }
+
+        self.out.push(' ');
+    }
+
+    /// Print the expression at the start of a statement, in parentheses if
+    /// its leftmost operand is a `match`
+    fn leading(&mut self, expr: &Expression) {
+        if starts_with_match(expr) {
+            self.out.push('(');
+            self.expression(expr, 0);
+            self.out.push(')');
+        } else {
+            self.expression(expr, 0);
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        self.out.push_str("{\n");
+        self.indent += 1;
+
+        for stmt in &block.statements {
+            self.statement(stmt);
+        }
+
+        self.indent -= 1;
+        self.out.push_str(&"    ".repeat(self.indent));
+        self.out.push('}');
+    }
+
+    /// Print an expression appearing in a context that binds with
+    /// `context` precedence. If the expression binds looser than that, it
+    /// has to be wrapped in parentheses.
+    fn expression(&mut self, expr: &Expression, context: u8) {
+        match &expr.kind {
+            ExpressionKind::Number(num) => self.out.push_str(&num.to_string()),
+            ExpressionKind::Bool(value) => self.out.push_str(&value.to_string()),
+            ExpressionKind::String(string) => {
+                self.out.push('"');
+                self.out.push_str(&escape(string));
+                self.out.push('"');
+            }
+            ExpressionKind::Char(chr) => {
+                self.out.push('\'');
+                self.out.push_str(&escape(&chr.to_string()));
+                self.out.push('\'');
+            }
+            ExpressionKind::Identifier(ident) => self.out.push_str(ident),
+            ExpressionKind::Unary { op, operand } => {
+                let precedence = op.precedence();
+                let parenthesize = precedence < context;
+
+                if parenthesize {
+                    self.out.push('(');
+                }
+
+                if op.is_postfix() {
+                    self.expression(operand, precedence);
+                    self.out.push_str(op.symbol());
+                } else {
+                    self.out.push_str(op.symbol());
+
+                    // `- -x` must not run together into `--x`, nor `&mut x`
+                    // into `&mutx`
+                    let spaced = *op == UnaryOperator::RefMut
+                        || matches!(
+                            operand.kind,
+                            ExpressionKind::Unary {
+                                op: UnaryOperator::Neg | UnaryOperator::PreDecrement,
+                                ..
+                            }
+                        );
+
+                    if spaced {
+                        self.out.push(' ');
+                    }
+
+                    self.expression(operand, precedence);
+                }
+
+                if parenthesize {
+                    self.out.push(')');
+                }
+            }
+            ExpressionKind::Binary { op, lhs, rhs } => {
+                let precedence = op.precedence();
+                let parenthesize = precedence < context;
+
+                if parenthesize {
+                    self.out.push('(');
+                }
+
+                // Operators are left associative, so a right operand of the
+                // same precedence needs parentheses while a left one doesn't.
+                self.expression(lhs, precedence);
+                self.out.push(' ');
+                self.out.push_str(op.symbol());
+                self.out.push(' ');
+                self.expression(rhs, precedence + 1);
+
+                if parenthesize {
+                    self.out.push(')');
+                }
+            }
+            ExpressionKind::Cast { expression, ty } => {
+                let parenthesize = CAST_PRECEDENCE < context;
+
+                if parenthesize {
+                    self.out.push('(');
+                }
+
+                self.expression(expression, CAST_PRECEDENCE);
+                self.out.push_str(" as ");
+                self.ty(ty, false);
+
+                if parenthesize {
+                    self.out.push(')');
+                }
+            }
+            ExpressionKind::Call { callee, arguments } => {
+                // Calls bind as tightly as the postfix operators. Calling a
+                // field needs parentheses, as it would be a method call.
+                if let ExpressionKind::Field { .. } = callee.kind {
+                    self.out.push('(');
+                    self.expression(callee, 0);
+                    self.out.push(')');
+                } else {
+                    self.expression(callee, UnaryOperator::PostIncrement.precedence());
+                }
+
+                self.out.push('(');
+                self.expressions(arguments);
+                self.out.push(')');
+            }
+            ExpressionKind::Field { expression, field } => {
+                self.dotted(expression);
+                self.out.push_str(field);
+            }
+            ExpressionKind::MethodCall {
+                receiver,
+                method,
+                arguments,
+            } => {
+                self.dotted(receiver);
+                self.out.push_str(method);
+                self.out.push('(');
+                self.expressions(arguments);
+                self.out.push(')');
+            }
+            ExpressionKind::StructLiteral { name, fields } => {
+                self.out.push_str(name);
+
+                if fields.is_empty() {
+                    self.out.push_str(" {}");
+                    return;
+                }
+
+                self.out.push_str(" { ");
+
+                for (i, field) in fields.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+
+                    self.out.push_str(&field.name);
+                    self.out.push_str(": ");
+                    self.expression(&field.value, 0);
+                }
+
+                self.out.push_str(" }");
+            }
+            ExpressionKind::Array(elements) => {
+                self.out.push('[');
+                self.expressions(elements);
+                self.out.push(']');
+            }
+            ExpressionKind::ArrayRepeat { value, length } => {
+                self.out.push('[');
+                self.expression(value, 0);
+                self.out.push_str("; ");
+                self.expression(length, 0);
+                self.out.push(']');
+            }
+            ExpressionKind::Index { expression, index } => {
+                // Indexing binds as tightly as the postfix operators
+                self.expression(expression, UnaryOperator::PostIncrement.precedence());
+                self.out.push('[');
+                self.expression(index, 0);
+                self.out.push(']');
+            }
+            ExpressionKind::Slice {
+                expression,
+                start,
+                end,
+            } => {
+                self.expression(expression, UnaryOperator::PostIncrement.precedence());
+                self.out.push('[');
+
+                if let Some(start) = start {
+                    self.expression(start, 0);
+                }
+
+                self.out.push_str("..");
+
+                if let Some(end) = end {
+                    self.expression(end, 0);
+                }
+
+                self.out.push(']');
+            }
+            ExpressionKind::Path(segments) => self.out.push_str(&segments.join("::")),
+            ExpressionKind::Match { scrutinee, arms } => {
+                self.out.push_str("match ");
+
+                // Like in the condition of an `if`
+                let mut finder = StructLiteralFinder(false);
+                finder.visit_expression(scrutinee);
+
+                if finder.0 {
+                    self.out.push('(');
+                    self.expression(scrutinee, 0);
+                    self.out.push(')');
+                } else {
+                    self.expression(scrutinee, 0);
+                }
+
+                self.out.push_str(" {\n");
+                self.indent += 1;
+
+                for arm in arms {
+                    self.out.push_str(&"    ".repeat(self.indent));
+                    self.pattern(&arm.pattern);
+                    self.out.push_str(" => ");
+
+                    match &arm.body.kind {
+                        ExpressionKind::Block(block) => self.block(block),
+                        _ => {
+                            self.expression(&arm.body, 0);
+                            self.out.push(',');
+                        }
+                    }
+
+                    self.out.push('\n');
+                }
+
+                self.indent -= 1;
+                self.out.push_str(&"    ".repeat(self.indent));
+                self.out.push('}');
+            }
+            ExpressionKind::Block(block) => self.block(block),
+            ExpressionKind::Comptime(inner) => {
+                self.out.push('$');
+
+                match &inner.kind {
+                    ExpressionKind::Block(block) => self.block(block),
+                    _ => {
+                        self.out.push('(');
+                        self.expression(inner, 0);
+                        self.out.push(')');
+                    }
+                }
+            }
+            ExpressionKind::Closure(closure) => {
+                // The body extends as far as an expression can, so a closure
+                // anywhere but on its own takes what follows it
+                let parenthesize = context > 0;
+
+                if parenthesize {
+                    self.out.push('(');
+                }
+
+                self.out.push('|');
+
+                for (i, parameter) in closure.parameters.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+
+                    self.out.push_str(&parameter.name);
+
+                    if let Some(ty) = &parameter.ty {
+                        self.out.push_str(" : ");
+                        self.ty(ty, true);
+                    }
+                }
+
+                self.out.push('|');
+
+                if let Some(result) = &closure.result {
+                    self.out.push_str(" -> ");
+                    self.ty(result, true);
+                }
+
+                self.out.push_str(" = ");
+
+                match &closure.body.kind {
+                    ExpressionKind::Block(block) => self.block(block),
+                    _ => self.expression(&closure.body, 0),
+                }
+
+                if parenthesize {
+                    self.out.push(')');
+                }
+            }
+        }
+    }
+
+    /// The expression before the `.` of a field access or a method call,
+    /// and the `.`. It binds as tightly as the postfix operators. The `.`
+    /// would be taken as part of a number before it.
+    fn dotted(&mut self, expression: &Expression) {
+        if let ExpressionKind::Number(_) = expression.kind {
+            self.out.push('(');
+            self.expression(expression, 0);
+            self.out.push(')');
+        } else {
+            self.expression(expression, UnaryOperator::PostIncrement.precedence());
+        }
+
+        self.out.push('.');
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        match &pattern.kind {
+            PatternKind::Wildcard => self.out.push('_'),
+            PatternKind::Binding(name) => self.out.push_str(name),
+            PatternKind::Number(num) => self.out.push_str(&num.to_string()),
+            PatternKind::Bool(value) => self.out.push_str(&value.to_string()),
+            PatternKind::Char(chr) => {
+                self.out.push('\'');
+                self.out.push_str(&escape(&chr.to_string()));
+                self.out.push('\'');
+            }
+            PatternKind::Variant { path, fields } => {
+                self.out.push_str(&path.join("::"));
+
+                if !fields.is_empty() {
+                    self.out.push('(');
+
+                    for (i, field) in fields.iter().enumerate() {
+                        if i > 0 {
+                            self.out.push_str(", ");
+                        }
+
+                        self.pattern(field);
+                    }
+
+                    self.out.push(')');
+                }
+            }
+        }
+    }
+
+    /// Comma separated expressions
+    fn expressions(&mut self, exprs: &[Expression]) {
+        for (i, expr) in exprs.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+
+            self.expression(expr, 0);
+        }
+    }
+
+    /// Print a type. Without `bare_generics` generic arguments are written
+    /// `Pair::<A, B>`, which is needed after `as`.
+    fn ty(&mut self, ty: &Type, bare_generics: bool) {
+        match &ty.kind {
+            TypeKind::Named(path) => {
+                self.out.push_str(&path.segments.join("::"));
+
+                if !path.arguments.is_empty() {
+                    if !bare_generics {
+                        self.out.push_str("::");
+                    }
+
+                    self.out.push('<');
+                    self.types(&path.arguments);
+                    self.out.push('>');
+                }
+            }
+            TypeKind::Reference { inner, mutable } => {
+                self.out.push_str(if *mutable { "&mut " } else { "&" });
+                self.ty(inner, bare_generics);
+            }
+            TypeKind::Array { element, length } => {
+                self.out.push('[');
+                self.ty(element, true);
+                self.out.push_str("; ");
+                self.expression(length, 0);
+                self.out.push(']');
+            }
+            TypeKind::Slice(element) => {
+                self.out.push('[');
+                self.ty(element, true);
+                self.out.push(']');
+            }
+            TypeKind::Tuple(elements) => {
+                self.out.push('(');
+                self.types(elements);
+
+                if elements.len() == 1 {
+                    self.out.push(',');
+                }
+
+                self.out.push(')');
+            }
+            TypeKind::Function { parameters, result } => {
+                self.out.push_str("func(");
+                self.types(parameters);
+                self.out.push(')');
+
+                if let Some(result) = result {
+                    self.out.push_str(" -> ");
+                    self.ty(result, bare_generics);
+                }
+            }
+        }
+    }
+
+    /// Comma separated types
+    fn types(&mut self, types: &[Type]) {
+        for (i, ty) in types.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+
+            self.ty(ty, true);
+        }
+    }
+}
+
+/// Escape a string so it can be printed between quotes
+fn escape(string: &str) -> String {
+    let mut out = String::with_capacity(string.len());
+
+    for chr in string.chars() {
+        match chr {
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            '\0' => out.push_str("\\0"),
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\'' => out.push_str("\\'"),
+            chr => out.push(chr),
+        }
+    }
+
+    out
+}
+
+/// Whether the leftmost operand of an expression is a `match`
+fn starts_with_match(expr: &Expression) -> bool {
+    match &expr.kind {
+        ExpressionKind::Match { .. } => true,
+        ExpressionKind::Unary { op, operand } if op.is_postfix() => starts_with_match(operand),
+        ExpressionKind::Binary { lhs: operand, .. }
+        | ExpressionKind::Cast {
+            expression: operand,
+            ..
+        }
+        | ExpressionKind::Call {
+            callee: operand, ..
+        }
+        | ExpressionKind::Field {
+            expression: operand,
+            ..
+        }
+        | ExpressionKind::MethodCall {
+            receiver: operand, ..
+        }
+        | ExpressionKind::Index {
+            expression: operand,
+            ..
+        }
+        | ExpressionKind::Slice {
+            expression: operand,
+            ..
+        } => starts_with_match(operand),
+        _ => false,
+    }
+}
+
+/// Finds out whether an expression contains a struct literal
+struct StructLiteralFinder(bool);
+
+impl Visitor<'_> for StructLiteralFinder {
+    fn visit_expression(&mut self, expr: &Expression) {
+        match expr.kind {
+            ExpressionKind::StructLiteral { .. } => self.0 = true,
+            _ => walk_expression(self, expr),
+        }
+    }
+}
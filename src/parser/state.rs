@@ -1,80 +1,1796 @@
+use crate::error::OrionError;
 use crate::lexer::state::Lexer;
 use crate::lexer::tokens::*;
 use crate::parser::ast::*;
+use crate::parser::stream::TokenStream;
+use crate::syntax::green::{Checkpoint, GreenBuilder, GreenNode};
+use crate::syntax::kind::SyntaxKind;
+use crate::syntax::red::SyntaxNode;
 
-pub type Program = Vec<Statement>;
+/// How deeply expressions, types and blocks may nest. The parser is
+/// recursive, so without a limit deeply nested input would overflow the
+/// stack, even in the smaller stacks of spawned threads.
+const MAX_NESTING: usize = 128;
 
+/// Parses a token stream into the AST, building the lossless syntax tree
+/// from [`crate::syntax`] along the way. Every token goes through `bump`,
+/// which also records it, and the whitespace and comments before it, in the
+/// syntax tree.
 pub struct Parser<'a> {
-    lexer: Lexer<'a>,
+    tokens: TokenStream<'a>,
+    builder: GreenBuilder,
+    // How much of the source is in the syntax tree so far
+    consumed: usize,
+    errors: Vec<OrionError>,
+    // The finished syntax tree, once parsing is done
+    green: Option<GreenNode>,
+    // How many expressions, types or blocks are being parsed inside each
+    // other
+    nesting: usize,
+    // The nesting at which statements declare the items of a module: the
+    // top level, and the branches of a `$if` there
+    items: usize,
+    // Whether a `{` after a name starts a struct literal, which it doesn't
+    // in the condition of an `if`
+    struct_literals: bool,
 }
 
 impl<'a> Parser<'a> {
     pub fn new(lexer: Lexer<'a>) -> Self {
-        Self { lexer }
+        Self {
+            tokens: TokenStream::new(lexer),
+            builder: GreenBuilder::default(),
+            consumed: 0,
+            errors: vec![],
+            green: None,
+            nesting: 0,
+            items: 0,
+            struct_literals: true,
+        }
     }
 
-    pub fn parse(&mut self) -> Program {
+    /// Parse the whole input. After a syntax error the parser skips ahead to
+    /// the next statement and carries on, so every error can be found with
+    /// [`Parser::syntax`]; the first one is returned here.
+    pub fn parse(&mut self) -> Result<Program, OrionError> {
+        // Leading trivia is part of the root, unlike for other nodes
+        self.builder.start_node(SyntaxKind::SourceFile);
+        let stmts = self.statements(&TokenKind::Eof);
+
+        let source = self.tokens.source();
+        self.trivia(source.len());
+        self.builder.finish_node();
+        self.green = Some(std::mem::take(&mut self.builder).finish());
+
+        match self.errors.first() {
+            Some(error) => Err(error.clone()),
+            None => Ok(stmts),
+        }
+    }
+
+    /// The concrete syntax tree of the input and all the syntax errors in
+    /// it, parsing the input first if that hasn't happened yet.
+    pub fn syntax(&mut self) -> (SyntaxNode, Vec<OrionError>) {
+        if self.green.is_none() {
+            let _ = self.parse();
+        }
+
+        let green = self.green.clone().expect("the input was parsed");
+
+        (SyntaxNode::new_root(green), self.errors.clone())
+    }
+
+    /// Parse statements up to `end`, which is left for the caller. After a
+    /// syntax error the parser skips ahead to the next statement and carries
+    /// on.
+    fn statements(&mut self, end: &TokenKind) -> Vec<Statement> {
         let mut stmts = vec![];
 
-        while let Some(token) = self.lexer.by_ref().next() {
-            match token.kind {
-                TokenKind::Let => {
-                    let identifier = if let Some(ident) = self.lexer.by_ref().next() {
-                        ident
-                    } else {
-                        panic!("no identifier succeding a let identifier.");
-                    };
+        while !self.tokens.at(end) && !self.tokens.at(&TokenKind::Eof) {
+            let depth = self.builder.depth();
+
+            match self.parse_statement() {
+                Ok(stmt) => stmts.push(stmt),
+                Err(error) => {
+                    self.errors.push(error);
+                    // Close whatever was being parsed, it stays in the tree
+                    // as far as it got
+                    self.builder.finish_to(depth);
+                    self.recover();
+                }
+            }
+        }
+
+        stmts
+    }
+
+    /// Skip the tokens up to the end of the statement that failed to parse,
+    /// wrapping them in an error node. A `}` ends the skipping as well, as
+    /// it most likely closes the block the statement is in.
+    fn recover(&mut self) {
+        self.start_node(SyntaxKind::Error);
+
+        // Always skip at least one token, so the parser makes progress
+        let mut token = self.bump();
+
+        while token.kind != TokenKind::Semi
+            && !self.tokens.at(&TokenKind::Let)
+            && !self.tokens.at(&TokenKind::Func)
+            && !self.tokens.at(&TokenKind::RBrace)
+            && !self.tokens.at(&TokenKind::Eof)
+        {
+            token = self.bump();
+        }
+
+        self.builder.finish_node();
+    }
+
+    /// Consume the next token, adding it to the syntax tree
+    fn bump(&mut self) -> Token<'a> {
+        let token = self.tokens.advance();
+
+        if token.kind != TokenKind::Eof {
+            self.trivia(token.span.start);
+
+            let text = &self.tokens.source()[token.span.start..token.span.end];
+            self.builder
+                .token(SyntaxKind::from_token(&token.kind), text);
+            self.consumed = token.span.end;
+        }
+
+        token
+    }
+
+    /// Add the source text up to `end` that the parser skips over to the
+    /// syntax tree
+    fn trivia(&mut self, end: usize) {
+        let source = self.tokens.source();
+
+        for (kind, text) in crate::syntax::trivia(&source[self.consumed..end]) {
+            self.builder.token(kind, text);
+        }
+
+        self.consumed = end;
+    }
+
+    /// Start a syntax tree node at the next token. The trivia in front of it
+    /// stays outside of the node.
+    fn start_node(&mut self, kind: SyntaxKind) {
+        let start = self.tokens.peek().span.start;
+        self.trivia(start);
+        self.builder.start_node(kind);
+    }
+
+    /// A checkpoint to start a node at the next token once its kind is known
+    fn checkpoint(&mut self) -> Checkpoint {
+        let start = self.tokens.peek().span.start;
+        self.trivia(start);
+        self.builder.checkpoint()
+    }
+
+    /// Consume the next token if it is of the given kind
+    fn eat(&mut self, kind: &TokenKind) -> Option<Token<'a>> {
+        if self.tokens.at(kind) {
+            Some(self.bump())
+        } else {
+            None
+        }
+    }
+
+    /// Consume the next token, which has to be of the given kind
+    fn expect(&mut self, kind: &TokenKind) -> Result<Token<'a>, OrionError> {
+        if self.tokens.at(kind) {
+            Ok(self.bump())
+        } else {
+            Err(self.tokens.unexpected(&format!("`{kind}`")))
+        }
+    }
+
+    /// Comma separated items up to the closing `end` token, which is
+    /// consumed as well. A trailing comma is allowed.
+    fn list<T>(
+        &mut self,
+        end: &TokenKind<'a>,
+        mut item: impl FnMut(&mut Self) -> Result<T, OrionError>,
+    ) -> Result<Vec<T>, OrionError> {
+        let mut items = vec![];
+
+        while !self.at_closing(end) {
+            items.push(item(self)?);
+
+            if self.eat(&TokenKind::Comma).is_none() {
+                break;
+            }
+        }
+
+        self.at_closing(end);
+        self.expect(end)?;
+
+        Ok(items)
+    }
+
+    /// Whether the next token is the closing `end` token. A `>` or `|` may
+    /// be glued to what follows it, `>>` closing two lists of generic
+    /// arguments at once.
+    fn at_closing(&mut self, end: &TokenKind<'a>) -> bool {
+        self.tokens.split_closing(end);
+        self.tokens.at(end)
+    }
+
+    fn parse_statement(&mut self) -> Result<Statement, OrionError> {
+        let kind = self.tokens.peek().kind.clone();
+
+        match kind {
+            TokenKind::Let => self.parse_let(),
+            TokenKind::Func => self.parse_function(false),
+            TokenKind::Return => self.parse_return(),
+            TokenKind::If => self.parse_if(),
+            TokenKind::Dollar if self.tokens.peek_nth(1).kind == TokenKind::If => {
+                self.parse_comptime_if()
+            }
+            TokenKind::Struct => self.parse_struct(),
+            TokenKind::Enum => self.parse_enum(),
+            TokenKind::Match => self.parse_match_statement(),
+            // Only the top level declares what a module holds
+            TokenKind::Pub if self.nesting == self.items => match self.tokens.peek_nth(1).kind {
+                TokenKind::Func => self.parse_function(false),
+                TokenKind::Struct => self.parse_struct(),
+                TokenKind::Enum => self.parse_enum(),
+                TokenKind::Mod => self.parse_module(),
+                TokenKind::Trait => self.parse_trait(),
+                _ => {
+                    self.bump();
+                    Err(self
+                        .tokens
+                        .unexpected("`func`, `struct`, `enum`, `trait` or `mod`"))
+                }
+            },
+            TokenKind::Mod if self.nesting == self.items => self.parse_module(),
+            TokenKind::Import if self.nesting == self.items => self.parse_import(),
+            TokenKind::Trait if self.nesting == self.items => self.parse_trait(),
+            TokenKind::Impl if self.nesting == self.items => self.parse_impl(),
+            TokenKind::LBrace => {
+                let block = self.parse_block()?;
+
+                Ok(Statement {
+                    span: block.span,
+                    kind: StatementKind::Block(block),
+                })
+            }
+            kind if starts_operand(&kind) => self.parse_expression_statement(),
+            _ => Err(self.tokens.unexpected("a statement")),
+        }
+    }
+
+    /// `expression;`, `place = value;` or a compound assignment like
+    /// `place += value;`
+    fn parse_expression_statement(&mut self) -> Result<Statement, OrionError> {
+        let checkpoint = self.checkpoint();
+        let expression = self.parse_expression()?;
+        let start = expression.span.start;
+        let next = self.tokens.peek().kind.clone();
+
+        let assignment = match next {
+            TokenKind::Eq => Some(None),
+            _ => compound_assignment(&next).map(Some),
+        };
+
+        let kind = if let Some(operator) = assignment {
+            self.builder
+                .start_node_at(checkpoint, SyntaxKind::AssignStmt);
+            let token = self.bump();
+
+            if !expression.is_place() {
+                return Err(OrionError::NotAssignable {
+                    operator: token.kind.to_string(),
+                    loc: token.loc,
+                });
+            }
+
+            let value = self.parse_expression()?;
+
+            StatementKind::Assign {
+                place: expression,
+                operator,
+                value,
+            }
+        } else {
+            self.builder.start_node_at(checkpoint, SyntaxKind::ExprStmt);
+            StatementKind::Expression(expression)
+        };
+
+        self.expect(&TokenKind::Semi)?;
+        self.builder.finish_node();
+
+        Ok(Statement {
+            kind,
+            span: Span::new(start, self.tokens.last_end()),
+        })
+    }
+
+    /// `let name : T = expression;` or `let name := expression;`, with
+    /// `let mut name` for a variable that can be assigned to, and `let name
+    /// : T;` for one assigned later. For historical reasons `let name =
+    /// expression;` is accepted as well.
+    fn parse_let(&mut self) -> Result<Statement, OrionError> {
+        self.start_node(SyntaxKind::LetStmt);
+        let start = self.expect(&TokenKind::Let)?.span.start;
+        let mutable = self.eat(&TokenKind::Mut).is_some();
+        let name = self.expect_identifier()?;
+
+        // Only a variable with a type can be declared without a value
+        let (ty, initial) = if self.eat(&TokenKind::Colon).is_some() {
+            let ty = self.parse_type(true)?;
+
+            let initial = match self.eat(&TokenKind::Semi) {
+                Some(_) => None,
+                None => {
+                    self.expect(&TokenKind::Eq)?;
+                    Some(self.parse_expression()?)
+                }
+            };
+
+            (Some(ty), initial)
+        } else if self.eat(&TokenKind::Eq).is_some()
+            || self.eat(&TokenKind::UntypedAssignment).is_some()
+        {
+            (None, Some(self.parse_expression()?))
+        } else {
+            return Err(self.tokens.unexpected("`:`, `=` or `:=`"));
+        };
+
+        if initial.is_some() {
+            self.expect(&TokenKind::Semi)?;
+        }
+
+        self.builder.finish_node();
+
+        Ok(Statement {
+            kind: StatementKind::Let {
+                name,
+                mutable,
+                ty,
+                initial,
+            },
+            span: Span::new(start, self.tokens.last_end()),
+        })
+    }
+
+    /// `func name(a : T, b : U) : R = { ... }`, where the result type can be
+    /// left out: `func name() : = { ... }`. A `method` in an `impl` can't be
+    /// `pub` nor generic, and its first parameter is `self`.
+    fn parse_function(&mut self, method: bool) -> Result<Statement, OrionError> {
+        self.start_node(SyntaxKind::FunctionDecl);
+        let start = self.tokens.peek().span.start;
+        let public = !method && self.eat(&TokenKind::Pub).is_some();
+        self.expect(&TokenKind::Func)?;
+        let name = self.expect_identifier()?;
+
+        let generics = match method {
+            true => vec![],
+            false => self.parse_generic_params(true)?,
+        };
+
+        let parameters = self.parse_parameters(method)?;
+
+        let result = if self.eat(&TokenKind::Colon).is_some() {
+            let result = match self.tokens.at(&TokenKind::Eq) {
+                true => None,
+                false => Some(self.parse_type(true)?),
+            };
+
+            self.expect(&TokenKind::Eq)?;
+            result
+        } else if self.eat(&TokenKind::UntypedAssignment).is_some() {
+            None
+        } else {
+            self.expect(&TokenKind::Eq)?;
+            None
+        };
+
+        let body = self.parse_block()?;
+        self.builder.finish_node();
+
+        let function = Function {
+            name,
+            public,
+            generics,
+            parameters,
+            result,
+            body,
+        };
+
+        Ok(Statement {
+            kind: StatementKind::Function(function),
+            span: Span::new(start, self.tokens.last_end()),
+        })
+    }
+
+    /// `<A, B>`, the type parameters of a generic function or struct, if
+    /// there are any. With `bounds`, the traits a parameter's type
+    /// arguments implement can follow it, `<T : Show + Eq>`.
+    fn parse_generic_params(&mut self, bounds: bool) -> Result<Vec<Generic>, OrionError> {
+        if !self.tokens.at(&TokenKind::Lesser) {
+            return Ok(vec![]);
+        }
+
+        self.start_node(SyntaxKind::GenericParams);
+        self.bump();
+
+        let generics = self.list(&TokenKind::Greater, |parser| {
+            parser.start_node(SyntaxKind::GenericParam);
+            let start = parser.tokens.peek().span.start;
+            let name = parser.expect_identifier()?;
+            let mut traits = vec![];
+
+            if bounds && parser.eat(&TokenKind::Colon).is_some() {
+                traits.push(parser.parse_path_segments()?);
+
+                while parser.eat(&TokenKind::Plus).is_some() {
+                    traits.push(parser.parse_path_segments()?);
+                }
+            }
 
-                    if !matches!(
-                        self.lexer.by_ref().next(),
-                        Some(Token {
-                            kind: TokenKind::Eq,
-                            ..
-                        })
-                    ) {
-                        panic!("Assignment expected after let expression");
+            parser.builder.finish_node();
+
+            Ok(Generic {
+                name,
+                bounds: traits,
+                span: Span::new(start, parser.tokens.last_end()),
+            })
+        })?;
+
+        self.builder.finish_node();
+
+        Ok(generics)
+    }
+
+    /// The parameters of a function with their parentheses. The first
+    /// parameter of a `method` is `self`.
+    fn parse_parameters(&mut self, method: bool) -> Result<Vec<Parameter>, OrionError> {
+        self.start_node(SyntaxKind::ParamList);
+        self.expect(&TokenKind::LPar)?;
+
+        // A method takes `self` first, so its parameters can't be empty
+        if method && self.tokens.at(&TokenKind::RPar) {
+            return Err(self.tokens.unexpected("`self`"));
+        }
+
+        let mut receiver = method;
+        let parameters = self.list(&TokenKind::RPar, |parser| {
+            match std::mem::take(&mut receiver) {
+                true => parser.parse_self(),
+                false => parser.parse_parameter(),
+            }
+        })?;
+
+        self.builder.finish_node();
+
+        Ok(parameters)
+    }
+
+    /// `self`, the parameter of a method taking the value it is called on,
+    /// whose type is `Self`
+    fn parse_self(&mut self) -> Result<Parameter, OrionError> {
+        let token = self.tokens.peek().clone();
+
+        if token.kind != TokenKind::Identifier("self") {
+            return Err(self.tokens.unexpected("`self`"));
+        }
+
+        self.start_node(SyntaxKind::Param);
+        self.bump();
+        self.builder.finish_node();
+
+        let path = Path {
+            segments: vec!["Self".to_string()],
+            arguments: vec![],
+        };
+
+        Ok(Parameter {
+            name: "self".to_string(),
+            ty: Type::new(TypeKind::Named(path), token.span),
+            span: token.span,
+        })
+    }
+
+    /// `name : T`
+    fn parse_parameter(&mut self) -> Result<Parameter, OrionError> {
+        self.start_node(SyntaxKind::Param);
+        let start = self.tokens.peek().span.start;
+        let name = self.expect_identifier()?;
+        self.expect(&TokenKind::Colon)?;
+        let ty = self.parse_type(true)?;
+        self.builder.finish_node();
+
+        Ok(Parameter {
+            name,
+            span: Span::new(start, ty.span.end),
+            ty,
+        })
+    }
+
+    /// `struct Name { field : T, ... }`
+    fn parse_struct(&mut self) -> Result<Statement, OrionError> {
+        self.start_node(SyntaxKind::StructDecl);
+        let start = self.tokens.peek().span.start;
+        let public = self.eat(&TokenKind::Pub).is_some();
+        self.expect(&TokenKind::Struct)?;
+        let name = self.expect_identifier()?;
+        let generics = self.parse_generic_params(false)?;
+        self.expect(&TokenKind::LBrace)?;
+
+        let fields = self.list(&TokenKind::RBrace, |parser| {
+            parser.start_node(SyntaxKind::FieldDecl);
+            let start = parser.tokens.peek().span.start;
+            let name = parser.expect_identifier()?;
+            parser.expect(&TokenKind::Colon)?;
+            let ty = parser.parse_type(true)?;
+            parser.builder.finish_node();
+
+            Ok(Field {
+                name,
+                span: Span::new(start, ty.span.end),
+                ty,
+            })
+        })?;
+
+        self.builder.finish_node();
+
+        Ok(Statement {
+            kind: StatementKind::Struct(Struct {
+                name,
+                public,
+                generics,
+                fields,
+            }),
+            span: Span::new(start, self.tokens.last_end()),
+        })
+    }
+
+    /// `enum Name { Variant(T, U), Other, ... }`
+    fn parse_enum(&mut self) -> Result<Statement, OrionError> {
+        self.start_node(SyntaxKind::EnumDecl);
+        let start = self.tokens.peek().span.start;
+        let public = self.eat(&TokenKind::Pub).is_some();
+        self.expect(&TokenKind::Enum)?;
+        let name = self.expect_identifier()?;
+        self.expect(&TokenKind::LBrace)?;
+
+        let variants = self.list(&TokenKind::RBrace, |parser| {
+            parser.start_node(SyntaxKind::VariantDecl);
+            let start = parser.tokens.peek().span.start;
+            let name = parser.expect_identifier()?;
+
+            let fields = match parser.eat(&TokenKind::LPar) {
+                Some(_) => parser.list(&TokenKind::RPar, |parser| parser.parse_type(true))?,
+                None => vec![],
+            };
+
+            parser.builder.finish_node();
+
+            Ok(Variant {
+                name,
+                fields,
+                span: Span::new(start, parser.tokens.last_end()),
+            })
+        })?;
+
+        self.builder.finish_node();
+
+        Ok(Statement {
+            kind: StatementKind::Enum(Enum {
+                name,
+                public,
+                variants,
+            }),
+            span: Span::new(start, self.tokens.last_end()),
+        })
+    }
+
+    /// `mod name;`
+    fn parse_module(&mut self) -> Result<Statement, OrionError> {
+        self.start_node(SyntaxKind::ModuleDecl);
+        let start = self.tokens.peek().span.start;
+        let public = self.eat(&TokenKind::Pub).is_some();
+        self.expect(&TokenKind::Mod)?;
+        let name = self.expect_identifier()?;
+        self.expect(&TokenKind::Semi)?;
+        self.builder.finish_node();
+
+        Ok(Statement {
+            kind: StatementKind::Module(Module { name, public }),
+            span: Span::new(start, self.tokens.last_end()),
+        })
+    }
+
+    /// `import path::to::name;`
+    fn parse_import(&mut self) -> Result<Statement, OrionError> {
+        self.start_node(SyntaxKind::ImportDecl);
+        let start = self.expect(&TokenKind::Import)?.span.start;
+        let path = self.parse_path_segments()?;
+        self.expect(&TokenKind::Semi)?;
+        self.builder.finish_node();
+
+        Ok(Statement {
+            kind: StatementKind::Import(path),
+            span: Span::new(start, self.tokens.last_end()),
+        })
+    }
+
+    /// `trait Name { func method(self, a : T) : R; ... }`
+    fn parse_trait(&mut self) -> Result<Statement, OrionError> {
+        self.start_node(SyntaxKind::TraitDecl);
+        let start = self.tokens.peek().span.start;
+        let public = self.eat(&TokenKind::Pub).is_some();
+        self.expect(&TokenKind::Trait)?;
+        let name = self.expect_identifier()?;
+        self.expect(&TokenKind::LBrace)?;
+        let mut methods = vec![];
+
+        while !self.tokens.at(&TokenKind::RBrace) {
+            self.start_node(SyntaxKind::MethodDecl);
+            let start = self.expect(&TokenKind::Func)?.span.start;
+            let name = self.expect_identifier()?;
+            let parameters = self.parse_parameters(true)?;
+
+            let result = match self.eat(&TokenKind::Colon) {
+                Some(_) => Some(self.parse_type(true)?),
+                None => None,
+            };
+
+            self.expect(&TokenKind::Semi)?;
+            self.builder.finish_node();
+
+            methods.push(Method {
+                name,
+                parameters,
+                result,
+                span: Span::new(start, self.tokens.last_end()),
+            });
+        }
+
+        self.expect(&TokenKind::RBrace)?;
+        self.builder.finish_node();
+
+        Ok(Statement {
+            kind: StatementKind::Trait(Trait {
+                name,
+                public,
+                methods,
+            }),
+            span: Span::new(start, self.tokens.last_end()),
+        })
+    }
+
+    /// `impl Trait for Type { func method(self) : R = { ... } ... }`
+    fn parse_impl(&mut self) -> Result<Statement, OrionError> {
+        self.start_node(SyntaxKind::ImplDecl);
+        let start = self.expect(&TokenKind::Impl)?.span.start;
+        let trait_path = self.parse_path_segments()?;
+        self.expect(&TokenKind::For)?;
+        let ty = self.parse_type(true)?;
+        self.expect(&TokenKind::LBrace)?;
+        let mut methods = vec![];
+
+        while !self.tokens.at(&TokenKind::RBrace) {
+            methods.push(self.parse_function(true)?);
+        }
+
+        self.expect(&TokenKind::RBrace)?;
+        self.builder.finish_node();
+
+        Ok(Statement {
+            kind: StatementKind::Impl(Impl {
+                trait_path,
+                ty,
+                methods,
+            }),
+            span: Span::new(start, self.tokens.last_end()),
+        })
+    }
+
+    /// A `match` used as a statement, which like other statements ending
+    /// in a block doesn't need a `;`
+    fn parse_match_statement(&mut self) -> Result<Statement, OrionError> {
+        self.start_node(SyntaxKind::ExprStmt);
+        let expression = self.parse_match()?;
+        self.eat(&TokenKind::Semi);
+        self.builder.finish_node();
+
+        Ok(Statement {
+            span: Span::new(expression.span.start, self.tokens.last_end()),
+            kind: StatementKind::Expression(expression),
+        })
+    }
+
+    /// `{ statements }`
+    fn parse_block(&mut self) -> Result<Block, OrionError> {
+        self.start_node(SyntaxKind::Block);
+        let start = self.expect(&TokenKind::LBrace)?.span.start;
+        let statements = self.nested(|parser| Ok(parser.statements(&TokenKind::RBrace)))?;
+        self.expect(&TokenKind::RBrace)?;
+        self.builder.finish_node();
+
+        Ok(Block {
+            statements,
+            span: Span::new(start, self.tokens.last_end()),
+        })
+    }
+
+    /// `return;` or `return expression;`
+    fn parse_return(&mut self) -> Result<Statement, OrionError> {
+        self.start_node(SyntaxKind::ReturnStmt);
+        let start = self.expect(&TokenKind::Return)?.span.start;
+
+        let value = match self.tokens.at(&TokenKind::Semi) {
+            true => None,
+            false => Some(self.parse_expression()?),
+        };
+
+        self.expect(&TokenKind::Semi)?;
+        self.builder.finish_node();
+
+        Ok(Statement {
+            kind: StatementKind::Return(value),
+            span: Span::new(start, self.tokens.last_end()),
+        })
+    }
+
+    /// `if condition { ... }`, optionally followed by `else { ... }` or
+    /// `else if ...`
+    fn parse_if(&mut self) -> Result<Statement, OrionError> {
+        self.start_node(SyntaxKind::IfStmt);
+        let start = self.expect(&TokenKind::If)?.span.start;
+        let condition = self.struct_literals(false, |parser| parser.parse_expression())?;
+        let then = self.parse_block()?;
+
+        let otherwise = if self.eat(&TokenKind::Else).is_some() {
+            let otherwise = match self.tokens.at(&TokenKind::If) {
+                true => self.nested(|parser| parser.parse_if())?,
+                false => {
+                    let block = self.parse_block()?;
+
+                    Statement {
+                        span: block.span,
+                        kind: StatementKind::Block(block),
                     }
+                }
+            };
+
+            Some(Box::new(otherwise))
+        } else {
+            None
+        };
+
+        self.builder.finish_node();
+
+        Ok(Statement {
+            kind: StatementKind::If {
+                condition,
+                then,
+                otherwise,
+            },
+            span: Span::new(start, self.tokens.last_end()),
+        })
+    }
+
+    /// `$if condition { ... } else $if ... else { ... }`. The branch taken
+    /// replaces the `$if` when compiling, so at the top level the branches
+    /// declare items like the top level does.
+    fn parse_comptime_if(&mut self) -> Result<Statement, OrionError> {
+        let items = self.nesting == self.items;
 
-                    let expression = self.parse_expression();
+        self.start_node(SyntaxKind::ComptimeIfStmt);
+        let start = self.expect(&TokenKind::Dollar)?.span.start;
+        self.expect(&TokenKind::If)?;
+        let condition = self.struct_literals(false, |parser| parser.parse_expression())?;
+        let then = self.declaring_items(items, |parser| parser.parse_block())?;
 
-                    if !matches!(
-                        self.lexer.by_ref().next(),
-                        Some(Token {
-                            kind: TokenKind::Semi,
-                            ..
-                        })
-                    ) {
-                        panic!("Unclosed let assignment");
+        let otherwise = if self.eat(&TokenKind::Else).is_some() {
+            let otherwise = match self.tokens.at(&TokenKind::Dollar) {
+                true => self.declaring_items(items, |parser| {
+                    parser.nested(|parser| parser.parse_comptime_if())
+                })?,
+                false => {
+                    let block = self.declaring_items(items, |parser| parser.parse_block())?;
+
+                    Statement {
+                        span: block.span,
+                        kind: StatementKind::Block(block),
                     }
+                }
+            };
+
+            Some(Box::new(otherwise))
+        } else {
+            None
+        };
+
+        self.builder.finish_node();
+
+        Ok(Statement {
+            kind: StatementKind::ComptimeIf {
+                condition,
+                then,
+                otherwise,
+            },
+            span: Span::new(start, self.tokens.last_end()),
+        })
+    }
+
+    /// Run `parse` with the statements one level deeper declaring items, if
+    /// `items` is set
+    fn declaring_items<T>(
+        &mut self,
+        items: bool,
+        parse: impl FnOnce(&mut Self) -> Result<T, OrionError>,
+    ) -> Result<T, OrionError> {
+        if !items {
+            return parse(self);
+        }
+
+        let outer = std::mem::replace(&mut self.items, self.nesting + 1);
+        let result = parse(self);
+        self.items = outer;
+
+        result
+    }
+
+    /// A type. Generic arguments right after a name, as in `Pair<A, B>`,
+    /// are only allowed with `bare_generics`, otherwise they have to be
+    /// written `Pair::<A, B>`. This is the case after `as`, where `<` would
+    /// be a comparison.
+    fn parse_type(&mut self, bare_generics: bool) -> Result<Type, OrionError> {
+        self.nested(|parser| parser.parse_type_inner(bare_generics))
+    }
+
+    fn parse_type_inner(&mut self, bare_generics: bool) -> Result<Type, OrionError> {
+        let token = self.tokens.peek().clone();
+
+        let kind = match token.kind {
+            TokenKind::Identifier(_) => {
+                self.start_node(SyntaxKind::NamedType);
+                let path = self.parse_path(bare_generics)?;
+                self.builder.finish_node();
+
+                TypeKind::Named(path)
+            }
+            TokenKind::Ampersand => {
+                self.start_node(SyntaxKind::ReferenceType);
+                self.bump();
+                let mutable = self.eat(&TokenKind::Mut).is_some();
+                let inner = self.parse_type(bare_generics)?;
+                self.builder.finish_node();
+
+                TypeKind::Reference {
+                    inner: Box::new(inner),
+                    mutable,
+                }
+            }
+            // `[T; N]` or `[T]`
+            TokenKind::LBracket => {
+                let checkpoint = self.checkpoint();
+                self.bump();
+                let element = Box::new(self.parse_type(true)?);
+
+                let (kind, node) = if self.eat(&TokenKind::Semi).is_some() {
+                    let length = Box::new(self.parse_delimited_expression()?);
+                    (TypeKind::Array { element, length }, SyntaxKind::ArrayType)
+                } else {
+                    (TypeKind::Slice(element), SyntaxKind::SliceType)
+                };
+
+                self.builder.start_node_at(checkpoint, node);
+                self.expect(&TokenKind::RBracket)?;
+                self.builder.finish_node();
+
+                kind
+            }
+            // `()`, `(T)`, `(T,)` or `(A, B)`
+            TokenKind::LPar => {
+                let checkpoint = self.checkpoint();
+                self.bump();
+
+                if self.tokens.at(&TokenKind::RPar) {
+                    self.builder
+                        .start_node_at(checkpoint, SyntaxKind::TupleType);
+                    self.bump();
+                    self.builder.finish_node();
 
-                    let stmt = Statement::Let {
-                        name: format!("{}", identifier),
-                        initial: expression,
+                    TypeKind::Tuple(vec![])
+                } else {
+                    let first = self.parse_type(true)?;
+
+                    if self.tokens.at(&TokenKind::RPar) {
+                        self.builder
+                            .start_node_at(checkpoint, SyntaxKind::ParenType);
+                        self.bump();
+                        self.builder.finish_node();
+
+                        return Ok(Type::new(
+                            first.kind,
+                            Span::new(token.span.start, self.tokens.last_end()),
+                        ));
+                    }
+
+                    self.builder
+                        .start_node_at(checkpoint, SyntaxKind::TupleType);
+                    self.expect(&TokenKind::Comma)?;
+                    let mut elements = vec![first];
+                    elements.extend(self.list(&TokenKind::RPar, |parser| parser.parse_type(true))?);
+                    self.builder.finish_node();
+
+                    TypeKind::Tuple(elements)
+                }
+            }
+            // `func(A, B) -> R`
+            TokenKind::Func => {
+                self.start_node(SyntaxKind::FunctionType);
+                self.bump();
+                self.expect(&TokenKind::LPar)?;
+                let parameters = self.list(&TokenKind::RPar, |parser| parser.parse_type(true))?;
+
+                let result = match self.eat(&TokenKind::RightArrow) {
+                    Some(_) => Some(Box::new(self.parse_type(bare_generics)?)),
+                    None => None,
+                };
+
+                self.builder.finish_node();
+
+                TypeKind::Function { parameters, result }
+            }
+            _ => return Err(self.tokens.unexpected("a type")),
+        };
+
+        Ok(Type::new(
+            kind,
+            Span::new(token.span.start, self.tokens.last_end()),
+        ))
+    }
+
+    /// `a::b::C`, optionally followed by generic arguments
+    fn parse_path(&mut self, bare_generics: bool) -> Result<Path, OrionError> {
+        let mut segments = vec![self.expect_identifier()?];
+
+        loop {
+            if self.eat(&TokenKind::ColonColon).is_some() {
+                if self.tokens.at(&TokenKind::Lesser) {
+                    break;
+                }
+
+                segments.push(self.expect_identifier()?);
+            } else if !(bare_generics && self.tokens.at(&TokenKind::Lesser)) {
+                return Ok(Path {
+                    segments,
+                    arguments: vec![],
+                });
+            } else {
+                break;
+            }
+        }
+
+        self.start_node(SyntaxKind::GenericArgs);
+        self.expect(&TokenKind::Lesser)?;
+        let arguments = self.list(&TokenKind::Greater, |parser| parser.parse_type(true))?;
+        self.builder.finish_node();
+
+        Ok(Path {
+            segments,
+            arguments,
+        })
+    }
+
+    fn expect_identifier(&mut self) -> Result<String, OrionError> {
+        match self.tokens.peek().kind {
+            TokenKind::Identifier(ident) => {
+                self.bump();
+                Ok(ident.to_string())
+            }
+            _ => Err(self.tokens.unexpected("an identifier")),
+        }
+    }
+
+    fn parse_expression(&mut self) -> Result<Expression, OrionError> {
+        self.nested(|parser| parser.parse_binary(0))
+    }
+
+    /// Run `parse` with struct literals allowed or not. Within delimiters
+    /// like parentheses they are always allowed again.
+    fn struct_literals<T>(
+        &mut self,
+        allowed: bool,
+        parse: impl FnOnce(&mut Self) -> Result<T, OrionError>,
+    ) -> Result<T, OrionError> {
+        let outer = std::mem::replace(&mut self.struct_literals, allowed);
+        let result = parse(self);
+        self.struct_literals = outer;
+
+        result
+    }
+
+    /// An expression between delimiters, where struct literals are allowed
+    fn parse_delimited_expression(&mut self) -> Result<Expression, OrionError> {
+        self.struct_literals(true, |parser| parser.parse_expression())
+    }
+
+    /// Run `parse` one level of nesting deeper, failing if that's too deep
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, OrionError>,
+    ) -> Result<T, OrionError> {
+        if self.nesting >= MAX_NESTING {
+            let loc = self.tokens.peek().loc;
+            return Err(OrionError::NestingTooDeep { loc });
+        }
+
+        self.nesting += 1;
+        let result = parse(self);
+        self.nesting -= 1;
+
+        result
+    }
+
+    /// Precedence climbing over the binary operators. Only operators binding
+    /// at least as tightly as `min_precedence` are consumed, and since all
+    /// operators are left associative the right hand side must bind strictly
+    /// tighter.
+    fn parse_binary(&mut self, min_precedence: u8) -> Result<Expression, OrionError> {
+        let checkpoint = self.checkpoint();
+        let mut lhs = self.parse_cast()?;
+
+        while let Some(op) = binary_operator(&self.tokens.peek().kind) {
+            if op.precedence() < min_precedence {
+                break;
+            }
+
+            self.builder
+                .start_node_at(checkpoint, SyntaxKind::BinaryExpr);
+            self.bump();
+            let rhs = self.parse_binary(op.precedence() + 1)?;
+            self.builder.finish_node();
+
+            let span = lhs.span.to(rhs.span);
+            lhs = Expression::new(
+                ExpressionKind::Binary {
+                    op,
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                },
+                span,
+            );
+        }
+
+        // `<-` assigns in some languages, and `a<-1` may be meant as
+        // `a < -1`. It is neither here, so say what to write instead.
+        let token = self.tokens.peek();
+
+        if token.kind == TokenKind::LeftArrow {
+            return Err(OrionError::ArrowAssignment { loc: token.loc });
+        }
+
+        Ok(lhs)
+    }
+
+    /// `expression as T`, which may be chained: `x as u8 as u32`
+    fn parse_cast(&mut self) -> Result<Expression, OrionError> {
+        let checkpoint = self.checkpoint();
+        let mut expression = self.parse_unary()?;
+
+        while self.tokens.at(&TokenKind::As) {
+            self.builder.start_node_at(checkpoint, SyntaxKind::CastExpr);
+            self.bump();
+            let ty = self.parse_type(false)?;
+            self.builder.finish_node();
+
+            let span = expression.span.to(ty.span);
+            let kind = ExpressionKind::Cast {
+                expression: Box::new(expression),
+                ty,
+            };
+
+            expression = Expression::new(kind, span);
+        }
+
+        Ok(expression)
+    }
+
+    /// Prefix operators, which may be stacked: `-~x`
+    fn parse_unary(&mut self) -> Result<Expression, OrionError> {
+        let token = self.tokens.peek().clone();
+
+        let op = match token.kind {
+            TokenKind::Minus => UnaryOperator::Neg,
+            TokenKind::Bang => UnaryOperator::Not,
+            TokenKind::Tilde => UnaryOperator::BitNot,
+            TokenKind::Ampersand => UnaryOperator::Ref,
+            TokenKind::Star => UnaryOperator::Deref,
+            TokenKind::Increment => UnaryOperator::PreIncrement,
+            TokenKind::Decrement => UnaryOperator::PreDecrement,
+            _ => return self.parse_postfix(),
+        };
+
+        self.start_node(SyntaxKind::PrefixExpr);
+        self.bump();
+
+        let op = match op {
+            UnaryOperator::Ref if self.eat(&TokenKind::Mut).is_some() => UnaryOperator::RefMut,
+            op => op,
+        };
+
+        let operand = self.nested(|parser| parser.parse_unary())?;
+        self.builder.finish_node();
+
+        let span = token.span.to(operand.span);
+        unary(op, operand, span, token.loc)
+    }
+
+    /// Postfix `++` and `--`, calls, field accesses, method calls and
+    /// indexing
+    fn parse_postfix(&mut self) -> Result<Expression, OrionError> {
+        let checkpoint = self.checkpoint();
+        let mut expression = self.parse_primary()?;
+
+        loop {
+            let op = match self.tokens.peek().kind {
+                TokenKind::Increment => UnaryOperator::PostIncrement,
+                TokenKind::Decrement => UnaryOperator::PostDecrement,
+                TokenKind::LPar => {
+                    self.builder.start_node_at(checkpoint, SyntaxKind::CallExpr);
+                    self.start_node(SyntaxKind::ArgList);
+                    self.bump();
+                    let arguments = self.list(&TokenKind::RPar, |parser| {
+                        parser.parse_delimited_expression()
+                    })?;
+                    self.builder.finish_node();
+                    self.builder.finish_node();
+
+                    let span = Span::new(expression.span.start, self.tokens.last_end());
+                    let kind = ExpressionKind::Call {
+                        callee: Box::new(expression),
+                        arguments,
                     };
 
-                    stmts.push(stmt);
+                    expression = Expression::new(kind, span);
+                    continue;
+                }
+                TokenKind::Dot => {
+                    let start = expression.span.start;
+                    self.bump();
+                    let name = self.expect_identifier()?;
+
+                    let kind = if self.tokens.at(&TokenKind::LPar) {
+                        self.builder
+                            .start_node_at(checkpoint, SyntaxKind::MethodCallExpr);
+                        self.start_node(SyntaxKind::ArgList);
+                        self.bump();
+                        let arguments = self.list(&TokenKind::RPar, |parser| {
+                            parser.parse_delimited_expression()
+                        })?;
+                        self.builder.finish_node();
+
+                        ExpressionKind::MethodCall {
+                            receiver: Box::new(expression),
+                            method: name,
+                            arguments,
+                        }
+                    } else {
+                        self.builder
+                            .start_node_at(checkpoint, SyntaxKind::FieldExpr);
+
+                        ExpressionKind::Field {
+                            expression: Box::new(expression),
+                            field: name,
+                        }
+                    };
+
+                    self.builder.finish_node();
+                    let span = Span::new(start, self.tokens.last_end());
+                    expression = Expression::new(kind, span);
+                    continue;
+                }
+                TokenKind::LBracket => {
+                    expression = self.parse_index(checkpoint, expression)?;
+                    continue;
+                }
+                _ => break,
+            };
+
+            self.builder
+                .start_node_at(checkpoint, SyntaxKind::PostfixExpr);
+            let token = self.bump();
+            self.builder.finish_node();
+
+            let span = expression.span.to(token.span);
+            expression = unary(op, expression, span, token.loc)?;
+        }
+
+        Ok(expression)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expression, OrionError> {
+        let token = self.tokens.peek().clone();
+
+        let (kind, node) = match token.kind {
+            TokenKind::Number(num) => (
+                ExpressionKind::Number(parse_number(num, token.loc)?),
+                SyntaxKind::NumberExpr,
+            ),
+            TokenKind::True => (ExpressionKind::Bool(true), SyntaxKind::LiteralExpr),
+            TokenKind::False => (ExpressionKind::Bool(false), SyntaxKind::LiteralExpr),
+            TokenKind::Quote(quote) => (
+                ExpressionKind::String(unescape(&quote[1..quote.len() - 1], token.loc)?),
+                SyntaxKind::LiteralExpr,
+            ),
+            TokenKind::Char(literal) => (
+                ExpressionKind::Char(parse_char(literal, token.loc)?),
+                SyntaxKind::LiteralExpr,
+            ),
+            TokenKind::Identifier(_)
+                if self.struct_literals && self.tokens.peek_nth(1).kind == TokenKind::LBrace =>
+            {
+                return self.parse_struct_literal();
+            }
+            TokenKind::Identifier(_) if self.tokens.peek_nth(1).kind == TokenKind::ColonColon => {
+                self.start_node(SyntaxKind::PathExpr);
+                let segments = self.parse_path_segments()?;
+                self.builder.finish_node();
+
+                return Ok(Expression::new(
+                    ExpressionKind::Path(segments),
+                    Span::new(token.span.start, self.tokens.last_end()),
+                ));
+            }
+            TokenKind::Match => return self.parse_match(),
+            TokenKind::Identifier(ident) => {
+                // What it refers to is found by `crate::sema::names`
+                (
+                    ExpressionKind::Identifier(ident.to_string()),
+                    SyntaxKind::IdentifierExpr,
+                )
+            }
+            TokenKind::LPar => {
+                self.start_node(SyntaxKind::ParenExpr);
+                self.bump();
+                let mut expression = self.parse_delimited_expression()?;
+                self.expect(&TokenKind::RPar)?;
+                self.builder.finish_node();
+
+                // `(T)x` is how C spells a cast. Here it is always a
+                // parenthesized value, so if `T` can only be a type point the
+                // user at `as`.
+                if let ExpressionKind::Identifier(name) = &expression.kind {
+                    if is_primitive_type(name) && starts_operand(&self.tokens.peek().kind) {
+                        return Err(OrionError::CStyleCast {
+                            ty: name.clone(),
+                            loc: token.loc,
+                        });
+                    }
+                }
+
+                // The parentheses are part of the expression's span
+                expression.span = Span::new(token.span.start, self.tokens.last_end());
+
+                return Ok(expression);
+            }
+            TokenKind::LBracket => return self.parse_array(),
+            TokenKind::Bar => return self.parse_closure(),
+            TokenKind::Dollar => return self.parse_comptime(),
+            _ => return Err(self.tokens.unexpected("an expression")),
+        };
+
+        self.start_node(node);
+        self.bump();
+        self.builder.finish_node();
+
+        Ok(Expression::new(kind, token.span))
+    }
+
+    /// `|a : T, b| -> R = value`, the body being an expression or a block
+    fn parse_closure(&mut self) -> Result<Expression, OrionError> {
+        self.start_node(SyntaxKind::ClosureExpr);
+        let start = self.expect(&TokenKind::Bar)?.span.start;
+
+        let parameters = self.list(&TokenKind::Bar, |parser| {
+            parser.start_node(SyntaxKind::ClosureParam);
+            let start = parser.tokens.peek().span.start;
+            let name = parser.expect_identifier()?;
+
+            let ty = match parser.eat(&TokenKind::Colon) {
+                Some(_) => Some(parser.parse_type(true)?),
+                None => None,
+            };
+
+            parser.builder.finish_node();
+
+            Ok(ClosureParameter {
+                name,
+                ty,
+                span: Span::new(start, parser.tokens.last_end()),
+            })
+        })?;
+
+        let result = match self.eat(&TokenKind::RightArrow) {
+            Some(_) => Some(Box::new(self.parse_type(true)?)),
+            None => None,
+        };
+
+        self.expect(&TokenKind::Eq)?;
+
+        let body = if self.tokens.at(&TokenKind::LBrace) {
+            let block = self.parse_block()?;
+
+            let span = block.span;
+            Expression::new(ExpressionKind::Block(block), span)
+        } else {
+            self.parse_expression()?
+        };
+
+        self.builder.finish_node();
+
+        let kind = ExpressionKind::Closure(Closure {
+            parameters,
+            result,
+            body: Box::new(body),
+        });
+
+        Ok(Expression::new(
+            kind,
+            Span::new(start, self.tokens.last_end()),
+        ))
+    }
+
+    /// `$(value)`, or `${ ... }` which gives the value it returns
+    fn parse_comptime(&mut self) -> Result<Expression, OrionError> {
+        self.start_node(SyntaxKind::ComptimeExpr);
+        let start = self.expect(&TokenKind::Dollar)?.span.start;
+
+        let inner = if self.tokens.at(&TokenKind::LBrace) {
+            let block = self.parse_block()?;
+
+            let span = block.span;
+            Expression::new(ExpressionKind::Block(block), span)
+        } else if self.eat(&TokenKind::LPar).is_some() {
+            let inner = self.parse_delimited_expression()?;
+            self.expect(&TokenKind::RPar)?;
+
+            inner
+        } else {
+            return Err(self.tokens.unexpected("`(` or `{`"));
+        };
+
+        self.builder.finish_node();
+
+        Ok(Expression::new(
+            ExpressionKind::Comptime(Box::new(inner)),
+            Span::new(start, self.tokens.last_end()),
+        ))
+    }
+
+    /// `[a, b, c]` or `[value; length]`
+    fn parse_array(&mut self) -> Result<Expression, OrionError> {
+        let checkpoint = self.checkpoint();
+        let start = self.bump().span.start;
+
+        let (kind, node) = if self.eat(&TokenKind::RBracket).is_some() {
+            (ExpressionKind::Array(vec![]), SyntaxKind::ArrayExpr)
+        } else {
+            let value = self.parse_delimited_expression()?;
+
+            if self.eat(&TokenKind::Semi).is_some() {
+                let length = self.parse_delimited_expression()?;
+                self.expect(&TokenKind::RBracket)?;
+
+                let kind = ExpressionKind::ArrayRepeat {
+                    value: Box::new(value),
+                    length: Box::new(length),
+                };
+
+                (kind, SyntaxKind::ArrayRepeatExpr)
+            } else {
+                let mut elements = vec![value];
+
+                if self.eat(&TokenKind::Comma).is_some() {
+                    elements.extend(self.list(&TokenKind::RBracket, |parser| {
+                        parser.parse_delimited_expression()
+                    })?);
+                } else {
+                    self.expect(&TokenKind::RBracket)?;
                 }
-                _ => unimplemented!(),
+
+                (ExpressionKind::Array(elements), SyntaxKind::ArrayExpr)
             }
+        };
+
+        // The kind of node is only known once the `]` is reached
+        self.builder.start_node_at(checkpoint, node);
+        self.builder.finish_node();
+
+        Ok(Expression::new(
+            kind,
+            Span::new(start, self.tokens.last_end()),
+        ))
+    }
+
+    /// `expression[index]`, or `expression[start..end]` where either bound
+    /// can be left out
+    fn parse_index(
+        &mut self,
+        checkpoint: Checkpoint,
+        expression: Expression,
+    ) -> Result<Expression, OrionError> {
+        self.bump();
+        let span_start = expression.span.start;
+
+        let start = if self.tokens.at(&TokenKind::DotDot) {
+            None
+        } else {
+            Some(self.parse_delimited_expression()?)
+        };
+
+        let (kind, node) = match start {
+            Some(index) if self.eat(&TokenKind::DotDot).is_none() => {
+                let kind = ExpressionKind::Index {
+                    expression: Box::new(expression),
+                    index: Box::new(index),
+                };
+
+                (kind, SyntaxKind::IndexExpr)
+            }
+            start => {
+                if start.is_none() {
+                    self.expect(&TokenKind::DotDot)?;
+                }
+
+                let end = if self.tokens.at(&TokenKind::RBracket) {
+                    None
+                } else {
+                    Some(self.parse_delimited_expression()?)
+                };
+
+                let kind = ExpressionKind::Slice {
+                    expression: Box::new(expression),
+                    start: start.map(Box::new),
+                    end: end.map(Box::new),
+                };
+
+                (kind, SyntaxKind::SliceExpr)
+            }
+        };
+
+        self.expect(&TokenKind::RBracket)?;
+
+        self.builder.start_node_at(checkpoint, node);
+        self.builder.finish_node();
+
+        Ok(Expression::new(
+            kind,
+            Span::new(span_start, self.tokens.last_end()),
+        ))
+    }
+
+    /// `a::b::c`
+    fn parse_path_segments(&mut self) -> Result<Vec<String>, OrionError> {
+        let mut segments = vec![self.expect_identifier()?];
+
+        while self.eat(&TokenKind::ColonColon).is_some() {
+            segments.push(self.expect_identifier()?);
         }
 
-        stmts
+        Ok(segments)
+    }
+
+    /// `match scrutinee { pattern => value, ... }`. The comma after an arm
+    /// whose value is a block can be left out.
+    fn parse_match(&mut self) -> Result<Expression, OrionError> {
+        self.start_node(SyntaxKind::MatchExpr);
+        let start = self.expect(&TokenKind::Match)?.span.start;
+        let scrutinee = self.struct_literals(false, |parser| parser.parse_expression())?;
+        self.expect(&TokenKind::LBrace)?;
+
+        let mut arms = vec![];
+
+        while !self.tokens.at(&TokenKind::RBrace) {
+            self.start_node(SyntaxKind::MatchArm);
+            let pattern = self.parse_pattern()?;
+            self.expect(&TokenKind::FatArrow)?;
+
+            let body = if self.tokens.at(&TokenKind::LBrace) {
+                let block = self.parse_block()?;
+                self.eat(&TokenKind::Comma);
+
+                let span = block.span;
+                Expression::new(ExpressionKind::Block(block), span)
+            } else {
+                let body = self.parse_delimited_expression()?;
+
+                if self.eat(&TokenKind::Comma).is_none() && !self.tokens.at(&TokenKind::RBrace) {
+                    return Err(self.tokens.unexpected("`,` or `}`"));
+                }
+
+                body
+            };
+
+            self.builder.finish_node();
+
+            arms.push(MatchArm {
+                span: pattern.span.to(body.span),
+                pattern,
+                body,
+            });
+        }
+
+        self.expect(&TokenKind::RBrace)?;
+        self.builder.finish_node();
+
+        let kind = ExpressionKind::Match {
+            scrutinee: Box::new(scrutinee),
+            arms,
+        };
+
+        Ok(Expression::new(
+            kind,
+            Span::new(start, self.tokens.last_end()),
+        ))
     }
 
-    fn parse_expression(&mut self) -> Expression {
-        match self.lexer.next() {
-            Some(Token {
-                kind: TokenKind::Number(num),
-                ..
-            }) => Expression::Number(num.replace("_", "").parse::<f64>().unwrap()),
-            Some(Token {
-                kind: TokenKind::Identifier(ident),
-                ..
-            }) => {
-                // TODO: Add checking if it is an existing variable, function, etc...
-                Expression::Identifier(ident.to_string())
+    fn parse_pattern(&mut self) -> Result<Pattern, OrionError> {
+        self.nested(|parser| parser.parse_pattern_inner())
+    }
+
+    /// `_`, a name, a literal, or a variant with patterns for its fields
+    fn parse_pattern_inner(&mut self) -> Result<Pattern, OrionError> {
+        let token = self.tokens.peek().clone();
+
+        let (kind, node) = match token.kind {
+            TokenKind::Identifier(_) if self.tokens.peek_nth(1).kind == TokenKind::ColonColon => {
+                self.start_node(SyntaxKind::VariantPat);
+                let path = self.parse_path_segments()?;
+
+                let fields = match self.eat(&TokenKind::LPar) {
+                    Some(_) => self.list(&TokenKind::RPar, |parser| parser.parse_pattern())?,
+                    None => vec![],
+                };
+
+                self.builder.finish_node();
+
+                return Ok(Pattern {
+                    kind: PatternKind::Variant { path, fields },
+                    span: Span::new(token.span.start, self.tokens.last_end()),
+                });
+            }
+            TokenKind::Identifier("_") => (PatternKind::Wildcard, SyntaxKind::WildcardPat),
+            TokenKind::Identifier(name) => (
+                PatternKind::Binding(name.to_string()),
+                SyntaxKind::BindingPat,
+            ),
+            TokenKind::Number(num) => (
+                PatternKind::Number(parse_number(num, token.loc)?),
+                SyntaxKind::LiteralPat,
+            ),
+            // A negative number
+            TokenKind::Minus => {
+                self.start_node(SyntaxKind::LiteralPat);
+                self.bump();
+
+                let number = self.tokens.peek().clone();
+                let TokenKind::Number(num) = number.kind else {
+                    return Err(self.tokens.unexpected("a number"));
+                };
+
+                self.bump();
+                self.builder.finish_node();
+
+                return Ok(Pattern {
+                    kind: PatternKind::Number(-parse_number(num, number.loc)?),
+                    span: token.span.to(number.span),
+                });
             }
-            _ => Expression::Nil,
+            TokenKind::True => (PatternKind::Bool(true), SyntaxKind::LiteralPat),
+            TokenKind::False => (PatternKind::Bool(false), SyntaxKind::LiteralPat),
+            TokenKind::Char(literal) => (
+                PatternKind::Char(parse_char(literal, token.loc)?),
+                SyntaxKind::LiteralPat,
+            ),
+            _ => return Err(self.tokens.unexpected("a pattern")),
+        };
+
+        self.start_node(node);
+        self.bump();
+        self.builder.finish_node();
+
+        Ok(Pattern {
+            kind,
+            span: token.span,
+        })
+    }
+
+    /// `Name { field: value, ... }`
+    fn parse_struct_literal(&mut self) -> Result<Expression, OrionError> {
+        self.start_node(SyntaxKind::StructLit);
+        let start = self.tokens.peek().span.start;
+        let name = self.expect_identifier()?;
+        self.expect(&TokenKind::LBrace)?;
+
+        let fields = self.list(&TokenKind::RBrace, |parser| {
+            parser.start_node(SyntaxKind::FieldInit);
+            let start = parser.tokens.peek().span.start;
+            let name = parser.expect_identifier()?;
+            parser.expect(&TokenKind::Colon)?;
+            let value = parser.parse_delimited_expression()?;
+            parser.builder.finish_node();
+
+            Ok(FieldInit {
+                name,
+                span: Span::new(start, value.span.end),
+                value,
+            })
+        })?;
+
+        self.builder.finish_node();
+
+        Ok(Expression::new(
+            ExpressionKind::StructLiteral { name, fields },
+            Span::new(start, self.tokens.last_end()),
+        ))
+    }
+}
+
+/// Parse a number literal, which may contain `_` separators. A whole
+/// number has to fit in a `u64`, the largest integer type.
+fn parse_number(literal: &str, loc: Location) -> Result<Number, OrionError> {
+    let digits = literal.replace('_', "");
+
+    let number = if digits.contains('.') {
+        digits.parse::<f64>().ok().map(Number::Float)
+    } else {
+        digits
+            .parse::<u64>()
+            .ok()
+            .map(|num| Number::Integer(num.into()))
+    };
+
+    number.ok_or_else(|| OrionError::InvalidNumber {
+        literal: literal.to_string(),
+        loc,
+    })
+}
+
+/// A unary expression, checking that mutating operators are applied to a
+/// place
+fn unary(
+    op: UnaryOperator,
+    operand: Expression,
+    span: Span,
+    loc: Location,
+) -> Result<Expression, OrionError> {
+    if op.is_mutating() && !operand.is_place() {
+        return Err(OrionError::NotAssignable {
+            operator: op.symbol().to_string(),
+            loc,
+        });
+    }
+
+    let kind = ExpressionKind::Unary {
+        op,
+        operand: Box::new(operand),
+    };
+
+    Ok(Expression::new(kind, span))
+}
+
+/// Resolve the escape sequences in the body of a string or character literal
+fn unescape(body: &str, loc: Location) -> Result<String, OrionError> {
+    let mut out = String::with_capacity(body.len());
+    let mut chars = body.chars();
+
+    while let Some(chr) = chars.next() {
+        if chr != '\\' {
+            out.push(chr);
+            continue;
         }
+
+        let escaped = match chars.next() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('0') => '\0',
+            Some('\\') => '\\',
+            Some('"') => '"',
+            Some('\'') => '\'',
+            other => {
+                return Err(OrionError::InvalidEscape {
+                    escape: format!("\\{}", other.map(String::from).unwrap_or_default()),
+                    loc,
+                })
+            }
+        };
+
+        out.push(escaped);
     }
+
+    Ok(out)
+}
+
+/// Parse a character literal, including its quotes
+fn parse_char(literal: &str, loc: Location) -> Result<char, OrionError> {
+    let body = unescape(&literal[1..literal.len() - 1], loc)?;
+    let mut chars = body.chars();
+
+    match (chars.next(), chars.next()) {
+        (Some(chr), None) => Ok(chr),
+        _ => Err(OrionError::InvalidCharLiteral {
+            literal: literal.to_string(),
+            loc,
+        }),
+    }
+}
+
+/// Whether a token can start the operand of a prefix operator, which is
+/// what would follow the type in a C-style cast
+fn starts_operand(kind: &TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::Identifier(_)
+            | TokenKind::Number(_)
+            | TokenKind::Quote(_)
+            | TokenKind::Char(_)
+            | TokenKind::True
+            | TokenKind::False
+            | TokenKind::LPar
+            | TokenKind::LBracket
+            | TokenKind::Match
+            | TokenKind::Dollar
+            | TokenKind::Star
+            | TokenKind::Ampersand
+            | TokenKind::Minus
+            | TokenKind::Bang
+            | TokenKind::Tilde
+            | TokenKind::Increment
+            | TokenKind::Decrement
+    )
+}
+
+/// The operator of a compound assignment token, like `+` for `+=`
+fn compound_assignment(kind: &TokenKind) -> Option<BinaryOperator> {
+    let op = match kind {
+        TokenKind::PlusEq => BinaryOperator::Add,
+        TokenKind::MinusEq => BinaryOperator::Sub,
+        TokenKind::StarEq => BinaryOperator::Mul,
+        TokenKind::SlashEq => BinaryOperator::Div,
+        TokenKind::PercentEq => BinaryOperator::Rem,
+        TokenKind::AmpersandEq => BinaryOperator::BitAnd,
+        TokenKind::BarEq => BinaryOperator::BitOr,
+        TokenKind::HatEq => BinaryOperator::BitXor,
+        TokenKind::LesserLesserEq => BinaryOperator::Shl,
+        TokenKind::GreaterGreaterEq => BinaryOperator::Shr,
+        _ => return None,
+    };
+
+    Some(op)
+}
+
+/// The binary operator a token stands for, if any
+fn binary_operator(kind: &TokenKind) -> Option<BinaryOperator> {
+    let op = match kind {
+        TokenKind::Plus => BinaryOperator::Add,
+        TokenKind::Minus => BinaryOperator::Sub,
+        TokenKind::Star => BinaryOperator::Mul,
+        TokenKind::Slash => BinaryOperator::Div,
+        TokenKind::Percent => BinaryOperator::Rem,
+        TokenKind::Ampersand => BinaryOperator::BitAnd,
+        TokenKind::Bar => BinaryOperator::BitOr,
+        TokenKind::Hat => BinaryOperator::BitXor,
+        TokenKind::LesserLesser => BinaryOperator::Shl,
+        TokenKind::GreaterGreater => BinaryOperator::Shr,
+        TokenKind::EqEq => BinaryOperator::Eq,
+        TokenKind::BangEq => BinaryOperator::NotEq,
+        TokenKind::Lesser => BinaryOperator::Less,
+        TokenKind::LesserEq => BinaryOperator::LessEq,
+        TokenKind::Greater => BinaryOperator::Greater,
+        TokenKind::GreaterEq => BinaryOperator::GreaterEq,
+        _ => return None,
+    };
+
+    Some(op)
 }
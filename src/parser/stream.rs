@@ -0,0 +1,180 @@
+//! A buffered stream of tokens for the parser.
+//!
+//! Tokens are pulled from the [`Lexer`] on demand and kept around, which
+//! gives the parser arbitrary lookahead and lets it go back to an earlier
+//! [`Checkpoint`] when it tried the wrong path. Comments are not part of the
+//! grammar, so they are skipped here and never reach the parser.
+use crate::error::OrionError;
+use crate::lexer::state::Lexer;
+use crate::lexer::tokens::*;
+use std::mem::discriminant;
+
+pub struct TokenStream<'a> {
+    lexer: Lexer<'a>,
+    // Every significant token lexed so far. The last one is always `Eof`
+    // once the lexer is exhausted.
+    tokens: Vec<Token<'a>>,
+    // Index of the next token to be consumed
+    pos: usize,
+}
+
+/// A position in the stream to go back to with [`TokenStream::rewind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint(usize);
+
+impl<'a> TokenStream<'a> {
+    pub fn new(lexer: Lexer<'a>) -> Self {
+        Self {
+            lexer,
+            tokens: vec![],
+            pos: 0,
+        }
+    }
+
+    /// Make sure the token `n` places ahead is buffered, as far as the input
+    /// goes.
+    fn fill(&mut self, n: usize) {
+        while self.tokens.len() <= self.pos + n {
+            if matches!(
+                self.tokens.last(),
+                Some(Token {
+                    kind: TokenKind::Eof,
+                    ..
+                })
+            ) {
+                return;
+            }
+
+            let token = self.lexer.next_token();
+
+            if !matches!(token.kind, TokenKind::Comment(_)) {
+                self.tokens.push(token);
+            }
+        }
+    }
+
+    /// The source text the tokens come from
+    pub fn source(&self) -> &'a str {
+        self.lexer.input()
+    }
+
+    /// The next token, without consuming it
+    pub fn peek(&mut self) -> &Token<'a> {
+        self.peek_nth(0)
+    }
+
+    /// The token `n` places ahead, `peek_nth(0)` being the next token. Looking
+    /// past the end of the input gives the `Eof` token.
+    pub fn peek_nth(&mut self, n: usize) -> &Token<'a> {
+        self.fill(n);
+
+        let index = (self.pos + n).min(self.tokens.len() - 1);
+        &self.tokens[index]
+    }
+
+    /// Consume the next token. At the end of the input this keeps returning
+    /// the `Eof` token.
+    pub fn advance(&mut self) -> Token<'a> {
+        let token = self.peek().clone();
+
+        if token.kind != TokenKind::Eof {
+            self.pos += 1;
+        }
+
+        token
+    }
+
+    /// Whether the next token is of the given kind. Payloads are ignored, so
+    /// `at(&TokenKind::Identifier(""))` matches any identifier.
+    pub fn at(&mut self, kind: &TokenKind) -> bool {
+        discriminant(&self.peek().kind) == discriminant(kind)
+    }
+
+    /// Consume the next token if it is of the given kind
+    pub fn eat_if(&mut self, kind: &TokenKind) -> Option<Token<'a>> {
+        if self.at(kind) {
+            Some(self.advance())
+        } else {
+            None
+        }
+    }
+
+    /// Consume the next token, which has to be of the given kind
+    pub fn expect(&mut self, kind: &TokenKind) -> Result<Token<'a>, OrionError> {
+        if self.at(kind) {
+            Ok(self.advance())
+        } else {
+            Err(self.unexpected(&format!("`{kind}`")))
+        }
+    }
+
+    /// Split a token in front that starts with the closing `end` token into
+    /// `end` and the rest: a `>>`, `>=` or `>>=` to close nested generic
+    /// arguments like `Pair<A, Pair<B, C>>`, or a `|=` to close the
+    /// parameters of a closure like `|x|= x`
+    pub fn split_closing(&mut self, end: &TokenKind<'a>) {
+        let token = self.peek().clone();
+
+        let rest = match (end, &token.kind) {
+            (TokenKind::Greater, TokenKind::GreaterGreater) => TokenKind::Greater,
+            (TokenKind::Greater, TokenKind::GreaterEq) => TokenKind::Eq,
+            (TokenKind::Greater, TokenKind::GreaterGreaterEq) => TokenKind::GreaterEq,
+            (TokenKind::Bar, TokenKind::BarEq) => TokenKind::Eq,
+            _ => return,
+        };
+
+        let start = token.span.start;
+        let loc = Location::new(token.loc.line(), token.loc.column() + 1);
+
+        let tokens = [
+            Token {
+                kind: end.clone(),
+                loc: token.loc,
+                span: Span::new(start, start + 1),
+            },
+            Token {
+                kind: rest,
+                loc,
+                span: Span::new(start + 1, token.span.end),
+            },
+        ];
+
+        self.tokens.splice(self.pos..=self.pos, tokens);
+    }
+
+    /// An error for the next token not being what the grammar `expected`
+    pub fn unexpected(&mut self, expected: &str) -> OrionError {
+        let token = self.peek();
+
+        let found = match token.kind {
+            TokenKind::Eof => "end of file".to_string(),
+            TokenKind::Error => "an invalid token".to_string(),
+            _ => format!("`{}`", token.kind),
+        };
+
+        OrionError::UnexpectedToken {
+            expected: expected.to_string(),
+            found,
+            loc: token.loc,
+        }
+    }
+
+    /// Where the last consumed token ended
+    pub fn last_end(&self) -> usize {
+        match self.pos {
+            0 => 0,
+            pos => self.tokens[pos - 1].span.end,
+        }
+    }
+
+    /// The position of the next token, to go back to with [`Self::rewind`]
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.pos)
+    }
+
+    /// Go back to a checkpoint, the tokens consumed since then will be read
+    /// again.
+    pub fn rewind(&mut self, checkpoint: Checkpoint) {
+        self.pos = checkpoint.0;
+    }
+}
@@ -0,0 +1,580 @@
+//! Traversal traits over the AST.
+//!
+//! Passes such as name resolution, type checking or linting implement
+//! [`Visitor`] and only override the `visit_*` methods for the nodes they care
+//! about. Every method defaults to the matching `walk_*` function, which
+//! visits the node's children, so an overriding method can call the walk
+//! function itself to keep descending.
+//!
+//! [`VisitorMut`] is the same idea over mutable references, for passes that
+//! rewrite the tree in place (desugaring, constant folding, ...). A node can
+//! be replaced entirely by assigning through the reference.
+use crate::parser::ast::*;
+
+/// Read-only traversal of the AST. The `'ast` lifetime allows a visitor to
+/// hold on to references into the tree, e.g. in a side table.
+pub trait Visitor<'ast>: Sized {
+    fn visit_program(&mut self, program: &'ast Program) {
+        walk_program(self, program);
+    }
+
+    fn visit_statement(&mut self, stmt: &'ast Statement) {
+        walk_statement(self, stmt);
+    }
+
+    fn visit_block(&mut self, block: &'ast Block) {
+        walk_block(self, block);
+    }
+
+    fn visit_expression(&mut self, expr: &'ast Expression) {
+        walk_expression(self, expr);
+    }
+
+    fn visit_type(&mut self, ty: &'ast Type) {
+        walk_type(self, ty);
+    }
+
+    fn visit_generic(&mut self, generic: &'ast Generic) {
+        walk_generic(self, generic);
+    }
+
+    fn visit_match_arm(&mut self, arm: &'ast MatchArm) {
+        walk_match_arm(self, arm);
+    }
+
+    fn visit_pattern(&mut self, pattern: &'ast Pattern) {
+        walk_pattern(self, pattern);
+    }
+}
+
+pub fn walk_program<'ast, V: Visitor<'ast>>(visitor: &mut V, program: &'ast Program) {
+    for stmt in program {
+        visitor.visit_statement(stmt);
+    }
+}
+
+pub fn walk_statement<'ast, V: Visitor<'ast>>(visitor: &mut V, stmt: &'ast Statement) {
+    match &stmt.kind {
+        StatementKind::Let { ty, initial, .. } => {
+            if let Some(ty) = ty {
+                visitor.visit_type(ty);
+            }
+
+            if let Some(initial) = initial {
+                visitor.visit_expression(initial);
+            }
+        }
+        StatementKind::Function(function) => {
+            for generic in &function.generics {
+                visitor.visit_generic(generic);
+            }
+
+            for parameter in &function.parameters {
+                visitor.visit_type(&parameter.ty);
+            }
+
+            if let Some(result) = &function.result {
+                visitor.visit_type(result);
+            }
+
+            visitor.visit_block(&function.body);
+        }
+        StatementKind::Return(value) => {
+            if let Some(value) = value {
+                visitor.visit_expression(value);
+            }
+        }
+        StatementKind::If {
+            condition,
+            then,
+            otherwise,
+        }
+        | StatementKind::ComptimeIf {
+            condition,
+            then,
+            otherwise,
+        } => {
+            visitor.visit_expression(condition);
+            visitor.visit_block(then);
+
+            if let Some(otherwise) = otherwise {
+                visitor.visit_statement(otherwise);
+            }
+        }
+        StatementKind::Block(block) => visitor.visit_block(block),
+        StatementKind::Expression(expression) => visitor.visit_expression(expression),
+        StatementKind::Assign { place, value, .. } => {
+            visitor.visit_expression(place);
+            visitor.visit_expression(value);
+        }
+        StatementKind::Struct(structure) => {
+            for generic in &structure.generics {
+                visitor.visit_generic(generic);
+            }
+
+            for field in &structure.fields {
+                visitor.visit_type(&field.ty);
+            }
+        }
+        StatementKind::Enum(enumeration) => {
+            for variant in &enumeration.variants {
+                for field in &variant.fields {
+                    visitor.visit_type(field);
+                }
+            }
+        }
+        StatementKind::Trait(declaration) => {
+            for method in &declaration.methods {
+                for parameter in &method.parameters {
+                    visitor.visit_type(&parameter.ty);
+                }
+
+                if let Some(result) = &method.result {
+                    visitor.visit_type(result);
+                }
+            }
+        }
+        StatementKind::Impl(implementation) => {
+            visitor.visit_type(&implementation.ty);
+
+            for method in &implementation.methods {
+                visitor.visit_statement(method);
+            }
+        }
+        StatementKind::Module(_) | StatementKind::Import(_) => {}
+    }
+}
+
+pub fn walk_block<'ast, V: Visitor<'ast>>(visitor: &mut V, block: &'ast Block) {
+    for stmt in &block.statements {
+        visitor.visit_statement(stmt);
+    }
+}
+
+pub fn walk_expression<'ast, V: Visitor<'ast>>(visitor: &mut V, expr: &'ast Expression) {
+    match &expr.kind {
+        ExpressionKind::Unary { operand, .. } => visitor.visit_expression(operand),
+        ExpressionKind::Binary { lhs, rhs, .. } => {
+            visitor.visit_expression(lhs);
+            visitor.visit_expression(rhs);
+        }
+        ExpressionKind::Cast { expression, ty } => {
+            visitor.visit_expression(expression);
+            visitor.visit_type(ty);
+        }
+        ExpressionKind::Call { callee, arguments } => {
+            visitor.visit_expression(callee);
+
+            for argument in arguments {
+                visitor.visit_expression(argument);
+            }
+        }
+        ExpressionKind::Field { expression, .. } => visitor.visit_expression(expression),
+        ExpressionKind::MethodCall {
+            receiver,
+            arguments,
+            ..
+        } => {
+            visitor.visit_expression(receiver);
+
+            for argument in arguments {
+                visitor.visit_expression(argument);
+            }
+        }
+        ExpressionKind::StructLiteral { fields, .. } => {
+            for field in fields {
+                visitor.visit_expression(&field.value);
+            }
+        }
+        ExpressionKind::Array(elements) => {
+            for element in elements {
+                visitor.visit_expression(element);
+            }
+        }
+        ExpressionKind::ArrayRepeat { value, length } => {
+            visitor.visit_expression(value);
+            visitor.visit_expression(length);
+        }
+        ExpressionKind::Index { expression, index } => {
+            visitor.visit_expression(expression);
+            visitor.visit_expression(index);
+        }
+        ExpressionKind::Slice {
+            expression,
+            start,
+            end,
+        } => {
+            visitor.visit_expression(expression);
+
+            for bound in [start, end].into_iter().flatten() {
+                visitor.visit_expression(bound);
+            }
+        }
+        ExpressionKind::Match { scrutinee, arms } => {
+            visitor.visit_expression(scrutinee);
+
+            for arm in arms {
+                visitor.visit_match_arm(arm);
+            }
+        }
+        ExpressionKind::Block(block) => visitor.visit_block(block),
+        ExpressionKind::Comptime(inner) => visitor.visit_expression(inner),
+        ExpressionKind::Closure(closure) => {
+            for ty in closure
+                .parameters
+                .iter()
+                .filter_map(|parameter| parameter.ty.as_ref())
+            {
+                visitor.visit_type(ty);
+            }
+
+            if let Some(result) = &closure.result {
+                visitor.visit_type(result);
+            }
+
+            visitor.visit_expression(&closure.body);
+        }
+        // Leaves, nothing to descend into
+        ExpressionKind::Number(_)
+        | ExpressionKind::Bool(_)
+        | ExpressionKind::String(_)
+        | ExpressionKind::Char(_)
+        | ExpressionKind::Identifier(_)
+        | ExpressionKind::Path(_) => {}
+    }
+}
+
+pub fn walk_type<'ast, V: Visitor<'ast>>(visitor: &mut V, ty: &'ast Type) {
+    match &ty.kind {
+        TypeKind::Named(path) => {
+            for argument in &path.arguments {
+                visitor.visit_type(argument);
+            }
+        }
+        TypeKind::Reference { inner, .. } | TypeKind::Slice(inner) => visitor.visit_type(inner),
+        TypeKind::Array { element, length } => {
+            visitor.visit_type(element);
+            visitor.visit_expression(length);
+        }
+        TypeKind::Tuple(elements) => {
+            for element in elements {
+                visitor.visit_type(element);
+            }
+        }
+        TypeKind::Function { parameters, result } => {
+            for parameter in parameters {
+                visitor.visit_type(parameter);
+            }
+
+            if let Some(result) = result {
+                visitor.visit_type(result);
+            }
+        }
+    }
+}
+
+/// The bounds of a type parameter are paths to traits, with nothing to
+/// descend into
+pub fn walk_generic<'ast, V: Visitor<'ast>>(_visitor: &mut V, _generic: &'ast Generic) {}
+
+pub fn walk_match_arm<'ast, V: Visitor<'ast>>(visitor: &mut V, arm: &'ast MatchArm) {
+    visitor.visit_pattern(&arm.pattern);
+    visitor.visit_expression(&arm.body);
+}
+
+pub fn walk_pattern<'ast, V: Visitor<'ast>>(visitor: &mut V, pattern: &'ast Pattern) {
+    match &pattern.kind {
+        PatternKind::Variant { fields, .. } => {
+            for field in fields {
+                visitor.visit_pattern(field);
+            }
+        }
+        PatternKind::Wildcard
+        | PatternKind::Binding(_)
+        | PatternKind::Number(_)
+        | PatternKind::Bool(_)
+        | PatternKind::Char(_) => {}
+    }
+}
+
+/// Mutable traversal of the AST, for passes that rewrite nodes in place.
+pub trait VisitorMut: Sized {
+    fn visit_program_mut(&mut self, program: &mut Program) {
+        walk_program_mut(self, program);
+    }
+
+    fn visit_statement_mut(&mut self, stmt: &mut Statement) {
+        walk_statement_mut(self, stmt);
+    }
+
+    fn visit_block_mut(&mut self, block: &mut Block) {
+        walk_block_mut(self, block);
+    }
+
+    fn visit_expression_mut(&mut self, expr: &mut Expression) {
+        walk_expression_mut(self, expr);
+    }
+
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        walk_type_mut(self, ty);
+    }
+
+    fn visit_generic_mut(&mut self, generic: &mut Generic) {
+        walk_generic_mut(self, generic);
+    }
+
+    fn visit_match_arm_mut(&mut self, arm: &mut MatchArm) {
+        walk_match_arm_mut(self, arm);
+    }
+
+    fn visit_pattern_mut(&mut self, pattern: &mut Pattern) {
+        walk_pattern_mut(self, pattern);
+    }
+}
+
+pub fn walk_program_mut<V: VisitorMut>(visitor: &mut V, program: &mut Program) {
+    for stmt in program {
+        visitor.visit_statement_mut(stmt);
+    }
+}
+
+pub fn walk_statement_mut<V: VisitorMut>(visitor: &mut V, stmt: &mut Statement) {
+    match &mut stmt.kind {
+        StatementKind::Let { ty, initial, .. } => {
+            if let Some(ty) = ty {
+                visitor.visit_type_mut(ty);
+            }
+
+            if let Some(initial) = initial {
+                visitor.visit_expression_mut(initial);
+            }
+        }
+        StatementKind::Function(function) => {
+            for generic in &mut function.generics {
+                visitor.visit_generic_mut(generic);
+            }
+
+            for parameter in &mut function.parameters {
+                visitor.visit_type_mut(&mut parameter.ty);
+            }
+
+            if let Some(result) = &mut function.result {
+                visitor.visit_type_mut(result);
+            }
+
+            visitor.visit_block_mut(&mut function.body);
+        }
+        StatementKind::Return(value) => {
+            if let Some(value) = value {
+                visitor.visit_expression_mut(value);
+            }
+        }
+        StatementKind::If {
+            condition,
+            then,
+            otherwise,
+        }
+        | StatementKind::ComptimeIf {
+            condition,
+            then,
+            otherwise,
+        } => {
+            visitor.visit_expression_mut(condition);
+            visitor.visit_block_mut(then);
+
+            if let Some(otherwise) = otherwise {
+                visitor.visit_statement_mut(otherwise);
+            }
+        }
+        StatementKind::Block(block) => visitor.visit_block_mut(block),
+        StatementKind::Expression(expression) => visitor.visit_expression_mut(expression),
+        StatementKind::Assign { place, value, .. } => {
+            visitor.visit_expression_mut(place);
+            visitor.visit_expression_mut(value);
+        }
+        StatementKind::Struct(structure) => {
+            for generic in &mut structure.generics {
+                visitor.visit_generic_mut(generic);
+            }
+
+            for field in &mut structure.fields {
+                visitor.visit_type_mut(&mut field.ty);
+            }
+        }
+        StatementKind::Enum(enumeration) => {
+            for variant in &mut enumeration.variants {
+                for field in &mut variant.fields {
+                    visitor.visit_type_mut(field);
+                }
+            }
+        }
+        StatementKind::Trait(declaration) => {
+            for method in &mut declaration.methods {
+                for parameter in &mut method.parameters {
+                    visitor.visit_type_mut(&mut parameter.ty);
+                }
+
+                if let Some(result) = &mut method.result {
+                    visitor.visit_type_mut(result);
+                }
+            }
+        }
+        StatementKind::Impl(implementation) => {
+            visitor.visit_type_mut(&mut implementation.ty);
+
+            for method in &mut implementation.methods {
+                visitor.visit_statement_mut(method);
+            }
+        }
+        StatementKind::Module(_) | StatementKind::Import(_) => {}
+    }
+}
+
+pub fn walk_block_mut<V: VisitorMut>(visitor: &mut V, block: &mut Block) {
+    for stmt in &mut block.statements {
+        visitor.visit_statement_mut(stmt);
+    }
+}
+
+pub fn walk_expression_mut<V: VisitorMut>(visitor: &mut V, expr: &mut Expression) {
+    match &mut expr.kind {
+        ExpressionKind::Unary { operand, .. } => visitor.visit_expression_mut(operand),
+        ExpressionKind::Binary { lhs, rhs, .. } => {
+            visitor.visit_expression_mut(lhs);
+            visitor.visit_expression_mut(rhs);
+        }
+        ExpressionKind::Cast { expression, ty } => {
+            visitor.visit_expression_mut(expression);
+            visitor.visit_type_mut(ty);
+        }
+        ExpressionKind::Call { callee, arguments } => {
+            visitor.visit_expression_mut(callee);
+
+            for argument in arguments {
+                visitor.visit_expression_mut(argument);
+            }
+        }
+        ExpressionKind::Field { expression, .. } => visitor.visit_expression_mut(expression),
+        ExpressionKind::MethodCall {
+            receiver,
+            arguments,
+            ..
+        } => {
+            visitor.visit_expression_mut(receiver);
+
+            for argument in arguments {
+                visitor.visit_expression_mut(argument);
+            }
+        }
+        ExpressionKind::StructLiteral { fields, .. } => {
+            for field in fields {
+                visitor.visit_expression_mut(&mut field.value);
+            }
+        }
+        ExpressionKind::Array(elements) => {
+            for element in elements {
+                visitor.visit_expression_mut(element);
+            }
+        }
+        ExpressionKind::ArrayRepeat { value, length } => {
+            visitor.visit_expression_mut(value);
+            visitor.visit_expression_mut(length);
+        }
+        ExpressionKind::Index { expression, index } => {
+            visitor.visit_expression_mut(expression);
+            visitor.visit_expression_mut(index);
+        }
+        ExpressionKind::Slice {
+            expression,
+            start,
+            end,
+        } => {
+            visitor.visit_expression_mut(expression);
+
+            for bound in [start, end].into_iter().flatten() {
+                visitor.visit_expression_mut(bound);
+            }
+        }
+        ExpressionKind::Match { scrutinee, arms } => {
+            visitor.visit_expression_mut(scrutinee);
+
+            for arm in arms {
+                visitor.visit_match_arm_mut(arm);
+            }
+        }
+        ExpressionKind::Block(block) => visitor.visit_block_mut(block),
+        ExpressionKind::Comptime(inner) => visitor.visit_expression_mut(inner),
+        ExpressionKind::Closure(closure) => {
+            for ty in closure
+                .parameters
+                .iter_mut()
+                .filter_map(|parameter| parameter.ty.as_mut())
+            {
+                visitor.visit_type_mut(ty);
+            }
+
+            if let Some(result) = &mut closure.result {
+                visitor.visit_type_mut(result);
+            }
+
+            visitor.visit_expression_mut(&mut closure.body);
+        }
+        ExpressionKind::Number(_)
+        | ExpressionKind::Bool(_)
+        | ExpressionKind::String(_)
+        | ExpressionKind::Char(_)
+        | ExpressionKind::Identifier(_)
+        | ExpressionKind::Path(_) => {}
+    }
+}
+
+pub fn walk_type_mut<V: VisitorMut>(visitor: &mut V, ty: &mut Type) {
+    match &mut ty.kind {
+        TypeKind::Named(path) => {
+            for argument in &mut path.arguments {
+                visitor.visit_type_mut(argument);
+            }
+        }
+        TypeKind::Reference { inner, .. } | TypeKind::Slice(inner) => visitor.visit_type_mut(inner),
+        TypeKind::Array { element, length } => {
+            visitor.visit_type_mut(element);
+            visitor.visit_expression_mut(length);
+        }
+        TypeKind::Tuple(elements) => {
+            for element in elements {
+                visitor.visit_type_mut(element);
+            }
+        }
+        TypeKind::Function { parameters, result } => {
+            for parameter in parameters {
+                visitor.visit_type_mut(parameter);
+            }
+
+            if let Some(result) = result {
+                visitor.visit_type_mut(result);
+            }
+        }
+    }
+}
+
+pub fn walk_generic_mut<V: VisitorMut>(_visitor: &mut V, _generic: &mut Generic) {}
+
+pub fn walk_match_arm_mut<V: VisitorMut>(visitor: &mut V, arm: &mut MatchArm) {
+    visitor.visit_pattern_mut(&mut arm.pattern);
+    visitor.visit_expression_mut(&mut arm.body);
+}
+
+pub fn walk_pattern_mut<V: VisitorMut>(visitor: &mut V, pattern: &mut Pattern) {
+    match &mut pattern.kind {
+        PatternKind::Variant { fields, .. } => {
+            for field in fields {
+                visitor.visit_pattern_mut(field);
+            }
+        }
+        PatternKind::Wildcard
+        | PatternKind::Binding(_)
+        | PatternKind::Number(_)
+        | PatternKind::Bool(_)
+        | PatternKind::Char(_) => {}
+    }
+}
@@ -0,0 +1,2547 @@
+//! The type checker: checks that values are of the types expected of them,
+//! in variables, arguments, results, operators and matches, and how
+//! structs, enums and arrays are used.
+//!
+//! Expressions are given a type where one is known, and a variable declared
+//! with `:=` has the type of its initializer. Number literals don't have a
+//! type of their own, they fit wherever a number type they are a value of
+//! is expected: a whole number in the range of an integer type, like `-1`
+//! for an `i8` but not a `u8`, or any number for a float. A variable
+//! declared from them, like `let b := 10;`, has the type of the first use
+//! of it that tells one: with a `u32` variable `a`, `a + b` makes `b` a
+//! `u32`. Arithmetic on literals alone is done in the integer type they
+//! are given for, a result outside its range being reported as overflow,
+//! like `200 + 100` for a `u8`. The operands of a binary operator are of
+//! the same type, except for the amount a shift shifts by, and one it
+//! applies to: arithmetic and negation to numbers, shifts and `~` to
+//! integers, `&`, `|` and `^` to integers and bools, `!` to bools and the
+//! ordering comparisons to numbers and chars. Names that aren't declared,
+//! which [`crate::sema::names`] reports, have no known type, so an
+//! expression using them is left alone instead of causing a cascade of
+//! errors.
+//!
+//! A number widens to the number types holding all its values, see
+//! [`Primitive::widens_to`]: a `u8` can be given for a `u32`, an `i16` or
+//! an `f32`, but a `u32` can't be for an `i32`. The operands of a binary
+//! operator widen to the type of either: with a `u8` `a` and a `u32` `b`,
+//! `a + b` and `b + a` both are `u32`s. Any other conversion is written
+//! with `as`, which may wrap or round the value.
+//!
+//! A value of the wrong type is reported with the type expected and the
+//! type found, at the value and, where it is written, at what requires the
+//! type expected: the type of a variable, a parameter or a result, the
+//! left operand of a binary operator or the first element of an array. A
+//! number that doesn't widen is reported with the cast converting it, so
+//! `b + a` for an `i32` `b` and a `u32` `a` asks for `a as i32`.
+//!
+//! The body of a generic function is checked once, its type parameters
+//! being types nothing is known about, so only `==` and `!=` apply to
+//! them. Where a generic function or struct
+//! is used, its type arguments are inferred from the values given for its
+//! parameters or fields and from the type expected of it, and the calls are
+//! recorded for [`crate::sema::generics`] to find the instances needed.
+//! The type arguments have to implement the traits their type parameters
+//! are bound to.
+//!
+//! A method call is resolved by the type of its receiver, among the
+//! methods of the traits the type implements, or for a type parameter the
+//! traits it is bound to. The methods of an impl are checked like
+//! functions, `Self` being the type they are implemented for.
+//!
+//! A closure is a value of a function type. The types of its parameters
+//! and its result can be left out where it is given for a parameter or a
+//! variable of a function type, and its result is otherwise the type of
+//! its body, or `()` for a block. Every closure checked is recorded with
+//! what it captures, for [`crate::sema::closures`] to lower it.
+//!
+//! Only a variable declared `let mut` can be assigned to, with `=`, a
+//! compound assignment like `+=`, or `++` and `--`, or have a `&mut`
+//! reference taken to it. Parameters, the names patterns bind and
+//! functions can't be. Only a `&mut` reference can be written through,
+//! like `*r = 1` or `r.x = 1`, however the variable holding it is
+//! declared. It can be given where a `&` reference is expected, but not
+//! the other way around, and slicing through one gives a `&mut` slice.
+use crate::error::OrionError;
+use crate::lexer::tokens::Span;
+use crate::parser::ast::*;
+use crate::parser::printer::print_expression;
+use crate::sema::closures::{captures, lift, CaptureMode, Captured, Converted};
+use crate::sema::generics::{Calls, FunctionId};
+use crate::sema::patterns::{Constructor, Pat, Usefulness};
+use crate::sema::structs::{array_length, EnumDef, Structs};
+use crate::sema::traits::{MethodDef, TraitDef, Traits, ORD};
+use crate::sema::ty::{Primitive, Ty};
+use crate::sema::Diagnostics;
+use std::collections::HashMap;
+
+/// A variable or function in scope
+#[derive(Debug, Clone)]
+struct Binding {
+    /// `None` if the type couldn't be resolved
+    ty: Option<Ty>,
+    /// The declaration and type parameters of a generic function, whose
+    /// type mentions them
+    generic: Option<Generics>,
+    /// Whether it is a variable declared `let mut`, which can be assigned to
+    mutable: bool,
+    /// Where it is declared, if that is in the module being checked
+    declared: Option<Span>,
+    /// For a variable declared with `:=` from number literals, the number
+    /// in [`Numbers`] its type is found from
+    number: Option<usize>,
+    /// Whether it is a variable declared without an initializer, which can
+    /// be assigned to once, see [`crate::sema::flow`]
+    deferred: bool,
+    /// Where the types of the parameters of a function are written, if
+    /// that is in the module being checked
+    parameters: Vec<Span>,
+    /// For a variable holding a closure that captures a variable by
+    /// reference, the name of that variable
+    borrows: Option<String>,
+}
+
+impl Binding {
+    fn variable(ty: Option<Ty>, mutable: bool, declared: Option<Span>) -> Self {
+        Self {
+            ty,
+            generic: None,
+            mutable,
+            declared,
+            number: None,
+            deferred: false,
+            parameters: vec![],
+            borrows: None,
+        }
+    }
+}
+
+/// The types of the variables declared with `:=` from number literals,
+/// like `let b := 10;`. Such a variable has the type of the first use of it
+/// telling one, like `a + b` for a `u32` variable `a`. A variable declared
+/// from others of them, like `let c := b * 2;`, has the same type as them.
+/// The literals they are declared from are checked to fit the type once it
+/// is known.
+#[derive(Default)]
+struct Numbers {
+    /// The number each one has the type of, itself for the first of those
+    /// sharing a type
+    parents: Vec<usize>,
+    types: Vec<Option<Ty>>,
+    /// The literals of the numbers sharing the type of each first one, while
+    /// it isn't known
+    literals: Vec<Vec<Literal>>,
+}
+
+/// A number literal, negative if it is written after a `-`, and where it is
+type Literal = (Number, Span);
+
+impl Numbers {
+    /// A number of unknown type declared from `literals`, sharing the type
+    /// of `others`
+    fn add(&mut self, others: &[usize], literals: Vec<Literal>) -> usize {
+        let number = self.parents.len();
+        self.parents.push(number);
+        self.types.push(None);
+        self.literals.push(literals);
+
+        for &other in others {
+            let root = self.root(other);
+
+            if root != number {
+                self.parents[root] = number;
+                let literals = std::mem::take(&mut self.literals[root]);
+                self.literals[number].extend(literals);
+            }
+        }
+
+        number
+    }
+
+    fn root(&self, mut number: usize) -> usize {
+        while self.parents[number] != number {
+            number = self.parents[number];
+        }
+
+        number
+    }
+
+    fn ty(&self, number: usize) -> Option<&Ty> {
+        self.types[self.root(number)].as_ref()
+    }
+
+    /// Give a number of unknown type the type `ty`, returning the literals
+    /// that have the type now
+    fn settle(&mut self, number: usize, ty: &Ty) -> Vec<Literal> {
+        let root = self.root(number);
+
+        if self.types[root].is_some() {
+            return vec![];
+        }
+
+        self.types[root] = Some(ty.clone());
+        std::mem::take(&mut self.literals[root])
+    }
+}
+
+/// A value given for a parameter or a field, with the type declared for it
+/// and where that is written, if it is in the module being checked
+type Given<'e> = (&'e Expression, &'e Ty, Option<Span>);
+
+/// A generic function, with the names of its type parameters and the
+/// traits each of them is bound to
+#[derive(Debug, Clone)]
+struct Generics {
+    function: FunctionId,
+    names: Vec<String>,
+    bounds: Vec<Vec<String>>,
+}
+
+pub struct Checker<'a, 'd> {
+    structs: &'a Structs,
+    traits: &'a Traits,
+    diagnostics: &'a mut Diagnostics<'d>,
+    /// The variables and functions in scope, innermost scope last
+    scopes: Vec<HashMap<String, Binding>>,
+    /// The result type of the function being checked
+    result: Option<Ty>,
+    /// Where the result type of the function being checked is written
+    result_at: Option<Span>,
+    /// The type parameters of the function being checked
+    generics: Vec<String>,
+    /// The traits the type parameters of the function being checked are
+    /// bound to
+    bounds: HashMap<String, Vec<String>>,
+    /// The type `Self` stands for in the method being checked
+    self_ty: Option<Ty>,
+    /// The function being checked, `None` at the top level
+    function: Option<FunctionId>,
+    /// What the closures of the function being checked are named after,
+    /// `main::` in `main` and nothing at the top level
+    owner: String,
+    /// How many closures of the function being checked were met, which
+    /// numbers them
+    numbered: usize,
+    /// The first scope of the function being checked. The variables in it
+    /// and the scopes after it are the ones closures capture.
+    frame: usize,
+    /// The module being checked, for a program split across files
+    module: usize,
+    numbers: Numbers,
+    calls: Calls,
+    closures: Vec<Converted>,
+}
+
+impl<'a, 'd> Checker<'a, 'd> {
+    pub fn new(
+        structs: &'a Structs,
+        traits: &'a Traits,
+        diagnostics: &'a mut Diagnostics<'d>,
+    ) -> Self {
+        Self {
+            structs,
+            traits,
+            diagnostics,
+            scopes: vec![],
+            result: None,
+            result_at: None,
+            generics: vec![],
+            bounds: HashMap::new(),
+            self_ty: None,
+            function: None,
+            owner: String::new(),
+            numbered: 0,
+            frame: 0,
+            module: 0,
+            numbers: Numbers::default(),
+            calls: Calls::default(),
+            closures: vec![],
+        }
+    }
+
+    pub fn program(&mut self, program: &Program) {
+        // The variables of the top level are reached without capturing
+        // them, those of the blocks in it are captured
+        self.frame = self.scopes.len() + 1;
+        self.statements(program);
+    }
+
+    /// The generic functions and the calls made to them, and the closures,
+    /// once checked
+    pub fn into_results(self) -> (Calls, Vec<Converted>) {
+        (self.calls, self.closures)
+    }
+
+    /// Check a module of a program split across files, its names being
+    /// resolved. The functions and top-level variables of every module can
+    /// be used by their full names, the variables having the type they are
+    /// annotated with.
+    pub fn module(&mut self, programs: &[&Program], index: usize) {
+        self.module = index;
+        self.scopes.push(HashMap::new());
+        let mut quiet = Diagnostics::new("");
+
+        for (module, program) in programs.iter().enumerate() {
+            for stmt in program.iter() {
+                match &stmt.kind {
+                    // The functions of the module itself are declared with
+                    // the other statements of its top level
+                    StatementKind::Function(function) if module != index => {
+                        let id = FunctionId::new(module, stmt.span);
+                        self.declare_function(function, id, false);
+                    }
+                    StatementKind::Let {
+                        name,
+                        mutable,
+                        ty: Some(ty),
+                        ..
+                    } => {
+                        let ty = self.structs.resolve_sized(ty, &[], &mut quiet);
+                        let declared = (module == index).then_some(stmt.span);
+                        self.declare(name, Binding::variable(ty, *mutable, declared));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        self.program(programs[index]);
+        self.scopes.pop();
+    }
+
+    /// Check statements in a scope of their own. Functions can be called
+    /// before they are declared, so their signatures are known up front.
+    fn statements(&mut self, statements: &[Statement]) {
+        self.scopes.push(HashMap::new());
+
+        for stmt in statements {
+            if let StatementKind::Function(function) = &stmt.kind {
+                let id = FunctionId::new(self.module, stmt.span);
+                self.declare_function(function, id, true);
+            }
+        }
+
+        for stmt in statements {
+            self.statement(stmt);
+        }
+
+        self.scopes.pop();
+    }
+
+    fn statement(&mut self, stmt: &Statement) {
+        match &stmt.kind {
+            StatementKind::Let {
+                name,
+                mutable,
+                ty,
+                initial,
+            } => {
+                let mut number = None;
+
+                let ty = match (ty, initial) {
+                    (Some(written), initial) => {
+                        let ty = self.resolve_sized(written);
+
+                        match (&ty, initial) {
+                            (Some(ty), Some(initial)) => {
+                                self.expect(initial, ty, Some(written.span))
+                            }
+                            (None, Some(initial)) => {
+                                self.expression(initial);
+                            }
+                            (_, None) => {}
+                        }
+
+                        ty
+                    }
+                    // The parser only leaves out the initializer of a
+                    // variable with a type
+                    (None, None) => None,
+                    (None, Some(initial)) => {
+                        let ty = self.expression(initial);
+
+                        // Its type is told by how it is used
+                        if ty.is_none() {
+                            number = self.unknown_numbers(initial).map(|others| {
+                                let literals = literals(initial);
+                                self.numbers.add(&others, literals)
+                            });
+                        }
+
+                        ty
+                    }
+                };
+
+                let mut binding = Binding::variable(ty, *mutable, Some(stmt.span));
+                binding.number = number;
+                binding.deferred = initial.is_none();
+                binding.borrows = initial.as_ref().and_then(|initial| self.borrows(initial));
+                self.declare(name, binding);
+            }
+            StatementKind::Function(function) => self.function(function, stmt.span, None),
+            StatementKind::Return(value) => {
+                if let Some(value) = value {
+                    self.escapes(value);
+                }
+
+                match (value, self.result.clone()) {
+                    (Some(value), Some(result)) => self.expect(value, &result, self.result_at),
+                    (Some(value), None) => {
+                        self.expression(value);
+                    }
+                    (None, Some(result)) if result != Ty::unit() => {
+                        self.diagnostics.report(OrionError::MismatchedTypes {
+                            expected: result.to_string(),
+                            found: Ty::unit().to_string(),
+                            origin: self.result_at.map(|span| self.diagnostics.loc(span)),
+                            loc: self.diagnostics.loc(stmt.span),
+                        });
+                    }
+                    (None, _) => {}
+                }
+            }
+            StatementKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                self.expect(condition, &Ty::Primitive(Primitive::Bool), None);
+                self.statements(&then.statements);
+
+                if let Some(otherwise) = otherwise {
+                    self.statement(otherwise);
+                }
+            }
+            // Taken or dropped when the program was loaded, see
+            // `crate::interp::comptime`
+            StatementKind::ComptimeIf { .. } => {}
+            StatementKind::Block(block) => self.statements(&block.statements),
+            StatementKind::Expression(expression) => {
+                self.expression(expression);
+            }
+            StatementKind::Assign {
+                place,
+                operator,
+                value,
+            } => {
+                self.written(place);
+                let ty = self.place(place);
+
+                if let Some(op) = operator {
+                    self.applies(op.symbol(), Operands::binary(*op), ty.as_ref(), place);
+                }
+
+                match (ty, operator) {
+                    // A shift can be by an amount of any integer type
+                    (_, Some(BinaryOperator::Shl | BinaryOperator::Shr)) => {
+                        self.expression(value);
+                    }
+                    (Some(ty), _) => {
+                        let origin = self.origin(place);
+                        self.expect(value, &ty, origin);
+                    }
+                    (None, _) => {
+                        if let Some(ty) = self.expression(value) {
+                            self.settle(place, &ty, None);
+                        }
+                    }
+                }
+            }
+            // Checked when the structs and enums were collected
+            StatementKind::Struct(_) | StatementKind::Enum(_) => {}
+            // Checked against their traits when they were collected
+            StatementKind::Trait(_) => {}
+            StatementKind::Impl(implementation) => {
+                let mut quiet = Diagnostics::new("");
+                // `Self` stands for a type nothing is known about if the
+                // impl is for a type that isn't one
+                let ty = self
+                    .structs
+                    .resolve_sized(&implementation.ty, &[], &mut quiet)
+                    .unwrap_or_else(|| Ty::Param("Self".to_string()));
+
+                for method in &implementation.methods {
+                    if let StatementKind::Function(function) = &method.kind {
+                        self.function(function, method.span, Some(ty.clone()));
+                    }
+                }
+            }
+            // Resolved when the modules were loaded
+            StatementKind::Module(_) | StatementKind::Import(_) => {}
+        }
+    }
+
+    /// Check a function, or a method if `self_ty` is the type it is
+    /// implemented for
+    fn function(&mut self, function: &Function, span: Span, self_ty: Option<Ty>) {
+        let names = function.generic_names();
+        let mut bounds = HashMap::new();
+
+        for (i, generic) in function.generics.iter().enumerate() {
+            if names[..i].contains(&generic.name) {
+                self.diagnostics.report(OrionError::DuplicateDefinition {
+                    name: generic.name.clone(),
+                    loc: self.diagnostics.loc(generic.span),
+                });
+            }
+
+            for bound in bound_names(generic) {
+                if self.traits.get(&bound).is_none() {
+                    self.diagnostics.report(OrionError::UnknownTrait {
+                        name: bound.clone(),
+                        loc: self.diagnostics.loc(generic.span),
+                    });
+                }
+            }
+
+            bounds.insert(generic.name.clone(), bound_names(generic));
+        }
+
+        // A function inside a generic one has type parameters of its own,
+        // and one inside a method can't name `Self`
+        let generics = std::mem::replace(&mut self.generics, names);
+        let bounds = std::mem::replace(&mut self.bounds, bounds);
+        let outer_self = std::mem::replace(&mut self.self_ty, self_ty);
+        let id = FunctionId::new(self.module, span);
+        let caller = self.function.replace(id);
+        let name = match &self.self_ty {
+            Some(ty) => format!("{ty}::{}::", function.name),
+            None => format!("{}::", function.name),
+        };
+        let owner = std::mem::replace(&mut self.owner, name);
+        let numbered = std::mem::replace(&mut self.numbered, 0);
+        let frame = std::mem::replace(&mut self.frame, self.scopes.len());
+        self.scopes.push(HashMap::new());
+
+        for parameter in &function.parameters {
+            let ty = self.resolve_sized(&parameter.ty);
+            let binding = Binding::variable(ty, false, Some(parameter.span));
+            self.declare(&parameter.name, binding);
+        }
+
+        let result = match &function.result {
+            Some(result) => self.resolve_sized(result),
+            None => Some(Ty::unit()),
+        };
+
+        let outer = std::mem::replace(&mut self.result, result);
+        let result_at = function.result.as_ref().map(|result| result.span);
+        let outer_at = std::mem::replace(&mut self.result_at, result_at);
+        self.statements(&function.body.statements);
+        self.result = outer;
+        self.result_at = outer_at;
+
+        self.scopes.pop();
+        self.frame = frame;
+        self.owner = owner;
+        self.numbered = numbered;
+        self.function = caller;
+        self.generics = generics;
+        self.bounds = bounds;
+        self.self_ty = outer_self;
+    }
+
+    /// The variable a closure captures by reference, if `value` is such a
+    /// closure or a variable holding one
+    fn borrows(&self, value: &Expression) -> Option<String> {
+        match &value.kind {
+            ExpressionKind::Closure(closure) => captures(closure, |name| self.is_local(name))
+                .into_iter()
+                .find(|capture| capture.mode != CaptureMode::Value)
+                .map(|capture| capture.name),
+            ExpressionKind::Identifier(name) => self.binding(name)?.borrows.clone(),
+            _ => None,
+        }
+    }
+
+    /// Report a value returned from a function or a closure that is a
+    /// closure capturing a variable by reference, which wouldn't live as
+    /// long as it
+    fn escapes(&mut self, value: &Expression) {
+        if let Some(name) = self.borrows(value) {
+            self.diagnostics.report(OrionError::EscapingClosure {
+                name,
+                loc: self.diagnostics.loc(value.span),
+            });
+        }
+    }
+
+    /// Resolve a written type, in which the type parameters of the function
+    /// being checked are types, and `Self` in a method
+    fn resolve_sized(&mut self, ty: &Type) -> Option<Ty> {
+        let Some(self_ty) = &self.self_ty else {
+            return self
+                .structs
+                .resolve_sized(ty, &self.generics, self.diagnostics);
+        };
+
+        // Methods have no type parameters of their own
+        let generics = ["Self".to_string()];
+        let resolved = self
+            .structs
+            .resolve_sized(ty, &generics, self.diagnostics)?;
+
+        Some(resolved.substitute(&HashMap::from([(generics[0].clone(), self_ty.clone())])))
+    }
+
+    /// The type of a function, without reporting the errors in the types
+    /// it mentions, which is done once the function itself is checked
+    fn signature(&self, function: &Function) -> Option<Ty> {
+        let mut quiet = Diagnostics::new("");
+        let generics = &function.generic_names();
+
+        let parameters = function
+            .parameters
+            .iter()
+            .map(|parameter| {
+                self.structs
+                    .resolve_sized(&parameter.ty, generics, &mut quiet)
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let result = match &function.result {
+            Some(result) => self.structs.resolve_sized(result, generics, &mut quiet)?,
+            None => Ty::unit(),
+        };
+
+        Some(Ty::Function(parameters, Box::new(result)))
+    }
+
+    fn declare(&mut self, name: &str, binding: Binding) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), binding);
+        }
+    }
+
+    /// Declare a function, `here` telling whether it is in the module being
+    /// checked
+    fn declare_function(&mut self, function: &Function, id: FunctionId, here: bool) {
+        let ty = self.signature(function);
+        let generic = (!function.generics.is_empty()).then(|| Generics {
+            function: id,
+            names: function.generic_names(),
+            bounds: function.generics.iter().map(bound_names).collect(),
+        });
+
+        self.calls.declare(id, function);
+
+        let parameters = if here {
+            let types = function.parameters.iter();
+            types.map(|parameter| parameter.ty.span).collect()
+        } else {
+            vec![]
+        };
+
+        let binding = Binding {
+            ty,
+            generic,
+            mutable: false,
+            declared: None,
+            number: None,
+            deferred: false,
+            parameters,
+            borrows: None,
+        };
+
+        self.declare(&function.name, binding);
+    }
+
+    fn binding(&self, name: &str) -> Option<&Binding> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    fn lookup(&self, name: &str) -> Option<Ty> {
+        let binding = self.binding(name)?;
+
+        match binding.number {
+            Some(number) => self.numbers.ty(number).cloned(),
+            None => binding.ty.clone(),
+        }
+    }
+
+    /// What the type of a place written to comes from, the declaration of
+    /// a variable or the place itself
+    fn origin(&self, place: &Expression) -> Option<Span> {
+        match &place.kind {
+            ExpressionKind::Identifier(name) => self.binding(name)?.declared,
+            _ => Some(place.span),
+        }
+    }
+
+    /// The numbers of the variables of unknown type in an expression made
+    /// only of number literals, such variables and arithmetic on them,
+    /// `None` for any other expression
+    fn unknown_numbers(&self, expression: &Expression) -> Option<Vec<usize>> {
+        match &expression.kind {
+            ExpressionKind::Number(_) => Some(vec![]),
+            ExpressionKind::Identifier(name) => {
+                let number = self.binding(name)?.number?;
+                self.numbers.ty(number).is_none().then(|| vec![number])
+            }
+            ExpressionKind::Unary {
+                op: UnaryOperator::Neg | UnaryOperator::BitNot,
+                operand,
+            } => self.unknown_numbers(operand),
+            ExpressionKind::Binary { op, lhs, rhs } if op.precedence() > 1 => {
+                let mut numbers = self.unknown_numbers(lhs)?;
+                numbers.extend(self.unknown_numbers(rhs)?);
+                Some(numbers)
+            }
+            _ => None,
+        }
+    }
+
+    /// Give the literals and the variables of unknown type an expression is
+    /// made of the number type `ty` it was found to have, which `origin` is
+    /// what requires if it is written somewhere
+    fn settle(&mut self, expression: &Expression, ty: &Ty, origin: Option<Span>) {
+        let Ty::Primitive(primitive) = ty else {
+            return;
+        };
+
+        if !primitive.is_numeric() {
+            return;
+        }
+
+        for (number, span) in literals(expression) {
+            self.literal(number, span, *primitive, origin);
+        }
+
+        // A variable negated before its type was known can't be unsigned
+        if !Operands::Signed.allow(*primitive) {
+            for operand in negated(expression) {
+                if self
+                    .unknown_numbers(operand)
+                    .is_some_and(|numbers| !numbers.is_empty())
+                {
+                    self.applies("-", Operands::Signed, Some(ty), operand);
+                }
+            }
+        }
+
+        if let Some(Err((operation, span))) = fold(expression, *primitive) {
+            self.diagnostics.report(OrionError::IntegerOverflow {
+                operation,
+                ty: primitive.to_string(),
+                loc: self.diagnostics.loc(span),
+            });
+        }
+
+        for number in self.unknown_numbers(expression).unwrap_or_default() {
+            for (number, span) in self.numbers.settle(number, ty) {
+                self.literal(number, span, *primitive, None);
+            }
+        }
+    }
+
+    /// Check that a number literal is a value of the number type it is
+    /// given for: a whole number in the range of an integer type, or a
+    /// number a float type has a finite value for
+    fn literal(&mut self, number: Number, span: Span, ty: Primitive, origin: Option<Span>) {
+        match (number, ty.range()) {
+            (Number::Integer(value), Some((min, max))) if value < min || value > max => {
+                self.diagnostics.report(OrionError::LiteralOutOfRange {
+                    literal: number.to_string(),
+                    ty: ty.to_string(),
+                    min,
+                    max,
+                    loc: self.diagnostics.loc(span),
+                });
+            }
+            // A float literal is read as an `f64`, which only becomes
+            // infinite as an `f32`
+            (Number::Float(value), None)
+                if ty == Primitive::F32 && (value as f32).is_infinite() =>
+            {
+                self.diagnostics.report(OrionError::FloatOutOfRange {
+                    literal: self.diagnostics.text(span).to_string(),
+                    ty: ty.to_string(),
+                    loc: self.diagnostics.loc(span),
+                });
+            }
+            (Number::Float(_), Some(_)) => {
+                self.diagnostics.report(OrionError::MismatchedTypes {
+                    expected: ty.to_string(),
+                    found: "float".to_string(),
+                    origin: origin.map(|span| self.diagnostics.loc(span)),
+                    loc: self.diagnostics.loc(span),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    /// Check that a place written to isn't in a variable that can't be
+    /// assigned to. Writing through a reference changes what it refers to,
+    /// which any variable holding the reference can do.
+    fn written(&mut self, place: &Expression) {
+        let Some(name) = self.written_variable(place) else {
+            return;
+        };
+
+        let declared = match self.binding(name) {
+            // A variable declared without a value is given one, which
+            // `crate::sema::flow` checks happens once
+            Some(binding)
+                if binding.deferred && matches!(place.kind, ExpressionKind::Identifier(_)) =>
+            {
+                return
+            }
+            Some(binding) if !binding.mutable => binding.declared,
+            _ => return,
+        };
+
+        self.diagnostics.report(OrionError::ImmutableAssignment {
+            name: name.to_string(),
+            declared: declared.map(|span| self.diagnostics.loc(span)),
+            loc: self.diagnostics.loc(place.span),
+        });
+    }
+
+    /// The variable holding a place, like `p` for `p.items[0]`, unless the
+    /// place is behind a reference or its type isn't known
+    fn written_variable<'e>(&self, place: &'e Expression) -> Option<&'e str> {
+        match &place.kind {
+            ExpressionKind::Identifier(name) => Some(name),
+            ExpressionKind::Field { expression, .. } | ExpressionKind::Index { expression, .. } => {
+                match self.place_ty(expression)? {
+                    Ty::Reference(..) => None,
+                    _ => self.written_variable(expression),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Check a place written to, returning its type. The references it is
+    /// reached through have to be `&mut`, like `r` for `*r = 1` or
+    /// `r.x = 1`.
+    fn place(&mut self, place: &Expression) -> Option<Ty> {
+        match &place.kind {
+            ExpressionKind::Field {
+                expression: inner,
+                field,
+            } => {
+                let ty = self.reached(inner)?;
+                self.field(&ty, field, place.span)
+            }
+            ExpressionKind::Index {
+                expression: inner,
+                index,
+            } => {
+                self.expect(index, &Ty::Primitive(Primitive::Usize), None);
+                let ty = self.reached(inner)?;
+                self.indexed(&ty, inner)
+            }
+            ExpressionKind::Unary {
+                op: UnaryOperator::Deref,
+                operand,
+            } => match self.reached(operand)? {
+                Ty::Reference(inner, _) => Some(*inner),
+                _ => None,
+            },
+            _ => self.expression(place),
+        }
+    }
+
+    /// Check what a place written to is in, like `p` for `p.x`, returning
+    /// its type. A reference it is, or is behind, has to be `&mut`, and
+    /// the type isn't given if one isn't.
+    fn reached(&mut self, expression: &Expression) -> Option<Ty> {
+        let ty = match expression.is_place() {
+            true => self.place(expression),
+            false => self.expression(expression),
+        };
+
+        let shared = match &ty {
+            Some(ty) => {
+                let mut behind = ty;
+
+                loop {
+                    match behind {
+                        Ty::Reference(inner, true) => behind = inner,
+                        Ty::Reference(_, false) => break Some(behind.to_string()),
+                        _ => break None,
+                    }
+                }
+            }
+            // `&x` is a `&` reference even if the type of `x` isn't known
+            None => match &expression.kind {
+                ExpressionKind::Unary {
+                    op: UnaryOperator::Ref,
+                    ..
+                } => Some("&_".to_string()),
+                _ => None,
+            },
+        };
+
+        if let Some(shared) = shared {
+            self.diagnostics.report(OrionError::ImmutableReference {
+                ty: shared,
+                loc: self.diagnostics.loc(expression.span),
+            });
+
+            return None;
+        }
+
+        ty
+    }
+
+    /// The type of a variable, field or element, found without checking
+    /// the place again
+    fn place_ty(&self, place: &Expression) -> Option<Ty> {
+        match &place.kind {
+            ExpressionKind::Identifier(name) => self.lookup(name),
+            ExpressionKind::Field { expression, field } => {
+                match deref(&self.place_ty(expression)?) {
+                    Ty::Struct(name, types) => {
+                        let def = self.structs.get(name)?;
+                        let (index, _) = def.field(field)?;
+                        Some(def.field_types(types).swap_remove(index))
+                    }
+                    _ => None,
+                }
+            }
+            ExpressionKind::Index { expression, .. } => match deref(&self.place_ty(expression)?) {
+                Ty::Array(element, _) | Ty::Slice(element) => Some((**element).clone()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Check that an expression is of the expected type, which `origin`
+    /// is what requires if it is written somewhere
+    fn expect(&mut self, expression: &Expression, expected: &Ty, origin: Option<Span>) {
+        let found = match (&expression.kind, expected) {
+            // The elements are checked one by one, so that number literals
+            // fit an array of numbers
+            (ExpressionKind::Array(elements), Ty::Array(element, length)) => {
+                self.array_length(*length, elements.len() as u64, expression);
+
+                for value in elements {
+                    self.expect(value, element, origin);
+                }
+
+                return;
+            }
+            (ExpressionKind::ArrayRepeat { value, length }, Ty::Array(element, expected)) => {
+                if let Some(length) = array_length(length, self.diagnostics) {
+                    self.array_length(*expected, length, expression);
+                }
+
+                self.expect(value, element, origin);
+                return;
+            }
+            // Every arm has to be of the expected type
+            (ExpressionKind::Match { scrutinee, arms }, _) => {
+                let expected = Some((expected, origin));
+                self.match_expression(scrutinee, arms, expected, expression);
+                return;
+            }
+            _ => self.found(expression, expected, origin),
+        };
+
+        if let Some(found) = found {
+            self.mismatch(expression, expected, found, origin);
+        }
+    }
+
+    /// Report a value of the wrong type. A number that could be converted
+    /// is reported with the cast converting it.
+    fn mismatch(
+        &mut self,
+        expression: &Expression,
+        expected: &Ty,
+        found: String,
+        origin: Option<Span>,
+    ) {
+        let origin = origin.map(|span| self.diagnostics.loc(span));
+        let loc = self.diagnostics.loc(expression.span);
+
+        let numbers = match (expected, Primitive::from_name(&found)) {
+            (Ty::Primitive(expected), Some(found))
+                if expected.is_numeric() && found.is_numeric() =>
+            {
+                Some((*expected, found))
+            }
+            _ => None,
+        };
+
+        if let Some((expected, found)) = numbers {
+            let value = print_expression(expression);
+
+            // A cast binds tighter than any binary operator
+            let cast = match expression.kind {
+                ExpressionKind::Binary { .. } => format!("({value}) as {expected}"),
+                _ => format!("{value} as {expected}"),
+            };
+
+            self.diagnostics.report(OrionError::NumberConversion {
+                expected,
+                found,
+                cast,
+                origin,
+                loc,
+            });
+        } else {
+            self.diagnostics.report(OrionError::MismatchedTypes {
+                expected: expected.to_string(),
+                found,
+                origin,
+                loc,
+            });
+        }
+    }
+
+    fn array_length(&mut self, expected: u64, found: u64, expression: &Expression) {
+        if expected != found {
+            self.diagnostics.report(OrionError::ArrayLengthMismatch {
+                expected,
+                found,
+                loc: self.diagnostics.loc(expression.span),
+            });
+        }
+    }
+
+    /// The type an expression was found to have if it isn't the expected
+    /// one
+    fn found(
+        &mut self,
+        expression: &Expression,
+        expected: &Ty,
+        origin: Option<Span>,
+    ) -> Option<String> {
+        // Number literals, and the variables declared from them whose type
+        // isn't known yet, take the number type expected of them
+        if self.unknown_numbers(expression).is_some() {
+            if matches!(expected, Ty::Primitive(primitive) if primitive.is_numeric()) {
+                self.settle(expression, expected, origin);
+                return None;
+            }
+
+            return Some("number".to_string());
+        }
+
+        match self.inferred(expression, Some(expected)) {
+            Some(found) if !coerces(&found, expected) => Some(found.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Check an expression, returning its type if it is known
+    fn expression(&mut self, expression: &Expression) -> Option<Ty> {
+        match &expression.kind {
+            ExpressionKind::Number(_) => None,
+            ExpressionKind::Bool(_) => Some(Ty::Primitive(Primitive::Bool)),
+            ExpressionKind::String(_) => Some(Ty::Reference(
+                Box::new(Ty::Primitive(Primitive::Str)),
+                false,
+            )),
+            ExpressionKind::Char(_) => Some(Ty::Primitive(Primitive::Char)),
+            ExpressionKind::Identifier(name) => match self.binding(name) {
+                // Only a call tells the type arguments of a generic function
+                Some(Binding {
+                    generic: Some(generics),
+                    ..
+                }) => {
+                    self.diagnostics.report(OrionError::UninferredType {
+                        parameter: generics.names[0].clone(),
+                        item: name.clone(),
+                        loc: self.diagnostics.loc(expression.span),
+                    });
+
+                    None
+                }
+                _ => self.lookup(name),
+            },
+            ExpressionKind::Unary { op, operand } => {
+                let ty = if op.is_mutating() {
+                    self.written(operand);
+                    self.place(operand)
+                } else {
+                    self.expression(operand)
+                };
+
+                self.applies(op.symbol(), Operands::unary(*op), ty.as_ref(), operand);
+                let operand = ty;
+
+                match op {
+                    UnaryOperator::Ref => Some(Ty::Reference(Box::new(operand?), false)),
+                    UnaryOperator::RefMut => Some(Ty::Reference(Box::new(operand?), true)),
+                    UnaryOperator::Deref => match operand? {
+                        Ty::Reference(inner, _) => Some(*inner),
+                        _ => None,
+                    },
+                    UnaryOperator::Not => Some(Ty::Primitive(Primitive::Bool)),
+                    _ => operand,
+                }
+            }
+            ExpressionKind::Binary { op, lhs, rhs } => self.binary(*op, lhs, rhs),
+            ExpressionKind::Cast { expression, ty } => {
+                self.expression(expression);
+                self.resolve_sized(ty)
+            }
+            ExpressionKind::Call { .. }
+            | ExpressionKind::StructLiteral { .. }
+            | ExpressionKind::Closure(_) => self.inferred(expression, None),
+            ExpressionKind::MethodCall {
+                receiver,
+                method,
+                arguments,
+            } => self.method_call(receiver, method, arguments, expression.span),
+            ExpressionKind::Field {
+                expression: inner,
+                field,
+            } => {
+                let ty = self.expression(inner)?;
+                self.field(&ty, field, expression.span)
+            }
+            // The elements are of the type of the first one whose type is
+            // known
+            ExpressionKind::Array(elements) => {
+                let mut element = None;
+
+                for value in elements {
+                    match &element {
+                        Some((ty, first)) => self.expect(value, ty, Some(*first)),
+                        None => element = self.expression(value).map(|ty| (ty, value.span)),
+                    }
+                }
+
+                let (element, _) = element?;
+                Some(Ty::Array(Box::new(element), elements.len() as u64))
+            }
+            ExpressionKind::ArrayRepeat { value, length } => {
+                let element = self.expression(value);
+                let length = array_length(length, self.diagnostics)?;
+
+                Some(Ty::Array(Box::new(element?), length))
+            }
+            ExpressionKind::Index {
+                expression: inner,
+                index,
+            } => {
+                self.expect(index, &Ty::Primitive(Primitive::Usize), None);
+                self.element(inner)
+            }
+            ExpressionKind::Slice {
+                expression: inner,
+                start,
+                end,
+            } => {
+                for bound in [start, end].into_iter().flatten() {
+                    self.expect(bound, &Ty::Primitive(Primitive::Usize), None);
+                }
+
+                // A slice taken through a `&mut` reference can be written
+                // through too
+                let ty = self.expression(inner)?;
+                let mutable = matches!(ty, Ty::Reference(_, true));
+                let element = self.indexed(&ty, inner)?;
+
+                Some(Ty::Reference(
+                    Box::new(Ty::Slice(Box::new(element))),
+                    mutable,
+                ))
+            }
+            // A variant without fields is a value of its enum, and one with
+            // fields a function making such a value
+            ExpressionKind::Path(path) => {
+                let (def, index) = self.variant(path, expression.span)?;
+                let enumeration = Ty::Enum(def.name.clone());
+
+                match &def.variants[index].fields {
+                    fields if fields.is_empty() => Some(enumeration),
+                    fields => Some(Ty::Function(fields.clone(), Box::new(enumeration))),
+                }
+            }
+            ExpressionKind::Match { scrutinee, arms } => {
+                self.match_expression(scrutinee, arms, None, expression)
+            }
+            // A block ending in a `return` never gives a value, so it fits
+            // wherever one is expected
+            ExpressionKind::Block(block) => {
+                self.statements(&block.statements);
+
+                match block.statements.last() {
+                    Some(Statement {
+                        kind: StatementKind::Return(_),
+                        ..
+                    }) => None,
+                    _ => Some(Ty::unit()),
+                }
+            }
+            // Replaced by the literal of its value before the checks, the
+            // type of which is only known then
+            ExpressionKind::Comptime(_) => None,
+        }
+    }
+
+    /// Check the operands of a binary operator, which are of the same type,
+    /// the type of the first one whose type is known. A shift can be by an
+    /// amount of any integer type. A comparison is a `bool`, the other
+    /// operators are of the type of their operands.
+    fn binary(&mut self, op: BinaryOperator, lhs: &Expression, rhs: &Expression) -> Option<Ty> {
+        let operands = Operands::binary(op);
+
+        let ty = match op {
+            BinaryOperator::Shl | BinaryOperator::Shr => {
+                let ty = self.expression(lhs);
+                let amount = self.expression(rhs);
+                self.applies(op.symbol(), operands, ty.as_ref(), lhs);
+                self.applies(op.symbol(), operands, amount.as_ref(), rhs);
+
+                return ty;
+            }
+            _ => match self.expression(lhs) {
+                Some(ty) => self.operand(rhs, ty, lhs.span),
+                None => {
+                    let ty = self.expression(rhs);
+
+                    // Number literals on the left take the type of the
+                    // right, and aren't checked again otherwise
+                    if let (Some(ty), Some(_)) = (&ty, self.unknown_numbers(lhs)) {
+                        self.expect(lhs, ty, Some(rhs.span));
+                    }
+
+                    ty
+                }
+            },
+        };
+
+        // The type of the operands is the one of the left, unless only the
+        // right one has a type
+        let operand = match self.unknown_numbers(lhs) {
+            Some(_) if ty.is_some() => rhs,
+            _ => lhs,
+        };
+
+        self.applies(op.symbol(), operands, ty.as_ref(), operand);
+
+        if op.precedence() == 1 {
+            Some(Ty::Primitive(Primitive::Bool))
+        } else {
+            ty
+        }
+    }
+
+    /// Check that an operator applies to its operand, of type `ty` if it is
+    /// known. A number literal, or arithmetic on them, is a number of any
+    /// type, an integer unless it is written with a point.
+    fn applies(&mut self, symbol: &str, operands: Operands, ty: Option<&Ty>, operand: &Expression) {
+        let found = match ty {
+            Some(Ty::Primitive(primitive)) if operands.allow(*primitive) => return,
+            // Nothing is known about a type parameter, so only operators
+            // applying to any type apply to it, and comparisons if it is
+            // bound to `Ord`
+            Some(_) if operands == Operands::Any => return,
+            Some(ty @ Ty::Param(_))
+                if operands == Operands::Ordered && self.implements(ty, ORD) =>
+            {
+                return
+            }
+            Some(ty) => ty.to_string(),
+            None => {
+                let float = match self.unknown_numbers(operand) {
+                    Some(_) => literals(operand)
+                        .iter()
+                        .any(|(number, _)| matches!(number, Number::Float(_))),
+                    None => return,
+                };
+
+                let integer = !float && operands.allow(Primitive::I32);
+
+                if integer || operands.allow(Primitive::F64) {
+                    return;
+                }
+
+                match float {
+                    true => "float".to_string(),
+                    false => "integer".to_string(),
+                }
+            }
+        };
+
+        self.diagnostics.report(OrionError::InvalidOperand {
+            operator: symbol.to_string(),
+            ty: found,
+            takes: operands.describe().to_string(),
+            loc: self.diagnostics.loc(operand.span),
+        });
+    }
+
+    /// Check the right operand of a binary operator against the type `ty`
+    /// of the left one at `lhs`, returning the type of both. A number of a
+    /// type the left one widens to makes both of that type, and numbers of
+    /// types neither widens to have none, the mismatch being reported once.
+    fn operand(&mut self, rhs: &Expression, ty: Ty, lhs: Span) -> Option<Ty> {
+        let left = match ty {
+            Ty::Primitive(left) if left.is_numeric() && self.unknown_numbers(rhs).is_none() => left,
+            _ => {
+                self.expect(rhs, &ty, Some(lhs));
+                return Some(ty);
+            }
+        };
+
+        match self.inferred(rhs, Some(&ty)) {
+            Some(Ty::Primitive(right)) if left.widens_to(right) => Some(Ty::Primitive(right)),
+            Some(found) if !coerces(&found, &ty) => {
+                self.mismatch(rhs, &ty, found.to_string(), Some(lhs));
+                None
+            }
+            _ => Some(ty),
+        }
+    }
+
+    /// Check a call or a struct literal, whose type arguments are inferred
+    /// from the type expected of it, if known, when what it makes is
+    /// generic, or a closure, whose types can be those expected of it
+    fn inferred(&mut self, expression: &Expression, expected: Option<&Ty>) -> Option<Ty> {
+        match &expression.kind {
+            ExpressionKind::Call { callee, arguments } => {
+                if let Some(name) = self.builtin(callee, arguments) {
+                    return self.builtin_call(name, arguments);
+                }
+
+                if let ExpressionKind::Identifier(name) = &callee.kind {
+                    if let Some(
+                        binding @ Binding {
+                            generic: Some(_), ..
+                        },
+                    ) = self.binding(name)
+                    {
+                        let binding = binding.clone();
+                        return self.generic_call(
+                            name,
+                            binding,
+                            arguments,
+                            expected,
+                            expression.span,
+                        );
+                    }
+                }
+
+                // A function called by name tells where the types of its
+                // parameters are written
+                let origins = match &callee.kind {
+                    ExpressionKind::Identifier(name) => self
+                        .binding(name)
+                        .map(|binding| binding.parameters.clone())
+                        .unwrap_or_default(),
+                    _ => vec![],
+                };
+
+                let ty = self.expression(callee);
+
+                match ty {
+                    Some(Ty::Function(parameters, result)) => {
+                        let name = match &callee.kind {
+                            ExpressionKind::Identifier(name) => name.clone(),
+                            ExpressionKind::Path(path) => path.join("::"),
+                            _ => Ty::Function(parameters.clone(), result.clone()).to_string(),
+                        };
+
+                        if !self.arity(&name, parameters.len(), arguments, expression.span) {
+                            return None;
+                        }
+
+                        for (i, (argument, parameter)) in
+                            arguments.iter().zip(&parameters).enumerate()
+                        {
+                            self.expect(argument, parameter, origins.get(i).copied());
+                        }
+
+                        Some(*result)
+                    }
+                    _ => {
+                        if let Some(ty) = ty {
+                            self.diagnostics.report(OrionError::NotCallable {
+                                ty: ty.to_string(),
+                                loc: self.diagnostics.loc(callee.span),
+                            });
+                        }
+
+                        for argument in arguments {
+                            self.expression(argument);
+                        }
+
+                        None
+                    }
+                }
+            }
+            ExpressionKind::StructLiteral { name, fields } => {
+                self.struct_literal(name, fields, expected, expression)
+            }
+            ExpressionKind::Closure(closure) => match expected {
+                Some(Ty::Function(parameters, result)) => {
+                    self.closure(closure, Some(parameters), Some(result), expression.span)
+                }
+                _ => self.closure(closure, None, None, expression.span),
+            },
+            _ => self.expression(expression),
+        }
+    }
+
+    /// Check a closure. The types of its parameters and result that aren't
+    /// written are the `parameters` and `result` expected of it, if known.
+    /// Without a result type, its body is of the result type, unless it is
+    /// a block, which returns `()`.
+    fn closure(
+        &mut self,
+        closure: &Closure,
+        parameters: Option<&[Ty]>,
+        result: Option<&Ty>,
+        span: Span,
+    ) -> Option<Ty> {
+        let name = format!("{}closure#{}", self.owner, self.numbered);
+        self.numbered += 1;
+
+        // Expected types for another number of parameters tell nothing
+        let parameters = parameters.filter(|types| types.len() == closure.parameters.len());
+
+        let captured = captures(closure, |name| self.is_local(name))
+            .into_iter()
+            .map(|capture| {
+                Some(Captured {
+                    ty: self.lookup(&capture.name)?,
+                    name: capture.name,
+                    mode: capture.mode,
+                })
+            })
+            .collect::<Option<Vec<_>>>();
+
+        let mut types = vec![];
+
+        for (i, parameter) in closure.parameters.iter().enumerate() {
+            let ty = match (&parameter.ty, parameters) {
+                (Some(ty), _) => self.resolve_sized(ty),
+                (None, Some(expected)) => Some(expected[i].clone()),
+                (None, None) => {
+                    self.diagnostics.report(OrionError::UntypedParameter {
+                        name: parameter.name.clone(),
+                        loc: self.diagnostics.loc(parameter.span),
+                    });
+
+                    None
+                }
+            };
+
+            types.push(ty);
+        }
+
+        let origin = closure.result.as_ref().map(|ty| ty.span);
+
+        // `None` if the result is found from the body
+        let declared = match (&closure.result, result, &closure.body.kind) {
+            (Some(ty), _, _) => Some(self.resolve_sized(ty)),
+            (None, Some(expected), _) => Some(Some(expected.clone())),
+            (None, None, ExpressionKind::Block(_)) => Some(Some(Ty::unit())),
+            (None, None, _) => None,
+        };
+
+        self.scopes.push(HashMap::new());
+
+        for (parameter, ty) in closure.parameters.iter().zip(&types) {
+            let binding = Binding::variable(ty.clone(), false, Some(parameter.span));
+            self.declare(&parameter.name, binding);
+        }
+
+        let outer = std::mem::replace(&mut self.result, declared.clone().flatten());
+        let outer_at = std::mem::replace(&mut self.result_at, origin);
+
+        let result = match declared {
+            // Whether every path through a block returns a value of the
+            // written result is for [`crate::sema::flow`] to tell
+            Some(Some(result))
+                if closure.result.is_some()
+                    && matches!(closure.body.kind, ExpressionKind::Block(_)) =>
+            {
+                self.expression(&closure.body);
+                Some(result)
+            }
+            Some(Some(result)) => {
+                self.expect(&closure.body, &result, origin);
+                Some(result)
+            }
+            Some(None) => {
+                self.expression(&closure.body);
+                None
+            }
+            None => self.expression(&closure.body),
+        };
+
+        // A body that isn't a block is returned
+        if !matches!(closure.body.kind, ExpressionKind::Block(_)) {
+            self.escapes(&closure.body);
+        }
+
+        self.result = outer;
+        self.result_at = outer_at;
+        self.scopes.pop();
+
+        let parameters = types.into_iter().collect::<Option<Vec<_>>>()?;
+        let result = result?;
+
+        if let Some(captures) = captured {
+            let function = lift(&name, closure, &captures, &parameters, &result, span);
+
+            self.closures.push(Converted {
+                name,
+                loc: self.diagnostics.loc(span),
+                captures,
+                parameters: parameters.clone(),
+                result: result.clone(),
+                function,
+            });
+        }
+
+        Some(Ty::Function(parameters, Box::new(result)))
+    }
+
+    /// Whether a variable is declared in the function being checked, rather
+    /// than at the top level
+    fn is_local(&self, name: &str) -> bool {
+        self.scopes
+            .iter()
+            .rposition(|scope| scope.contains_key(name))
+            .is_some_and(|index| index >= self.frame)
+    }
+
+    /// Check a call to a generic function, inferring its type arguments
+    fn generic_call(
+        &mut self,
+        name: &str,
+        binding: Binding,
+        arguments: &[Expression],
+        expected: Option<&Ty>,
+        span: Span,
+    ) -> Option<Ty> {
+        let (Some(Ty::Function(parameters, result)), Some(generics)) =
+            (binding.ty, binding.generic)
+        else {
+            for argument in arguments {
+                self.expression(argument);
+            }
+
+            return None;
+        };
+
+        if !self.arity(name, parameters.len(), arguments, span) {
+            return None;
+        }
+
+        let mut inference = Inference::new(&generics.names);
+        let values: Vec<Given> = arguments
+            .iter()
+            .zip(&parameters)
+            .enumerate()
+            .map(|(i, (argument, parameter))| {
+                (argument, parameter, binding.parameters.get(i).copied())
+            })
+            .collect();
+        let complete = self.infer(&mut inference, &values, expected.map(|ty| (&*result, ty)));
+
+        let types = self.type_arguments(&inference, name, complete, span)?;
+
+        for (ty, bounds) in types.iter().zip(&generics.bounds) {
+            for bound in bounds {
+                // A bound naming no trait was reported with the function
+                if self.traits.get(bound).is_some() && !self.implements(ty, bound) {
+                    self.diagnostics.report(OrionError::MissingImpl {
+                        trait_name: bound.clone(),
+                        ty: ty.to_string(),
+                        loc: self.diagnostics.loc(span),
+                    });
+                }
+            }
+        }
+
+        self.calls.call(
+            self.function,
+            generics.function,
+            name,
+            types,
+            span,
+            self.module,
+        );
+
+        Some(result.substitute(&inference.found))
+    }
+
+    /// Whether a call gives as many arguments as the function it calls has
+    /// parameters, reporting it and checking the arguments on their own
+    /// otherwise
+    fn arity(
+        &mut self,
+        callee: &str,
+        expected: usize,
+        arguments: &[Expression],
+        span: Span,
+    ) -> bool {
+        if arguments.len() == expected {
+            return true;
+        }
+
+        self.diagnostics.report(OrionError::ArgumentCount {
+            callee: callee.to_string(),
+            expected,
+            found: arguments.len(),
+            loc: self.diagnostics.loc(span),
+        });
+
+        for argument in arguments {
+            self.expression(argument);
+        }
+
+        false
+    }
+
+    /// Whether a type implements a trait. A type parameter implements the
+    /// traits it is bound to.
+    fn implements(&self, ty: &Ty, trait_name: &str) -> bool {
+        match ty {
+            Ty::Param(name) => self
+                .bounds
+                .get(name)
+                .is_some_and(|bounds| bounds.iter().any(|bound| bound == trait_name)),
+            _ => self.traits.implements(trait_name, ty),
+        }
+    }
+
+    /// Check a method call, the method being found by the type of the
+    /// receiver, which is read through references
+    fn method_call(
+        &mut self,
+        receiver: &Expression,
+        method: &str,
+        arguments: &[Expression],
+        span: Span,
+    ) -> Option<Ty> {
+        let Some(ty) = self.expression(receiver).map(|ty| deref(&ty).clone()) else {
+            for argument in arguments {
+                self.expression(argument);
+            }
+
+            return None;
+        };
+
+        let traits = self.traits;
+        let candidates: Vec<&TraitDef> = match &ty {
+            Ty::Param(name) => self
+                .bounds
+                .get(name)
+                .into_iter()
+                .flatten()
+                .filter_map(|bound| traits.get(bound))
+                .collect(),
+            _ => traits.implemented(&ty).collect(),
+        };
+
+        let found: Vec<(&TraitDef, &MethodDef)> = candidates
+            .into_iter()
+            .filter_map(|def| Some((def, def.method(method)?)))
+            .collect();
+
+        let signature = match found.as_slice() {
+            [] => {
+                self.diagnostics.report(OrionError::UnknownMethod {
+                    ty: ty.to_string(),
+                    method: method.to_string(),
+                    loc: self.diagnostics.loc(span),
+                });
+
+                None
+            }
+            [(_, def)] => def.ty_for(&ty),
+            [(first, _), (second, _), ..] => {
+                self.diagnostics.report(OrionError::AmbiguousMethod {
+                    ty: ty.to_string(),
+                    method: method.to_string(),
+                    traits: (first.name.clone(), second.name.clone()),
+                    loc: self.diagnostics.loc(span),
+                });
+
+                None
+            }
+        };
+
+        // `self` is the receiver, the arguments are for the other
+        // parameters
+        match signature {
+            Some(Ty::Function(parameters, result))
+                if self.arity(method, parameters.len() - 1, arguments, span) =>
+            {
+                for (argument, parameter) in arguments.iter().zip(&parameters[1..]) {
+                    self.expect(argument, parameter, None);
+                }
+
+                Some(*result)
+            }
+            Some(Ty::Function(..)) => None,
+            _ => {
+                for argument in arguments {
+                    self.expression(argument);
+                }
+
+                None
+            }
+        }
+    }
+
+    /// Check the values given for the parameters or fields of a generic
+    /// function or struct, finding its type arguments from their types, and
+    /// from the type `expected` of what is made if it is known. Number
+    /// literals fit many types, and closures can leave out the types of
+    /// their parameters, so they are checked last, against what the rest
+    /// tells. Returns whether the type of every other value is known.
+    fn infer(
+        &mut self,
+        inference: &mut Inference,
+        values: &[Given],
+        expected: Option<(&Ty, &Ty)>,
+    ) -> bool {
+        let mut deferred = vec![];
+        let mut complete = true;
+
+        for &(value, declared, origin) in values {
+            if let Some(ty) = inference.apply(declared) {
+                self.expect(value, &ty, origin);
+            } else if matches!(value.kind, ExpressionKind::Closure(_))
+                || self.unknown_numbers(value).is_some()
+            {
+                deferred.push((value, declared, origin));
+            } else if let Some(found) = self.expression(value) {
+                self.unify(inference, declared, &found, value, origin);
+            } else {
+                complete = false;
+            }
+        }
+
+        if let Some((made, expected)) = expected {
+            inference.unify(made, expected);
+        }
+
+        for (value, declared, origin) in deferred {
+            if let Some(ty) = inference.apply(declared) {
+                self.expect(value, &ty, origin);
+                continue;
+            }
+
+            let (ExpressionKind::Closure(closure), Ty::Function(parameters, _)) =
+                (&value.kind, declared)
+            else {
+                continue;
+            };
+
+            // The types of its parameters may be known while its result
+            // isn't, which its body then tells
+            let parameters = parameters
+                .iter()
+                .map(|parameter| inference.apply(parameter))
+                .collect::<Option<Vec<_>>>();
+
+            match self.closure(closure, parameters.as_deref(), None, value.span) {
+                Some(found) => self.unify(inference, declared, &found, value, origin),
+                None => complete = false,
+            }
+        }
+
+        complete
+    }
+
+    /// Find type arguments from the type `found` of a value given for the
+    /// `declared` type, reporting it if they disagree with those found
+    /// before
+    fn unify(
+        &mut self,
+        inference: &mut Inference,
+        declared: &Ty,
+        found: &Ty,
+        value: &Expression,
+        origin: Option<Span>,
+    ) {
+        if !inference.unify(declared, found) {
+            self.diagnostics.report(OrionError::MismatchedTypes {
+                expected: declared.substitute(&inference.found).to_string(),
+                found: found.to_string(),
+                origin: origin.map(|span| self.diagnostics.loc(span)),
+                loc: self.diagnostics.loc(value.span),
+            });
+        }
+    }
+
+    /// The type arguments found for a generic function or struct. The
+    /// first type parameter nothing told the type of is reported, unless
+    /// some value had no known type to tell it.
+    fn type_arguments(
+        &mut self,
+        inference: &Inference,
+        item: &str,
+        complete: bool,
+        span: Span,
+    ) -> Option<Vec<Ty>> {
+        let mut types = vec![];
+
+        for generic in inference.generics {
+            match inference.found.get(generic) {
+                Some(ty) => types.push(ty.clone()),
+                None => {
+                    if complete {
+                        self.diagnostics.report(OrionError::UninferredType {
+                            parameter: generic.clone(),
+                            item: item.to_string(),
+                            loc: self.diagnostics.loc(span),
+                        });
+                    }
+
+                    return None;
+                }
+            }
+        }
+
+        Some(types)
+    }
+
+    /// The enum and the position of the variant a path names, like
+    /// `Shape::Circle`
+    fn variant(&mut self, path: &[String], span: Span) -> Option<(&'a EnumDef, usize)> {
+        let structs = self.structs;
+        let (variant, enumeration) = path.split_last()?;
+        let name = enumeration.join("::");
+
+        let Some(def) = structs.get_enum(&name) else {
+            let error = if structs.get(&name).is_some() || is_primitive_type(&name) {
+                OrionError::NotAnEnum {
+                    name,
+                    loc: self.diagnostics.loc(span),
+                }
+            } else {
+                OrionError::UnknownType {
+                    name,
+                    loc: self.diagnostics.loc(span),
+                }
+            };
+
+            self.diagnostics.report(error);
+            return None;
+        };
+
+        match def.variant(variant) {
+            Some((index, _)) => Some((def, index)),
+            None => {
+                self.diagnostics.report(OrionError::UnknownVariant {
+                    enumeration: name,
+                    variant: variant.clone(),
+                    loc: self.diagnostics.loc(span),
+                });
+
+                None
+            }
+        }
+    }
+
+    /// Check a `match`, and that its arms are exhaustive and reachable. The
+    /// arms are of the expected type if there is one, with what requires
+    /// it, otherwise of the type of the first arm whose type is known.
+    fn match_expression(
+        &mut self,
+        scrutinee: &Expression,
+        arms: &[MatchArm],
+        expected: Option<(&Ty, Option<Span>)>,
+        expression: &Expression,
+    ) -> Option<Ty> {
+        // Like fields, enums are matched through references
+        let mut column = self
+            .expression(scrutinee)
+            .map(|ty| (deref(&ty).clone(), scrutinee.span));
+        let mut ty = expected.map(|(ty, origin)| (ty.clone(), origin));
+        let mut rows = vec![];
+        let mut valid = true;
+
+        for arm in arms {
+            self.scopes.push(HashMap::new());
+
+            let matched = column.as_ref().map(|(ty, _)| ty);
+            let origin = column.as_ref().map(|(_, origin)| *origin);
+
+            match self.pattern(&arm.pattern, matched, origin) {
+                Some(pat) => {
+                    // Without the type of the scrutinee, the first pattern
+                    // telling what is matched decides it
+                    if column.is_none() {
+                        column = pattern_type(&pat).map(|ty| (ty, arm.pattern.span));
+                    }
+
+                    rows.push((pat, arm.pattern.span));
+                }
+                None => valid = false,
+            }
+
+            match &ty {
+                Some((ty, origin)) => self.expect(&arm.body, ty, *origin),
+                None => {
+                    let found = self.expression(&arm.body);
+                    ty = found.map(|found| (found, Some(arm.body.span)));
+                }
+            }
+
+            self.scopes.pop();
+        }
+
+        // Patterns with errors would only add confusing reports
+        if valid {
+            self.exhaustive(&rows, column.map(|(ty, _)| ty), expression);
+        }
+
+        ty.map(|(ty, _)| ty)
+    }
+
+    fn exhaustive(&mut self, rows: &[(Pat, Span)], column: Option<Ty>, expression: &Expression) {
+        let usefulness = Usefulness::new(self.structs);
+        let tys = [column];
+
+        for (i, (pat, span)) in rows.iter().enumerate() {
+            let before: Vec<Vec<Pat>> =
+                rows[..i].iter().map(|(pat, _)| vec![pat.clone()]).collect();
+
+            if !usefulness.useful(&before, std::slice::from_ref(pat), &tys) {
+                self.diagnostics.report(OrionError::UnreachablePattern {
+                    loc: self.diagnostics.loc(*span),
+                });
+            }
+        }
+
+        let rows: Vec<Vec<Pat>> = rows.iter().map(|(pat, _)| vec![pat.clone()]).collect();
+        let missing = usefulness.missing(&rows, &tys);
+
+        if !missing.is_empty() {
+            self.diagnostics.report(OrionError::NonExhaustiveMatch {
+                missing: missing.iter().map(|row| row[0].to_string()).collect(),
+                loc: self.diagnostics.loc(expression.span),
+            });
+        }
+    }
+
+    /// Check a pattern against the type of what it matches, which `origin`
+    /// is the expression of if there is one, declaring the names it binds.
+    /// `None` is returned after reporting an error.
+    fn pattern(&mut self, pattern: &Pattern, ty: Option<&Ty>, origin: Option<Span>) -> Option<Pat> {
+        let (constructor, fields, found) = match &pattern.kind {
+            PatternKind::Wildcard => return Some(Pat::Wild),
+            PatternKind::Binding(name) => {
+                let binding = Binding::variable(ty.cloned(), false, Some(pattern.span));
+                self.declare(name, binding);
+                return Some(Pat::Wild);
+            }
+            // Like number literals, number patterns fit any number type
+            // they are a value of
+            PatternKind::Number(num) => match ty {
+                Some(Ty::Primitive(primitive)) if primitive.is_numeric() => {
+                    self.literal(*num, pattern.span, *primitive, origin);
+                    return Some(Pat::Constructor(Constructor::Number(*num), vec![]));
+                }
+                None => return Some(Pat::Constructor(Constructor::Number(*num), vec![])),
+                Some(ty) => {
+                    self.diagnostics.report(OrionError::MismatchedTypes {
+                        expected: ty.to_string(),
+                        found: "number".to_string(),
+                        origin: origin.map(|span| self.diagnostics.loc(span)),
+                        loc: self.diagnostics.loc(pattern.span),
+                    });
+
+                    return None;
+                }
+            },
+            PatternKind::Bool(value) => (
+                Constructor::Bool(*value),
+                vec![],
+                Ty::Primitive(Primitive::Bool),
+            ),
+            PatternKind::Char(chr) => (
+                Constructor::Char(*chr),
+                vec![],
+                Ty::Primitive(Primitive::Char),
+            ),
+            PatternKind::Variant { path, fields } => {
+                let Some((def, index)) = self.variant(path, pattern.span) else {
+                    self.bindings(fields);
+                    return None;
+                };
+
+                let variant = &def.variants[index];
+
+                if variant.fields.len() != fields.len() {
+                    self.diagnostics.report(OrionError::PatternArity {
+                        variant: path.join("::"),
+                        expected: variant.fields.len(),
+                        found: fields.len(),
+                        loc: self.diagnostics.loc(pattern.span),
+                    });
+
+                    self.bindings(fields);
+                    return None;
+                }
+
+                let fields: Vec<Option<Pat>> = fields
+                    .iter()
+                    .zip(&variant.fields)
+                    .map(|(field, ty)| self.pattern(field, Some(ty), None))
+                    .collect();
+
+                let constructor = Constructor::Variant {
+                    enumeration: def.name.clone(),
+                    variant: variant.name.clone(),
+                    index,
+                };
+
+                (
+                    constructor,
+                    fields.into_iter().collect::<Option<_>>()?,
+                    Ty::Enum(def.name.clone()),
+                )
+            }
+        };
+
+        let pat = Pat::Constructor(constructor, fields);
+
+        match ty {
+            Some(ty) if *ty != found => {
+                self.diagnostics.report(OrionError::MismatchedTypes {
+                    expected: ty.to_string(),
+                    found: found.to_string(),
+                    origin: origin.map(|span| self.diagnostics.loc(span)),
+                    loc: self.diagnostics.loc(pattern.span),
+                });
+
+                None
+            }
+            _ => Some(pat),
+        }
+    }
+
+    /// Declare the names bound by patterns that couldn't be checked, so
+    /// they don't refer to something else
+    fn bindings(&mut self, patterns: &[Pattern]) {
+        for pattern in patterns {
+            self.pattern(pattern, None, None);
+        }
+    }
+
+    /// The type of the field `field` of a value of type `ty`, read
+    /// through references
+    fn field(&mut self, ty: &Ty, field: &str, span: Span) -> Option<Ty> {
+        let ty = deref(ty);
+
+        let found = match ty {
+            Ty::Struct(name, types) => self.structs.get(name).and_then(|def| {
+                let (index, _) = def.field(field)?;
+                Some(def.field_types(types).swap_remove(index))
+            }),
+            _ => None,
+        };
+
+        if found.is_none() {
+            self.diagnostics.report(OrionError::UnknownField {
+                ty: ty.to_string(),
+                field: field.to_string(),
+                loc: self.diagnostics.loc(span),
+            });
+        }
+
+        found
+    }
+
+    /// The type of the elements of an indexed or sliced expression, which
+    /// are reached through references
+    fn element(&mut self, expression: &Expression) -> Option<Ty> {
+        let ty = self.expression(expression)?;
+        self.indexed(&ty, expression)
+    }
+
+    /// The type of the elements of `expression`, of type `ty`, read through
+    /// references
+    fn indexed(&mut self, ty: &Ty, expression: &Expression) -> Option<Ty> {
+        match deref(ty) {
+            Ty::Array(element, _) | Ty::Slice(element) => Some((**element).clone()),
+            _ => {
+                self.diagnostics.report(OrionError::NotIndexable {
+                    ty: ty.to_string(),
+                    loc: self.diagnostics.loc(expression.span),
+                });
+
+                None
+            }
+        }
+    }
+
+    /// The name of the builtin function a call is to, given as many
+    /// arguments as it takes. A function of the same name takes its place.
+    fn builtin<'e>(&self, callee: &'e Expression, arguments: &[Expression]) -> Option<&'e str> {
+        let ExpressionKind::Identifier(name) = &callee.kind else {
+            return None;
+        };
+
+        let parameters = if builtin_operator(name).is_some() {
+            2
+        } else {
+            1
+        };
+
+        (is_builtin_function(name)
+            && arguments.len() == parameters
+            && !self
+                .scopes
+                .iter()
+                .any(|scope| scope.contains_key(name.as_str())))
+        .then_some(name)
+    }
+
+    /// Check a call to a builtin function: `len`, which takes an array, a
+    /// slice or a string, or one like `wrapping_add`, which takes two
+    /// integers of the same type like its operator
+    fn builtin_call(&mut self, name: &str, arguments: &[Expression]) -> Option<Ty> {
+        let Some(op) = builtin_operator(name) else {
+            let ty = self.expression(&arguments[0])?;
+
+            return match deref(&ty) {
+                Ty::Array(..) | Ty::Slice(_) | Ty::Primitive(Primitive::Str) => {
+                    Some(Ty::Primitive(Primitive::Usize))
+                }
+                _ => {
+                    self.diagnostics.report(OrionError::NoLength {
+                        ty: ty.to_string(),
+                        loc: self.diagnostics.loc(arguments[0].span),
+                    });
+
+                    None
+                }
+            };
+        };
+
+        match self.binary(op, &arguments[0], &arguments[1])? {
+            Ty::Primitive(primitive) if primitive.is_integer() => Some(Ty::Primitive(primitive)),
+            ty => {
+                self.diagnostics.report(OrionError::MismatchedTypes {
+                    expected: "integer".to_string(),
+                    found: ty.to_string(),
+                    origin: None,
+                    loc: self.diagnostics.loc(arguments[0].span),
+                });
+
+                None
+            }
+        }
+    }
+
+    fn struct_literal(
+        &mut self,
+        name: &str,
+        fields: &[FieldInit],
+        expected: Option<&Ty>,
+        expression: &Expression,
+    ) -> Option<Ty> {
+        let structs = self.structs;
+
+        let Some(def) = structs.get(name) else {
+            let error = if is_primitive_type(name) {
+                OrionError::NotAStruct {
+                    name: name.to_string(),
+                    loc: self.diagnostics.loc(expression.span),
+                }
+            } else {
+                OrionError::UnknownType {
+                    name: name.to_string(),
+                    loc: self.diagnostics.loc(expression.span),
+                }
+            };
+
+            self.diagnostics.report(error);
+
+            for field in fields {
+                self.expression(&field.value);
+            }
+
+            return None;
+        };
+
+        let mut initialized: Vec<&str> = vec![];
+        let mut values = vec![];
+
+        for field in fields {
+            if initialized.contains(&field.name.as_str()) {
+                self.diagnostics.report(OrionError::DuplicateField {
+                    structure: name.to_string(),
+                    field: field.name.clone(),
+                    loc: self.diagnostics.loc(field.span),
+                });
+            }
+
+            match def.field(&field.name) {
+                Some((_, declared)) => {
+                    let origin = (def.module == self.module).then_some(declared.span);
+                    initialized.push(&field.name);
+                    values.push((&field.value, &declared.ty, origin));
+                }
+                None => {
+                    self.diagnostics.report(OrionError::UnknownField {
+                        ty: name.to_string(),
+                        field: field.name.clone(),
+                        loc: self.diagnostics.loc(field.span),
+                    });
+
+                    self.expression(&field.value);
+                }
+            }
+        }
+
+        let ty = if def.generics.is_empty() {
+            for (value, declared, origin) in values {
+                self.expect(value, declared, origin);
+            }
+
+            Some(Ty::Struct(name.to_string(), vec![]))
+        } else {
+            let generics = def.generics.iter().cloned().map(Ty::Param).collect();
+            let generic = Ty::Struct(name.to_string(), generics);
+
+            let mut inference = Inference::new(&def.generics);
+            let complete = self.infer(&mut inference, &values, expected.map(|ty| (&generic, ty)));
+
+            self.type_arguments(&inference, name, complete, expression.span)
+                .map(|types| Ty::Struct(name.to_string(), types))
+        };
+
+        let missing: Vec<String> = def
+            .fields
+            .iter()
+            .filter(|field| !initialized.contains(&field.name.as_str()))
+            .map(|field| field.name.clone())
+            .collect();
+
+        if !missing.is_empty() {
+            self.diagnostics.report(OrionError::MissingFields {
+                structure: name.to_string(),
+                fields: missing,
+                loc: self.diagnostics.loc(expression.span),
+            });
+        }
+
+        ty
+    }
+}
+
+/// The type arguments of a generic function or struct, as they are found
+/// from how it is used
+struct Inference<'g> {
+    generics: &'g [String],
+    found: HashMap<String, Ty>,
+}
+
+impl<'g> Inference<'g> {
+    fn new(generics: &'g [String]) -> Self {
+        Self {
+            generics,
+            found: HashMap::new(),
+        }
+    }
+
+    /// Find the type parameters the `declared` type mentions from the type
+    /// `found` of a value given for it, returning whether they agree with
+    /// the types found before
+    fn unify(&mut self, declared: &Ty, found: &Ty) -> bool {
+        match (declared, found) {
+            (Ty::Param(name), _) if self.generics.contains(name) => match self.found.get(name) {
+                Some(known) => known == found,
+                None => {
+                    self.found.insert(name.clone(), found.clone());
+                    true
+                }
+            },
+            (Ty::Struct(declared_name, declared), Ty::Struct(found_name, found)) => {
+                declared_name == found_name && self.unify_all(declared, found)
+            }
+            // A `&mut` reference also is a `&` one
+            (Ty::Reference(declared, expected), Ty::Reference(found, mutable))
+                if mutable >= expected =>
+            {
+                match (&**declared, &**found) {
+                    // A reference to an array also is a reference to a
+                    // slice of it
+                    (Ty::Slice(declared), Ty::Array(found, _)) => self.unify(declared, found),
+                    _ => self.unify(declared, found),
+                }
+            }
+            (Ty::Array(declared, expected), Ty::Array(found, length)) => {
+                expected == length && self.unify(declared, found)
+            }
+            (Ty::Slice(declared), Ty::Slice(found)) => self.unify(declared, found),
+            (Ty::Tuple(declared), Ty::Tuple(found)) => self.unify_all(declared, found),
+            (Ty::Function(declared, declared_result), Ty::Function(found, found_result)) => {
+                self.unify_all(declared, found) && self.unify(declared_result, found_result)
+            }
+            _ => declared == found,
+        }
+    }
+
+    fn unify_all(&mut self, declared: &[Ty], found: &[Ty]) -> bool {
+        declared.len() == found.len()
+            && declared
+                .iter()
+                .zip(found)
+                .all(|(declared, found)| self.unify(declared, found))
+    }
+
+    /// The `declared` type with the type parameters found so far in place,
+    /// if they are all it mentions
+    fn apply(&self, declared: &Ty) -> Option<Ty> {
+        let unknown = self
+            .generics
+            .iter()
+            .any(|generic| !self.found.contains_key(generic) && declared.mentions(generic));
+
+        (!unknown).then(|| declared.substitute(&self.found))
+    }
+}
+
+/// The type of the values a pattern matches, if it tells
+fn pattern_type(pat: &Pat) -> Option<Ty> {
+    match pat {
+        Pat::Constructor(Constructor::Variant { enumeration, .. }, _) => {
+            Some(Ty::Enum(enumeration.clone()))
+        }
+        Pat::Constructor(Constructor::Bool(_), _) => Some(Ty::Primitive(Primitive::Bool)),
+        Pat::Constructor(Constructor::Char(_), _) => Some(Ty::Primitive(Primitive::Char)),
+        Pat::Constructor(Constructor::Number(_), _) | Pat::Wild => None,
+    }
+}
+
+/// The names of the traits a type parameter is bound to
+fn bound_names(generic: &Generic) -> Vec<String> {
+    generic
+        .bounds
+        .iter()
+        .map(|bound| bound.join("::"))
+        .collect()
+}
+
+/// The number literals of an expression made of literals, variables and
+/// arithmetic on them, a literal written after a `-` being negative
+fn literals(expression: &Expression) -> Vec<Literal> {
+    match &expression.kind {
+        ExpressionKind::Number(number) => vec![(*number, expression.span)],
+        ExpressionKind::Unary {
+            op: UnaryOperator::Neg,
+            operand,
+        } => match &operand.kind {
+            ExpressionKind::Number(number) => vec![(-*number, expression.span)],
+            _ => literals(operand),
+        },
+        ExpressionKind::Unary {
+            op: UnaryOperator::BitNot,
+            operand,
+        } => literals(operand),
+        ExpressionKind::Binary { op, lhs, rhs } if op.precedence() > 1 => {
+            let mut found = literals(lhs);
+            found.extend(literals(rhs));
+            found
+        }
+        _ => vec![],
+    }
+}
+
+/// The operands of the `-`s in an expression made of literals, variables
+/// and arithmetic on them
+fn negated(expression: &Expression) -> Vec<&Expression> {
+    match &expression.kind {
+        ExpressionKind::Unary {
+            op: UnaryOperator::Neg,
+            operand,
+        } => {
+            let mut found = vec![&**operand];
+            found.extend(negated(operand));
+            found
+        }
+        ExpressionKind::Unary {
+            op: UnaryOperator::BitNot,
+            operand,
+        } => negated(operand),
+        ExpressionKind::Binary { op, lhs, rhs } if op.precedence() > 1 => {
+            let mut found = negated(lhs);
+            found.extend(negated(rhs));
+            found
+        }
+        _ => vec![],
+    }
+}
+
+/// The value of arithmetic on number literals done in the integer type
+/// `ty`, or the operation overflowing it with where it is. `None` for any
+/// other expression, and when a literal isn't a value of the type or a
+/// division is by zero, which are reported otherwise.
+fn fold(expression: &Expression, ty: Primitive) -> Option<Result<i128, (String, Span)>> {
+    let (min, max) = ty.range()?;
+    let fits = |num: i128| (min..=max).contains(&num);
+
+    let (operation, num) = match &expression.kind {
+        ExpressionKind::Number(Number::Integer(num)) => return fits(*num).then_some(Ok(*num)),
+        ExpressionKind::Unary {
+            op: UnaryOperator::Neg,
+            operand,
+        } => {
+            let num = match &operand.kind {
+                ExpressionKind::Number(Number::Integer(num)) => {
+                    return fits(-num).then_some(Ok(-num))
+                }
+                _ => match fold(operand, ty)? {
+                    Ok(num) => num,
+                    overflow => return Some(overflow),
+                },
+            };
+
+            (format!("-({num})"), num.checked_neg())
+        }
+        ExpressionKind::Binary { op, lhs, rhs } if op.precedence() > 1 => {
+            let a = match fold(lhs, ty)? {
+                Ok(num) => num,
+                overflow => return Some(overflow),
+            };
+
+            let b = match fold(rhs, ty)? {
+                Ok(num) => num,
+                overflow => return Some(overflow),
+            };
+
+            let bits = ty.bits()? as i128;
+
+            let num = match op {
+                BinaryOperator::Div | BinaryOperator::Rem if b == 0 => return None,
+                BinaryOperator::Add => a.checked_add(b),
+                BinaryOperator::Sub => a.checked_sub(b),
+                BinaryOperator::Mul => a.checked_mul(b),
+                BinaryOperator::Div => a.checked_div(b),
+                BinaryOperator::Rem => a.checked_rem(b),
+                BinaryOperator::BitAnd => Some(a & b),
+                BinaryOperator::BitOr => Some(a | b),
+                BinaryOperator::BitXor => Some(a ^ b),
+                // A shift only overflows by the width of the type or more
+                _ if !(0..bits).contains(&b) => None,
+                BinaryOperator::Shl => Some(ty.wrap(a << b)),
+                _ => Some(a >> b),
+            };
+
+            (format!("{a} {} {b}", op.symbol()), num)
+        }
+        _ => return None,
+    };
+
+    match num.filter(|num| fits(*num)) {
+        Some(num) => Some(Ok(num)),
+        None => Some(Err((operation, expression.span))),
+    }
+}
+
+/// The types of the operands an operator applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operands {
+    /// Arithmetic, increments and decrements
+    Numbers,
+    /// `-`, which has no value to give for an unsigned integer
+    Signed,
+    /// Shifts and `~`
+    Integers,
+    /// `&`, `|` and `^`
+    Bitwise,
+    /// `!`
+    Bool,
+    /// `<`, `<=`, `>` and `>=`
+    Ordered,
+    /// Equality, references and dereferences, which are checked otherwise
+    Any,
+}
+
+impl Operands {
+    fn binary(op: BinaryOperator) -> Self {
+        match op {
+            BinaryOperator::Add
+            | BinaryOperator::Sub
+            | BinaryOperator::Mul
+            | BinaryOperator::Div
+            | BinaryOperator::Rem => Operands::Numbers,
+            BinaryOperator::BitAnd | BinaryOperator::BitOr | BinaryOperator::BitXor => {
+                Operands::Bitwise
+            }
+            BinaryOperator::Shl | BinaryOperator::Shr => Operands::Integers,
+            BinaryOperator::Less
+            | BinaryOperator::LessEq
+            | BinaryOperator::Greater
+            | BinaryOperator::GreaterEq => Operands::Ordered,
+            BinaryOperator::Eq | BinaryOperator::NotEq => Operands::Any,
+        }
+    }
+
+    fn unary(op: UnaryOperator) -> Self {
+        match op {
+            UnaryOperator::Neg => Operands::Signed,
+            UnaryOperator::PreIncrement
+            | UnaryOperator::PreDecrement
+            | UnaryOperator::PostIncrement
+            | UnaryOperator::PostDecrement => Operands::Numbers,
+            UnaryOperator::BitNot => Operands::Integers,
+            UnaryOperator::Not => Operands::Bool,
+            UnaryOperator::Ref | UnaryOperator::RefMut | UnaryOperator::Deref => Operands::Any,
+        }
+    }
+
+    fn allow(&self, ty: Primitive) -> bool {
+        match self {
+            Operands::Numbers => ty.is_numeric(),
+            Operands::Signed => ty.is_signed() || ty.is_float(),
+            Operands::Integers => ty.is_integer(),
+            Operands::Bitwise => ty.is_integer() || ty == Primitive::Bool,
+            Operands::Bool => ty == Primitive::Bool,
+            Operands::Ordered => ty.is_numeric() || ty == Primitive::Char,
+            Operands::Any => true,
+        }
+    }
+
+    /// The types allowed, for errors
+    fn describe(&self) -> &'static str {
+        match self {
+            Operands::Numbers => "numbers",
+            Operands::Signed => "signed integers and floats",
+            Operands::Integers => "integers",
+            Operands::Bitwise => "integers and `bool`",
+            Operands::Bool => "`bool`",
+            Operands::Ordered => "numbers and `char`",
+            Operands::Any => "values of any type",
+        }
+    }
+}
+
+/// The type behind any number of references
+fn deref(mut ty: &Ty) -> &Ty {
+    while let Ty::Reference(referenced, _) = ty {
+        ty = referenced;
+    }
+
+    ty
+}
+
+/// Whether a value of type `found` can be used where `expected` is. A
+/// reference to an array also is a reference to a slice of it, a `&mut`
+/// reference also is a `&` one, and a number is a value of the types it
+/// widens to.
+fn coerces(found: &Ty, expected: &Ty) -> bool {
+    match (found, expected) {
+        (Ty::Primitive(found), Ty::Primitive(expected)) if found != expected => {
+            found.widens_to(*expected)
+        }
+        (Ty::Reference(found, mutable), Ty::Reference(expected, wanted)) if mutable >= wanted => {
+            match (&**found, &**expected) {
+                (Ty::Array(found, _), Ty::Slice(expected)) => found == expected,
+                _ => found == expected,
+            }
+        }
+        _ => found == expected,
+    }
+}
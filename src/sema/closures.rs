@@ -0,0 +1,498 @@
+//! Closures: what they capture and how they are lowered.
+//!
+//! A closure can use the variables of the functions and closures around
+//! it, which it captures when it is made. A variable the closure assigns
+//! to, increments or takes a reference to is captured by reference, the
+//! closure and the function sharing it, by a `&mut` one if the closure
+//! writes to it. Any other variable is captured by value, copied into the
+//! closure, so that later changes to the variable don't show in it. A
+//! closure inside another one captures what it uses from both, the outer
+//! closure capturing it first. Functions declared in a closure capture
+//! nothing, they can't use the variables around them.
+//!
+//! A closure capturing a variable by reference can't outlive it, so it
+//! can't be returned from the function or closure the variable is in.
+//!
+//! Closure conversion lowers closures to what any backend has. A function
+//! value is a pair of the code to run and an environment, which holds the
+//! captured variables, by value or as references. The code of a closure is
+//! a function of its own, lifted to the top level, taking a reference to
+//! its environment before its parameters. A named function is a pair with
+//! an empty environment, so that calls through function values are made
+//! the same way for both. [`Converted`] describes the lifted function of a
+//! closure and holds it, with the captured variables read from the
+//! environment.
+use crate::lexer::tokens::{Location, Span};
+use crate::parser::ast::*;
+use crate::parser::visit::{
+    walk_block, walk_block_mut, walk_expression, walk_expression_mut, walk_statement,
+    walk_statement_mut, Visitor, VisitorMut,
+};
+use crate::sema::ty::Ty;
+use std::fmt::{Display, Formatter, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CaptureMode {
+    Value,
+    Reference,
+    Mutable,
+}
+
+/// A variable of the functions around a closure that the closure uses
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capture {
+    pub name: String,
+    pub mode: CaptureMode,
+}
+
+/// The variables a closure captures, in the order it first uses them.
+/// `is_local` tells whether a name the closure doesn't declare itself is a
+/// variable of the functions around it, rather than a top-level item.
+pub fn captures(closure: &Closure, is_local: impl Fn(&str) -> bool) -> Vec<Capture> {
+    let mut finder = CaptureFinder {
+        scopes: vec![],
+        captures: vec![],
+        is_local,
+    };
+
+    finder.closure(closure);
+    finder.captures
+}
+
+struct CaptureFinder<'ast, F> {
+    /// The names declared inside the closure, innermost scope last
+    scopes: Vec<Vec<&'ast str>>,
+    captures: Vec<Capture>,
+    is_local: F,
+}
+
+impl<'ast, F: Fn(&str) -> bool> CaptureFinder<'ast, F> {
+    fn closure(&mut self, closure: &'ast Closure) {
+        let parameters = closure
+            .parameters
+            .iter()
+            .map(|parameter| parameter.name.as_str())
+            .collect();
+
+        self.scopes.push(parameters);
+        self.visit_expression(&closure.body);
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, name: &'ast str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(name);
+        }
+    }
+
+    fn capture(&mut self, name: &str, mode: CaptureMode) {
+        let declared = self.scopes.iter().any(|scope| scope.contains(&name));
+
+        if declared || !(self.is_local)(name) {
+            return;
+        }
+
+        match self
+            .captures
+            .iter_mut()
+            .find(|capture| capture.name == name)
+        {
+            Some(capture) if mode > capture.mode => capture.mode = mode,
+            Some(_) => {}
+            None => self.captures.push(Capture {
+                name: name.to_string(),
+                mode,
+            }),
+        }
+    }
+
+    /// Capture the variable a place is in by reference, as it is written to
+    /// or referred to
+    fn referred(&mut self, place: &Expression, mode: CaptureMode) {
+        if let Some(name) = root(place) {
+            self.capture(name, mode);
+        }
+    }
+}
+
+impl<'ast, F: Fn(&str) -> bool> Visitor<'ast> for CaptureFinder<'ast, F> {
+    fn visit_statement(&mut self, stmt: &'ast Statement) {
+        match &stmt.kind {
+            StatementKind::Let { name, initial, .. } => {
+                if let Some(initial) = initial {
+                    self.visit_expression(initial);
+                }
+
+                self.declare(name);
+            }
+            // Declared when the block around it started
+            StatementKind::Function(_) => {}
+            StatementKind::Assign { place, value, .. } => {
+                self.referred(place, CaptureMode::Mutable);
+                self.visit_expression(place);
+                self.visit_expression(value);
+            }
+            _ => walk_statement(self, stmt),
+        }
+    }
+
+    fn visit_block(&mut self, block: &'ast Block) {
+        let functions = block
+            .statements
+            .iter()
+            .filter_map(|stmt| match &stmt.kind {
+                StatementKind::Function(function) => Some(function.name.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        self.scopes.push(functions);
+        walk_block(self, block);
+        self.scopes.pop();
+    }
+
+    fn visit_expression(&mut self, expr: &'ast Expression) {
+        match &expr.kind {
+            ExpressionKind::Identifier(name) => self.capture(name, CaptureMode::Value),
+            ExpressionKind::Unary { op, operand } => {
+                if op.is_mutating() {
+                    self.referred(operand, CaptureMode::Mutable);
+                } else if *op == UnaryOperator::Ref {
+                    self.referred(operand, CaptureMode::Reference);
+                }
+
+                self.visit_expression(operand);
+            }
+            ExpressionKind::Match { scrutinee, arms } => {
+                self.visit_expression(scrutinee);
+
+                for arm in arms {
+                    let mut bindings = vec![];
+                    pattern_bindings(&arm.pattern, &mut bindings);
+
+                    self.scopes.push(bindings);
+                    self.visit_expression(&arm.body);
+                    self.scopes.pop();
+                }
+            }
+            ExpressionKind::Closure(closure) => self.closure(closure),
+            _ => walk_expression(self, expr),
+        }
+    }
+
+    // Types don't mention variables
+    fn visit_type(&mut self, _: &'ast Type) {}
+}
+
+/// The variable a place is in, like `p` for `p.items[0]`
+fn root(place: &Expression) -> Option<&str> {
+    match &place.kind {
+        ExpressionKind::Identifier(name) => Some(name),
+        ExpressionKind::Field { expression, .. } | ExpressionKind::Index { expression, .. } => {
+            root(expression)
+        }
+        _ => None,
+    }
+}
+
+fn pattern_bindings<'ast>(pattern: &'ast Pattern, bindings: &mut Vec<&'ast str>) {
+    match &pattern.kind {
+        PatternKind::Binding(name) => bindings.push(name),
+        PatternKind::Variant { fields, .. } => {
+            for field in fields {
+                pattern_bindings(field, bindings);
+            }
+        }
+        PatternKind::Wildcard
+        | PatternKind::Number(_)
+        | PatternKind::Bool(_)
+        | PatternKind::Char(_) => {}
+    }
+}
+
+/// A captured variable of a converted closure, with its type
+#[derive(Debug, Clone, PartialEq)]
+pub struct Captured {
+    pub name: String,
+    pub mode: CaptureMode,
+    pub ty: Ty,
+}
+
+impl Captured {
+    /// The type the variable has in the environment
+    pub fn stored(&self) -> Ty {
+        match self.mode {
+            CaptureMode::Value => self.ty.clone(),
+            CaptureMode::Reference => Ty::Reference(Box::new(self.ty.clone()), false),
+            CaptureMode::Mutable => Ty::Reference(Box::new(self.ty.clone()), true),
+        }
+    }
+}
+
+/// A closure lowered to a function of its own and an environment
+#[derive(Debug, Clone, PartialEq)]
+pub struct Converted {
+    /// The name of the lifted function, `closure#N` after the function the
+    /// closure is in, `N` counting its closures from 0
+    pub name: String,
+    pub loc: Location,
+    pub captures: Vec<Captured>,
+    pub parameters: Vec<Ty>,
+    pub result: Ty,
+    /// The lifted function, see [`lift`]
+    pub function: Function,
+}
+
+impl Converted {
+    /// The type of the environment, a tuple of the captured variables
+    pub fn environment(&self) -> Ty {
+        Ty::Tuple(self.captures.iter().map(Captured::stored).collect())
+    }
+
+    /// The type of the lifted function, which takes a reference to the
+    /// environment first
+    pub fn lifted(&self) -> Ty {
+        let mut parameters = vec![Ty::Reference(Box::new(self.environment()), false)];
+        parameters.extend(self.parameters.iter().cloned());
+
+        Ty::Function(parameters, Box::new(self.result.clone()))
+    }
+}
+
+/// `main::closure#0 : func(&(u32, &mut u32), u32) -> u32 capturing count, &mut total`
+impl Display for Converted {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{} : {}", self.name, self.lifted())?;
+
+        for (i, captured) in self.captures.iter().enumerate() {
+            let separator = if i > 0 { "," } else { " capturing" };
+            let reference = match captured.mode {
+                CaptureMode::Value => "",
+                CaptureMode::Reference => "&",
+                CaptureMode::Mutable => "&mut ",
+            };
+
+            write!(f, "{separator} {reference}{}", captured.name)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The name of the parameter of a lifted function its environment is
+/// given for, which no variable can have
+pub const ENVIRONMENT: &str = "environment#";
+
+/// The function a closure is lifted to, named `name`. It takes a reference
+/// to the environment, a tuple of the `captures`, before the parameters of
+/// the closure, and reads the captured variables from it: `x` becomes
+/// `(*environment#).0` for a variable captured by value and
+/// `*(*environment#).0` for one captured by reference. The closures inside
+/// it reach them the same way. A body that isn't a block is returned.
+pub fn lift(
+    name: &str,
+    closure: &Closure,
+    captures: &[Captured],
+    parameters: &[Ty],
+    result: &Ty,
+    span: Span,
+) -> Function {
+    let environment = Ty::Reference(
+        Box::new(Ty::Tuple(captures.iter().map(Captured::stored).collect())),
+        false,
+    );
+
+    let mut lifted = vec![Parameter {
+        name: ENVIRONMENT.to_string(),
+        ty: written(&environment, span),
+        span,
+    }];
+
+    for (parameter, ty) in closure.parameters.iter().zip(parameters) {
+        lifted.push(Parameter {
+            name: parameter.name.clone(),
+            ty: written(ty, parameter.span),
+            span: parameter.span,
+        });
+    }
+
+    let body = &closure.body;
+    let mut body = match &body.kind {
+        ExpressionKind::Block(block) => block.clone(),
+        _ => Block {
+            statements: vec![Statement {
+                kind: StatementKind::Return(Some(body.as_ref().clone())),
+                span: body.span,
+            }],
+            span: body.span,
+        },
+    };
+
+    let names = closure.parameters.iter().map(|p| p.name.clone()).collect();
+    let mut rewriter = Rewriter {
+        captures,
+        scopes: vec![names],
+    };
+    rewriter.visit_block_mut(&mut body);
+
+    Function {
+        name: name.to_string(),
+        public: false,
+        generics: vec![],
+        parameters: lifted,
+        result: (*result != Ty::unit()).then(|| written(result, span)),
+        body,
+    }
+}
+
+/// The type written for a type, at `span`
+fn written(ty: &Ty, span: Span) -> Type {
+    let written = |ty: &Ty| written(ty, span);
+    let named = |name: &str, arguments: Vec<Type>| {
+        TypeKind::Named(Path {
+            segments: name.split("::").map(str::to_string).collect(),
+            arguments,
+        })
+    };
+
+    let kind = match ty {
+        Ty::Primitive(primitive) => named(primitive.name(), vec![]),
+        Ty::Struct(name, arguments) => named(name, arguments.iter().map(written).collect()),
+        Ty::Enum(name) | Ty::Param(name) => named(name, vec![]),
+        Ty::Reference(inner, mutable) => TypeKind::Reference {
+            inner: Box::new(written(inner)),
+            mutable: *mutable,
+        },
+        Ty::Array(element, length) => TypeKind::Array {
+            element: Box::new(written(element)),
+            length: Box::new(Expression::new(
+                ExpressionKind::Number(Number::Integer(*length as i128)),
+                span,
+            )),
+        },
+        Ty::Slice(element) => TypeKind::Slice(Box::new(written(element))),
+        Ty::Tuple(elements) => TypeKind::Tuple(elements.iter().map(written).collect()),
+        Ty::Function(parameters, result) => TypeKind::Function {
+            parameters: parameters.iter().map(written).collect(),
+            result: (**result != Ty::unit()).then(|| Box::new(written(result))),
+        },
+    };
+
+    Type::new(kind, span)
+}
+
+/// Rewrites the uses of captured variables in the body of a lifted closure
+/// to reads from its environment
+struct Rewriter<'c> {
+    captures: &'c [Captured],
+    /// The names declared inside the closure, which hide captured
+    /// variables, innermost scope last
+    scopes: Vec<Vec<String>>,
+}
+
+impl Rewriter<'_> {
+    fn declare(&mut self, name: &str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(name.to_string());
+        }
+    }
+
+    /// What a captured variable is read as, if `name` is one
+    fn environment(&self, name: &str, span: Span) -> Option<ExpressionKind> {
+        if self
+            .scopes
+            .iter()
+            .any(|scope| scope.iter().any(|n| n == name))
+        {
+            return None;
+        }
+
+        let index = self.captures.iter().position(|c| c.name == name)?;
+
+        let unary = |op, operand| ExpressionKind::Unary {
+            op,
+            operand: Box::new(Expression::new(operand, span)),
+        };
+
+        let environment = ExpressionKind::Identifier(ENVIRONMENT.to_string());
+        let field = ExpressionKind::Field {
+            expression: Box::new(Expression::new(
+                unary(UnaryOperator::Deref, environment),
+                span,
+            )),
+            field: index.to_string(),
+        };
+
+        Some(match self.captures[index].mode {
+            CaptureMode::Value => field,
+            CaptureMode::Reference | CaptureMode::Mutable => unary(UnaryOperator::Deref, field),
+        })
+    }
+}
+
+impl VisitorMut for Rewriter<'_> {
+    fn visit_statement_mut(&mut self, stmt: &mut Statement) {
+        match &mut stmt.kind {
+            StatementKind::Let { name, initial, .. } => {
+                if let Some(initial) = initial {
+                    self.visit_expression_mut(initial);
+                }
+
+                let name = name.clone();
+                self.declare(&name);
+            }
+            // Functions don't use the variables around them
+            StatementKind::Function(_) => {}
+            _ => walk_statement_mut(self, stmt),
+        }
+    }
+
+    fn visit_block_mut(&mut self, block: &mut Block) {
+        let functions = block
+            .statements
+            .iter()
+            .filter_map(|stmt| match &stmt.kind {
+                StatementKind::Function(function) => Some(function.name.clone()),
+                _ => None,
+            })
+            .collect();
+
+        self.scopes.push(functions);
+        walk_block_mut(self, block);
+        self.scopes.pop();
+    }
+
+    fn visit_expression_mut(&mut self, expr: &mut Expression) {
+        match &mut expr.kind {
+            ExpressionKind::Identifier(name) => {
+                if let Some(kind) = self.environment(name, expr.span) {
+                    expr.kind = kind;
+                }
+            }
+            ExpressionKind::Match { scrutinee, arms } => {
+                self.visit_expression_mut(scrutinee);
+
+                for arm in arms {
+                    let mut bindings = vec![];
+                    pattern_bindings(&arm.pattern, &mut bindings);
+
+                    self.scopes
+                        .push(bindings.into_iter().map(str::to_string).collect());
+                    self.visit_expression_mut(&mut arm.body);
+                    self.scopes.pop();
+                }
+            }
+            ExpressionKind::Closure(closure) => {
+                let parameters = closure.parameters.iter().map(|p| p.name.clone());
+
+                self.scopes.push(parameters.collect());
+                self.visit_expression_mut(&mut closure.body);
+                self.scopes.pop();
+            }
+            _ => walk_expression_mut(self, expr),
+        }
+    }
+
+    // Types don't mention variables
+    fn visit_type_mut(&mut self, _: &mut Type) {}
+}
@@ -0,0 +1,611 @@
+//! Control flow: the paths running the body of a function can take.
+//!
+//! The body of every function and closure is lowered to a control-flow
+//! graph, see [`Graph`]. Its nodes are statements always run one after the
+//! other, and its edges are where running can go next: into either branch
+//! of an `if` or any arm of a `match`, past them once they are done, and
+//! out of the body from a `return`. What no path from the start of the body
+//! reaches never runs.
+//!
+//! A function with a result other than `()` that can reach the end of its
+//! body without returning is reported, as is a closure with such a result
+//! and a block for a body: the caller would get nothing back. Both paths of
+//! an `if` with an `else` returning, or every arm of a `match`, is enough.
+//! A statement no path reaches is warned about, only the first of those
+//! following each other in a block, and the items declared in a block,
+//! which don't run, aren't.
+//!
+//! A variable declared without an initializer, `let x : u8;`, must be
+//! assigned on every path to where it is read, which the nodes tell by
+//! recording the reads and writes of variables in the order they happen,
+//! see [`Step`]. The variables assigned for certain, and those assigned on
+//! some path, flow forward along the edges: into a node come those assigned
+//! on every edge, or on any edge. A read of one not assigned on every path
+//! is reported with where a path missing the assignment starts, and a
+//! variable that isn't `mut` can only be assigned while no path has
+//! assigned it yet. Creating a closure reads the variables it captures. A
+//! top-level variable needs an initializer, as functions can read it at
+//! any time. The graph has no cycles as the language has no loops, so the
+//! nodes are in an order where every edge goes forward, and one pass over
+//! them is enough.
+use crate::error::OrionError;
+use crate::lexer::tokens::Span;
+use crate::parser::ast::*;
+use crate::parser::printer::print_type;
+use crate::parser::visit::{walk_expression, walk_statement, Visitor};
+use crate::sema::names::{Definition, Names};
+use crate::sema::Diagnostics;
+
+/// The control-flow graph of the body of a function or a closure
+#[derive(Debug)]
+pub struct Graph<'ast> {
+    pub nodes: Vec<Node<'ast>>,
+    /// The node reached by running the body to its end, without returning
+    pub end: usize,
+    /// The node every path leaves the body through, returning or reaching
+    /// the end
+    pub exit: usize,
+}
+
+/// The node of a graph where running the body starts
+pub const ENTRY: usize = 0;
+
+/// Statements run one after the other, with the nodes running can go to
+/// next
+#[derive(Debug, Default)]
+pub struct Node<'ast> {
+    pub statements: Vec<&'ast Statement>,
+    /// The reads and writes of variables declared in the body, in order
+    pub steps: Vec<Step>,
+    pub successors: Vec<usize>,
+    /// Where the path through the node starts, for the first node of a
+    /// branch of an `if` or an arm of a `match`. The branch of an `if`
+    /// without an `else` that skips it starts at the `if`.
+    pub start: Option<Span>,
+}
+
+/// What happens to a variable declared in the body, by the span of its
+/// `let`, at `at`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    Read { variable: Span, at: Span },
+    Write { variable: Span, at: Span },
+}
+
+impl<'ast> Graph<'ast> {
+    /// The graph of the body of a function, or of a closure with a block
+    /// for a body, in `module`
+    pub fn build(body: &'ast Block, names: &Names, module: usize) -> Self {
+        let mut builder = Builder::new(names, module);
+        builder.visit_block(body);
+        builder.finish().0
+    }
+
+    /// Which nodes some path from the start of the body reaches
+    pub fn reachable(&self) -> Vec<bool> {
+        let mut reachable = vec![false; self.nodes.len()];
+        reachable[ENTRY] = true;
+
+        // Edges only go forward, so a node is reached once every node
+        // before it is known
+        for (index, node) in self.nodes.iter().enumerate() {
+            if reachable[index] {
+                for &successor in &node.successors {
+                    reachable[successor] = true;
+                }
+            }
+        }
+
+        reachable
+    }
+
+    /// The nodes every node is a successor of
+    pub fn predecessors(&self) -> Vec<Vec<usize>> {
+        let mut predecessors = vec![vec![]; self.nodes.len()];
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            for &successor in &node.successors {
+                predecessors[successor].push(index);
+            }
+        }
+
+        predecessors
+    }
+}
+
+/// Check the control flow of the functions and closures of `module`, whose
+/// names are resolved in `names`
+pub fn check(program: &Program, names: &Names, module: usize, diagnostics: &mut Diagnostics) {
+    for stmt in program {
+        if let StatementKind::Let {
+            name,
+            initial: None,
+            ..
+        } = &stmt.kind
+        {
+            diagnostics.report(OrionError::UninitializedGlobal {
+                name: name.clone(),
+                loc: diagnostics.loc(stmt.span),
+            });
+        }
+    }
+
+    let mut checker = Checker {
+        names,
+        module,
+        diagnostics,
+    };
+
+    checker.visit_program(program);
+}
+
+struct Checker<'a, 'd> {
+    names: &'a Names,
+    module: usize,
+    diagnostics: &'a mut Diagnostics<'d>,
+}
+
+/// A variable declared without an initializer in the body being checked
+struct Variable<'ast> {
+    name: &'ast str,
+    mutable: bool,
+    /// The span of its `let`
+    declared: Span,
+}
+
+/// The variables assigned on every path to a point, and those assigned on
+/// some path, by their index in the variables of the body
+#[derive(Clone)]
+struct Assigned {
+    every: Vec<bool>,
+    some: Vec<bool>,
+}
+
+impl Assigned {
+    /// What comes into a node from a node before it, `None` at first
+    fn merge(into: &mut Option<Assigned>, from: &Assigned) {
+        match into {
+            Some(into) => {
+                for (i, every) in into.every.iter_mut().enumerate() {
+                    *every &= from.every[i];
+                    into.some[i] |= from.some[i];
+                }
+            }
+            None => *into = Some(from.clone()),
+        }
+    }
+}
+
+impl Checker<'_, '_> {
+    /// Check a body whose result is `result`, `name` being the name of its
+    /// function, if it isn't a closure's
+    fn body(&mut self, body: &Block, result: Option<&Type>, name: Option<&str>) {
+        let mut builder = Builder::new(self.names, self.module);
+        builder.visit_block(body);
+        let variables = std::mem::take(&mut builder.variables);
+        let (graph, statements) = builder.finish();
+        let reachable = graph.reachable();
+
+        for (stmt, node, previous) in statements {
+            if !reachable[node] && reachable[previous] {
+                self.diagnostics.warn(OrionError::UnreachableCode {
+                    loc: self.diagnostics.loc(stmt.span),
+                });
+            }
+        }
+
+        if !variables.is_empty() {
+            self.assignments(&graph, &reachable, &variables);
+        }
+
+        let Some(result) = result.filter(|ty| !is_unit(ty)) else {
+            return;
+        };
+
+        if reachable[graph.end] {
+            // The closing brace of the body
+            let end = Span::new(body.span.end.saturating_sub(1), body.span.end);
+
+            self.diagnostics.report(OrionError::MissingReturn {
+                function: name.map(str::to_string),
+                ty: print_type(result),
+                loc: self.diagnostics.loc(end),
+            });
+        }
+    }
+
+    /// Check that the variables declared without an initializer are
+    /// assigned before they are read, and only once unless they are `mut`
+    fn assignments(&mut self, graph: &Graph, reachable: &[bool], variables: &[Variable]) {
+        let none = Assigned {
+            every: vec![false; variables.len()],
+            some: vec![false; variables.len()],
+        };
+
+        let mut incoming: Vec<Option<Assigned>> = vec![None; graph.nodes.len()];
+        incoming[ENTRY] = Some(none.clone());
+        let mut outgoing = Vec::with_capacity(graph.nodes.len());
+
+        for (index, node) in graph.nodes.iter().enumerate() {
+            // Nothing reaches a node without any coming in, like the one
+            // after a `return`, so it holds for every variable that it is
+            // assigned on every path through it
+            let mut assigned = incoming[index].take().unwrap_or_else(|| Assigned {
+                every: vec![true; variables.len()],
+                some: vec![false; variables.len()],
+            });
+
+            for step in &node.steps {
+                let (Step::Read { variable, at } | Step::Write { variable, at }) = *step;
+
+                let Some(i) = variables.iter().position(|v| v.declared == variable) else {
+                    continue;
+                };
+
+                let variable = &variables[i];
+
+                match step {
+                    Step::Read { .. } if !assigned.every[i] => {
+                        if reachable[index] {
+                            let missing = assigned.some[i]
+                                .then(|| missing(graph, &outgoing, index, i))
+                                .flatten();
+
+                            self.diagnostics.report(OrionError::Uninitialized {
+                                name: variable.name.to_string(),
+                                missing: missing.map(|span| self.diagnostics.loc(span)),
+                                loc: self.diagnostics.loc(at),
+                            });
+                        }
+
+                        // Reported once on its path
+                        assigned.every[i] = true;
+                    }
+                    Step::Read { .. } => {}
+                    Step::Write { .. } => {
+                        if !variable.mutable && assigned.some[i] && reachable[index] {
+                            self.diagnostics.report(OrionError::ImmutableAssignment {
+                                name: variable.name.to_string(),
+                                declared: Some(self.diagnostics.loc(variable.declared)),
+                                loc: self.diagnostics.loc(at),
+                            });
+                        }
+
+                        assigned.every[i] = true;
+                        assigned.some[i] = true;
+                    }
+                }
+            }
+
+            for &successor in &node.successors {
+                Assigned::merge(&mut incoming[successor], &assigned);
+            }
+
+            outgoing.push(assigned);
+        }
+    }
+}
+
+/// Where a path to `node` missing the assignment of variable `i` starts,
+/// when some other path to it assigns it. `outgoing` is what is assigned
+/// leaving every node before it.
+fn missing(graph: &Graph, outgoing: &[Assigned], node: usize, i: usize) -> Option<Span> {
+    let predecessors = graph.predecessors();
+    let mut current = node;
+
+    // Back along the paths not assigning it, up to where one splits from
+    // the paths that do
+    loop {
+        let mut unassigned = predecessors[current]
+            .iter()
+            .copied()
+            .filter(|&p| !outgoing[p].every[i]);
+
+        let never = unassigned.clone().find(|&p| !outgoing[p].some[i]);
+
+        match never {
+            Some(p) => {
+                current = p;
+                break;
+            }
+            None => current = unassigned.next_back()?,
+        }
+    }
+
+    // No path to this node assigns it, the first node of its branch tells
+    // which path it is
+    loop {
+        if let Some(start) = graph.nodes[current].start {
+            return Some(start);
+        }
+
+        current = *predecessors[current].first()?;
+    }
+}
+
+impl<'ast> Visitor<'ast> for Checker<'_, '_> {
+    fn visit_statement(&mut self, stmt: &'ast Statement) {
+        if let StatementKind::Function(function) = &stmt.kind {
+            let result = function.result.as_ref();
+            self.body(&function.body, result, Some(&function.name));
+        }
+
+        walk_statement(self, stmt);
+    }
+
+    fn visit_expression(&mut self, expr: &'ast Expression) {
+        match &expr.kind {
+            ExpressionKind::Closure(closure) => {
+                if let ExpressionKind::Block(body) = &closure.body.kind {
+                    self.body(body, closure.result.as_deref(), None);
+                }
+
+                walk_expression(self, expr);
+            }
+            // Evaluated while compiling, and replaced by a literal
+            ExpressionKind::Comptime(_) => {}
+            _ => walk_expression(self, expr),
+        }
+    }
+}
+
+/// Whether a type is `()`, the result of functions returning nothing
+fn is_unit(ty: &Type) -> bool {
+    matches!(&ty.kind, TypeKind::Tuple(elements) if elements.is_empty())
+}
+
+/// Lowers a body to its graph, node by node
+struct Builder<'ast, 'a> {
+    names: &'a Names,
+    module: usize,
+    nodes: Vec<Node<'ast>>,
+    /// The node the statements lowered go to
+    current: usize,
+    /// The nodes leaving the body by a `return`
+    exits: Vec<usize>,
+    /// Every statement run, with its node and the node of the statement
+    /// before it in its block, or its own for the first one
+    statements: Vec<(&'ast Statement, usize, usize)>,
+    /// The variables declared without an initializer
+    variables: Vec<Variable<'ast>>,
+}
+
+impl<'ast, 'a> Builder<'ast, 'a> {
+    fn new(names: &'a Names, module: usize) -> Self {
+        Self {
+            names,
+            module,
+            nodes: vec![Node::default()],
+            current: ENTRY,
+            exits: vec![],
+            statements: vec![],
+            variables: vec![],
+        }
+    }
+
+    fn node(&mut self) -> usize {
+        self.nodes.push(Node::default());
+        self.nodes.len() - 1
+    }
+
+    fn edge(&mut self, from: usize, to: usize) {
+        self.nodes[from].successors.push(to);
+    }
+
+    /// Start a node following `from`, the first of a path starting at
+    /// `start`
+    fn branch(&mut self, from: usize, start: Span) {
+        let node = self.node();
+        self.nodes[node].start = Some(start);
+        self.edge(from, node);
+        self.current = node;
+    }
+
+    /// Start a node following all of `ends`
+    fn join(&mut self, ends: &[usize]) {
+        let node = self.node();
+
+        for &end in ends {
+            self.edge(end, node);
+        }
+
+        self.current = node;
+    }
+
+    /// The variable declared in a block an identifier at `span` names
+    fn local(&self, span: Span) -> Option<Span> {
+        match self.names.get(self.module, span)? {
+            Definition::Local(declared) => Some(declared),
+            _ => None,
+        }
+    }
+
+    fn step(&mut self, step: Step) {
+        self.nodes[self.current].steps.push(step);
+    }
+
+    fn finish(mut self) -> (Graph<'ast>, Vec<(&'ast Statement, usize, usize)>) {
+        let end = self.current;
+        let exit = self.node();
+        self.edge(end, exit);
+
+        for from in std::mem::take(&mut self.exits) {
+            self.edge(from, exit);
+        }
+
+        let graph = Graph {
+            nodes: self.nodes,
+            end,
+            exit,
+        };
+
+        (graph, self.statements)
+    }
+}
+
+impl<'ast> Visitor<'ast> for Builder<'ast, '_> {
+    fn visit_block(&mut self, block: &'ast Block) {
+        let mut previous = None;
+
+        for stmt in &block.statements {
+            // Items are declared before the block runs
+            if let StatementKind::Function(_)
+            | StatementKind::Struct(_)
+            | StatementKind::Enum(_)
+            | StatementKind::Trait(_)
+            | StatementKind::Impl(_)
+            | StatementKind::Module(_)
+            | StatementKind::Import(_) = stmt.kind
+            {
+                continue;
+            }
+
+            let node = self.current;
+            self.statements.push((stmt, node, previous.unwrap_or(node)));
+            previous = Some(node);
+
+            self.visit_statement(stmt);
+        }
+    }
+
+    fn visit_statement(&mut self, stmt: &'ast Statement) {
+        self.nodes[self.current].statements.push(stmt);
+
+        match &stmt.kind {
+            StatementKind::Let {
+                name,
+                mutable,
+                initial: None,
+                ..
+            } => self.variables.push(Variable {
+                name,
+                mutable: *mutable,
+                declared: stmt.span,
+            }),
+            StatementKind::Return(value) => {
+                if let Some(value) = value {
+                    self.visit_expression(value);
+                }
+
+                // What follows in the block starts a node nothing leads to
+                self.exits.push(self.current);
+                self.current = self.node();
+            }
+            StatementKind::If {
+                condition,
+                then,
+                otherwise,
+            }
+            | StatementKind::ComptimeIf {
+                condition,
+                then,
+                otherwise,
+            } => {
+                self.visit_expression(condition);
+                let before = self.current;
+
+                self.branch(before, then.span);
+                self.visit_block(then);
+                let then = self.current;
+
+                match otherwise {
+                    Some(otherwise) => {
+                        self.branch(before, otherwise.span);
+                        self.visit_statement(otherwise);
+                    }
+                    None => self.branch(before, stmt.span),
+                }
+
+                let otherwise = self.current;
+                self.join(&[then, otherwise]);
+            }
+            // The value is found before the variable is written, and a
+            // compound assignment reads it first
+            StatementKind::Assign {
+                place,
+                operator,
+                value,
+            } => match (&place.kind, self.local(place.span)) {
+                (ExpressionKind::Identifier(_), Some(variable)) => {
+                    self.visit_expression(value);
+
+                    if operator.is_some() {
+                        self.step(Step::Read {
+                            variable,
+                            at: place.span,
+                        });
+                    }
+
+                    self.step(Step::Write {
+                        variable,
+                        at: place.span,
+                    });
+                }
+                _ => walk_statement(self, stmt),
+            },
+            _ => walk_statement(self, stmt),
+        }
+    }
+
+    fn visit_expression(&mut self, expr: &'ast Expression) {
+        match &expr.kind {
+            ExpressionKind::Identifier(_) => {
+                if let Some(variable) = self.local(expr.span) {
+                    self.step(Step::Read {
+                        variable,
+                        at: expr.span,
+                    });
+                }
+            }
+            ExpressionKind::Match { scrutinee, arms } => {
+                self.visit_expression(scrutinee);
+                let before = self.current;
+
+                let ends: Vec<usize> = arms
+                    .iter()
+                    .map(|arm| {
+                        self.branch(before, arm.span);
+                        self.visit_expression(&arm.body);
+                        self.current
+                    })
+                    .collect();
+
+                self.join(&ends);
+            }
+            // A closure's body runs when it is called, and is a graph of
+            // its own, but creating it reads what it captures. Those
+            // declared in it aren't variables of this body.
+            ExpressionKind::Closure(_) => {
+                let mut captured = Captured {
+                    builder: self,
+                    at: expr.span,
+                };
+
+                walk_expression(&mut captured, expr);
+            }
+            ExpressionKind::Comptime(_) => {}
+            _ => walk_expression(self, expr),
+        }
+    }
+}
+
+/// Records the variables a closure mentions as read where it is created
+struct Captured<'b, 'ast, 'a> {
+    builder: &'b mut Builder<'ast, 'a>,
+    at: Span,
+}
+
+impl<'ast> Visitor<'ast> for Captured<'_, 'ast, '_> {
+    fn visit_expression(&mut self, expr: &'ast Expression) {
+        if let ExpressionKind::Identifier(_) = expr.kind {
+            if let Some(variable) = self.builder.local(expr.span) {
+                self.builder.step(Step::Read {
+                    variable,
+                    at: self.at,
+                });
+            }
+        }
+
+        walk_expression(self, expr);
+    }
+}
@@ -0,0 +1,192 @@
+//! Monomorphization: the instances of generic functions a program needs.
+//!
+//! A generic function is checked once, its type parameters standing for
+//! any type. Every call to it is given type arguments, inferred from the
+//! arguments of the call and the type expected of its value, and a code
+//! generator makes a copy of the function for every list of type arguments
+//! it is called with, an instance.
+//!
+//! The calls made in the body of a generic function depend on its own type
+//! arguments, so the instances are found from the calls made outside of
+//! generic functions, substituting the type arguments of every instance
+//! found in the calls made by its body.
+use crate::error::OrionError;
+use crate::lexer::tokens::Span;
+use crate::parser::ast::Function;
+use crate::sema::ty::{arguments, Ty};
+use crate::sema::Diagnostics;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::{Display, Formatter, Result};
+
+/// How deeply instances may need other instances. A generic function
+/// calling itself with larger type arguments, like `f(&x)` in the body of
+/// `func f<T>(x : T)`, would otherwise need infinitely many.
+const MAX_INSTANCE_DEPTH: usize = 64;
+
+/// A generic function with the types its type parameters stand for
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Instance {
+    pub function: String,
+    pub arguments: Vec<Ty>,
+}
+
+impl Display for Instance {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let arguments: Vec<String> = self.arguments.iter().map(Ty::to_string).collect();
+        write!(f, "{}<{}>", self.function, arguments.join(", "))
+    }
+}
+
+/// A function of a program: the module it is declared in and where its
+/// declaration starts in the text of that module, which no other function
+/// of the module shares
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FunctionId {
+    pub module: usize,
+    pub start: usize,
+}
+
+impl FunctionId {
+    pub fn new(module: usize, span: Span) -> Self {
+        Self {
+            module,
+            start: span.start,
+        }
+    }
+}
+
+/// A call to a generic function, with the type arguments inferred for it
+struct Call {
+    /// The function the call is made in, `None` at the top level
+    caller: Option<FunctionId>,
+    callee: FunctionId,
+    /// The name the function is called by
+    name: String,
+    arguments: Vec<Ty>,
+    span: Span,
+    /// The module the call is made in, whose diagnostics errors go to
+    module: usize,
+}
+
+/// The generic functions of a program and the calls made to them, found
+/// while checking it
+#[derive(Default)]
+pub struct Calls {
+    /// The name and type parameters of every generic function
+    functions: HashMap<FunctionId, (String, Vec<String>)>,
+    calls: Vec<Call>,
+}
+
+impl Calls {
+    pub(crate) fn declare(&mut self, id: FunctionId, function: &Function) {
+        if !function.generics.is_empty() {
+            let generic = (function.name.clone(), function.generic_names());
+            self.functions.insert(id, generic);
+        }
+    }
+
+    pub(crate) fn call(
+        &mut self,
+        caller: Option<FunctionId>,
+        callee: FunctionId,
+        name: &str,
+        arguments: Vec<Ty>,
+        span: Span,
+        module: usize,
+    ) {
+        self.calls.push(Call {
+            caller,
+            callee,
+            name: name.to_string(),
+            arguments,
+            span,
+            module,
+        });
+    }
+
+    /// Add the generic functions and calls found in another module
+    pub fn extend(&mut self, other: Calls) {
+        self.functions.extend(other.functions);
+        self.calls.extend(other.calls);
+    }
+
+    /// The instances of generic functions the program needs, in the order
+    /// they are first needed. `diagnostics` holds the diagnostics of every
+    /// module, a single one for a program in one file.
+    pub fn instances(&self, diagnostics: &mut [Diagnostics]) -> Vec<Instance> {
+        let mut instances = vec![];
+        let mut seen = HashSet::new();
+        let mut reported = HashSet::new();
+
+        // Every function to instantiate comes with the call that needs it,
+        // where errors about it are reported
+        let mut queue: VecDeque<(&Call, Vec<Ty>, usize)> = self
+            .calls
+            .iter()
+            .filter(|call| match call.caller {
+                Some(caller) => !self.functions.contains_key(&caller),
+                None => true,
+            })
+            .map(|call| (call, call.arguments.clone(), 0))
+            .collect();
+
+        while let Some((needed, types, depth)) = queue.pop_front() {
+            let function = needed.callee;
+
+            if !seen.insert((function, types.clone())) {
+                continue;
+            }
+
+            let Some((name, generics)) = self.functions.get(&function) else {
+                let diagnostics = &mut diagnostics[needed.module];
+
+                diagnostics.report(OrionError::UndefinedName {
+                    name: needed.name.clone(),
+                    loc: diagnostics.loc(needed.span),
+                });
+
+                continue;
+            };
+
+            let arguments = arguments(generics, &types);
+
+            instances.push(Instance {
+                function: name.clone(),
+                arguments: types,
+            });
+
+            let made = self
+                .calls
+                .iter()
+                .filter(|call| call.caller == Some(function));
+
+            for call in made {
+                if depth + 1 >= MAX_INSTANCE_DEPTH {
+                    if reported.insert(call.callee) {
+                        let diagnostics = &mut diagnostics[call.module];
+
+                        let function = self.functions.get(&call.callee);
+                        let name = function.map_or(&call.name, |(name, _)| name);
+
+                        diagnostics.report(OrionError::InstantiationTooDeep {
+                            function: name.clone(),
+                            loc: diagnostics.loc(call.span),
+                        });
+                    }
+
+                    continue;
+                }
+
+                let types = call
+                    .arguments
+                    .iter()
+                    .map(|ty| ty.substitute(&arguments))
+                    .collect();
+
+                queue.push_back((call, types, depth + 1));
+            }
+        }
+
+        instances
+    }
+}
@@ -0,0 +1,201 @@
+//! How values are laid out in memory, for a code generator.
+//!
+//! Struct fields are laid out in the order they are declared, each at the
+//! next offset that is a multiple of its alignment, as C does. A struct is
+//! aligned like its most aligned field, and its size is padded to a
+//! multiple of that alignment so the fields of every element of an array
+//! stay aligned. Tuples are laid out like structs with unnamed fields.
+//!
+//! An enum starts with a `u8` tag telling which variant the value is,
+//! followed by the fields of that variant laid out like a struct. It is as
+//! large as its largest variant.
+//!
+//! A generic struct has no layout of its own, every instance of it has one,
+//! with its type arguments in place of the type parameters.
+use crate::sema::structs::Structs;
+use crate::sema::ty::{Primitive, Ty};
+use std::collections::{HashMap, HashSet};
+
+/// How many bytes a pointer takes up on the machine the semantic checks
+/// assume, which gives `usize` and `isize` their values
+pub const POINTER_SIZE: u64 = 8;
+
+/// The machine code is generated for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Target {
+    /// The size and alignment of references, `usize` and `isize`, in bytes
+    pub pointer_size: u64,
+}
+
+impl Default for Target {
+    fn default() -> Self {
+        Self {
+            pointer_size: POINTER_SIZE,
+        }
+    }
+}
+
+/// The size and alignment of a type, in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    pub size: u64,
+    pub align: u64,
+}
+
+impl Layout {
+    pub fn new(size: u64, align: u64) -> Self {
+        Self { size, align }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructLayout {
+    pub layout: Layout,
+    /// The offset of every field from the start of the struct, in the order
+    /// the fields are declared
+    pub offsets: Vec<u64>,
+}
+
+/// Computes the layouts of the types of a program, remembering the layout
+/// of every struct and enum once computed.
+pub struct Layouts<'a> {
+    structs: &'a Structs,
+    target: Target,
+    cache: HashMap<String, Option<StructLayout>>,
+    /// The structs and enums whose layout is being computed, which a
+    /// recursive type would run into again
+    computing: HashSet<String>,
+}
+
+impl<'a> Layouts<'a> {
+    pub fn new(structs: &'a Structs, target: Target) -> Self {
+        Self {
+            structs,
+            target,
+            cache: HashMap::new(),
+            computing: HashSet::new(),
+        }
+    }
+
+    /// The layout of a type, or `None` if its size isn't known: an unsized
+    /// type, an unknown type or a type containing itself.
+    pub fn of(&mut self, ty: &Ty) -> Option<Layout> {
+        let pointer = self.target.pointer_size;
+
+        let layout = match ty {
+            Ty::Primitive(primitive) => match primitive {
+                Primitive::U8 | Primitive::I8 | Primitive::Bool => Layout::new(1, 1),
+                Primitive::U16 | Primitive::I16 => Layout::new(2, 2),
+                Primitive::U32 | Primitive::I32 | Primitive::F32 | Primitive::Char => {
+                    Layout::new(4, 4)
+                }
+                Primitive::U64 | Primitive::I64 | Primitive::F64 => Layout::new(8, 8),
+                Primitive::Usize | Primitive::Isize => Layout::new(pointer, pointer),
+                Primitive::Str => return None,
+            },
+            Ty::Struct(name, types) => self.of_instance(name, types)?.layout,
+            Ty::Enum(name) => self.of_enum(name)?,
+            // A reference to a value without a known size also holds its
+            // length
+            Ty::Reference(inner, _) if !inner.is_sized() => Layout::new(2 * pointer, pointer),
+            Ty::Reference(..) | Ty::Function(..) => Layout::new(pointer, pointer),
+            Ty::Array(element, length) => {
+                let element = self.of(element)?;
+                Layout::new(element.size.checked_mul(*length)?, element.align)
+            }
+            Ty::Slice(_) | Ty::Param(_) => return None,
+            Ty::Tuple(elements) => self.of_fields(elements.iter())?.layout,
+        };
+
+        Some(layout)
+    }
+
+    /// The layout of a struct, with the offsets of its fields
+    pub fn of_struct(&mut self, name: &str) -> Option<StructLayout> {
+        self.of_instance(name, &[])
+    }
+
+    /// The layout of an instance of a generic struct, given its type
+    /// arguments, with the offsets of its fields
+    pub fn of_instance(&mut self, name: &str, types: &[Ty]) -> Option<StructLayout> {
+        let key = Ty::Struct(name.to_string(), types.to_vec()).to_string();
+
+        if let Some(layout) = self.cache.get(&key) {
+            return layout.clone();
+        }
+
+        if !self.computing.insert(key.clone()) {
+            return None;
+        }
+
+        let structs = self.structs;
+        let layout = structs
+            .get(name)
+            .filter(|def| def.generics.len() == types.len())
+            .and_then(|def| self.of_fields(def.field_types(types).iter()));
+
+        self.computing.remove(&key);
+        self.cache.insert(key, layout.clone());
+
+        layout
+    }
+
+    /// The layout of an enum, which is the same whatever variant a value is
+    pub fn of_enum(&mut self, name: &str) -> Option<Layout> {
+        if let Some(layout) = self.cache.get(name) {
+            return layout.as_ref().map(|layout| layout.layout);
+        }
+
+        if !self.computing.insert(name.to_string()) {
+            return None;
+        }
+
+        let structs = self.structs;
+        let tag = Ty::Primitive(Primitive::U8);
+
+        // Every variant is laid out like a struct starting with the tag
+        let variants = structs.get_enum(name).and_then(|def| {
+            def.variants
+                .iter()
+                .map(|variant| self.of_fields(std::iter::once(&tag).chain(&variant.fields)))
+                .collect::<Option<Vec<_>>>()
+        });
+
+        // The offsets of the fields depend on the variant, so none are kept
+        let layout = variants.map(|variants| {
+            let size = variants.iter().map(|variant| variant.layout.size).max();
+            let align = variants.iter().map(|variant| variant.layout.align).max();
+            let align = align.unwrap_or(1);
+
+            StructLayout {
+                layout: Layout::new(size.unwrap_or(0).next_multiple_of(align), align),
+                offsets: vec![],
+            }
+        });
+
+        self.computing.remove(name);
+        self.cache.insert(name.to_string(), layout.clone());
+
+        layout.map(|layout| layout.layout)
+    }
+
+    fn of_fields<'t>(&mut self, fields: impl Iterator<Item = &'t Ty>) -> Option<StructLayout> {
+        let mut offsets = vec![];
+        let mut size: u64 = 0;
+        let mut align = 1;
+
+        for ty in fields {
+            let field = self.of(ty)?;
+
+            size = size.next_multiple_of(field.align);
+            offsets.push(size);
+            size = size.checked_add(field.size)?;
+            align = align.max(field.align);
+        }
+
+        Some(StructLayout {
+            layout: Layout::new(size.next_multiple_of(align), align),
+            offsets,
+        })
+    }
+}
@@ -0,0 +1,192 @@
+//! Semantic analysis: the checks that need more than the grammar.
+//!
+//! The passes run over the AST once it has been parsed. [`names`] resolves
+//! the names used as values to their declarations, [`structs`] collects the
+//! struct and enum declarations, [`traits`] the traits and the impls of
+//! them, [`check`](mod@check) checks how they are used, with [`patterns`] telling
+//! whether matches cover every value, [`closures`] finds what closures
+//! capture and lowers them to functions, [`flow`] follows the paths
+//! through every body to find missing returns, unreachable code and
+//! variables read before they are assigned,
+//! [`generics`] finds the instances
+//! of generic functions the program needs and [`layout`] computes how their
+//! values are laid out in memory for a code generator. Errors don't stop
+//! the analysis, every pass reports as many as it can find. Warnings are
+//! kept apart from them, as they don't stop the program from compiling.
+//!
+//! A program split across files is checked once [`crate::module::resolve()`]
+//! has given every item its full name, with [`check_modules`]: the modules
+//! are checked side by side, each reporting the errors found in its file.
+pub mod check;
+pub mod closures;
+pub mod flow;
+pub mod generics;
+pub mod layout;
+pub mod names;
+pub mod patterns;
+pub mod structs;
+pub mod traits;
+pub mod ty;
+
+use crate::error::OrionError;
+use crate::lexer::tokens::{Location, Span};
+use crate::module::Module;
+use crate::parser::ast::Program;
+use crate::sema::check::Checker;
+use crate::sema::closures::Converted;
+use crate::sema::generics::{Calls, Instance};
+use crate::sema::names::Names;
+use crate::sema::structs::Structs;
+use crate::sema::traits::Traits;
+
+/// Run the semantic checks over a parsed program, returning every error
+/// found. `source` is the text the program was parsed from, to point the
+/// errors at their line and column.
+pub fn check(program: &Program, source: &str) -> Vec<OrionError> {
+    analyze(program, source).errors
+}
+
+/// Run the semantic checks over a parsed program, also finding the
+/// instances of generic functions it needs
+pub fn analyze(program: &Program, source: &str) -> Analysis {
+    let mut diagnostics = Diagnostics::new(source);
+    let names = Names::resolve(program, &mut diagnostics);
+    let structs = Structs::collect(program, &mut diagnostics);
+    let traits = Traits::collect(program, &structs, &mut diagnostics);
+
+    let mut checker = Checker::new(&structs, &traits, &mut diagnostics);
+    checker.program(program);
+    let (calls, closures) = checker.into_results();
+    flow::check(program, &names, 0, &mut diagnostics);
+
+    let instances = calls.instances(std::slice::from_mut(&mut diagnostics));
+    let warnings = diagnostics.take_warnings();
+
+    Analysis {
+        errors: diagnostics.into_errors(),
+        warnings,
+        names,
+        instances,
+        closures,
+    }
+}
+
+/// Run the semantic checks over the modules of a program once their names
+/// are resolved, returning every error found with the file it is in.
+pub fn check_modules(modules: &[Module]) -> Vec<OrionError> {
+    analyze_modules(modules).errors
+}
+
+/// Run the semantic checks over the modules of a program, also finding the
+/// instances of generic functions it needs
+pub fn analyze_modules(modules: &[Module]) -> Analysis {
+    let programs: Vec<&Program> = modules.iter().map(|module| &module.program).collect();
+    let mut diagnostics: Vec<Diagnostics> = modules
+        .iter()
+        .map(|module| Diagnostics::new(&module.source))
+        .collect();
+
+    let names = Names::resolve_modules(&programs, &mut diagnostics);
+    let structs = Structs::collect_modules(&programs, &mut diagnostics);
+    let traits = Traits::collect_modules(&programs, &structs, &mut diagnostics);
+    let mut calls = Calls::default();
+    let mut closures = vec![];
+
+    for (index, diagnostics) in diagnostics.iter_mut().enumerate() {
+        let mut checker = Checker::new(&structs, &traits, diagnostics);
+        checker.module(&programs, index);
+
+        let (module_calls, module_closures) = checker.into_results();
+        calls.extend(module_calls);
+        closures.extend(module_closures);
+        flow::check(programs[index], &names, index, diagnostics);
+    }
+
+    let instances = calls.instances(&mut diagnostics);
+    let mut warnings = vec![];
+
+    let errors = modules
+        .iter()
+        .zip(diagnostics)
+        .flat_map(|(module, mut diagnostics)| {
+            let found = diagnostics.take_warnings();
+            warnings.extend(found.into_iter().map(|warning| module.in_file(warning)));
+
+            let errors = diagnostics.into_errors();
+            errors.into_iter().map(|error| module.in_file(error))
+        })
+        .collect();
+
+    Analysis {
+        errors,
+        warnings,
+        names,
+        instances,
+        closures,
+    }
+}
+
+/// What the semantic checks found in a program
+#[derive(Debug)]
+pub struct Analysis {
+    pub errors: Vec<OrionError>,
+    /// What the checks found suspicious without being an error, like
+    /// unreachable code
+    pub warnings: Vec<OrionError>,
+    /// What the names used as values refer to, see [`names`]
+    pub names: Names,
+    /// The instances of generic functions the program needs, see
+    /// [`generics`]
+    pub instances: Vec<Instance>,
+    /// The closures of the program lowered to functions, see [`closures`]
+    pub closures: Vec<Converted>,
+}
+
+/// The errors and warnings found by the passes
+pub struct Diagnostics<'a> {
+    source: &'a str,
+    errors: Vec<OrionError>,
+    warnings: Vec<OrionError>,
+}
+
+impl<'a> Diagnostics<'a> {
+    pub fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            errors: vec![],
+            warnings: vec![],
+        }
+    }
+
+    /// Where a span starts in the source
+    pub fn loc(&self, span: Span) -> Location {
+        let start = span.start.min(self.source.len());
+        Location::from_input(&self.source[..start])
+    }
+
+    /// The source text of a span
+    pub fn text(&self, span: Span) -> &'a str {
+        self.source.get(span.start..span.end).unwrap_or_default()
+    }
+
+    pub fn report(&mut self, error: OrionError) {
+        self.errors.push(error);
+    }
+
+    pub fn warn(&mut self, warning: OrionError) {
+        self.warnings.push(warning);
+    }
+
+    /// The warnings found so far, leaving none
+    pub fn take_warnings(&mut self) -> Vec<OrionError> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    pub fn errors(&self) -> &[OrionError] {
+        &self.errors
+    }
+
+    pub fn into_errors(self) -> Vec<OrionError> {
+        self.errors
+    }
+}
@@ -0,0 +1,370 @@
+//! Name resolution: what every name used as a value refers to.
+//!
+//! Every identifier of an expression is resolved to the declaration it
+//! names, a variable, a parameter, a name bound by a pattern or a function,
+//! and recorded in [`Names`] for the later passes. A name that refers to
+//! nothing is reported, as is a function, parameter or pattern binding
+//! declared twice in the same place. Types and paths like `Shape::Circle`
+//! are resolved by [`crate::sema::structs`] and [`crate::sema::traits`].
+//!
+//! A name refers to the innermost declaration of it in reach:
+//!
+//! - A block is a scope, as are the parameters of a function or a closure
+//!   and the names bound by the pattern of a match arm.
+//! - A variable can be used from the statement after its `let` up to the end
+//!   of its block. Declaring it again shadows it, in the same scope too, the
+//!   initializer still seeing the earlier one: `let x := x + 1;`.
+//! - A function can be used anywhere in its block, before its declaration
+//!   too. Two functions of the same name in one block are an error.
+//! - A function sees its parameters, what it declares itself and the top
+//!   level, but not the variables and functions of the functions around it.
+//!   A closure sees everything around it, which it captures.
+//! - The body of a function sees every function and top-level variable of
+//!   the program, as they are all declared by the time it runs. The top
+//!   level itself only sees its variables once they are declared.
+//! - `len`, giving the length of an array or slice, and the arithmetic
+//!   builtins like `wrapping_add` are the builtins of
+//!   [`BUILTIN_FUNCTIONS`] when they are called and no declaration of them
+//!   is in reach.
+//!
+//! For a program split across files, the items of every module are in
+//! reach by the full names [`crate::module::resolve()`] gave them. The code
+//! evaluated while compiling is resolved when it runs, see
+//! [`crate::interp::comptime`].
+use crate::error::OrionError;
+use crate::lexer::tokens::Span;
+use crate::parser::ast::*;
+use crate::parser::visit::{walk_expression, Visitor};
+use crate::sema::Diagnostics;
+use std::collections::HashMap;
+
+/// What a name refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Definition {
+    /// A function declared at `span` in `module`, at its top level or in a
+    /// block
+    Function { module: usize, span: Span },
+    /// A variable declared at the top level of `module`
+    Global { module: usize, span: Span },
+    /// A variable declared in a block, in the module of the name
+    Local(Span),
+    /// A parameter of a function or a closure, `self` included
+    Parameter(Span),
+    /// A name bound by the pattern of a match arm
+    Binding(Span),
+    /// A builtin function, like `len`
+    Builtin,
+}
+
+/// What the identifiers of the modules of a program refer to, keyed by
+/// where they start
+#[derive(Debug, Default)]
+pub struct Names {
+    modules: Vec<HashMap<usize, Definition>>,
+}
+
+impl Names {
+    /// Resolve the names of a program
+    pub fn resolve(program: &Program, diagnostics: &mut Diagnostics) -> Self {
+        Self::resolve_modules(&[program], std::slice::from_mut(diagnostics))
+    }
+
+    /// Resolve the names of the modules of a program, once their items have
+    /// their full names. The errors found in each module are reported to
+    /// its own diagnostics.
+    pub fn resolve_modules(programs: &[&Program], diagnostics: &mut [Diagnostics]) -> Self {
+        let mut items = HashMap::new();
+
+        for (module, program) in programs.iter().enumerate() {
+            for stmt in program.iter() {
+                let (name, definition) = match &stmt.kind {
+                    StatementKind::Function(function) => (
+                        &function.name,
+                        Definition::Function {
+                            module,
+                            span: stmt.span,
+                        },
+                    ),
+                    StatementKind::Let { name, .. } => (
+                        name,
+                        Definition::Global {
+                            module,
+                            span: stmt.span,
+                        },
+                    ),
+                    _ => continue,
+                };
+
+                // The last declaration of a variable is the one functions
+                // see once the top level has run
+                items.insert(name.as_str(), definition);
+            }
+        }
+
+        let modules = programs
+            .iter()
+            .zip(diagnostics)
+            .enumerate()
+            .map(|(module, (program, diagnostics))| {
+                let mut resolver = Resolver {
+                    items: &items,
+                    scopes: vec![],
+                    frame: 0,
+                    module,
+                    names: HashMap::new(),
+                    diagnostics,
+                };
+
+                resolver.statements(program);
+                resolver.names
+            })
+            .collect();
+
+        Self { modules }
+    }
+
+    /// What the identifier at `span` in `module` refers to, `None` if it
+    /// isn't an identifier or refers to nothing
+    pub fn get(&self, module: usize, span: Span) -> Option<Definition> {
+        self.modules.get(module)?.get(&span.start).copied()
+    }
+}
+
+struct Resolver<'ast, 'a, 'd> {
+    /// The functions and top-level variables of every module
+    items: &'a HashMap<&'ast str, Definition>,
+    /// The names declared around the code being resolved, innermost scope
+    /// last
+    scopes: Vec<HashMap<&'ast str, Definition>>,
+    /// The first scope of the function being resolved, 0 at the top level.
+    /// The scopes before it are out of reach.
+    frame: usize,
+    module: usize,
+    names: HashMap<usize, Definition>,
+    diagnostics: &'a mut Diagnostics<'d>,
+}
+
+impl<'ast> Resolver<'ast, '_, '_> {
+    fn lookup(&self, name: &str) -> Option<Definition> {
+        let local = self.scopes[self.frame..]
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name));
+
+        // The top level only sees its own variables once they are declared,
+        // which puts them in its scope
+        local.copied().or_else(|| {
+            self.items.get(name).copied().filter(|item| match item {
+                Definition::Global { module, .. } => self.frame > 0 || *module != self.module,
+                _ => true,
+            })
+        })
+    }
+
+    /// Declare a name in the innermost scope, shadowing any declaration of
+    /// it in reach
+    fn declare(&mut self, name: &'ast str, definition: Definition) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name, definition);
+        }
+    }
+
+    /// Declare the names of a scope that can't be declared twice in it
+    fn declare_once(&mut self, name: &'ast str, definition: Definition, span: Span) {
+        let scope = self.scopes.last().expect("a scope to declare in");
+
+        if scope.contains_key(name) {
+            self.diagnostics.report(OrionError::DuplicateDefinition {
+                name: name.to_string(),
+                loc: self.diagnostics.loc(span),
+            });
+
+            return;
+        }
+
+        self.declare(name, definition);
+    }
+
+    /// Resolve statements in a scope of their own, their functions being
+    /// declared up front
+    fn statements(&mut self, statements: &'ast [Statement]) {
+        self.scopes.push(HashMap::new());
+
+        for stmt in statements {
+            if let StatementKind::Function(function) = &stmt.kind {
+                let definition = Definition::Function {
+                    module: self.module,
+                    span: stmt.span,
+                };
+
+                self.declare_once(&function.name, definition, stmt.span);
+            }
+        }
+
+        for stmt in statements {
+            self.visit_statement(stmt);
+        }
+
+        self.scopes.pop();
+    }
+
+    fn function(&mut self, function: &'ast Function) {
+        let frame = std::mem::replace(&mut self.frame, self.scopes.len());
+        self.scopes.push(HashMap::new());
+
+        for parameter in &function.parameters {
+            let definition = Definition::Parameter(parameter.span);
+            self.declare_once(&parameter.name, definition, parameter.span);
+        }
+
+        self.visit_block(&function.body);
+        self.scopes.pop();
+        self.frame = frame;
+    }
+
+    fn pattern(&mut self, pattern: &'ast Pattern) {
+        match &pattern.kind {
+            PatternKind::Binding(name) => {
+                let definition = Definition::Binding(pattern.span);
+                self.declare_once(name, definition, pattern.span);
+            }
+            PatternKind::Variant { fields, .. } => {
+                for field in fields {
+                    self.pattern(field);
+                }
+            }
+            PatternKind::Wildcard
+            | PatternKind::Number(_)
+            | PatternKind::Bool(_)
+            | PatternKind::Char(_) => {}
+        }
+    }
+
+    fn identifier(&mut self, name: &str, span: Span, called: bool) {
+        let definition = match self.lookup(name) {
+            Some(definition) => definition,
+            None if called && is_builtin_function(name) => Definition::Builtin,
+            None => {
+                self.diagnostics.report(OrionError::UndefinedName {
+                    name: name.to_string(),
+                    loc: self.diagnostics.loc(span),
+                });
+
+                return;
+            }
+        };
+
+        self.names.insert(span.start, definition);
+    }
+}
+
+impl<'ast> Visitor<'ast> for Resolver<'ast, '_, '_> {
+    fn visit_statement(&mut self, stmt: &'ast Statement) {
+        match &stmt.kind {
+            StatementKind::Let { name, initial, .. } => {
+                if let Some(initial) = initial {
+                    self.visit_expression(initial);
+                }
+
+                let definition = if self.scopes.len() == 1 {
+                    Definition::Global {
+                        module: self.module,
+                        span: stmt.span,
+                    }
+                } else {
+                    Definition::Local(stmt.span)
+                };
+
+                self.declare(name, definition);
+            }
+            StatementKind::Function(function) => self.function(function),
+            StatementKind::Return(value) => {
+                if let Some(value) = value {
+                    self.visit_expression(value);
+                }
+            }
+            StatementKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                self.visit_expression(condition);
+                self.visit_block(then);
+
+                if let Some(otherwise) = otherwise {
+                    self.visit_statement(otherwise);
+                }
+            }
+            // Taken or dropped when the program was loaded, see
+            // `crate::interp::comptime`
+            StatementKind::ComptimeIf { .. } => {}
+            StatementKind::Block(block) => self.visit_block(block),
+            StatementKind::Expression(expression) => self.visit_expression(expression),
+            StatementKind::Assign { place, value, .. } => {
+                self.visit_expression(place);
+                self.visit_expression(value);
+            }
+            // The methods are reached through the type of their receiver
+            // rather than by name
+            StatementKind::Impl(implementation) => {
+                for method in &implementation.methods {
+                    if let StatementKind::Function(function) = &method.kind {
+                        self.function(function);
+                    }
+                }
+            }
+            StatementKind::Struct(_)
+            | StatementKind::Enum(_)
+            | StatementKind::Trait(_)
+            | StatementKind::Module(_)
+            | StatementKind::Import(_) => {}
+        }
+    }
+
+    fn visit_block(&mut self, block: &'ast Block) {
+        self.statements(&block.statements);
+    }
+
+    fn visit_expression(&mut self, expr: &'ast Expression) {
+        match &expr.kind {
+            ExpressionKind::Identifier(name) => self.identifier(name, expr.span, false),
+            ExpressionKind::Call { callee, arguments } => {
+                match &callee.kind {
+                    ExpressionKind::Identifier(name) => self.identifier(name, callee.span, true),
+                    _ => self.visit_expression(callee),
+                }
+
+                for argument in arguments {
+                    self.visit_expression(argument);
+                }
+            }
+            ExpressionKind::Match { scrutinee, arms } => {
+                self.visit_expression(scrutinee);
+
+                for arm in arms {
+                    self.scopes.push(HashMap::new());
+                    self.pattern(&arm.pattern);
+                    self.visit_expression(&arm.body);
+                    self.scopes.pop();
+                }
+            }
+            ExpressionKind::Closure(closure) => {
+                self.scopes.push(HashMap::new());
+
+                for parameter in &closure.parameters {
+                    let definition = Definition::Parameter(parameter.span);
+                    self.declare_once(&parameter.name, definition, parameter.span);
+                }
+
+                self.visit_expression(&closure.body);
+                self.scopes.pop();
+            }
+            // Resolved when it runs
+            ExpressionKind::Comptime(_) => {}
+            _ => walk_expression(self, expr),
+        }
+    }
+
+    // The lengths of array types are numbers, not names
+    fn visit_type(&mut self, _: &'ast Type) {}
+}
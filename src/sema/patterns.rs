@@ -0,0 +1,285 @@
+//! Exhaustiveness and reachability of the arms of a `match`.
+//!
+//! Both questions come down to whether a pattern is useful: whether it
+//! matches some value that none of the patterns before it match. An arm
+//! whose pattern isn't useful can never run. A `match` is exhaustive when a
+//! `_` after its last arm wouldn't be useful, and the values that `_` would
+//! still match are reported as examples of what is missing.
+//!
+//! The algorithm is the one from Maranget's "Warnings for pattern
+//! matching": the patterns are rows of a matrix, and a column is split by
+//! the constructors its patterns start with, until no columns are left.
+use crate::parser::ast::Number;
+use crate::sema::structs::Structs;
+use crate::sema::ty::{Primitive, Ty};
+use std::fmt::{Display, Formatter, Result};
+
+/// A pattern as far as matching is concerned, with bindings and wildcards
+/// being the same
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pat {
+    Wild,
+    Constructor(Constructor, Vec<Pat>),
+}
+
+/// What a value is made of: the variant of an enum or a literal
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constructor {
+    Variant {
+        enumeration: String,
+        variant: String,
+        index: usize,
+    },
+    Bool(bool),
+    Number(Number),
+    Char(char),
+}
+
+/// Finds useful patterns, knowing the variants of every enum
+pub struct Usefulness<'a> {
+    structs: &'a Structs,
+}
+
+impl<'a> Usefulness<'a> {
+    pub fn new(structs: &'a Structs) -> Self {
+        Self { structs }
+    }
+
+    /// Whether `row` matches a value none of `rows` match, the columns
+    /// being of the types `tys`. A type that isn't known has too many
+    /// values to list, like numbers.
+    pub fn useful(&self, rows: &[Vec<Pat>], row: &[Pat], tys: &[Option<Ty>]) -> bool {
+        let Some((head, rest)) = row.split_first() else {
+            return rows.is_empty();
+        };
+
+        match head {
+            Pat::Constructor(constructor, fields) => {
+                let rows = self.specialize(rows, constructor);
+                let row = [&fields[..], rest].concat();
+
+                self.useful(&rows, &row, &self.field_types(constructor, tys))
+            }
+            Pat::Wild => match self.complete(rows, &tys[0]) {
+                Some(constructors) => constructors.iter().any(|constructor| {
+                    let rows = self.specialize(rows, constructor);
+                    let row = [wildcards(self.arity(constructor)), rest.to_vec()].concat();
+
+                    self.useful(&rows, &row, &self.field_types(constructor, tys))
+                }),
+                None => self.useful(&default(rows), rest, &tys[1..]),
+            },
+        }
+    }
+
+    /// Rows of patterns matching values none of `rows` match, one for
+    /// every way they fall short. An empty list means `rows` match every
+    /// value of the types `tys`.
+    pub fn missing(&self, rows: &[Vec<Pat>], tys: &[Option<Ty>]) -> Vec<Vec<Pat>> {
+        let Some(ty) = tys.first() else {
+            return match rows.is_empty() {
+                true => vec![vec![]],
+                false => vec![],
+            };
+        };
+
+        if let Some(constructors) = self.complete(rows, ty) {
+            let mut missing = vec![];
+
+            for constructor in constructors {
+                let rows = self.specialize(rows, &constructor);
+                let arity = self.arity(&constructor);
+
+                for mut witness in self.missing(&rows, &self.field_types(&constructor, tys)) {
+                    let fields = witness.drain(..arity).collect();
+                    witness.insert(0, Pat::Constructor(constructor.clone(), fields));
+                    missing.push(witness);
+                }
+            }
+
+            return missing;
+        }
+
+        let rest = self.missing(&default(rows), &tys[1..]);
+
+        if rest.is_empty() {
+            return rest;
+        }
+
+        // The constructors that no row starts with are missing. If there are
+        // none to list, any value not matched by the rows is.
+        let used = heads(rows);
+        let heads: Vec<Pat> = match self.constructors(ty) {
+            Some(constructors) if !used.is_empty() => constructors
+                .into_iter()
+                .filter(|constructor| !used.contains(constructor))
+                .map(|constructor| {
+                    let fields = wildcards(self.arity(&constructor));
+                    Pat::Constructor(constructor, fields)
+                })
+                .collect(),
+            _ => vec![Pat::Wild],
+        };
+
+        heads
+            .iter()
+            .flat_map(|head| {
+                rest.iter()
+                    .map(move |witness| [vec![head.clone()], witness.clone()].concat())
+            })
+            .collect()
+    }
+
+    /// Every constructor of a type, if there are few enough to list
+    fn constructors(&self, ty: &Option<Ty>) -> Option<Vec<Constructor>> {
+        match ty.as_ref()? {
+            Ty::Primitive(Primitive::Bool) => {
+                Some(vec![Constructor::Bool(false), Constructor::Bool(true)])
+            }
+            Ty::Enum(name) => {
+                let def = self.structs.get_enum(name)?;
+
+                let constructors = def
+                    .variants
+                    .iter()
+                    .enumerate()
+                    .map(|(index, variant)| Constructor::Variant {
+                        enumeration: name.clone(),
+                        variant: variant.name.clone(),
+                        index,
+                    })
+                    .collect();
+
+                Some(constructors)
+            }
+            _ => None,
+        }
+    }
+
+    /// The constructors of a type if the first column of `rows` uses all
+    /// of them. A `_` in the column then only matters for each of them in
+    /// turn, otherwise it also stands for the values no row starts with.
+    fn complete(&self, rows: &[Vec<Pat>], ty: &Option<Ty>) -> Option<Vec<Constructor>> {
+        let constructors = self.constructors(ty)?;
+        let used = heads(rows);
+
+        constructors
+            .iter()
+            .all(|constructor| used.contains(constructor))
+            .then_some(constructors)
+    }
+
+    /// The rows matching a value made with `constructor`, with their first
+    /// pattern replaced by the patterns for its fields
+    fn specialize(&self, rows: &[Vec<Pat>], constructor: &Constructor) -> Vec<Vec<Pat>> {
+        rows.iter()
+            .filter_map(|row| match &row[0] {
+                Pat::Constructor(head, fields) if head == constructor => {
+                    Some([&fields[..], &row[1..]].concat())
+                }
+                Pat::Constructor(..) => None,
+                Pat::Wild => Some([wildcards(self.arity(constructor)), row[1..].to_vec()].concat()),
+            })
+            .collect()
+    }
+
+    /// The types of the columns once the first one is split into the
+    /// fields of `constructor`
+    fn field_types(&self, constructor: &Constructor, tys: &[Option<Ty>]) -> Vec<Option<Ty>> {
+        let fields = match constructor {
+            Constructor::Variant {
+                enumeration, index, ..
+            } => self
+                .structs
+                .get_enum(enumeration)
+                .map(|def| {
+                    def.variants[*index]
+                        .fields
+                        .iter()
+                        .cloned()
+                        .map(Some)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            _ => vec![],
+        };
+
+        [fields, tys[1..].to_vec()].concat()
+    }
+
+    fn arity(&self, constructor: &Constructor) -> usize {
+        match constructor {
+            Constructor::Variant {
+                enumeration, index, ..
+            } => self
+                .structs
+                .get_enum(enumeration)
+                .map_or(0, |def| def.variants[*index].fields.len()),
+            _ => 0,
+        }
+    }
+}
+
+/// The rows starting with a `_`, without it
+fn default(rows: &[Vec<Pat>]) -> Vec<Vec<Pat>> {
+    rows.iter()
+        .filter(|row| row[0] == Pat::Wild)
+        .map(|row| row[1..].to_vec())
+        .collect()
+}
+
+/// The constructors the rows start with
+fn heads(rows: &[Vec<Pat>]) -> Vec<Constructor> {
+    let mut heads = vec![];
+
+    for row in rows {
+        if let Pat::Constructor(constructor, _) = &row[0] {
+            if !heads.contains(constructor) {
+                heads.push(constructor.clone());
+            }
+        }
+    }
+
+    heads
+}
+
+fn wildcards(count: usize) -> Vec<Pat> {
+    vec![Pat::Wild; count]
+}
+
+/// Patterns are displayed as they would be written
+impl Display for Pat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Pat::Wild => write!(f, "_"),
+            Pat::Constructor(constructor, fields) => {
+                match constructor {
+                    Constructor::Variant {
+                        enumeration,
+                        variant,
+                        ..
+                    } => write!(f, "{enumeration}::{variant}")?,
+                    Constructor::Bool(value) => write!(f, "{value}")?,
+                    Constructor::Number(num) => write!(f, "{num}")?,
+                    Constructor::Char(chr) => write!(f, "{chr:?}")?,
+                }
+
+                if !fields.is_empty() {
+                    write!(f, "(")?;
+
+                    for (i, field) in fields.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+
+                        write!(f, "{field}")?;
+                    }
+
+                    write!(f, ")")?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
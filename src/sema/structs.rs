@@ -0,0 +1,507 @@
+//! The struct and enum declarations of a program, and resolving written
+//! types.
+//!
+//! Structs and enums may be used before they are declared, so all of them
+//! are collected before any field type is resolved. A type holding itself
+//! by value, directly or through other types, would be infinitely large and
+//! is reported; holding itself behind a reference is fine.
+//!
+//! The fields of a generic struct are resolved once, their types mentioning
+//! the type parameters of the struct. The fields of an instance like
+//! `Pair<u8, bool>` are found by substituting its type arguments.
+use crate::error::OrionError;
+use crate::lexer::tokens::Span;
+use crate::parser::ast::*;
+use crate::parser::printer::print_type;
+use crate::parser::visit::{walk_statement, Visitor};
+use crate::sema::ty::{arguments, Primitive, Ty};
+use crate::sema::Diagnostics;
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub struct StructDef {
+    pub name: String,
+    /// The names of the type parameters of a generic struct
+    pub generics: Vec<String>,
+    pub fields: Vec<FieldDef>,
+    pub span: Span,
+    /// The module it is declared in
+    pub module: usize,
+}
+
+impl StructDef {
+    /// The field with the given name and its position in the declaration
+    pub fn field(&self, name: &str) -> Option<(usize, &FieldDef)> {
+        self.fields
+            .iter()
+            .enumerate()
+            .find(|(_, field)| field.name == name)
+    }
+
+    /// The types of the fields of an instance of the struct, given its type
+    /// arguments, in the order the fields are declared
+    pub fn field_types(&self, types: &[Ty]) -> Vec<Ty> {
+        let arguments = arguments(&self.generics, types);
+
+        self.fields
+            .iter()
+            .map(|field| field.ty.substitute(&arguments))
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+pub struct FieldDef {
+    pub name: String,
+    pub ty: Ty,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct EnumDef {
+    pub name: String,
+    pub variants: Vec<VariantDef>,
+    pub span: Span,
+}
+
+impl EnumDef {
+    /// The variant with the given name and its position in the declaration
+    pub fn variant(&self, name: &str) -> Option<(usize, &VariantDef)> {
+        self.variants
+            .iter()
+            .enumerate()
+            .find(|(_, variant)| variant.name == name)
+    }
+}
+
+#[derive(Debug)]
+pub struct VariantDef {
+    pub name: String,
+    pub fields: Vec<Ty>,
+    pub span: Span,
+}
+
+/// Every struct and enum declared in a program, by name. Structs and enums
+/// share their names, a struct and an enum can't be called the same.
+#[derive(Debug, Default)]
+pub struct Structs {
+    defs: Vec<StructDef>,
+    index: HashMap<String, usize>,
+    enums: Vec<EnumDef>,
+    enum_index: HashMap<String, usize>,
+}
+
+impl Structs {
+    /// Collect the struct and enum declarations of a program, wherever
+    /// they are, and check them.
+    pub fn collect(program: &Program, diagnostics: &mut Diagnostics) -> Self {
+        Self::collect_modules(&[program], std::slice::from_mut(diagnostics))
+    }
+
+    /// Collect the struct and enum declarations of the modules of a
+    /// program, once their names are resolved. The errors found in each
+    /// module are reported to its own diagnostics.
+    pub fn collect_modules(programs: &[&Program], diagnostics: &mut [Diagnostics]) -> Self {
+        let mut collector = Collector {
+            module: 0,
+            declarations: vec![],
+        };
+
+        for (module, program) in programs.iter().enumerate() {
+            collector.module = module;
+            collector.visit_program(program);
+        }
+
+        let mut structs = Structs::default();
+        // Only the first declaration of a name is kept
+        let mut kept = vec![];
+
+        for (declaration, span, module) in &collector.declarations {
+            let name = declaration.name();
+            let diagnostics = &mut diagnostics[*module];
+
+            if structs.index.contains_key(name) || structs.enum_index.contains_key(name) {
+                diagnostics.report(OrionError::DuplicateDefinition {
+                    name: name.to_string(),
+                    loc: diagnostics.loc(*span),
+                });
+
+                kept.push(false);
+                continue;
+            }
+
+            match declaration {
+                Declaration::Struct(structure) => {
+                    structs.index.insert(name.to_string(), structs.defs.len());
+                    structs.defs.push(StructDef {
+                        name: name.to_string(),
+                        generics: structure.generic_names(),
+                        fields: vec![],
+                        span: *span,
+                        module: *module,
+                    });
+                }
+                Declaration::Enum(_) => {
+                    structs
+                        .enum_index
+                        .insert(name.to_string(), structs.enums.len());
+                    structs.enums.push(EnumDef {
+                        name: name.to_string(),
+                        variants: vec![],
+                        span: *span,
+                    });
+                }
+            }
+
+            kept.push(true);
+        }
+
+        let declarations: Vec<_> = collector
+            .declarations
+            .iter()
+            .zip(kept)
+            .filter_map(|(declaration, kept)| kept.then_some(declaration))
+            .collect();
+
+        // Field types can only be resolved once every type is known
+        for (declaration, _, module) in &declarations {
+            let diagnostics = &mut diagnostics[*module];
+
+            match declaration {
+                Declaration::Struct(structure) => {
+                    let index = structs.index[&structure.name];
+                    structs.defs[index].fields = structs.fields(structure, diagnostics);
+                }
+                Declaration::Enum(enumeration) => {
+                    let index = structs.enum_index[&enumeration.name];
+                    structs.enums[index].variants = structs.variants(enumeration, diagnostics);
+                }
+            }
+        }
+
+        for (declaration, span, module) in &declarations {
+            let name = declaration.name();
+
+            let ty = match declaration {
+                Declaration::Struct(structure) => {
+                    let generics = structure.generic_names().into_iter().map(Ty::Param);
+                    Ty::Struct(name.to_string(), generics.collect())
+                }
+                Declaration::Enum(_) => Ty::Enum(name.to_string()),
+            };
+
+            if structs.contains(name, &ty, &mut vec![]) {
+                let diagnostics = &mut diagnostics[*module];
+
+                diagnostics.report(OrionError::RecursiveStruct {
+                    name: name.to_string(),
+                    loc: diagnostics.loc(*span),
+                });
+            }
+        }
+
+        structs
+    }
+
+    fn fields(&self, structure: &Struct, diagnostics: &mut Diagnostics) -> Vec<FieldDef> {
+        let mut fields: Vec<FieldDef> = vec![];
+
+        let generics = structure.generic_names();
+
+        for (i, generic) in structure.generics.iter().enumerate() {
+            if generics[..i].contains(&generic.name) {
+                diagnostics.report(OrionError::DuplicateDefinition {
+                    name: generic.name.clone(),
+                    loc: diagnostics.loc(generic.span),
+                });
+            }
+        }
+
+        for field in &structure.fields {
+            if fields.iter().any(|other| other.name == field.name) {
+                diagnostics.report(OrionError::DuplicateField {
+                    structure: structure.name.clone(),
+                    field: field.name.clone(),
+                    loc: diagnostics.loc(field.span),
+                });
+
+                continue;
+            }
+
+            if let Some(ty) = self.resolve_sized(&field.ty, &generics, diagnostics) {
+                fields.push(FieldDef {
+                    name: field.name.clone(),
+                    ty,
+                    span: field.span,
+                });
+            }
+        }
+
+        fields
+    }
+
+    /// The variants of an enum. Like in a struct, a field whose type
+    /// couldn't be resolved is left out.
+    fn variants(&self, enumeration: &Enum, diagnostics: &mut Diagnostics) -> Vec<VariantDef> {
+        let mut variants: Vec<VariantDef> = vec![];
+
+        for variant in &enumeration.variants {
+            if variants.iter().any(|other| other.name == variant.name) {
+                diagnostics.report(OrionError::DuplicateVariant {
+                    enumeration: enumeration.name.clone(),
+                    variant: variant.name.clone(),
+                    loc: diagnostics.loc(variant.span),
+                });
+
+                continue;
+            }
+
+            let fields = variant
+                .fields
+                .iter()
+                .filter_map(|field| self.resolve_sized(field, &[], diagnostics))
+                .collect();
+
+            variants.push(VariantDef {
+                name: variant.name.clone(),
+                fields,
+                span: variant.span,
+            });
+        }
+
+        variants
+    }
+
+    pub fn get(&self, name: &str) -> Option<&StructDef> {
+        self.index.get(name).map(|&index| &self.defs[index])
+    }
+
+    /// The structs in the order they were declared
+    pub fn iter(&self) -> impl Iterator<Item = &StructDef> {
+        self.defs.iter()
+    }
+
+    pub fn get_enum(&self, name: &str) -> Option<&EnumDef> {
+        self.enum_index.get(name).map(|&index| &self.enums[index])
+    }
+
+    /// The enums in the order they were declared
+    pub fn enums(&self) -> impl Iterator<Item = &EnumDef> {
+        self.enums.iter()
+    }
+
+    /// Whether `ty` holds a `target` struct or enum by value, looking into
+    /// the fields of the types it holds. `seen` guards against cycles not
+    /// involving `target`.
+    fn contains(&self, target: &str, ty: &Ty, seen: &mut Vec<String>) -> bool {
+        match ty {
+            Ty::Struct(name, _) | Ty::Enum(name) => {
+                if seen.contains(name) {
+                    return name == target;
+                }
+
+                seen.push(name.clone());
+
+                if let (Some(def), Ty::Struct(_, types)) = (self.get(name), ty) {
+                    def.field_types(types)
+                        .iter()
+                        .any(|field| self.contains(target, field, seen))
+                } else if let Some(def) = self.get_enum(name) {
+                    def.variants
+                        .iter()
+                        .flat_map(|variant| &variant.fields)
+                        .any(|field| self.contains(target, field, seen))
+                } else {
+                    false
+                }
+            }
+            Ty::Array(element, _) => self.contains(target, element, seen),
+            Ty::Tuple(elements) => elements
+                .iter()
+                .any(|element| self.contains(target, element, seen)),
+            // Behind a pointer, so the size doesn't depend on the target
+            Ty::Primitive(_) | Ty::Reference(..) | Ty::Slice(_) | Ty::Function(..) => false,
+            // Whatever type it stands for, checked for every instance
+            Ty::Param(_) => false,
+        }
+    }
+
+    /// Resolve a written type, reporting names that aren't types. The type
+    /// parameters in `generics` are types too, in a generic function or
+    /// struct. `None` is returned after reporting an error.
+    pub fn resolve(
+        &self,
+        ty: &Type,
+        generics: &[String],
+        diagnostics: &mut Diagnostics,
+    ) -> Option<Ty> {
+        let resolved = match &ty.kind {
+            TypeKind::Named(path) => {
+                let name = match path.segments.as_slice() {
+                    [name] => name.as_str(),
+                    _ => "",
+                };
+
+                let expected = self.get(name).map_or(0, |def| def.generics.len());
+
+                if path.arguments.len() != expected && !name.is_empty() {
+                    let known = generics.iter().any(|generic| generic == name)
+                        || Primitive::from_name(name).is_some()
+                        || self.get(name).is_some()
+                        || self.get_enum(name).is_some();
+
+                    if known {
+                        diagnostics.report(OrionError::TypeArguments {
+                            name: name.to_string(),
+                            expected,
+                            found: path.arguments.len(),
+                            loc: diagnostics.loc(ty.span),
+                        });
+
+                        return None;
+                    }
+                }
+
+                if generics.iter().any(|generic| generic == name) {
+                    Ty::Param(name.to_string())
+                } else if let Some(primitive) = Primitive::from_name(name) {
+                    Ty::Primitive(primitive)
+                } else if self.get(name).is_some() {
+                    let arguments: Vec<Option<Ty>> = path
+                        .arguments
+                        .iter()
+                        .map(|argument| self.resolve_sized(argument, generics, diagnostics))
+                        .collect();
+
+                    Ty::Struct(
+                        name.to_string(),
+                        arguments.into_iter().collect::<Option<_>>()?,
+                    )
+                } else if self.get_enum(name).is_some() {
+                    Ty::Enum(name.to_string())
+                } else {
+                    diagnostics.report(OrionError::UnknownType {
+                        name: print_type(ty),
+                        loc: diagnostics.loc(ty.span),
+                    });
+
+                    return None;
+                }
+            }
+            TypeKind::Reference { inner, mutable } => Ty::Reference(
+                Box::new(self.resolve(inner, generics, diagnostics)?),
+                *mutable,
+            ),
+            TypeKind::Array { element, length } => {
+                let element = self.resolve_sized(element, generics, diagnostics);
+
+                Ty::Array(Box::new(element?), array_length(length, diagnostics)?)
+            }
+            TypeKind::Slice(element) => Ty::Slice(Box::new(self.resolve_sized(
+                element,
+                generics,
+                diagnostics,
+            )?)),
+            TypeKind::Tuple(elements) => {
+                let elements: Vec<Option<Ty>> = elements
+                    .iter()
+                    .map(|element| self.resolve_sized(element, generics, diagnostics))
+                    .collect();
+
+                Ty::Tuple(elements.into_iter().collect::<Option<_>>()?)
+            }
+            TypeKind::Function { parameters, result } => {
+                let parameters: Vec<Option<Ty>> = parameters
+                    .iter()
+                    .map(|parameter| self.resolve_sized(parameter, generics, diagnostics))
+                    .collect();
+
+                let result = match result {
+                    Some(result) => self.resolve_sized(result, generics, diagnostics)?,
+                    None => Ty::unit(),
+                };
+
+                Ty::Function(
+                    parameters.into_iter().collect::<Option<_>>()?,
+                    Box::new(result),
+                )
+            }
+        };
+
+        Some(resolved)
+    }
+
+    /// Resolve a written type that values are stored in, which must have a
+    /// known size
+    pub fn resolve_sized(
+        &self,
+        ty: &Type,
+        generics: &[String],
+        diagnostics: &mut Diagnostics,
+    ) -> Option<Ty> {
+        let resolved = self.resolve(ty, generics, diagnostics)?;
+
+        if !resolved.is_sized() {
+            diagnostics.report(OrionError::Unsized {
+                ty: resolved.to_string(),
+                loc: diagnostics.loc(ty.span),
+            });
+
+            return None;
+        }
+
+        Some(resolved)
+    }
+}
+
+/// The length of an array, which has to be written as a whole number
+pub fn array_length(length: &Expression, diagnostics: &mut Diagnostics) -> Option<u64> {
+    match length.kind {
+        ExpressionKind::Number(Number::Integer(length)) if length >= 0 => Some(length as u64),
+        _ => {
+            diagnostics.report(OrionError::InvalidArrayLength {
+                loc: diagnostics.loc(length.span),
+            });
+
+            None
+        }
+    }
+}
+
+enum Declaration<'ast> {
+    Struct(&'ast Struct),
+    Enum(&'ast Enum),
+}
+
+impl Declaration<'_> {
+    fn name(&self) -> &str {
+        match self {
+            Declaration::Struct(structure) => &structure.name,
+            Declaration::Enum(enumeration) => &enumeration.name,
+        }
+    }
+}
+
+/// Finds the struct and enum declarations in the modules of a program
+struct Collector<'ast> {
+    /// The module being visited
+    module: usize,
+    declarations: Vec<(Declaration<'ast>, Span, usize)>,
+}
+
+impl<'ast> Visitor<'ast> for Collector<'ast> {
+    fn visit_statement(&mut self, stmt: &'ast Statement) {
+        let declaration = match &stmt.kind {
+            StatementKind::Struct(structure) => Some(Declaration::Struct(structure)),
+            StatementKind::Enum(enumeration) => Some(Declaration::Enum(enumeration)),
+            _ => None,
+        };
+
+        if let Some(declaration) = declaration {
+            self.declarations
+                .push((declaration, stmt.span, self.module));
+        }
+
+        walk_statement(self, stmt);
+    }
+}
@@ -0,0 +1,413 @@
+//! The traits of a program and their implementations.
+//!
+//! A trait declares methods, which `impl Trait for Type` gives bodies for
+//! a type. In the signatures of a trait, `Self` stands for the type
+//! implementing it. Only structs and enums without type parameters can
+//! implement traits, each trait at most once, so the type of a receiver
+//! tells which method a call like `p.show()` runs: method calls are
+//! resolved when the program is checked. A type parameter bound to traits,
+//! like `T : Show`, has the methods of those traits, and every type
+//! argument given for it has to implement them.
+//!
+//! Two traits implemented for the same type can't both have a method of
+//! the same name, so a type and a name are enough to find a method.
+//!
+//! `Ord` is built in: it has no methods, and the numbers and characters,
+//! whose values `<`, `<=`, `>` and `>=` compare, are the types implementing
+//! it. A type parameter bound to it, like `T` in `func max<T : Ord>`, can
+//! be compared that way too.
+use crate::error::OrionError;
+use crate::lexer::tokens::Span;
+use crate::parser::ast::*;
+use crate::sema::structs::Structs;
+use crate::sema::ty::{Primitive, Ty};
+use crate::sema::Diagnostics;
+use std::collections::HashMap;
+
+/// The built-in trait of the types whose values can be ordered
+pub const ORD: &str = "Ord";
+
+#[derive(Debug)]
+pub struct TraitDef {
+    pub name: String,
+    pub methods: Vec<MethodDef>,
+    pub span: Span,
+    /// The module it is declared in
+    pub module: usize,
+}
+
+impl TraitDef {
+    pub fn method(&self, name: &str) -> Option<&MethodDef> {
+        self.methods.iter().find(|method| method.name == name)
+    }
+}
+
+#[derive(Debug)]
+pub struct MethodDef {
+    pub name: String,
+    /// The type of the method, `self` being its first parameter and `Self`
+    /// a [`Ty::Param`]. `None` if its signature couldn't be resolved.
+    pub ty: Option<Ty>,
+    pub span: Span,
+}
+
+impl MethodDef {
+    /// The type of the method for a type implementing its trait
+    pub fn ty_for(&self, ty: &Ty) -> Option<Ty> {
+        Some(self.ty.as_ref()?.substitute(&self_type(ty)))
+    }
+}
+
+#[derive(Debug)]
+pub struct ImplDef {
+    pub trait_name: String,
+    pub ty: Ty,
+    pub span: Span,
+    /// The module the impl is in, whose diagnostics errors go to
+    module: usize,
+}
+
+/// Every trait declared in a program by name, and the impls of them
+#[derive(Debug, Default)]
+pub struct Traits {
+    defs: Vec<TraitDef>,
+    index: HashMap<String, usize>,
+    impls: Vec<ImplDef>,
+}
+
+impl Traits {
+    /// Collect the traits and impls of a program, and check the impls
+    /// against their traits
+    pub fn collect(program: &Program, structs: &Structs, diagnostics: &mut Diagnostics) -> Self {
+        Self::collect_modules(&[program], structs, std::slice::from_mut(diagnostics))
+    }
+
+    /// Collect the traits and impls of the modules of a program, once their
+    /// names are resolved. The errors found in each module are reported to
+    /// its own diagnostics.
+    pub fn collect_modules(
+        programs: &[&Program],
+        structs: &Structs,
+        diagnostics: &mut [Diagnostics],
+    ) -> Self {
+        let mut traits = Traits::default();
+
+        // Declared for every program, so that no program declares its own
+        traits.index.insert(ORD.to_string(), traits.defs.len());
+        traits.defs.push(TraitDef {
+            name: ORD.to_string(),
+            methods: vec![],
+            span: Span::default(),
+            module: 0,
+        });
+
+        // Traits and impls are only declared at the top level
+        for (module, program) in programs.iter().enumerate() {
+            for stmt in program.iter() {
+                if let StatementKind::Trait(declared) = &stmt.kind {
+                    traits.declare(
+                        declared,
+                        stmt.span,
+                        module,
+                        structs,
+                        &mut diagnostics[module],
+                    );
+                }
+            }
+        }
+
+        for (module, program) in programs.iter().enumerate() {
+            for stmt in program.iter() {
+                if let StatementKind::Impl(implementation) = &stmt.kind {
+                    traits.implement(implementation, stmt.span, module, structs, diagnostics);
+                }
+            }
+        }
+
+        traits
+    }
+
+    fn declare(
+        &mut self,
+        declared: &Trait,
+        span: Span,
+        module: usize,
+        structs: &Structs,
+        diagnostics: &mut Diagnostics,
+    ) {
+        let name = &declared.name;
+
+        if self.index.contains_key(name)
+            || structs.get(name).is_some()
+            || structs.get_enum(name).is_some()
+        {
+            diagnostics.report(OrionError::DuplicateDefinition {
+                name: name.clone(),
+                loc: diagnostics.loc(span),
+            });
+
+            return;
+        }
+
+        let mut methods: Vec<MethodDef> = vec![];
+        let generics = ["Self".to_string()];
+
+        for method in &declared.methods {
+            if methods.iter().any(|other| other.name == method.name) {
+                diagnostics.report(OrionError::DuplicateDefinition {
+                    name: method.name.clone(),
+                    loc: diagnostics.loc(method.span),
+                });
+
+                continue;
+            }
+
+            let parameters: Vec<Option<Ty>> = method
+                .parameters
+                .iter()
+                .map(|parameter| structs.resolve_sized(&parameter.ty, &generics, diagnostics))
+                .collect();
+
+            let result = match &method.result {
+                Some(result) => structs.resolve_sized(result, &generics, diagnostics),
+                None => Some(Ty::unit()),
+            };
+
+            let ty = parameters
+                .into_iter()
+                .collect::<Option<Vec<_>>>()
+                .zip(result)
+                .map(|(parameters, result)| Ty::Function(parameters, Box::new(result)));
+
+            methods.push(MethodDef {
+                name: method.name.clone(),
+                ty,
+                span: method.span,
+            });
+        }
+
+        self.index.insert(name.clone(), self.defs.len());
+        self.defs.push(TraitDef {
+            name: name.clone(),
+            methods,
+            span,
+            module,
+        });
+    }
+
+    /// Check an impl against its trait, recording it if it is for a type
+    /// that can implement the trait. Its method bodies are checked with the
+    /// other functions.
+    fn implement(
+        &mut self,
+        implementation: &Impl,
+        span: Span,
+        module: usize,
+        structs: &Structs,
+        all: &mut [Diagnostics],
+    ) {
+        let trait_name = implementation.trait_path.join("::");
+        let diagnostics = &mut all[module];
+        let ty = structs.resolve_sized(&implementation.ty, &[], diagnostics);
+
+        let Some(def) = self.get(&trait_name) else {
+            diagnostics.report(OrionError::UnknownTrait {
+                name: trait_name,
+                loc: diagnostics.loc(span),
+            });
+
+            return;
+        };
+
+        if trait_name == ORD {
+            diagnostics.report(OrionError::BuiltinImpl {
+                trait_name,
+                loc: diagnostics.loc(span),
+            });
+
+            return;
+        }
+
+        let ty = match ty {
+            Some(ty @ (Ty::Enum(_) | Ty::Struct(_, _))) if !is_generic(&ty, structs) => ty,
+            Some(ty) => {
+                diagnostics.report(OrionError::InvalidImpl {
+                    ty: ty.to_string(),
+                    loc: diagnostics.loc(implementation.ty.span),
+                });
+
+                return;
+            }
+            None => return,
+        };
+
+        let mut provided: Vec<&str> = vec![];
+        let mut quiet = Diagnostics::new("");
+        let generics = ["Self".to_string()];
+
+        for stmt in &implementation.methods {
+            let StatementKind::Function(method) = &stmt.kind else {
+                continue;
+            };
+
+            if provided.contains(&method.name.as_str()) {
+                diagnostics.report(OrionError::DuplicateDefinition {
+                    name: method.name.clone(),
+                    loc: diagnostics.loc(stmt.span),
+                });
+
+                continue;
+            }
+
+            provided.push(&method.name);
+
+            let Some(declared) = def.method(&method.name) else {
+                diagnostics.report(OrionError::UnknownTraitMethod {
+                    trait_name: trait_name.clone(),
+                    method: method.name.clone(),
+                    loc: diagnostics.loc(stmt.span),
+                });
+
+                continue;
+            };
+
+            let parameters = method
+                .parameters
+                .iter()
+                .map(|parameter| structs.resolve_sized(&parameter.ty, &generics, &mut quiet))
+                .collect::<Option<Vec<_>>>();
+
+            let result = match &method.result {
+                Some(result) => structs.resolve_sized(result, &generics, &mut quiet),
+                None => Some(Ty::unit()),
+            };
+
+            // The errors in the types of the method are reported once it
+            // is checked
+            let Some((parameters, result)) = parameters.zip(result) else {
+                continue;
+            };
+
+            let found = Ty::Function(parameters, Box::new(result)).substitute(&self_type(&ty));
+
+            if let Some(expected) = declared.ty_for(&ty) {
+                if expected != found {
+                    let origin = (def.module == module).then(|| diagnostics.loc(declared.span));
+
+                    diagnostics.report(OrionError::MismatchedTypes {
+                        expected: expected.to_string(),
+                        found: found.to_string(),
+                        origin,
+                        loc: diagnostics.loc(stmt.span),
+                    });
+                }
+            }
+        }
+
+        let missing: Vec<String> = def
+            .methods
+            .iter()
+            .filter(|method| !provided.contains(&method.name.as_str()))
+            .map(|method| method.name.clone())
+            .collect();
+
+        if !missing.is_empty() {
+            diagnostics.report(OrionError::MissingMethods {
+                trait_name: trait_name.clone(),
+                ty: ty.to_string(),
+                methods: missing,
+                loc: diagnostics.loc(span),
+            });
+        }
+
+        for other in self.impls.iter().filter(|other| other.ty == ty) {
+            if other.trait_name == trait_name {
+                let first = all[other.module].loc(other.span);
+                let diagnostics = &mut all[module];
+
+                diagnostics.report(OrionError::ConflictingImpl {
+                    trait_name,
+                    ty: ty.to_string(),
+                    first,
+                    loc: diagnostics.loc(span),
+                });
+
+                return;
+            }
+
+            let shared = self.get(&other.trait_name).and_then(|other_def| {
+                def.methods
+                    .iter()
+                    .find(|method| other_def.method(&method.name).is_some())
+            });
+
+            if let Some(method) = shared {
+                let diagnostics = &mut all[module];
+
+                diagnostics.report(OrionError::AmbiguousMethod {
+                    ty: ty.to_string(),
+                    method: method.name.clone(),
+                    traits: (other.trait_name.clone(), trait_name),
+                    loc: diagnostics.loc(span),
+                });
+
+                return;
+            }
+        }
+
+        self.impls.push(ImplDef {
+            trait_name,
+            ty,
+            span,
+            module,
+        });
+    }
+
+    pub fn get(&self, name: &str) -> Option<&TraitDef> {
+        self.index.get(name).map(|&index| &self.defs[index])
+    }
+
+    /// The traits in the order they were declared
+    pub fn iter(&self) -> impl Iterator<Item = &TraitDef> {
+        self.defs.iter()
+    }
+
+    /// The impls in the order they were declared
+    pub fn impls(&self) -> impl Iterator<Item = &ImplDef> {
+        self.impls.iter()
+    }
+
+    pub fn implements(&self, trait_name: &str, ty: &Ty) -> bool {
+        if trait_name == ORD {
+            return matches!(ty, Ty::Primitive(primitive) if primitive.is_numeric() || *primitive == Primitive::Char);
+        }
+
+        self.impls.iter().any(|implementation| {
+            implementation.trait_name == trait_name && implementation.ty == *ty
+        })
+    }
+
+    /// The traits a type implements, in the order the impls were declared
+    pub fn implemented<'t>(&'t self, ty: &'t Ty) -> impl Iterator<Item = &'t TraitDef> {
+        self.impls
+            .iter()
+            .filter(move |implementation| implementation.ty == *ty)
+            .filter_map(|implementation| self.get(&implementation.trait_name))
+    }
+}
+
+/// Whether a struct type has type parameters, which can't implement
+/// traits
+fn is_generic(ty: &Ty, structs: &Structs) -> bool {
+    match ty {
+        Ty::Struct(name, _) => structs
+            .get(name)
+            .is_some_and(|def| !def.generics.is_empty()),
+        _ => false,
+    }
+}
+
+/// The type `Self` stands for
+fn self_type(ty: &Ty) -> HashMap<String, Ty> {
+    HashMap::from([("Self".to_string(), ty.clone())])
+}
@@ -0,0 +1,317 @@
+//! Types as the semantic passes see them.
+//!
+//! Unlike [`crate::parser::ast::Type`], which is what the user wrote, a
+//! [`Ty`] is resolved: `(u8)` and `u8` are the same type, and a name refers
+//! to a known struct, enum or primitive.
+//!
+//! Inside a generic function or struct, its type parameters are types of
+//! their own, [`Ty::Param`], which nothing is known about. Instances of a
+//! generic struct carry their type arguments, `Pair<u8, bool>`.
+use crate::sema::layout::POINTER_SIZE;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter, Result};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Ty {
+    Primitive(Primitive),
+    /// A struct, by name, with the type arguments of a generic struct
+    Struct(String, Vec<Ty>),
+    /// An enum, by name
+    Enum(String),
+    /// A reference, `&mut` if it can be written through
+    Reference(Box<Ty>, bool),
+    Array(Box<Ty>, u64),
+    Slice(Box<Ty>),
+    /// The empty tuple is the unit type, of functions returning nothing
+    Tuple(Vec<Ty>),
+    Function(Vec<Ty>, Box<Ty>),
+    /// A type parameter of the generic function or struct it is used in
+    Param(String),
+}
+
+impl Ty {
+    pub fn unit() -> Self {
+        Ty::Tuple(vec![])
+    }
+
+    /// Whether values of the type have a size known at compile time. `str`
+    /// and slices don't, so they can only be used behind a reference.
+    pub fn is_sized(&self) -> bool {
+        !matches!(self, Ty::Primitive(Primitive::Str) | Ty::Slice(_))
+    }
+
+    /// The type with the type parameters in `arguments` replaced by their
+    /// types
+    pub fn substitute(&self, arguments: &HashMap<String, Ty>) -> Ty {
+        let substitute = |ty: &Ty| ty.substitute(arguments);
+
+        match self {
+            Ty::Param(name) => arguments.get(name).cloned().unwrap_or_else(|| self.clone()),
+            Ty::Struct(name, types) => {
+                Ty::Struct(name.clone(), types.iter().map(substitute).collect())
+            }
+            Ty::Reference(inner, mutable) => Ty::Reference(Box::new(substitute(inner)), *mutable),
+            Ty::Array(element, length) => Ty::Array(Box::new(substitute(element)), *length),
+            Ty::Slice(element) => Ty::Slice(Box::new(substitute(element))),
+            Ty::Tuple(elements) => Ty::Tuple(elements.iter().map(substitute).collect()),
+            Ty::Function(parameters, result) => Ty::Function(
+                parameters.iter().map(substitute).collect(),
+                Box::new(substitute(result)),
+            ),
+            Ty::Primitive(_) | Ty::Enum(_) => self.clone(),
+        }
+    }
+
+    /// Whether the type mentions the type parameter `name`
+    pub fn mentions(&self, name: &str) -> bool {
+        match self {
+            Ty::Param(param) => param == name,
+            Ty::Struct(_, types) | Ty::Tuple(types) => types.iter().any(|ty| ty.mentions(name)),
+            Ty::Reference(inner, _) | Ty::Array(inner, _) | Ty::Slice(inner) => {
+                inner.mentions(name)
+            }
+            Ty::Function(parameters, result) => {
+                parameters.iter().any(|ty| ty.mentions(name)) || result.mentions(name)
+            }
+            Ty::Primitive(_) | Ty::Enum(_) => false,
+        }
+    }
+}
+
+/// The parameters of a generic function or struct, paired with the types
+/// they stand for
+pub fn arguments(generics: &[String], types: &[Ty]) -> HashMap<String, Ty> {
+    generics
+        .iter()
+        .cloned()
+        .zip(types.iter().cloned())
+        .collect()
+}
+
+impl Display for Ty {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Ty::Primitive(primitive) => write!(f, "{primitive}"),
+            Ty::Struct(name, arguments) if !arguments.is_empty() => {
+                write!(f, "{name}<")?;
+
+                for (i, argument) in arguments.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+
+                    write!(f, "{argument}")?;
+                }
+
+                write!(f, ">")
+            }
+            Ty::Struct(name, _) | Ty::Enum(name) | Ty::Param(name) => write!(f, "{name}"),
+            Ty::Reference(inner, false) => write!(f, "&{inner}"),
+            Ty::Reference(inner, true) => write!(f, "&mut {inner}"),
+            Ty::Array(element, length) => write!(f, "[{element}; {length}]"),
+            Ty::Slice(element) => write!(f, "[{element}]"),
+            Ty::Tuple(elements) => {
+                write!(f, "(")?;
+
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+
+                    write!(f, "{element}")?;
+                }
+
+                if elements.len() == 1 {
+                    write!(f, ",")?;
+                }
+
+                write!(f, ")")
+            }
+            Ty::Function(parameters, result) => {
+                write!(f, "func(")?;
+
+                for (i, parameter) in parameters.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+
+                    write!(f, "{parameter}")?;
+                }
+
+                write!(f, ")")?;
+
+                if **result != Ty::unit() {
+                    write!(f, " -> {result}")?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The types built into the language, see
+/// [`crate::parser::ast::PRIMITIVE_TYPES`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Primitive {
+    U8,
+    U16,
+    U32,
+    U64,
+    Usize,
+    I8,
+    I16,
+    I32,
+    I64,
+    Isize,
+    F32,
+    F64,
+    Bool,
+    Char,
+    Str,
+}
+
+impl Primitive {
+    pub fn from_name(name: &str) -> Option<Self> {
+        let primitive = match name {
+            "u8" => Primitive::U8,
+            "u16" => Primitive::U16,
+            "u32" => Primitive::U32,
+            "u64" => Primitive::U64,
+            "usize" => Primitive::Usize,
+            "i8" => Primitive::I8,
+            "i16" => Primitive::I16,
+            "i32" => Primitive::I32,
+            "i64" => Primitive::I64,
+            "isize" => Primitive::Isize,
+            "f32" => Primitive::F32,
+            "f64" => Primitive::F64,
+            "bool" => Primitive::Bool,
+            "char" => Primitive::Char,
+            "str" => Primitive::Str,
+            _ => return None,
+        };
+
+        Some(primitive)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Primitive::U8 => "u8",
+            Primitive::U16 => "u16",
+            Primitive::U32 => "u32",
+            Primitive::U64 => "u64",
+            Primitive::Usize => "usize",
+            Primitive::I8 => "i8",
+            Primitive::I16 => "i16",
+            Primitive::I32 => "i32",
+            Primitive::I64 => "i64",
+            Primitive::Isize => "isize",
+            Primitive::F32 => "f32",
+            Primitive::F64 => "f64",
+            Primitive::Bool => "bool",
+            Primitive::Char => "char",
+            Primitive::Str => "str",
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self,
+            Primitive::U8
+                | Primitive::U16
+                | Primitive::U32
+                | Primitive::U64
+                | Primitive::Usize
+                | Primitive::I8
+                | Primitive::I16
+                | Primitive::I32
+                | Primitive::I64
+                | Primitive::Isize
+        )
+    }
+
+    pub fn is_signed(&self) -> bool {
+        matches!(
+            self,
+            Primitive::I8 | Primitive::I16 | Primitive::I32 | Primitive::I64 | Primitive::Isize
+        )
+    }
+
+    /// How many bits wide an integer type is. `usize` and `isize` are as
+    /// wide as a pointer, [`POINTER_SIZE`] bytes.
+    pub fn bits(&self) -> Option<u32> {
+        match self {
+            Primitive::U8 | Primitive::I8 => Some(8),
+            Primitive::U16 | Primitive::I16 => Some(16),
+            Primitive::U32 | Primitive::I32 => Some(32),
+            Primitive::U64 | Primitive::I64 => Some(64),
+            Primitive::Usize | Primitive::Isize => Some(POINTER_SIZE as u32 * 8),
+            _ => None,
+        }
+    }
+
+    /// The smallest and the largest value of an integer type
+    pub fn range(&self) -> Option<(i128, i128)> {
+        let bits = self.bits()?;
+
+        if self.is_signed() {
+            Some((-(1 << (bits - 1)), (1 << (bits - 1)) - 1))
+        } else {
+            Some((0, (1 << bits) - 1))
+        }
+    }
+
+    /// The value of an integer type with the same low bits as `num`, which
+    /// is what wrapping around to a value of it gives. Other types leave
+    /// `num` as it is.
+    pub fn wrap(&self, num: i128) -> i128 {
+        let Some(bits) = self.bits() else {
+            return num;
+        };
+
+        // Keep the low bits, reading them as signed for a signed type
+        let shift = 128 - bits;
+        let num = num << shift;
+
+        if self.is_signed() {
+            num >> shift
+        } else {
+            ((num as u128) >> shift) as i128
+        }
+    }
+
+    /// Whether every value of this number type is a value of `other`, so
+    /// that it converts to it without a cast: an integer to a wider integer
+    /// type that is signed if it is, or to a float with enough digits, and
+    /// `f32` to `f64`. `usize` and `isize` are as wide as a pointer, so the
+    /// integer types of that width don't widen to them, nor they to those.
+    pub fn widens_to(&self, other: Primitive) -> bool {
+        match (self, other) {
+            (Primitive::F32, Primitive::F64) => true,
+            (
+                Primitive::U8 | Primitive::U16 | Primitive::I8 | Primitive::I16,
+                Primitive::F32 | Primitive::F64,
+            )
+            | (Primitive::U32 | Primitive::I32, Primitive::F64) => true,
+            _ if self.is_integer() && other.is_integer() => {
+                self.bits() < other.bits() && (other.is_signed() || !self.is_signed())
+            }
+            _ => false,
+        }
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, Primitive::F32 | Primitive::F64)
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        self.is_integer() || self.is_float()
+    }
+}
+
+impl Display for Primitive {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.name())
+    }
+}
@@ -0,0 +1,162 @@
+//! The green tree: immutable, position independent syntax nodes.
+//!
+//! A green node only knows its kind, its length and its children, so
+//! identical subtrees can be shared and an edit only has to rebuild the path
+//! from the root to the change. Positions and parents are provided by the red
+//! tree in [`crate::syntax::red`].
+use crate::syntax::kind::SyntaxKind;
+use std::rc::Rc;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GreenToken {
+    kind: SyntaxKind,
+    text: String,
+}
+
+impl GreenToken {
+    pub fn new(kind: SyntaxKind, text: &str) -> Self {
+        Self {
+            kind,
+            text: text.to_string(),
+        }
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GreenNode {
+    kind: SyntaxKind,
+    // Length of the text covered by the node, in bytes
+    len: usize,
+    children: Vec<GreenElement>,
+}
+
+impl GreenNode {
+    pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> Self {
+        let len = children.iter().map(GreenElement::len).sum();
+
+        Self {
+            kind,
+            len,
+            children,
+        }
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn children(&self) -> &[GreenElement] {
+        &self.children
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GreenElement {
+    Node(Rc<GreenNode>),
+    Token(Rc<GreenToken>),
+}
+
+impl GreenElement {
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            GreenElement::Node(node) => node.kind(),
+            GreenElement::Token(token) => token.kind(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.len(),
+            GreenElement::Token(token) => token.text().len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Builds a green tree bottom-up from a sequence of start/token/finish
+/// events, which is what a recursive descent parser naturally produces.
+#[derive(Debug, Default)]
+pub struct GreenBuilder {
+    // The open nodes, with the index of their first child in `children`
+    parents: Vec<(SyntaxKind, usize)>,
+    children: Vec<GreenElement>,
+}
+
+/// A position to start a node at retroactively, see
+/// [`GreenBuilder::start_node_at`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint(usize);
+
+impl GreenBuilder {
+    pub fn start_node(&mut self, kind: SyntaxKind) {
+        self.parents.push((kind, self.children.len()));
+    }
+
+    pub fn token(&mut self, kind: SyntaxKind, text: &str) {
+        self.children
+            .push(GreenElement::Token(Rc::new(GreenToken::new(kind, text))));
+    }
+
+    pub fn finish_node(&mut self) {
+        let (kind, first) = self.parents.pop().expect("no node to finish");
+        let children = self.children.split_off(first);
+
+        self.children
+            .push(GreenElement::Node(Rc::new(GreenNode::new(kind, children))));
+    }
+
+    /// Remember the current position, to later wrap everything added since
+    /// in a node. This is how left-recursive constructs like binary
+    /// expressions are built, as their kind is only known after the left
+    /// operand was parsed.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.children.len())
+    }
+
+    pub fn start_node_at(&mut self, checkpoint: Checkpoint, kind: SyntaxKind) {
+        self.parents.push((kind, checkpoint.0));
+    }
+
+    /// The number of open nodes
+    pub fn depth(&self) -> usize {
+        self.parents.len()
+    }
+
+    /// Finish open nodes until only `depth` are left
+    pub fn finish_to(&mut self, depth: usize) {
+        while self.parents.len() > depth {
+            self.finish_node();
+        }
+    }
+
+    /// The root of the finished tree
+    pub fn finish(mut self) -> GreenNode {
+        self.finish_to(0);
+
+        match self.children.pop() {
+            Some(GreenElement::Node(node)) if self.children.is_empty() => {
+                Rc::try_unwrap(node).unwrap_or_else(|node| (*node).clone())
+            }
+            _ => panic!("a syntax tree has a single root node"),
+        }
+    }
+}
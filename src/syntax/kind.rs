@@ -0,0 +1,155 @@
+//! The kinds of tokens and nodes in the concrete syntax tree
+use crate::lexer::tokens::TokenKind;
+
+/// Tokens are grouped in coarse classes, their text tells them apart. Nodes
+/// mirror the grammar the parser implements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyntaxKind {
+    // Tokens
+    Whitespace,
+    Comment,
+    Identifier,
+    Keyword,
+    Number,
+    Quote,
+    Char,
+    /// Operators and punctuation
+    Punct,
+    /// Text the lexer couldn't make sense of
+    ErrorToken,
+
+    // Nodes
+    SourceFile,
+    LetStmt,
+    FunctionDecl,
+    /// `<A, B>` after the name of a generic function or struct
+    GenericParams,
+    /// `T : Show + Eq` among the type parameters of a generic function
+    GenericParam,
+    /// The parameters of a function declaration, with their parentheses
+    ParamList,
+    Param,
+    Block,
+    ReturnStmt,
+    IfStmt,
+    /// `$if condition { ... } else { ... }`
+    ComptimeIfStmt,
+    ExprStmt,
+    /// `place = value;`
+    AssignStmt,
+    StructDecl,
+    /// `name : T` in a struct declaration
+    FieldDecl,
+    EnumDecl,
+    /// `Name(T, U)` or `Name` in an enum declaration
+    VariantDecl,
+    /// `mod name;`
+    ModuleDecl,
+    /// `import path::to::name;`
+    ImportDecl,
+    /// `trait Name { ... }`
+    TraitDecl,
+    /// `func name(self, a : T) : R;` in a trait declaration
+    MethodDecl,
+    /// `impl Trait for Type { ... }`
+    ImplDecl,
+    BinaryExpr,
+    ParenExpr,
+    NumberExpr,
+    /// `true`, `false`, strings and characters
+    LiteralExpr,
+    IdentifierExpr,
+    PrefixExpr,
+    PostfixExpr,
+    CastExpr,
+    CallExpr,
+    /// `expression.field`
+    FieldExpr,
+    /// `receiver.method(arguments)`
+    MethodCallExpr,
+    /// `Name { field: value, ... }`
+    StructLit,
+    /// `field: value` in a struct literal
+    FieldInit,
+    /// `[a, b, c]`
+    ArrayExpr,
+    /// `[value; length]`
+    ArrayRepeatExpr,
+    /// `expression[index]`
+    IndexExpr,
+    /// `expression[start..end]`
+    SliceExpr,
+    /// `Enum::Variant`
+    PathExpr,
+    /// `match scrutinee { arms }`
+    MatchExpr,
+    /// `pattern => value` in a match
+    MatchArm,
+    /// `|a : T, b| -> R = value`
+    ClosureExpr,
+    /// `name : T` or `name` among the parameters of a closure
+    ClosureParam,
+    /// `$(value)` or `${ ... }`
+    ComptimeExpr,
+    /// `_`
+    WildcardPat,
+    /// A name that binds the matched value
+    BindingPat,
+    /// A number, `true`, `false` or a character
+    LiteralPat,
+    /// `Enum::Variant(patterns)`
+    VariantPat,
+    /// The arguments of a call, with their parentheses
+    ArgList,
+    NamedType,
+    /// `<A, B>` after the path of a named type
+    GenericArgs,
+    ReferenceType,
+    ArrayType,
+    SliceType,
+    TupleType,
+    /// A type in parentheses, `(T)`
+    ParenType,
+    FunctionType,
+    /// Tokens skipped by the parser while recovering from an error
+    Error,
+}
+
+impl SyntaxKind {
+    /// Trivia carries no meaning for the grammar, but is kept so the tree
+    /// can reproduce the source exactly
+    pub fn is_trivia(self) -> bool {
+        matches!(self, SyntaxKind::Whitespace | SyntaxKind::Comment)
+    }
+
+    pub fn from_token(kind: &TokenKind) -> Self {
+        match kind {
+            TokenKind::Comment(_) => SyntaxKind::Comment,
+            TokenKind::Identifier(_) => SyntaxKind::Identifier,
+            TokenKind::Keyword(_)
+            | TokenKind::Let
+            | TokenKind::Return
+            | TokenKind::True
+            | TokenKind::False
+            | TokenKind::As
+            | TokenKind::Func
+            | TokenKind::If
+            | TokenKind::Else
+            | TokenKind::Struct
+            | TokenKind::Enum
+            | TokenKind::Match
+            | TokenKind::Mod
+            | TokenKind::Import
+            | TokenKind::Pub
+            | TokenKind::Trait
+            | TokenKind::Impl
+            | TokenKind::For
+            | TokenKind::Mut => SyntaxKind::Keyword,
+            TokenKind::Number(_) => SyntaxKind::Number,
+            TokenKind::Quote(_) => SyntaxKind::Quote,
+            TokenKind::Char(_) => SyntaxKind::Char,
+            TokenKind::Error | TokenKind::Eof => SyntaxKind::ErrorToken,
+            _ => SyntaxKind::Punct,
+        }
+    }
+}
@@ -0,0 +1,67 @@
+//! Lossless concrete syntax tree.
+//!
+//! While the AST in [`crate::parser::ast`] only keeps what matters for
+//! compiling, the concrete syntax tree keeps every byte of the source:
+//! whitespace, comments, parentheses, and the tokens skipped while
+//! recovering from errors. Printing the tree gives back the input exactly,
+//! which is what a formatter, refactorings or a language server need to
+//! change code without losing anything, and it is available for incomplete
+//! code as well.
+//!
+//! The parser builds the tree alongside the AST. It is stored as an
+//! immutable green tree ([`green`]), navigated through a red tree with
+//! parents and positions ([`red`]), and has a typed view on top ([`view`]).
+pub mod green;
+pub mod kind;
+pub mod red;
+pub mod view;
+
+use crate::error::OrionError;
+use crate::lexer::state::Lexer;
+use crate::parser::state::Parser;
+use crate::syntax::kind::SyntaxKind;
+use crate::syntax::red::SyntaxNode;
+
+/// The result of parsing a source file into a concrete syntax tree
+pub struct Parse {
+    pub root: SyntaxNode,
+    pub errors: Vec<OrionError>,
+}
+
+/// Parse a source file into a concrete syntax tree. This never fails, syntax
+/// errors are reported next to the tree and the offending code ends up in
+/// `Error` nodes.
+pub fn parse(source: &str) -> Parse {
+    let mut parser = Parser::new(Lexer::new(source));
+    let (root, errors) = parser.syntax();
+
+    Parse { root, errors }
+}
+
+/// Split the text between two tokens into trivia tokens. The parser never
+/// sees this text, so it is whitespace and comments, except after a lexer
+/// error where the rest of the input ends up here as well.
+pub(crate) fn trivia(text: &str) -> Vec<(SyntaxKind, &str)> {
+    let mut pieces = vec![];
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        let (kind, len) = if rest.starts_with(char::is_whitespace) {
+            let len = rest.find(|chr: char| !chr.is_whitespace());
+            (SyntaxKind::Whitespace, len.unwrap_or(rest.len()))
+        } else if rest.starts_with("//") {
+            (SyntaxKind::Comment, rest.find('\n').unwrap_or(rest.len()))
+        } else if let Some(body) = rest.strip_prefix("/*") {
+            let len = body.find("*/").map_or(rest.len(), |end| end + 4);
+            (SyntaxKind::Comment, len)
+        } else {
+            let len = rest.find(char::is_whitespace);
+            (SyntaxKind::ErrorToken, len.unwrap_or(rest.len()))
+        };
+
+        pieces.push((kind, &rest[..len]));
+        rest = &rest[len..];
+    }
+
+    pieces
+}
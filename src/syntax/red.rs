@@ -0,0 +1,188 @@
+//! The red tree: a view over the green tree with parents and positions.
+//!
+//! Red nodes are created lazily while walking down from the root and are
+//! cheap to clone. They are what tools navigate: every node and token knows
+//! its span in the source and can go up to its parent.
+use crate::lexer::tokens::Span;
+use crate::syntax::green::*;
+use crate::syntax::kind::SyntaxKind;
+use std::fmt::{Debug, Formatter, Result};
+use std::rc::Rc;
+
+#[derive(Clone)]
+pub struct SyntaxNode(Rc<NodeData>);
+
+struct NodeData {
+    green: Rc<GreenNode>,
+    parent: Option<SyntaxNode>,
+    // Byte offset of the node in the source
+    offset: usize,
+}
+
+#[derive(Clone)]
+pub struct SyntaxToken {
+    green: Rc<GreenToken>,
+    parent: SyntaxNode,
+    offset: usize,
+}
+
+#[derive(Clone, Debug)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl SyntaxNode {
+    pub fn new_root(green: GreenNode) -> Self {
+        Self(Rc::new(NodeData {
+            green: Rc::new(green),
+            parent: None,
+            offset: 0,
+        }))
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.green.kind()
+    }
+
+    pub fn green(&self) -> &GreenNode {
+        &self.0.green
+    }
+
+    pub fn span(&self) -> Span {
+        Span::new(self.0.offset, self.0.offset + self.0.green.len())
+    }
+
+    pub fn parent(&self) -> Option<SyntaxNode> {
+        self.0.parent.clone()
+    }
+
+    /// The exact source text covered by the node, trivia included
+    pub fn text(&self) -> String {
+        let mut text = String::with_capacity(self.0.green.len());
+
+        for token in self.tokens() {
+            text.push_str(token.text());
+        }
+
+        text
+    }
+
+    pub fn children_with_tokens(&self) -> Vec<SyntaxElement> {
+        let mut offset = self.0.offset;
+        let mut elements = vec![];
+
+        for child in self.0.green.children() {
+            let element = match child {
+                GreenElement::Node(green) => SyntaxElement::Node(SyntaxNode(Rc::new(NodeData {
+                    green: green.clone(),
+                    parent: Some(self.clone()),
+                    offset,
+                }))),
+                GreenElement::Token(green) => SyntaxElement::Token(SyntaxToken {
+                    green: green.clone(),
+                    parent: self.clone(),
+                    offset,
+                }),
+            };
+
+            offset += child.len();
+            elements.push(element);
+        }
+
+        elements
+    }
+
+    /// The child nodes, leaving out tokens
+    pub fn children(&self) -> Vec<SyntaxNode> {
+        self.children_with_tokens()
+            .into_iter()
+            .filter_map(|element| match element {
+                SyntaxElement::Node(node) => Some(node),
+                SyntaxElement::Token(_) => None,
+            })
+            .collect()
+    }
+
+    /// The direct child tokens, leaving out nodes
+    pub fn child_tokens(&self) -> Vec<SyntaxToken> {
+        self.children_with_tokens()
+            .into_iter()
+            .filter_map(|element| match element {
+                SyntaxElement::Token(token) => Some(token),
+                SyntaxElement::Node(_) => None,
+            })
+            .collect()
+    }
+
+    /// Every token in the subtree, in source order
+    pub fn tokens(&self) -> Vec<SyntaxToken> {
+        let mut tokens = vec![];
+
+        for element in self.children_with_tokens() {
+            match element {
+                SyntaxElement::Node(node) => tokens.extend(node.tokens()),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+
+        tokens
+    }
+
+    /// The node itself and every node below it, in preorder
+    pub fn descendants(&self) -> Vec<SyntaxNode> {
+        let mut nodes = vec![self.clone()];
+
+        for child in self.children() {
+            nodes.extend(child.descendants());
+        }
+
+        nodes
+    }
+
+    fn write_tree(&self, f: &mut Formatter<'_>, depth: usize) -> Result {
+        writeln!(f, "{}{:?}@{}", "  ".repeat(depth), self.kind(), self.span())?;
+
+        for element in self.children_with_tokens() {
+            match element {
+                SyntaxElement::Node(node) => node.write_tree(f, depth + 1)?,
+                SyntaxElement::Token(token) => {
+                    writeln!(f, "{}{:?}", "  ".repeat(depth + 1), token)?
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Debug for SyntaxNode {
+    /// The whole subtree, one node or token per line
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        self.write_tree(f, 0)
+    }
+}
+
+impl SyntaxToken {
+    pub fn kind(&self) -> SyntaxKind {
+        self.green.kind()
+    }
+
+    pub fn text(&self) -> &str {
+        self.green.text()
+    }
+
+    pub fn span(&self) -> Span {
+        Span::new(self.offset, self.offset + self.green.text().len())
+    }
+
+    pub fn parent(&self) -> SyntaxNode {
+        self.parent.clone()
+    }
+}
+
+impl Debug for SyntaxToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{:?}@{} {:?}", self.kind(), self.span(), self.text())
+    }
+}
@@ -0,0 +1,1029 @@
+//! Typed view over the concrete syntax tree.
+//!
+//! Each type wraps a [`SyntaxNode`] of a given kind and offers accessors for
+//! its parts. Since the tree may come from incomplete code, every accessor
+//! returns an `Option`: a `let` without an initializer still is a `LetStmt`.
+use crate::syntax::kind::SyntaxKind;
+use crate::syntax::red::*;
+
+pub trait AstNode: Sized {
+    /// View a node as this type, if it is of the right kind
+    fn cast(node: SyntaxNode) -> Option<Self>;
+
+    fn syntax(&self) -> &SyntaxNode;
+}
+
+macro_rules! ast_node {
+    ($name:ident) => {
+        #[derive(Debug, Clone)]
+        pub struct $name(SyntaxNode);
+
+        impl AstNode for $name {
+            fn cast(node: SyntaxNode) -> Option<Self> {
+                (node.kind() == SyntaxKind::$name).then(|| Self(node))
+            }
+
+            fn syntax(&self) -> &SyntaxNode {
+                &self.0
+            }
+        }
+    };
+}
+
+ast_node!(SourceFile);
+ast_node!(LetStmt);
+ast_node!(FunctionDecl);
+ast_node!(GenericParam);
+ast_node!(Param);
+ast_node!(Block);
+ast_node!(ReturnStmt);
+ast_node!(IfStmt);
+ast_node!(ComptimeIfStmt);
+ast_node!(ExprStmt);
+ast_node!(AssignStmt);
+ast_node!(StructDecl);
+ast_node!(FieldDecl);
+ast_node!(EnumDecl);
+ast_node!(VariantDecl);
+ast_node!(ModuleDecl);
+ast_node!(ImportDecl);
+ast_node!(TraitDecl);
+ast_node!(MethodDecl);
+ast_node!(ImplDecl);
+ast_node!(BinaryExpr);
+ast_node!(ParenExpr);
+ast_node!(NumberExpr);
+ast_node!(LiteralExpr);
+ast_node!(IdentifierExpr);
+ast_node!(PrefixExpr);
+ast_node!(PostfixExpr);
+ast_node!(CastExpr);
+ast_node!(CallExpr);
+ast_node!(FieldExpr);
+ast_node!(MethodCallExpr);
+ast_node!(StructLit);
+ast_node!(FieldInit);
+ast_node!(ArrayExpr);
+ast_node!(ArrayRepeatExpr);
+ast_node!(IndexExpr);
+ast_node!(SliceExpr);
+ast_node!(PathExpr);
+ast_node!(MatchExpr);
+ast_node!(MatchArm);
+ast_node!(ClosureExpr);
+ast_node!(ClosureParam);
+ast_node!(ComptimeExpr);
+ast_node!(WildcardPat);
+ast_node!(BindingPat);
+ast_node!(LiteralPat);
+ast_node!(VariantPat);
+ast_node!(NamedType);
+ast_node!(ReferenceType);
+ast_node!(ArrayType);
+ast_node!(SliceType);
+ast_node!(TupleType);
+ast_node!(ParenType);
+ast_node!(FunctionType);
+
+/// The `pub` keyword of a declaration, if it is public
+fn visibility(node: &SyntaxNode) -> Option<SyntaxToken> {
+    node.child_tokens()
+        .into_iter()
+        .find(|token| token.text() == "pub")
+}
+
+/// The type parameters of a generic function or struct
+fn generics(node: &SyntaxNode) -> impl Iterator<Item = GenericParam> {
+    node.children()
+        .into_iter()
+        .filter(|node| node.kind() == SyntaxKind::GenericParams)
+        .flat_map(|list| children(&list).collect::<Vec<_>>())
+}
+
+/// The parameters of a function or method declaration
+fn parameters(node: &SyntaxNode) -> impl Iterator<Item = Param> {
+    let list = node
+        .children()
+        .into_iter()
+        .find(|node| node.kind() == SyntaxKind::ParamList);
+
+    list.into_iter().flat_map(|list| children(&list))
+}
+
+/// The first child token of `node` with the given kind
+fn token(node: &SyntaxNode, kind: SyntaxKind) -> Option<SyntaxToken> {
+    node.child_tokens()
+        .into_iter()
+        .find(|token| token.kind() == kind)
+}
+
+/// The first child node of `node` that is a type
+fn ty(node: &SyntaxNode) -> Option<Type> {
+    types(node).next()
+}
+
+/// The child nodes of `node` that are types
+fn types(node: &SyntaxNode) -> impl Iterator<Item = Type> {
+    node.children().into_iter().filter_map(Type::cast)
+}
+
+/// The nodes of the given kind among the children of `node`
+fn children<N: AstNode>(node: &SyntaxNode) -> impl Iterator<Item = N> {
+    node.children().into_iter().filter_map(N::cast)
+}
+
+/// The child nodes of `node` that are expressions
+fn expressions(node: &SyntaxNode) -> impl Iterator<Item = Expr> {
+    node.children().into_iter().filter_map(Expr::cast)
+}
+
+impl SourceFile {
+    pub fn statements(&self) -> impl Iterator<Item = Stmt> {
+        self.0.children().into_iter().filter_map(Stmt::cast)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Stmt {
+    Let(LetStmt),
+    Function(FunctionDecl),
+    Return(ReturnStmt),
+    If(IfStmt),
+    ComptimeIf(ComptimeIfStmt),
+    Block(Block),
+    Expr(ExprStmt),
+    Assign(AssignStmt),
+    Struct(StructDecl),
+    Enum(EnumDecl),
+    Module(ModuleDecl),
+    Import(ImportDecl),
+    Trait(TraitDecl),
+    Impl(ImplDecl),
+}
+
+impl AstNode for Stmt {
+    fn cast(node: SyntaxNode) -> Option<Self> {
+        let stmt = match node.kind() {
+            SyntaxKind::LetStmt => Stmt::Let(LetStmt(node)),
+            SyntaxKind::FunctionDecl => Stmt::Function(FunctionDecl(node)),
+            SyntaxKind::ReturnStmt => Stmt::Return(ReturnStmt(node)),
+            SyntaxKind::IfStmt => Stmt::If(IfStmt(node)),
+            SyntaxKind::ComptimeIfStmt => Stmt::ComptimeIf(ComptimeIfStmt(node)),
+            SyntaxKind::Block => Stmt::Block(Block(node)),
+            SyntaxKind::ExprStmt => Stmt::Expr(ExprStmt(node)),
+            SyntaxKind::AssignStmt => Stmt::Assign(AssignStmt(node)),
+            SyntaxKind::StructDecl => Stmt::Struct(StructDecl(node)),
+            SyntaxKind::EnumDecl => Stmt::Enum(EnumDecl(node)),
+            SyntaxKind::ModuleDecl => Stmt::Module(ModuleDecl(node)),
+            SyntaxKind::ImportDecl => Stmt::Import(ImportDecl(node)),
+            SyntaxKind::TraitDecl => Stmt::Trait(TraitDecl(node)),
+            SyntaxKind::ImplDecl => Stmt::Impl(ImplDecl(node)),
+            _ => return None,
+        };
+
+        Some(stmt)
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Stmt::Let(stmt) => stmt.syntax(),
+            Stmt::Function(stmt) => stmt.syntax(),
+            Stmt::Return(stmt) => stmt.syntax(),
+            Stmt::If(stmt) => stmt.syntax(),
+            Stmt::ComptimeIf(stmt) => stmt.syntax(),
+            Stmt::Block(stmt) => stmt.syntax(),
+            Stmt::Expr(stmt) => stmt.syntax(),
+            Stmt::Assign(stmt) => stmt.syntax(),
+            Stmt::Struct(stmt) => stmt.syntax(),
+            Stmt::Enum(stmt) => stmt.syntax(),
+            Stmt::Module(stmt) => stmt.syntax(),
+            Stmt::Import(stmt) => stmt.syntax(),
+            Stmt::Trait(stmt) => stmt.syntax(),
+            Stmt::Impl(stmt) => stmt.syntax(),
+        }
+    }
+}
+
+impl LetStmt {
+    /// The `mut` of a variable that can be assigned to
+    pub fn mutability(&self) -> Option<SyntaxToken> {
+        self.0
+            .child_tokens()
+            .into_iter()
+            .find(|token| token.text() == "mut")
+    }
+
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Identifier)
+    }
+
+    /// The type annotation, if there is one
+    pub fn ty(&self) -> Option<Type> {
+        ty(&self.0)
+    }
+
+    /// The `=` or `:=` token
+    pub fn assignment(&self) -> Option<SyntaxToken> {
+        self.0
+            .child_tokens()
+            .into_iter()
+            .find(|token| matches!(token.text(), "=" | ":="))
+    }
+
+    pub fn initial(&self) -> Option<Expr> {
+        expressions(&self.0).next()
+    }
+}
+
+impl FunctionDecl {
+    pub fn visibility(&self) -> Option<SyntaxToken> {
+        visibility(&self.0)
+    }
+
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Identifier)
+    }
+
+    pub fn generics(&self) -> impl Iterator<Item = GenericParam> {
+        generics(&self.0)
+    }
+
+    pub fn parameters(&self) -> impl Iterator<Item = Param> {
+        parameters(&self.0)
+    }
+
+    /// The result type, if there is one
+    pub fn result(&self) -> Option<Type> {
+        ty(&self.0)
+    }
+
+    pub fn body(&self) -> Option<Block> {
+        children(&self.0).next()
+    }
+}
+
+impl GenericParam {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Identifier)
+    }
+
+    /// The names making up the path to each trait after the `:`
+    pub fn bounds(&self) -> Vec<Vec<SyntaxToken>> {
+        let mut bounds: Vec<Vec<SyntaxToken>> = vec![];
+
+        for token in self
+            .0
+            .child_tokens()
+            .into_iter()
+            .skip_while(|token| token.text() != ":")
+        {
+            match token.text() {
+                ":" | "+" => bounds.push(vec![]),
+                _ if token.kind() == SyntaxKind::Identifier => {
+                    if let Some(bound) = bounds.last_mut() {
+                        bound.push(token);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        bounds
+    }
+}
+
+impl Param {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Identifier)
+    }
+
+    pub fn ty(&self) -> Option<Type> {
+        ty(&self.0)
+    }
+}
+
+impl Block {
+    pub fn statements(&self) -> impl Iterator<Item = Stmt> {
+        children(&self.0)
+    }
+}
+
+impl ReturnStmt {
+    pub fn value(&self) -> Option<Expr> {
+        expressions(&self.0).next()
+    }
+}
+
+impl IfStmt {
+    pub fn condition(&self) -> Option<Expr> {
+        expressions(&self.0).next()
+    }
+
+    pub fn then(&self) -> Option<Block> {
+        children(&self.0).next()
+    }
+
+    /// The `else` branch, a block or another `if`
+    pub fn otherwise(&self) -> Option<Stmt> {
+        children::<Stmt>(&self.0).nth(1)
+    }
+}
+
+impl ComptimeIfStmt {
+    pub fn condition(&self) -> Option<Expr> {
+        expressions(&self.0).next()
+    }
+
+    pub fn then(&self) -> Option<Block> {
+        children(&self.0).next()
+    }
+
+    /// The `else` branch, a block or another `$if`
+    pub fn otherwise(&self) -> Option<Stmt> {
+        children::<Stmt>(&self.0).nth(1)
+    }
+}
+
+impl ExprStmt {
+    pub fn expression(&self) -> Option<Expr> {
+        expressions(&self.0).next()
+    }
+}
+
+impl AssignStmt {
+    pub fn place(&self) -> Option<Expr> {
+        expressions(&self.0).next()
+    }
+
+    /// The `=`, or a compound assignment like `+=`
+    pub fn operator(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Punct)
+    }
+
+    pub fn value(&self) -> Option<Expr> {
+        expressions(&self.0).nth(1)
+    }
+}
+
+impl StructDecl {
+    pub fn visibility(&self) -> Option<SyntaxToken> {
+        visibility(&self.0)
+    }
+
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Identifier)
+    }
+
+    pub fn generics(&self) -> impl Iterator<Item = GenericParam> {
+        generics(&self.0)
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = FieldDecl> {
+        children(&self.0)
+    }
+}
+
+impl FieldDecl {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Identifier)
+    }
+
+    pub fn ty(&self) -> Option<Type> {
+        ty(&self.0)
+    }
+}
+
+impl EnumDecl {
+    pub fn visibility(&self) -> Option<SyntaxToken> {
+        visibility(&self.0)
+    }
+
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Identifier)
+    }
+
+    pub fn variants(&self) -> impl Iterator<Item = VariantDecl> {
+        children(&self.0)
+    }
+}
+
+impl VariantDecl {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Identifier)
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = Type> {
+        types(&self.0)
+    }
+}
+
+impl ModuleDecl {
+    pub fn visibility(&self) -> Option<SyntaxToken> {
+        visibility(&self.0)
+    }
+
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Identifier)
+    }
+}
+
+impl ImportDecl {
+    /// The names making up the imported path
+    pub fn segments(&self) -> impl Iterator<Item = SyntaxToken> {
+        self.0
+            .child_tokens()
+            .into_iter()
+            .filter(|token| token.kind() == SyntaxKind::Identifier)
+    }
+}
+
+impl TraitDecl {
+    pub fn visibility(&self) -> Option<SyntaxToken> {
+        visibility(&self.0)
+    }
+
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Identifier)
+    }
+
+    pub fn methods(&self) -> impl Iterator<Item = MethodDecl> {
+        children(&self.0)
+    }
+}
+
+impl MethodDecl {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Identifier)
+    }
+
+    /// The parameters, the first of which is `self`
+    pub fn parameters(&self) -> impl Iterator<Item = Param> {
+        parameters(&self.0)
+    }
+
+    /// The result type, if there is one
+    pub fn result(&self) -> Option<Type> {
+        ty(&self.0)
+    }
+}
+
+impl ImplDecl {
+    /// The names making up the path to the trait
+    pub fn trait_path(&self) -> impl Iterator<Item = SyntaxToken> {
+        self.0
+            .child_tokens()
+            .into_iter()
+            .take_while(|token| token.text() != "for")
+            .filter(|token| token.kind() == SyntaxKind::Identifier)
+    }
+
+    /// The type implementing the trait
+    pub fn ty(&self) -> Option<Type> {
+        ty(&self.0)
+    }
+
+    pub fn methods(&self) -> impl Iterator<Item = FunctionDecl> {
+        children(&self.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Binary(BinaryExpr),
+    Paren(ParenExpr),
+    Number(NumberExpr),
+    Literal(LiteralExpr),
+    Identifier(IdentifierExpr),
+    Prefix(PrefixExpr),
+    Postfix(PostfixExpr),
+    Cast(CastExpr),
+    Call(CallExpr),
+    Field(FieldExpr),
+    MethodCall(MethodCallExpr),
+    StructLit(StructLit),
+    Array(ArrayExpr),
+    ArrayRepeat(ArrayRepeatExpr),
+    Index(IndexExpr),
+    Slice(SliceExpr),
+    Path(PathExpr),
+    Match(MatchExpr),
+    Closure(ClosureExpr),
+    Comptime(ComptimeExpr),
+}
+
+impl AstNode for Expr {
+    fn cast(node: SyntaxNode) -> Option<Self> {
+        let expr = match node.kind() {
+            SyntaxKind::BinaryExpr => Expr::Binary(BinaryExpr(node)),
+            SyntaxKind::ParenExpr => Expr::Paren(ParenExpr(node)),
+            SyntaxKind::NumberExpr => Expr::Number(NumberExpr(node)),
+            SyntaxKind::LiteralExpr => Expr::Literal(LiteralExpr(node)),
+            SyntaxKind::IdentifierExpr => Expr::Identifier(IdentifierExpr(node)),
+            SyntaxKind::PrefixExpr => Expr::Prefix(PrefixExpr(node)),
+            SyntaxKind::PostfixExpr => Expr::Postfix(PostfixExpr(node)),
+            SyntaxKind::CastExpr => Expr::Cast(CastExpr(node)),
+            SyntaxKind::CallExpr => Expr::Call(CallExpr(node)),
+            SyntaxKind::FieldExpr => Expr::Field(FieldExpr(node)),
+            SyntaxKind::MethodCallExpr => Expr::MethodCall(MethodCallExpr(node)),
+            SyntaxKind::StructLit => Expr::StructLit(StructLit(node)),
+            SyntaxKind::ArrayExpr => Expr::Array(ArrayExpr(node)),
+            SyntaxKind::ArrayRepeatExpr => Expr::ArrayRepeat(ArrayRepeatExpr(node)),
+            SyntaxKind::IndexExpr => Expr::Index(IndexExpr(node)),
+            SyntaxKind::SliceExpr => Expr::Slice(SliceExpr(node)),
+            SyntaxKind::PathExpr => Expr::Path(PathExpr(node)),
+            SyntaxKind::MatchExpr => Expr::Match(MatchExpr(node)),
+            SyntaxKind::ClosureExpr => Expr::Closure(ClosureExpr(node)),
+            SyntaxKind::ComptimeExpr => Expr::Comptime(ComptimeExpr(node)),
+            _ => return None,
+        };
+
+        Some(expr)
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Expr::Binary(expr) => expr.syntax(),
+            Expr::Paren(expr) => expr.syntax(),
+            Expr::Number(expr) => expr.syntax(),
+            Expr::Literal(expr) => expr.syntax(),
+            Expr::Identifier(expr) => expr.syntax(),
+            Expr::Prefix(expr) => expr.syntax(),
+            Expr::Postfix(expr) => expr.syntax(),
+            Expr::Cast(expr) => expr.syntax(),
+            Expr::Call(expr) => expr.syntax(),
+            Expr::Field(expr) => expr.syntax(),
+            Expr::MethodCall(expr) => expr.syntax(),
+            Expr::StructLit(expr) => expr.syntax(),
+            Expr::Array(expr) => expr.syntax(),
+            Expr::ArrayRepeat(expr) => expr.syntax(),
+            Expr::Index(expr) => expr.syntax(),
+            Expr::Slice(expr) => expr.syntax(),
+            Expr::Path(expr) => expr.syntax(),
+            Expr::Match(expr) => expr.syntax(),
+            Expr::Closure(expr) => expr.syntax(),
+            Expr::Comptime(expr) => expr.syntax(),
+        }
+    }
+}
+
+impl BinaryExpr {
+    pub fn lhs(&self) -> Option<Expr> {
+        expressions(&self.0).next()
+    }
+
+    pub fn operator(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Punct)
+    }
+
+    pub fn rhs(&self) -> Option<Expr> {
+        expressions(&self.0).nth(1)
+    }
+}
+
+impl ParenExpr {
+    pub fn inner(&self) -> Option<Expr> {
+        expressions(&self.0).next()
+    }
+}
+
+impl NumberExpr {
+    pub fn literal(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Number)
+    }
+}
+
+impl IdentifierExpr {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Identifier)
+    }
+}
+
+impl LiteralExpr {
+    /// The `true`, `false`, string or character token
+    pub fn literal(&self) -> Option<SyntaxToken> {
+        self.0
+            .child_tokens()
+            .into_iter()
+            .find(|token| !token.kind().is_trivia())
+    }
+}
+
+impl PrefixExpr {
+    pub fn operator(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Punct)
+    }
+
+    pub fn operand(&self) -> Option<Expr> {
+        expressions(&self.0).next()
+    }
+}
+
+impl PostfixExpr {
+    pub fn operand(&self) -> Option<Expr> {
+        expressions(&self.0).next()
+    }
+
+    pub fn operator(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Punct)
+    }
+}
+
+impl CastExpr {
+    pub fn expression(&self) -> Option<Expr> {
+        expressions(&self.0).next()
+    }
+
+    pub fn ty(&self) -> Option<Type> {
+        ty(&self.0)
+    }
+}
+
+impl CallExpr {
+    pub fn callee(&self) -> Option<Expr> {
+        expressions(&self.0).next()
+    }
+
+    pub fn arguments(&self) -> impl Iterator<Item = Expr> {
+        let list = self
+            .0
+            .children()
+            .into_iter()
+            .find(|node| node.kind() == SyntaxKind::ArgList);
+
+        list.into_iter().flat_map(|list| expressions(&list))
+    }
+}
+
+impl FieldExpr {
+    pub fn expression(&self) -> Option<Expr> {
+        expressions(&self.0).next()
+    }
+
+    pub fn field(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Identifier)
+    }
+}
+
+impl MethodCallExpr {
+    pub fn receiver(&self) -> Option<Expr> {
+        expressions(&self.0).next()
+    }
+
+    pub fn method(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Identifier)
+    }
+
+    pub fn arguments(&self) -> impl Iterator<Item = Expr> {
+        let list = self
+            .0
+            .children()
+            .into_iter()
+            .find(|node| node.kind() == SyntaxKind::ArgList);
+
+        list.into_iter().flat_map(|list| expressions(&list))
+    }
+}
+
+impl StructLit {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Identifier)
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = FieldInit> {
+        children(&self.0)
+    }
+}
+
+impl FieldInit {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Identifier)
+    }
+
+    pub fn value(&self) -> Option<Expr> {
+        expressions(&self.0).next()
+    }
+}
+
+impl ArrayExpr {
+    pub fn elements(&self) -> impl Iterator<Item = Expr> {
+        expressions(&self.0)
+    }
+}
+
+impl ArrayRepeatExpr {
+    pub fn value(&self) -> Option<Expr> {
+        expressions(&self.0).next()
+    }
+
+    pub fn length(&self) -> Option<Expr> {
+        expressions(&self.0).nth(1)
+    }
+}
+
+impl IndexExpr {
+    pub fn expression(&self) -> Option<Expr> {
+        expressions(&self.0).next()
+    }
+
+    pub fn index(&self) -> Option<Expr> {
+        expressions(&self.0).nth(1)
+    }
+}
+
+impl SliceExpr {
+    pub fn expression(&self) -> Option<Expr> {
+        expressions(&self.0).next()
+    }
+
+    /// The bound before the `..`, if there is one
+    pub fn start(&self) -> Option<Expr> {
+        let range = self.range()?.span();
+
+        expressions(&self.0)
+            .skip(1)
+            .find(|expr| expr.syntax().span().end <= range.start)
+    }
+
+    /// The bound after the `..`, if there is one
+    pub fn end(&self) -> Option<Expr> {
+        let range = self.range()?.span();
+
+        expressions(&self.0)
+            .skip(1)
+            .find(|expr| expr.syntax().span().start >= range.end)
+    }
+
+    /// The `..` token
+    pub fn range(&self) -> Option<SyntaxToken> {
+        self.0
+            .child_tokens()
+            .into_iter()
+            .find(|token| token.text() == "..")
+    }
+}
+
+impl PathExpr {
+    /// The names making up the path, `Shape` and `Circle` in
+    /// `Shape::Circle`
+    pub fn segments(&self) -> impl Iterator<Item = SyntaxToken> {
+        self.0
+            .child_tokens()
+            .into_iter()
+            .filter(|token| token.kind() == SyntaxKind::Identifier)
+    }
+}
+
+impl MatchExpr {
+    pub fn scrutinee(&self) -> Option<Expr> {
+        expressions(&self.0).next()
+    }
+
+    pub fn arms(&self) -> impl Iterator<Item = MatchArm> {
+        children(&self.0)
+    }
+}
+
+impl MatchArm {
+    pub fn pattern(&self) -> Option<Pattern> {
+        children(&self.0).next()
+    }
+
+    /// The value of the arm, unless it is a block
+    pub fn value(&self) -> Option<Expr> {
+        expressions(&self.0).next()
+    }
+
+    /// The block of the arm, unless its value is an expression
+    pub fn block(&self) -> Option<Block> {
+        children(&self.0).next()
+    }
+}
+
+impl ClosureExpr {
+    pub fn parameters(&self) -> impl Iterator<Item = ClosureParam> {
+        children(&self.0)
+    }
+
+    /// The type after the `->`, if the result is annotated
+    pub fn result(&self) -> Option<Type> {
+        ty(&self.0)
+    }
+
+    /// The body, unless it is a block
+    pub fn value(&self) -> Option<Expr> {
+        expressions(&self.0).next()
+    }
+
+    /// The body, unless it is an expression
+    pub fn block(&self) -> Option<Block> {
+        children(&self.0).next()
+    }
+}
+
+impl ClosureParam {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Identifier)
+    }
+
+    pub fn ty(&self) -> Option<Type> {
+        ty(&self.0)
+    }
+}
+
+impl ComptimeExpr {
+    /// What is evaluated, written `$(value)`
+    pub fn value(&self) -> Option<Expr> {
+        expressions(&self.0).next()
+    }
+
+    /// What is run, written `${ ... }`
+    pub fn block(&self) -> Option<Block> {
+        children(&self.0).next()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    Wildcard(WildcardPat),
+    Binding(BindingPat),
+    Literal(LiteralPat),
+    Variant(VariantPat),
+}
+
+impl AstNode for Pattern {
+    fn cast(node: SyntaxNode) -> Option<Self> {
+        let pattern = match node.kind() {
+            SyntaxKind::WildcardPat => Pattern::Wildcard(WildcardPat(node)),
+            SyntaxKind::BindingPat => Pattern::Binding(BindingPat(node)),
+            SyntaxKind::LiteralPat => Pattern::Literal(LiteralPat(node)),
+            SyntaxKind::VariantPat => Pattern::Variant(VariantPat(node)),
+            _ => return None,
+        };
+
+        Some(pattern)
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Pattern::Wildcard(pattern) => pattern.syntax(),
+            Pattern::Binding(pattern) => pattern.syntax(),
+            Pattern::Literal(pattern) => pattern.syntax(),
+            Pattern::Variant(pattern) => pattern.syntax(),
+        }
+    }
+}
+
+impl BindingPat {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Identifier)
+    }
+}
+
+impl LiteralPat {
+    /// The `-` in front of a negative number
+    pub fn minus(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Punct)
+    }
+
+    /// The number, `true`, `false` or character token
+    pub fn literal(&self) -> Option<SyntaxToken> {
+        self.0
+            .child_tokens()
+            .into_iter()
+            .find(|token| !token.kind().is_trivia() && token.kind() != SyntaxKind::Punct)
+    }
+}
+
+impl VariantPat {
+    /// The names making up the path of the variant
+    pub fn segments(&self) -> impl Iterator<Item = SyntaxToken> {
+        self.0
+            .child_tokens()
+            .into_iter()
+            .filter(|token| token.kind() == SyntaxKind::Identifier)
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = Pattern> {
+        children(&self.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Type {
+    Named(NamedType),
+    Reference(ReferenceType),
+    Array(ArrayType),
+    Slice(SliceType),
+    Tuple(TupleType),
+    Paren(ParenType),
+    Function(FunctionType),
+}
+
+impl AstNode for Type {
+    fn cast(node: SyntaxNode) -> Option<Self> {
+        let ty = match node.kind() {
+            SyntaxKind::NamedType => Type::Named(NamedType(node)),
+            SyntaxKind::ReferenceType => Type::Reference(ReferenceType(node)),
+            SyntaxKind::ArrayType => Type::Array(ArrayType(node)),
+            SyntaxKind::SliceType => Type::Slice(SliceType(node)),
+            SyntaxKind::TupleType => Type::Tuple(TupleType(node)),
+            SyntaxKind::ParenType => Type::Paren(ParenType(node)),
+            SyntaxKind::FunctionType => Type::Function(FunctionType(node)),
+            _ => return None,
+        };
+
+        Some(ty)
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Type::Named(ty) => ty.syntax(),
+            Type::Reference(ty) => ty.syntax(),
+            Type::Array(ty) => ty.syntax(),
+            Type::Slice(ty) => ty.syntax(),
+            Type::Tuple(ty) => ty.syntax(),
+            Type::Paren(ty) => ty.syntax(),
+            Type::Function(ty) => ty.syntax(),
+        }
+    }
+}
+
+impl NamedType {
+    /// The names making up the path, `a`, `b` and `C` in `a::b::C`
+    pub fn segments(&self) -> impl Iterator<Item = SyntaxToken> {
+        self.0
+            .child_tokens()
+            .into_iter()
+            .filter(|token| token.kind() == SyntaxKind::Identifier)
+    }
+
+    pub fn arguments(&self) -> impl Iterator<Item = Type> {
+        let arguments = self
+            .0
+            .children()
+            .into_iter()
+            .find(|node| node.kind() == SyntaxKind::GenericArgs);
+
+        arguments
+            .into_iter()
+            .flat_map(|arguments| types(&arguments))
+    }
+}
+
+impl ReferenceType {
+    /// The `mut` of a reference that can be written through
+    pub fn mutability(&self) -> Option<SyntaxToken> {
+        self.0
+            .child_tokens()
+            .into_iter()
+            .find(|token| token.text() == "mut")
+    }
+
+    /// The type referred to
+    pub fn inner(&self) -> Option<Type> {
+        ty(&self.0)
+    }
+}
+
+impl ArrayType {
+    pub fn element(&self) -> Option<Type> {
+        ty(&self.0)
+    }
+
+    pub fn length(&self) -> Option<Expr> {
+        expressions(&self.0).next()
+    }
+}
+
+impl SliceType {
+    pub fn element(&self) -> Option<Type> {
+        ty(&self.0)
+    }
+}
+
+impl TupleType {
+    pub fn elements(&self) -> impl Iterator<Item = Type> {
+        types(&self.0)
+    }
+}
+
+impl ParenType {
+    pub fn inner(&self) -> Option<Type> {
+        ty(&self.0)
+    }
+}
+
+impl FunctionType {
+    /// The parameter types, followed by the result type if there is one
+    pub fn types(&self) -> impl Iterator<Item = Type> {
+        types(&self.0)
+    }
+
+    /// The `->` token, present if the function type has a result
+    pub fn arrow(&self) -> Option<SyntaxToken> {
+        self.0
+            .child_tokens()
+            .into_iter()
+            .find(|token| token.text() == "->")
+    }
+}
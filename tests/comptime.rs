@@ -0,0 +1,345 @@
+use orion::error::OrionError;
+use orion::interp::comptime::{self, Config, Setting};
+use orion::interp::{stack_size, Interpreter, Overflow};
+use orion::module::{self, Module};
+use orion::parser::printer::print_program;
+use orion::sema;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Compile a program from files held in memory, the first being the entry
+/// file, returning the modules if it is valid
+fn compile(files: &[(&str, &str)], config: &Config) -> Result<Vec<Module>, Vec<String>> {
+    let messages = |errors: Vec<OrionError>| errors.iter().map(OrionError::message).collect();
+
+    let sources: HashMap<PathBuf, String> = files
+        .iter()
+        .map(|(file, source)| (PathBuf::from(file), source.to_string()))
+        .collect();
+
+    let entry = Path::new(files[0].0);
+    let mut modules = module::load(entry, sources[entry].clone(), config, |file| {
+        sources
+            .get(file)
+            .cloned()
+            .ok_or_else(|| io::ErrorKind::NotFound.into())
+    })
+    .map_err(messages)?;
+
+    let mut errors = module::resolve(&mut modules);
+
+    if errors.is_empty() {
+        errors = comptime::evaluate(&mut modules, config);
+    }
+
+    if errors.is_empty() {
+        errors = sema::check_modules(&modules);
+    }
+
+    match errors.is_empty() {
+        true => Ok(modules),
+        false => Err(messages(errors)),
+    }
+}
+
+fn run(files: &[(&str, &str)], config: &Config) -> Result<String, String> {
+    let modules = compile(files, config).map_err(|errors| errors.join("\n"))?;
+
+    match Interpreter::default().run_modules(&modules) {
+        Ok(value) => Ok(value.to_string()),
+        Err(error) => Err(error.message()),
+    }
+}
+
+/// The root module of a program in a single file, printed once compiled
+fn compiled(src: &str) -> String {
+    let modules = compile(&[("main.ori", src)], &Config::default()).unwrap();
+    print_program(&modules[0].program)
+}
+
+fn errors(src: &str, config: &Config) -> Vec<String> {
+    compile(&[("main.ori", src)], config).unwrap_err()
+}
+
+#[test]
+fn example_runs() {
+    let src = std::fs::read_to_string("examples/comptime.ori").unwrap();
+    let files = [("comptime.ori", src.as_str())];
+
+    let mut windows = Config::default();
+    windows.set("target_os=windows");
+
+    let expected = match std::env::consts::OS {
+        "windows" => "4",
+        _ => "124",
+    };
+
+    assert_eq!(run(&files, &Config::default()), Ok(expected.to_string()));
+    assert_eq!(run(&files, &windows), Ok("4".to_string()));
+}
+
+#[test]
+fn values_become_literals() {
+    let src = "enum Shape { Circle(f64), Empty }
+struct Point { x : i32, y : i32 }
+func point(x : i32) : Point = { return Point { x: x, y: 0 - x }; }
+let P := $(point(2));
+let S := $([Shape::Circle(1.5), Shape::Empty]);
+let T := ${ let t := \"text\"; return t; };
+let C := $((P.x as u8 + 97) as char);
+func main() : bool = { return $(P.y < 0) == $(T == \"text\"); }";
+
+    let expected = "enum Shape {
+    Circle(f64),
+    Empty,
+}
+struct Point {
+    x : i32,
+    y : i32,
+}
+func point(x : i32) : Point = {
+    return Point { x: x, y: 0 - x };
+}
+let P := Point { x: 2, y: -2 };
+let S := [Shape::Circle(1.5), Shape::Empty];
+let T := \"text\";
+let C := 'c';
+func main() : bool = {
+    return true == true;
+}
+";
+
+    assert_eq!(compiled(src), expected);
+}
+
+#[test]
+fn ifs_take_a_branch() {
+    let src = "$if size == \"small\" {
+    func limit() : u32 = { return 1; }
+} else $if size == \"large\" {
+    func limit() : u32 = { return 1000; }
+} else {
+    func limit() : u32 = { return 10; }
+}
+func main() : u32 = {
+    $if debug { return 0; }
+    return limit();
+}";
+
+    let files = [("main.ori", src)];
+
+    for (settings, expected) in [
+        (vec!["size=small", "debug=false"], "1"),
+        (vec!["size=large", "debug=false"], "1000"),
+        (vec!["size=medium", "debug=false"], "10"),
+        (vec!["size=large", "debug"], "0"),
+    ] {
+        let mut config = Config::default();
+
+        for setting in &settings {
+            config.set(setting);
+        }
+
+        assert_eq!(
+            run(&files, &config),
+            Ok(expected.to_string()),
+            "{settings:?}"
+        );
+    }
+
+    // A setting that isn't given is an error, rather than a misspelled one
+    // going unnoticed
+    assert_eq!(
+        errors(src, &Config::default()),
+        [
+            "main.ori: `size` is not defined at L1:C5, while evaluating at compile time from L1:C1.",
+            "main.ori: `debug` is not defined at L9:C9, while evaluating at compile time from L9:C5.",
+        ]
+    );
+}
+
+#[test]
+fn ifs_declare_modules() {
+    let files = [
+        (
+            "main.ori",
+            "$if extra { mod extra; }
+func main() : u32 = {
+    $if extra { return extra::value(); } else { return 0; }
+}",
+        ),
+        ("extra.ori", "pub func value() : u32 = { return 7; }"),
+    ];
+
+    let mut config = Config::default();
+    config.set("extra");
+    assert_eq!(run(&files, &config), Ok("7".to_string()));
+
+    // The module isn't even loaded without the setting
+    config.set("extra=false");
+    assert_eq!(run(&files[..1], &config), Ok("0".to_string()));
+}
+
+#[test]
+fn constants_across_modules() {
+    let files = [
+        (
+            "main.ori",
+            "mod math;
+let AREA := $(math::square(math::side()));
+func main() : u32 = { return AREA; }",
+        ),
+        (
+            "math.ori",
+            "let SIDE := $(3);
+pub func square(x : u32) : u32 = { return x * x; }
+pub func side() : u32 = { return $(SIDE + 1); }",
+        ),
+    ];
+
+    assert_eq!(run(&files, &Config::default()), Ok("16".to_string()));
+}
+
+#[test]
+fn errors_point_into_the_evaluated_code() {
+    let cases = [
+        (
+            "func get(i : usize) : u8 = { let a : [u8; 2] = [1, 2]; return a[i]; }
+let A := $(get(5));",
+            "main.ori: index 5 is out of bounds for a length of 2 at L1:C63, while evaluating at compile time from L2:C10.",
+        ),
+        (
+            "let A := $(1 / 0);",
+            "main.ori: division by zero at L1:C12, while evaluating at compile time from L1:C10.",
+        ),
+        // Only the top-level variables initialized with `$` are known, in
+        // the order they are declared
+        (
+            "let A := $(B); let B := $(1);",
+            "main.ori: `B` is not defined at L1:C12, while evaluating at compile time from L1:C10.",
+        ),
+        (
+            "func main() : u8 = { let x : u8 = 1; return $(x + 1); }",
+            "main.ori: `x` is not defined at L1:C47, while evaluating at compile time from L1:C45.",
+        ),
+        (
+            "let A := $([0; 1 << 40]);",
+            "main.ori: an array of 1099511627776 elements is too long at L1:C12, arrays can have at most 1048576, while evaluating at compile time from L1:C10.",
+        ),
+        (
+            "let R := $(&[1]);",
+            "main.ori: a `reference` value can't be the result of compile-time evaluation at L1:C10, only numbers, bools, characters, strings, arrays, structs and enums can.",
+        ),
+        (
+            "let U := ${ let a := 1; };",
+            "main.ori: a `()` value can't be the result of compile-time evaluation at L1:C10, only numbers, bools, characters, strings, arrays, structs and enums can.",
+        ),
+        (
+            "$if target_os {}",
+            "main.ori: expected `bool`, found `&str` at L1:C5.",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        assert_eq!(errors(src, &Config::default()), [expected], "{src}");
+    }
+}
+
+#[test]
+fn evaluation_is_limited() {
+    let src = "func spin(n : u32) : u32 = {
+    if n == 0 { return 0; }
+    return spin(n - 1) + spin(n - 1);
+}
+let A := $(spin(4));
+let B := $(spin(20));";
+
+    let config = Config {
+        max_steps: 1000,
+        ..Config::default()
+    };
+
+    assert_eq!(
+        errors(src, &config),
+        ["main.ori: evaluation took more than 1000 steps at L3:C17, while evaluating at compile time from L6:C10."]
+    );
+}
+
+#[test]
+fn calls_nest_as_deeply_as_allowed() {
+    let src = "func sum(n : u32) : u32 = {
+    if n == 0 { return 0; }
+    return n + sum(n - 1);
+}
+let A := $(sum(150));";
+
+    // Like the compiler, evaluate on a thread with the stack the calls need
+    let compile = |config: Config| {
+        std::thread::Builder::new()
+            .stack_size(stack_size(config.max_depth))
+            .spawn(move || {
+                compile(&[("main.ori", src)], &config)
+                    .map(|modules| print_program(&modules[0].program))
+            })
+            .unwrap()
+            .join()
+            .unwrap()
+    };
+
+    let printed = compile(Config::default()).unwrap();
+    assert!(printed.ends_with("let A := 11325;\n"), "{printed}");
+
+    let shallow = Config {
+        max_depth: 100,
+        ..Config::default()
+    };
+
+    assert_eq!(
+        compile(shallow).unwrap_err(),
+        ["main.ori: calls nested too deeply at L3:C16, while evaluating at compile time from L5:C10."]
+    );
+}
+
+#[test]
+fn evaluation_follows_the_overflow_mode() {
+    let src = "func double(x : u8) : u8 = { return x * 2; }
+let A := $(double(200));";
+
+    let checked = Config {
+        overflow: Overflow::Checked,
+        ..Config::default()
+    };
+
+    assert_eq!(
+        errors(src, &checked),
+        ["main.ori: `200 * 2` overflows `u8` at L1:C37, while evaluating at compile time from L2:C10."]
+    );
+
+    let wrapping = Config {
+        overflow: Overflow::Wrapping,
+        ..Config::default()
+    };
+
+    let modules = compile(&[("main.ori", src)], &wrapping).unwrap();
+    assert!(print_program(&modules[0].program).ends_with("let A := 144;\n"));
+}
+
+#[test]
+fn settings() {
+    let mut config = Config::default();
+    config.set("mode=fast");
+    config.set("debug");
+    config.set("debug=false");
+
+    let settings = &config.settings;
+    assert_eq!(settings[0].0, "target_os");
+    assert_eq!(settings[1].0, "target_arch");
+    assert_eq!(
+        settings[2..],
+        [
+            ("mode".to_string(), Setting::Text("fast".to_string())),
+            ("debug".to_string(), Setting::Flag(false)),
+        ]
+    );
+}
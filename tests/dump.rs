@@ -0,0 +1,104 @@
+//! Tests for the AST dumps `--emit ast` prints: the indented tree with
+//! spans and the JSON for tools. The S-expressions are tested by the
+//! round-trip tests, which compare programs through them.
+use orion::lexer::state::Lexer;
+use orion::parser::ast::*;
+use orion::parser::dump::{program_node, to_json, to_tree};
+use orion::parser::state::Parser;
+
+fn parse(src: &str) -> Program {
+    Parser::new(Lexer::new(src)).parse().unwrap()
+}
+
+fn tree(src: &str) -> String {
+    to_tree(&program_node(&parse(src)))
+}
+
+fn json(src: &str) -> String {
+    to_json(&program_node(&parse(src)))
+}
+
+#[test]
+fn trees_have_spans_and_labelled_children() {
+    assert_eq!(
+        tree("let variable := 12379;"),
+        "\
+program
+  let @ 0..22 name=variable mutable=false
+    initial: number @ 16..21 value=12379
+"
+    );
+
+    // The elements of a list aren't labelled, the nodes they are in are
+    assert_eq!(
+        tree("func add(a : u8, b : u8) : u8 = { return a + -b; }"),
+        "\
+program
+  function @ 0..50 name=add public=false
+    parameter @ 9..15 name=a
+      type: named @ 13..15 path=u8
+    parameter @ 17..23 name=b
+      type: named @ 21..23 path=u8
+    result: named @ 27..29 path=u8
+    body: block @ 32..50
+      return @ 34..48
+        value: binary @ 41..47 op=+
+          lhs: identifier @ 41..42 name=a
+          rhs: prefix @ 45..47 op=-
+            operand: identifier @ 46..47 name=b
+"
+    );
+
+    // Comptime expressions are dumped as written and strings are quoted
+    assert_eq!(
+        tree(r#"let size : u32 = $(4 * 1024); let name := "a\n\"b\"";"#),
+        r#"program
+  let @ 0..29 name=size mutable=false
+    type: named @ 11..14 path=u32
+    initial: comptime @ 17..28
+      value: binary @ 19..27 op=*
+        lhs: number @ 19..20 value=4
+        rhs: number @ 23..27 value=1024
+  let @ 30..53 name=name mutable=false
+    initial: string @ 42..52 value="a\n\"b\""
+"#
+    );
+}
+
+#[test]
+fn json_has_kinds_spans_and_named_children() {
+    assert_eq!(
+        json("let variable := 12379;"),
+        r#"{"kind":"program","statements":[{"kind":"let","span":{"start":0,"end":22},"name":"variable","mutable":false,"initial":{"kind":"number","span":{"start":16,"end":21},"value":12379}}]}"#
+    );
+
+    // Lists are arrays, empty ones too
+    assert_eq!(
+        json("func add(a : u8, b : u8) : u8 = { return a + -b; }"),
+        r#"{"kind":"program","statements":[{"kind":"function","span":{"start":0,"end":50},"name":"add","public":false,"generics":[],"parameters":[{"kind":"parameter","span":{"start":9,"end":15},"name":"a","type":{"kind":"named","span":{"start":13,"end":15},"path":"u8","arguments":[]}},{"kind":"parameter","span":{"start":17,"end":23},"name":"b","type":{"kind":"named","span":{"start":21,"end":23},"path":"u8","arguments":[]}}],"result":{"kind":"named","span":{"start":27,"end":29},"path":"u8","arguments":[]},"body":{"kind":"block","span":{"start":32,"end":50},"statements":[{"kind":"return","span":{"start":34,"end":48},"value":{"kind":"binary","span":{"start":41,"end":47},"op":"+","lhs":{"kind":"identifier","span":{"start":41,"end":42},"name":"a"},"rhs":{"kind":"prefix","span":{"start":45,"end":47},"op":"-","operand":{"kind":"identifier","span":{"start":46,"end":47},"name":"b"}}}}]}}]}"#
+    );
+
+    // Strings are escaped
+    assert_eq!(
+        json(r#"let name := "a\n\"b\"\\";"#),
+        r#"{"kind":"program","statements":[{"kind":"let","span":{"start":0,"end":25},"name":"name","mutable":false,"initial":{"kind":"string","span":{"start":12,"end":24},"value":"a\n\"b\"\\"}}]}"#
+    );
+}
+
+#[test]
+fn json_has_no_infinite_numbers() {
+    let mut program = parse("let x := 1.5;");
+
+    if let StatementKind::Let {
+        initial: Some(initial),
+        ..
+    } = &mut program[0].kind
+    {
+        initial.kind = ExpressionKind::Number(Number::Float(f64::INFINITY));
+    }
+
+    assert_eq!(
+        to_json(&program_node(&program)),
+        r#"{"kind":"program","statements":[{"kind":"let","span":{"start":0,"end":13},"name":"x","mutable":false,"initial":{"kind":"number","span":{"start":9,"end":12},"value":null}}]}"#
+    );
+}
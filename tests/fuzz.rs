@@ -0,0 +1,127 @@
+//! Grammar-based fuzzing of the lexer and parser: whatever the input, they
+//! must not panic and must not loop forever, and generated programs, which
+//! are valid by construction, must parse.
+use orion::fuzz::Generator;
+use orion::lexer::state::Lexer;
+use orion::lexer::tokens::TokenKind;
+use orion::parser::state::Parser;
+use orion::syntax;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// Run `check` on `input` in its own thread, failing if it panics or takes
+/// too long, which is taken as a sign of an endless loop.
+fn run(input: String, check: fn(&str)) {
+    let (sender, receiver) = mpsc::channel();
+    let source = input.clone();
+
+    thread::spawn(move || {
+        check(&source);
+        let _ = sender.send(());
+    });
+
+    match receiver.recv_timeout(Duration::from_secs(10)) {
+        Ok(()) => {}
+        Err(mpsc::RecvTimeoutError::Timeout) => panic!("timed out on {input:?}"),
+        Err(mpsc::RecvTimeoutError::Disconnected) => panic!("panicked on {input:?}"),
+    }
+}
+
+/// The lexer reaches the end of any input, making progress with every token
+fn lex(input: &str) {
+    let mut lexer = Lexer::new(input);
+    let mut last_end = None;
+
+    loop {
+        let token = lexer.next_token();
+
+        if token.kind == TokenKind::Eof {
+            break;
+        }
+
+        assert!(Some(token.span.end) > last_end, "no progress at {token:?}");
+        last_end = Some(token.span.end);
+    }
+}
+
+/// Parsing any input terminates, and the syntax tree holds all of it
+fn parse(input: &str) {
+    let _ = Parser::new(Lexer::new(input)).parse();
+
+    let parse = syntax::parse(input);
+    assert_eq!(parse.root.text(), input);
+}
+
+/// Generated programs parse without errors
+fn parse_valid(input: &str) {
+    if let Err(error) = Parser::new(Lexer::new(input)).parse() {
+        panic!("{error}");
+    }
+
+    let parse = syntax::parse(input);
+    assert!(parse.errors.is_empty());
+    assert_eq!(parse.root.text(), input);
+}
+
+#[test]
+fn valid_programs_parse() {
+    let mut generator = Generator::new(0x5eed);
+
+    for _ in 0..1000 {
+        let program = generator.program();
+        run(program.clone(), lex);
+        run(program, parse_valid);
+    }
+}
+
+#[test]
+fn corrupted_programs_dont_panic() {
+    let mut generator = Generator::new(0xbad_5eed);
+
+    for _ in 0..2000 {
+        let program = generator.corrupted_program();
+        run(program.clone(), lex);
+        run(program, parse);
+    }
+}
+
+#[test]
+fn known_problem_inputs() {
+    let inputs = [
+        "let a = 1.2.3;",
+        "let a = 1..2;",
+        "let a = @;",
+        "@@@",
+        "let a = \"unterminated",
+        "/* unclosed",
+        "let",
+        "let a",
+        "let a =",
+        "let a = (",
+        ")))",
+        "\u{a0}let a = 1;",
+        "let \0 = 1;",
+        "func f(",
+        "func f() : = {",
+        "func f() = { let a = 1; ",
+        "}}}",
+        "let a : Pair<u8, Pair<u8, u8>>= b;",
+        "let a : Pair<u8 = b;",
+        "let a : [u8; = b;",
+        "if a { } else",
+        "f(a, b",
+    ];
+
+    for input in inputs {
+        run(input.to_string(), lex);
+        run(input.to_string(), parse);
+    }
+
+    // Nesting deep enough to overflow the stack without a limit
+    let deep = format!("let a = {}1{};", "(".repeat(100_000), ")".repeat(100_000));
+    run(deep, parse);
+    run(format!("func f() = {}", "{".repeat(100_000)), parse);
+    run(format!("let a : {}u8 = b;", "&".repeat(100_000)), parse);
+    run(format!("let a : {}u8 = b;", "[(".repeat(100_000)), parse);
+}
@@ -0,0 +1,607 @@
+use orion::error::OrionError;
+use orion::interp::{stack_size, Interpreter, Overflow, MAX_CALL_DEPTH};
+use orion::lexer::state::Lexer;
+use orion::parser::state::Parser;
+
+/// Run a program with overflow checked, returning what `main` returned as
+/// it would be printed
+fn run(src: &str) -> Result<String, OrionError> {
+    run_with(src, Overflow::Checked)
+}
+
+/// Run a program with integer overflow doing what `overflow` says, on a
+/// thread with the stack calls nesting as deeply as they may need
+fn run_with(src: &str, overflow: Overflow) -> Result<String, OrionError> {
+    let src = src.to_string();
+
+    let run = move || {
+        let program = Parser::new(Lexer::new(&src)).parse().unwrap();
+        let value = Interpreter::new(&src)
+            .with_overflow(overflow)
+            .run(&program)?;
+
+        Ok(value.to_string())
+    };
+
+    std::thread::Builder::new()
+        .stack_size(stack_size(MAX_CALL_DEPTH))
+        .spawn(run)
+        .unwrap()
+        .join()
+        .unwrap()
+}
+
+fn main_returning(body: &str) -> String {
+    format!("func main() : = {{\n{body}\n}}")
+}
+
+#[test]
+fn examples_run() {
+    let cases = [
+        ("examples/arrays.ori", "47"),
+        ("examples/structs.ori", "()"),
+        ("examples/enums.ori", "27"),
+        ("examples/generics.ori", "40"),
+        ("examples/traits.ori", "25"),
+        ("examples/closures.ori", "30"),
+        ("examples/main.ori", "14"),
+        ("examples/functions.ori", "4"),
+    ];
+
+    for (path, expected) in cases {
+        let src = std::fs::read_to_string(path).unwrap();
+        assert_eq!(run(&src).unwrap(), expected, "{path}");
+    }
+}
+
+#[test]
+fn arrays_and_slices() {
+    let cases = [
+        ("return [1, 2, 3];", "[1, 2, 3]"),
+        ("return [true; 3];", "[true, true, true]"),
+        ("return [];", "[]"),
+        ("let a := [1, 2, 3]; return a[2];", "3"),
+        (
+            "let mut a := [[1, 2], [3, 4]]; a[1][0] = 9; return a;",
+            "[[1, 2], [9, 4]]",
+        ),
+        ("let a := [1, 2, 3, 4]; return a[1..3];", "&[2, 3]"),
+        ("let a := [1, 2, 3, 4]; return a[..];", "&[1, 2, 3, 4]"),
+        (
+            "let a := [1, 2, 3, 4]; let s := a[1..]; return s[1..][0];",
+            "3",
+        ),
+        (
+            "let mut a := [1, 2, 3]; let r := &mut a; r[0] = 7; return a;",
+            "[7, 2, 3]",
+        ),
+        (
+            "let mut a := [1, 2, 3, 4]; let s := (&mut a)[1..3]; s[0] = 0; return a;",
+            "[1, 0, 3, 4]",
+        ),
+        (
+            "let a := [0; 5]; return len(a) + len(a[2..]) + len(\"abc\");",
+            "11",
+        ),
+        ("let mut a := [1, 2]; a[0]++; return a;", "[2, 2]"),
+    ];
+
+    for (body, expected) in cases {
+        assert_eq!(run(&main_returning(body)).unwrap(), expected, "{body}");
+    }
+}
+
+#[test]
+fn integers_are_exact() {
+    let cases = [
+        ("return 18446744073709551615;", "18446744073709551615"),
+        ("return 9007199254740993 - 1;", "9007199254740992"),
+        ("return 7 / 2;", "3"),
+        ("return -7 % 2;", "-1"),
+        ("return 7.0 / 2;", "3.5"),
+        // A whole number given for a float is one
+        ("let x : f64 = 7; return x / 2;", "3.5"),
+        ("let mut x := 0.5; x = 7; return x / 2;", "3.5"),
+        // And so is an integer widened to a float
+        ("let a : u8 = 7; let x : f32 = a; return x / 2;", "3.5"),
+        ("return 1 == 1.0;", "true"),
+        ("return 300 as u8;", "44"),
+        ("return -1 as u8;", "255"),
+        ("return 255 as i8;", "-1"),
+        ("return 2.9 as u32;", "2"),
+        ("return 7 as f64 / 2;", "3.5"),
+    ];
+
+    for (body, expected) in cases {
+        assert_eq!(run(&main_returning(body)).unwrap(), expected, "{body}");
+    }
+
+    let src = "
+        struct P { x : f64 }
+        func half(x : f32) : f32 = { return x / 2; }
+        func main() : = { return half(3) + P { x: 1 }.x / 2; }
+    ";
+    assert_eq!(run(src).unwrap(), "2");
+}
+
+#[test]
+fn f32_arithmetic_is_rounded() {
+    let cases = [
+        ("let a : f32 = 16777216.0; return a + 1.0;", "16777216"),
+        ("let a : f64 = 16777216.0; return a + 1.0;", "16777217"),
+        (
+            "return 340000000000000000000000000000000000000.0 as f32 * 10.0;",
+            "inf",
+        ),
+        ("return 16777217 as f32;", "16777216"),
+        ("let a : f32 = 0.1; return a;", "0.1"),
+        ("let a : f32 = 0.1; return a as f64;", "0.10000000149011612"),
+        // Arithmetic with an `f64` is done in it
+        (
+            "let a : f32 = 16777216.0; let b : f64 = 1; return a + b;",
+            "16777217",
+        ),
+        (
+            "let mut a : f32 = 16777216.0; a++; a += 1.0; return a;",
+            "16777216",
+        ),
+        ("let mut a : f32 = 0.0; a = 16777217; return a;", "16777216"),
+    ];
+
+    for (body, expected) in cases {
+        assert_eq!(run(&main_returning(body)).unwrap(), expected, "{body}");
+    }
+}
+
+#[test]
+fn bounds_are_checked_at_runtime() {
+    let cases = [
+        (
+            "let a := [1, 2];\nreturn a[2];",
+            "index 2 is out of bounds for a length of 2 at L3:C8.",
+        ),
+        (
+            "let a := [1, 2];\nlet i := 0 - 1;\nreturn a[i];",
+            "index -1 is out of bounds for a length of 2 at L4:C8.",
+        ),
+        (
+            "let a := [1, 2, 3];\nlet s := a[1..];\nreturn s[2];",
+            "index 2 is out of bounds for a length of 2 at L4:C8.",
+        ),
+        (
+            "let a := [1, 2];\nreturn a[1..3];",
+            "slice 1..3 is out of bounds for a length of 2 at L3:C8.",
+        ),
+        (
+            "let a := [1, 2];\nreturn a[2..1];",
+            "slice 2..1 is out of bounds for a length of 2 at L3:C8.",
+        ),
+        (
+            "let mut a := [[1], [2]];\na[0][1] = 3;",
+            "index 1 is out of bounds for a length of 1 at L3:C1.",
+        ),
+        // Indices are exact, however large
+        (
+            "let a := [1, 2];\nreturn a[18446744073709551615];",
+            "index 18446744073709551615 is out of bounds for a length of 2 at L3:C8.",
+        ),
+        (
+            "let a := [1, 2];\nreturn a[0..9007199254740993];",
+            "slice 0..9007199254740993 is out of bounds for a length of 2 at L3:C8.",
+        ),
+    ];
+
+    for (body, expected) in cases {
+        let error = run(&main_returning(body)).unwrap_err();
+        assert_eq!(error.message(), expected, "{body}");
+    }
+}
+
+#[test]
+fn overflow_follows_the_mode() {
+    // What checked, wrapping and saturating overflow give
+    let cases = [
+        (
+            "let a : u8 = 250;\nreturn a + 10;",
+            "`250 + 10` overflows `u8` at L3:C8.",
+            "4",
+            "255",
+        ),
+        (
+            "let a : i8 = -100;\nreturn a - 100;",
+            "`-100 - 100` overflows `i8` at L3:C8.",
+            "56",
+            "-128",
+        ),
+        (
+            "let mut a : u16 = 0;\na--;\nreturn a;",
+            "`0 - 1` overflows `u16` at L3:C1.",
+            "65535",
+            "0",
+        ),
+        (
+            "let a : u64 = 18446744073709551615;\nreturn a * a;",
+            "`18446744073709551615 * 18446744073709551615` overflows `u64` at L3:C8.",
+            "1",
+            "18446744073709551615",
+        ),
+        (
+            "let a : i32 = -2147483648;\nreturn -a;",
+            "`-(-2147483648)` overflows `i32` at L3:C9.",
+            "-2147483648",
+            "2147483647",
+        ),
+        // Shifting wraps the amount around, or clamps the result
+        (
+            "let a : u8 = 1;\nreturn a << 9;",
+            "`1 << 9` overflows `u8` at L3:C8.",
+            "2",
+            "255",
+        ),
+        (
+            "let a : i8 = -100;\nreturn a << 9;",
+            "`-100 << 9` overflows `i8` at L3:C8.",
+            "56",
+            "-128",
+        ),
+        (
+            "let a : i8 = -100;\nreturn a >> 9;",
+            "`-100 >> 9` overflows `i8` at L3:C8.",
+            "-50",
+            "-1",
+        ),
+        (
+            "let a : u8 = 0;\nreturn a << 200;",
+            "`0 << 200` overflows `u8` at L3:C8.",
+            "0",
+            "0",
+        ),
+        // A number given for an integer type is made a value of it
+        (
+            "let b := 200;\nlet c : u8 = b + 100;\nreturn c;",
+            "`300` overflows `u8` at L3:C14.",
+            "44",
+            "255",
+        ),
+    ];
+
+    for (body, checked, wrapping, saturating) in cases {
+        let src = main_returning(body);
+        let error = run_with(&src, Overflow::Checked).unwrap_err();
+        assert_eq!(error.message(), checked, "{body}");
+        assert_eq!(
+            run_with(&src, Overflow::Wrapping).unwrap(),
+            wrapping,
+            "{body}"
+        );
+        assert_eq!(
+            run_with(&src, Overflow::Saturating).unwrap(),
+            saturating,
+            "{body}"
+        );
+    }
+
+    // The builtins say what their overflow does, and arithmetic is done in
+    // the type both operands widen to
+    let cases = [
+        ("let a : u8 = 250; return wrapping_add(a, 10);", "4"),
+        ("let a : u8 = 250; return saturating_add(a, 10);", "255"),
+        (
+            "let a : i16 = -30000; return wrapping_sub(a, 5000);",
+            "30536",
+        ),
+        (
+            "let a : i16 = -30000; return saturating_mul(a, 2);",
+            "-32768",
+        ),
+        ("let a : u8 = 200; let b : u32 = 100; return a + b;", "300"),
+        (
+            "let a : u32 = 4294967295; return wrapping_add(a as u8, 1);",
+            "0",
+        ),
+    ];
+
+    for (body, expected) in cases {
+        let src = main_returning(body);
+        assert_eq!(
+            run_with(&src, Overflow::Checked).unwrap(),
+            expected,
+            "{body}"
+        );
+    }
+
+    // A shift within the width saturates what it moves past the type
+    let cases = [
+        ("let a : u8 = 200; return a << 1;", "255"),
+        ("let a : i16 = -300; return a << 7;", "-32768"),
+        ("let a : i16 = 3; return a << 4;", "48"),
+        ("let a : i64 = -8; return a >> 2;", "-2"),
+    ];
+
+    for (body, expected) in cases {
+        let src = main_returning(body);
+        assert_eq!(
+            run_with(&src, Overflow::Saturating).unwrap(),
+            expected,
+            "{body}"
+        );
+    }
+}
+
+#[test]
+fn calls_nest_as_deeply_as_allowed() {
+    let src = "func sum(n : u64) : u64 = {
+    if n == 0 { return 0; }
+    return n + sum(n - 1);
+}
+func main() : u64 = { return sum(5000); }";
+
+    assert_eq!(run(src).unwrap(), "12502500");
+
+    let shallow = move || {
+        let program = Parser::new(Lexer::new(src)).parse().unwrap();
+        let result = Interpreter::new(src).with_max_depth(100).run(&program);
+        result.map(|value| value.to_string())
+    };
+
+    let error = std::thread::Builder::new()
+        .stack_size(stack_size(100))
+        .spawn(shallow)
+        .unwrap()
+        .join()
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(error.message(), "calls nested too deeply at L3:C16.");
+}
+
+#[test]
+fn runtime_errors() {
+    let cases = [
+        ("let a := 1;", "there is no `main` function to run."),
+        (
+            "func main() : = { return 1 % 0; }",
+            "division by zero at L1:C26.",
+        ),
+        (
+            "func main() : = { return nope; }",
+            "`nope` is not defined at L1:C26.",
+        ),
+        (
+            "func main() : = { main(); }",
+            "calls nested too deeply at L1:C19.",
+        ),
+        (
+            "func main() : = { return match 3 { 1 => 1 }; }",
+            "no arm of the `match` matches the value at L1:C26.",
+        ),
+        (
+            "func main() : = { let a : u8; return a; }",
+            "`a` is read at L1:C38 before it is assigned.",
+        ),
+        (
+            "func f(a : u8) : = {} func main() : = { f(1, 2); }",
+            "`f` takes 1 arguments, but 2 were given at L1:C41.",
+        ),
+        (
+            "func main() : = { let g := |x| = x; return g(); }",
+            "`closure` takes 1 arguments, but 0 were given at L1:C44.",
+        ),
+        (
+            "enum E { A(u8) } func main() : = { return E::A(); }",
+            "`E::A` takes 1 arguments, but 0 were given at L1:C43.",
+        ),
+        (
+            "func main() : = { return [0; 1000000000000]; }",
+            "an array of 1000000000000 elements is too long at L1:C26, arrays can have at most 1048576.",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        assert_eq!(run(src).unwrap_err().message(), expected, "{src}");
+    }
+}
+
+#[test]
+fn compound_assignments() {
+    let cases = [
+        (
+            "let mut a := 10; a += 5; a -= 1; a *= 2; a /= 4; a %= 4; return a;",
+            "3",
+        ),
+        (
+            "let mut b := 6; b &= 3; b |= 8; b ^= 1; b <<= 2; b >>= 1; return b;",
+            "22",
+        ),
+        ("let mut p := [[1, 2]]; p[0][1] += 3; return p;", "[[1, 5]]"),
+        // The bitwise operators apply to bools as well
+        (
+            "let mut b := true; b &= false; return b | true ^ false;",
+            "true",
+        ),
+        // The place is written to once, after the value is found
+        (
+            "let mut a := 1; let f := || -> u8 = { a += 2; return a; }; a += f(); return a;",
+            "6",
+        ),
+    ];
+
+    for (body, expected) in cases {
+        assert_eq!(run(&main_returning(body)).unwrap(), expected, "{body}");
+    }
+
+    let error = run(&main_returning("let mut a := 1;\na /= 0;")).unwrap_err();
+    assert_eq!(error.message(), "division by zero at L3:C1.");
+
+    // A variable declared without a value holds values of its type once
+    // assigned
+    let body = "let a : u8; if true { a = 2; } else { a = 3; } let mut b : u8; b = a; b += 250;";
+    assert_eq!(
+        run(&main_returning(&format!("{body} return b;"))).unwrap(),
+        "252"
+    );
+
+    let error = run(&main_returning(&format!("{body}\nb += 4;"))).unwrap_err();
+    assert_eq!(error.message(), "`252 + 4` overflows `u8` at L3:C1.");
+}
+
+#[test]
+fn functions_and_structs() {
+    let src = "
+        struct Point { x : i32, y : i32 }
+
+        func scale(p : &mut Point, by : i32) : = {
+            p.x = p.x * by;
+            p.y = p.y * by;
+        }
+
+        func main() : Point = {
+            let mut p := Point { x: 1, y: 2 };
+            scale(&mut p, 3);
+
+            if p.x > 2 {
+                p.y = p.y + 1;
+            } else {
+                return p;
+            }
+
+            return p;
+        }
+    ";
+
+    assert_eq!(run(src).unwrap(), "Point { x: 3, y: 7 }");
+}
+
+#[test]
+fn enums_and_matches() {
+    let shape = "enum Shape { Circle(f64), Rect(f64, f64), Empty }";
+    let cases = [
+        ("return Shape::Rect(2, 3);", "Shape::Rect(2, 3)"),
+        ("return [Shape::Empty, Shape::Circle(1)];", "[Shape::Empty, Shape::Circle(1)]"),
+        ("return Shape::Circle;", "func Shape::Circle"),
+        (
+            "return match Shape::Rect(2, 0) { Shape::Rect(_, 0) => 0, Shape::Rect(w, h) => w * h, _ => 1 };",
+            "0",
+        ),
+        ("return match 4 - 5 { -1 => 'n', 0 => 'z', _ => 'p' };", "'n'"),
+        (
+            "let s := Shape::Circle(2); match &s { Shape::Circle(r) => { return r; } _ => {} } return 0;",
+            "2",
+        ),
+    ];
+
+    for (body, expected) in cases {
+        let src = format!("{shape}\n{}", main_returning(body));
+        assert_eq!(run(&src).unwrap(), expected, "{body}");
+    }
+}
+
+#[test]
+fn generic_functions_and_structs() {
+    let items = "struct Pair<A, B> { first : A, second : B }
+        func pair<A, B>(first : A, second : B) : Pair<A, B> = { return Pair { first: first, second: second }; }
+        func last<T>(items : &[T]) : T = { return items[len(items) - 1]; }
+        func max<T : Ord>(a : T, b : T) : T = { if a > b { return a; } return b; }";
+    let cases = [
+        (
+            "let p : Pair<u8, char> = pair(1, 'a'); return p;",
+            "Pair { first: 1, second: 'a' }",
+        ),
+        (
+            "let a : [bool; 2] = [false, true]; return last(&a);",
+            "true",
+        ),
+        (
+            "let p : Pair<Pair<u8, u8>, bool> = pair(pair(1, 2), true); return p.first.second;",
+            "2",
+        ),
+        ("let a : i8 = -3; return max(a, -7);", "-3"),
+        ("return max('x', 'y');", "'y'"),
+        ("return max(0.25, 0.5);", "0.5"),
+    ];
+
+    for (body, expected) in cases {
+        let src = format!("{items}\n{}", main_returning(body));
+        assert_eq!(run(&src).unwrap(), expected, "{body}");
+    }
+}
+
+#[test]
+fn methods_are_dispatched_on_the_receiver() {
+    let items = "trait Describe { func describe(self, times : u8) : u8; }
+        struct Point { x : u8 }
+        enum Light { Red, Green }
+        impl Describe for Point { func describe(self, times : u8) : u8 = { return self.x * times; } }
+        impl Describe for Light {
+            func describe(self, times : u8) : u8 = {
+                return match self { Light::Red => 1, Light::Green => 2 } * times;
+            }
+        }
+        func twice<T : Describe>(value : T) : u8 = { return value.describe(2); }";
+    let cases = [
+        ("let p := Point { x: 3 }; return p.describe(1);", "3"),
+        (
+            "let p := Point { x: 3 }; let r := &&p; return r.describe(2);",
+            "6",
+        ),
+        ("return Light::Green.describe(5);", "10"),
+        ("return twice(Point { x: 4 }) + twice(Light::Red);", "10"),
+    ];
+
+    for (body, expected) in cases {
+        let src = format!("{items}\n{}", main_returning(body));
+        assert_eq!(run(&src).unwrap(), expected, "{body}");
+    }
+
+    let src = main_returning("return true.describe(2);");
+    assert_eq!(
+        run(&src).unwrap_err().message(),
+        "`bool` has no method `describe` at L2:C8."
+    );
+}
+
+#[test]
+fn closures_capture_by_value_and_reference() {
+    let items = "func twice(f : func(u8) -> u8, x : u8) : u8 = { return f(f(x)); }
+        func adder(n : u8) : func(u8) -> u8 = { return |x| = x + n; }";
+    let cases = [
+        // Captured by value, the closure keeps the value it was made with
+        ("let mut a := 1; let f := || = a; a = 5; return f();", "1"),
+        // Assigned to, `a` is shared with the closure
+        (
+            "let mut a := 1; let f := || = { a = a + 1; }; f(); f(); return a;",
+            "3",
+        ),
+        (
+            "let mut a := 1; let f := || = { a++; }; f(); return a;",
+            "2",
+        ),
+        (
+            "let mut p := [1, 2]; let f := || = { p[1] = 7; }; f(); return p;",
+            "[1, 7]",
+        ),
+        // A closure outlives the call that made it
+        ("let add := adder(10); return add(1) + adder(20)(2);", "33"),
+        ("return twice(|x| = x * 3, 2);", "18"),
+        // Nested closures capture through the closures around them
+        (
+            "let a := 2; let f := |x : u8| = |y : u8| = x * y + a; return f(3)(4);",
+            "14",
+        ),
+        (
+            "let f := |x : u8| -> u8 = { if x > 1 { return 1; } return 0; }; return f(5);",
+            "1",
+        ),
+        ("let f := || = 1; return f;", "closure"),
+    ];
+
+    for (body, expected) in cases {
+        let src = format!("{items}\n{}", main_returning(body));
+        assert_eq!(run(&src).unwrap(), expected, "{body}");
+    }
+
+    let src = main_returning("let f := |x : u8| = x; return f(1, 2);");
+    assert_eq!(
+        run(&src).unwrap_err().message(),
+        "`closure` takes 1 arguments, but 2 were given at L2:C31."
+    );
+}
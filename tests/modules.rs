@@ -0,0 +1,328 @@
+use orion::error::OrionError;
+use orion::interp::comptime::Config;
+use orion::interp::Interpreter;
+use orion::module::{self, Module};
+use orion::sema;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Load a program from files held in memory, the first being the entry file
+fn load(files: &[(&str, &str)]) -> Result<Vec<Module>, Vec<OrionError>> {
+    let sources: HashMap<PathBuf, String> = files
+        .iter()
+        .map(|(file, source)| (PathBuf::from(file), source.to_string()))
+        .collect();
+
+    let entry = Path::new(files[0].0);
+
+    module::load(entry, sources[entry].clone(), &Config::default(), |file| {
+        sources
+            .get(file)
+            .cloned()
+            .ok_or_else(|| io::ErrorKind::NotFound.into())
+    })
+}
+
+/// Load, resolve and check a program, returning the modules if it is valid
+fn check(files: &[(&str, &str)]) -> Result<Vec<Module>, Vec<String>> {
+    let messages = |errors: Vec<OrionError>| errors.iter().map(OrionError::message).collect();
+
+    let mut modules = load(files).map_err(messages)?;
+    let mut errors = module::resolve(&mut modules);
+
+    if errors.is_empty() {
+        errors = sema::check_modules(&modules);
+    }
+
+    match errors.is_empty() {
+        true => Ok(modules),
+        false => Err(messages(errors)),
+    }
+}
+
+fn run(files: &[(&str, &str)]) -> Result<String, String> {
+    let modules = check(files).map_err(|errors| errors.join("\n"))?;
+
+    match Interpreter::default().run_modules(&modules) {
+        Ok(value) => Ok(value.to_string()),
+        Err(error) => Err(error.message()),
+    }
+}
+
+#[test]
+fn example_runs() {
+    let dir = Path::new("examples/modules");
+    let entry = dir.join("main.ori");
+    let source = std::fs::read_to_string(&entry).unwrap();
+
+    let mut modules = module::load(&entry, source, &Config::default(), |file| {
+        std::fs::read_to_string(file)
+    })
+    .unwrap();
+    assert_eq!(module::resolve(&mut modules).len(), 0);
+    assert_eq!(sema::check_modules(&modules).len(), 0);
+
+    // No top-level variable is initialized with the items of another
+    // module, so the modules stay in the order they were loaded in
+    let names: Vec<String> = modules.iter().map(Module::name).collect();
+    assert_eq!(names, ["main", "math", "math::geometry"]);
+    assert_eq!(modules[2].file, dir.join("math").join("geometry.ori"));
+
+    let value = Interpreter::default().run_modules(&modules).unwrap();
+    assert_eq!(value.to_string(), "26");
+}
+
+#[test]
+fn names_paths_and_imports() {
+    let files = [
+        (
+            "main.ori",
+            "mod a;
+            import a::b::triple;
+            import a::b;
+            func main() : i32 = {
+                return a::twice(3) + triple(1) + b::one() + a::b::half(8);
+            }",
+        ),
+        (
+            "a.ori",
+            "pub mod b;
+            pub func twice(x : i32) : i32 = { return b::half(x * 4); }",
+        ),
+        (
+            "a/b.ori",
+            "pub func one() : i32 = { return 1; }
+            pub func half(x : i32) : i32 = { return x / 2; }
+            pub func triple(x : i32) : i32 = { return x * 3; }",
+        ),
+    ];
+
+    assert_eq!(run(&files), Ok("14".to_string()));
+}
+
+#[test]
+fn items_are_private_by_default() {
+    let files = [
+        (
+            "main.ori",
+            "mod a; func main() : i32 = { return a::b::g(); }",
+        ),
+        ("a.ori", "pub mod b; func h() : i32 = { return 2; }"),
+        // Private items are visible inside the module that declares them,
+        // and the modules inside it
+        (
+            "a/b.ori",
+            "import a::h; pub func g() : i32 = { return h(); }",
+        ),
+    ];
+
+    assert_eq!(run(&files), Ok("2".to_string()));
+}
+
+#[test]
+fn structs_and_enums_across_modules() {
+    let files = [
+        (
+            "main.ori",
+            "mod shapes;
+            import shapes::Point;
+            import shapes::Shape;
+            func main() : f64 = {
+                let p : Point = Point { x: 2, y: 3 };
+                return shapes::area(Shape::Rect(p.x, p.y)) + shapes::area(shapes::Shape::Empty);
+            }",
+        ),
+        (
+            "shapes.ori",
+            "pub struct Point { x : f64, y : f64 }
+            pub enum Shape { Rect(f64, f64), Empty }
+            pub func area(shape : Shape) : f64 = {
+                return match shape {
+                    Shape::Rect(w, h) => w * h,
+                    Shape::Empty => 0,
+                };
+            }",
+        ),
+    ];
+
+    assert_eq!(run(&files), Ok("6".to_string()));
+}
+
+#[test]
+fn traits_across_modules() {
+    let files = [
+        (
+            "main.ori",
+            "mod shapes;
+            import shapes::Area;
+            import shapes::Rect;
+            struct Square { side : u32 }
+            impl Area for Square { func area(self) : u32 = { return self.side * self.side; } }
+            func sum<T : shapes::Area>(a : T, b : T) : u32 = { return a.area() + b.area(); }
+            func main() : u32 = {
+                let rect : Rect = Rect { width: 2, height: 3 };
+                return sum(Square { side: 2 }, Square { side: 1 }) + rect.area();
+            }",
+        ),
+        (
+            "shapes.ori",
+            "pub trait Area { func area(self) : u32; }
+            pub struct Rect { width : u32, height : u32 }
+            impl Area for Rect { func area(self) : u32 = { return self.width * self.height; } }",
+        ),
+    ];
+
+    assert_eq!(run(&files), Ok("11".to_string()));
+
+    // A private trait can't be implemented outside of its module
+    let files = [
+        (
+            "main.ori",
+            "mod a; struct S { x : u8 } impl a::Show for S {}",
+        ),
+        ("a.ori", "trait Show {}"),
+    ];
+
+    assert_eq!(
+        check(&files).unwrap_err(),
+        ["main.ori: `Show` is private to module `a` at L1:C28, declare it `pub` to use it here."]
+    );
+}
+
+#[test]
+fn functions_call_each_other_across_modules() {
+    let files = [
+        (
+            "main.ori",
+            "mod a; mod b;
+            let total : u32 = b::count();
+            func main() : u32 = { return total + a::even(4); }",
+        ),
+        (
+            "a.ori",
+            "import b::odd;
+            pub func even(n : u32) : u32 = { if n == 0 { return 1; } return odd(n - 1); }",
+        ),
+        (
+            "b.ori",
+            "import a::even;
+            let start : u32 = 10;
+            pub func odd(n : u32) : u32 = { if n == 0 { return 0; } return even(n - 1); }
+            pub func count() : u32 = { return start; }",
+        ),
+    ];
+
+    // `total` is initialized with an item of `b`, whose variables are set
+    // first
+    let names: Vec<String> = check(&files).unwrap().iter().map(Module::name).collect();
+    assert_eq!(names, ["b", "main", "a"]);
+    assert_eq!(run(&files), Ok("11".to_string()));
+}
+
+#[test]
+fn module_errors() {
+    let cases = [
+        (
+            vec![("main.ori", "mod nope;")],
+            "main.ori: no file `nope.ori` for module `nope` at L1:C1.",
+        ),
+        (
+            vec![("main.ori", "mod main;")],
+            "main.ori: modules depend on each other in a cycle, `main` -> `main` at L1:C1.",
+        ),
+        (
+            vec![
+                ("main.ori", "mod a; mod b;"),
+                (
+                    "a.ori",
+                    "import b::g; let x : u8 = g(); pub func f() : u8 = { return x; }",
+                ),
+                (
+                    "b.ori",
+                    "import a::f; let y : u8 = f(); pub func g() : u8 = { return y; }",
+                ),
+            ],
+            "b.ori: modules depend on each other in a cycle, `a` -> `b` -> `a` at L1:C27.",
+        ),
+        (
+            vec![
+                ("main.ori", "mod a; import a::g;"),
+                ("a.ori", "pub func f() : = {}"),
+            ],
+            "main.ori: module `a` has no item `g` at L1:C8.",
+        ),
+        (
+            vec![
+                ("main.ori", "mod a; let x := a::f;"),
+                ("a.ori", "func f() : = {}"),
+            ],
+            "main.ori: `f` is private to module `a` at L1:C17, declare it `pub` to use it here.",
+        ),
+        (
+            vec![
+                ("main.ori", "mod a; import a::f::g;"),
+                ("a.ori", "pub func f() : = {}"),
+            ],
+            "main.ori: `a::f` is not a module at L1:C8.",
+        ),
+        (
+            vec![("main.ori", "mod a; mod a;"), ("a.ori", "")],
+            "main.ori: `a` is defined more than once at L1:C8.",
+        ),
+        (
+            vec![("main.ori", "mod a;"), ("a.ori", "let x : u8 = true;")],
+            "a.ori: expected `u8` because of L1:C9, found `bool` at L1:C14.",
+        ),
+        (
+            vec![("main.ori", "mod a;"), ("a.ori", "func f() : = { g(); }")],
+            "a.ori: `g` is not defined at L1:C16.",
+        ),
+        (
+            vec![("main.ori", "mod a;"), ("a.ori", "let x := ;")],
+            "a.ori: expected an expression, found `;` at L1:C10.",
+        ),
+    ];
+
+    for (files, expected) in cases {
+        let errors = check(&files).unwrap_err();
+        assert_eq!(errors, [expected], "{files:?}");
+    }
+}
+
+#[test]
+fn runtime_errors_name_their_file() {
+    let files = [
+        ("main.ori", "mod a;\nfunc main() : u8 = { return a::f(0); }"),
+        ("a.ori", "pub func f(x : u8) : u8 = {\n    return 1 % x;\n}"),
+    ];
+
+    assert_eq!(
+        run(&files),
+        Err("a.ori: division by zero at L2:C12.".to_string())
+    );
+}
+
+#[test]
+fn closures_across_modules() {
+    // `scale` is a parameter of the closure, hiding the function of the
+    // same name, and the closure made in `math` calls `twice` from there
+    let files = [
+        (
+            "main.ori",
+            "mod math;
+            func scale(x : u32) : u32 = { return x * 100; }
+            func main() : u32 = {
+                let by : func(u32, u32) -> u32 = |x, scale| = x * scale;
+                return math::twice_by(3)(by(1, 2));
+            }",
+        ),
+        (
+            "math.ori",
+            "func twice(x : u32) : u32 = { return x * 2; }
+            pub func twice_by(n : u32) : func(u32) -> u32 = { return |x| = twice(x) + n; }",
+        ),
+    ];
+
+    assert_eq!(run(&files), Ok("7".to_string()));
+}
@@ -0,0 +1,900 @@
+//! Round-trip tests for the pretty-printer: parsing the printed form of a
+//! program must give back the program that was printed.
+//!
+//! Programs are compared through their S-expression dumps, which leave out
+//! the spans that naturally differ between the original and printed source.
+use orion::fuzz::{Generator, Rng};
+use orion::lexer::state::Lexer;
+use orion::parser::ast::*;
+use orion::parser::dump::{program_node, to_sexpr};
+use orion::parser::printer::print_program;
+use orion::parser::state::Parser;
+
+fn parse(src: &str) -> Program {
+    Parser::new(Lexer::new(src)).parse().unwrap()
+}
+
+fn sexpr(program: &Program) -> String {
+    to_sexpr(&program_node(program))
+}
+
+fn assert_roundtrip(src: &str) {
+    let program = parse(src);
+    let printed = print_program(&program);
+
+    assert_eq!(
+        sexpr(&parse(&printed)),
+        sexpr(&program),
+        "printed as:\n{printed}"
+    );
+    // Printing is canonical, so printing again changes nothing
+    assert_eq!(print_program(&parse(&printed)), printed);
+}
+
+/// The examples the parser currently understands
+const EXAMPLES: &[&str] = &[
+    "examples/parsing.ori",
+    "examples/numbers.ori",
+    "examples/literals.ori",
+    "examples/references.ori",
+    "examples/adding_variables.ori",
+    "examples/main.ori",
+    "examples/functions.ori",
+    "examples/types.ori",
+    "examples/structs.ori",
+    "examples/arrays.ori",
+    "examples/enums.ori",
+    "examples/generics.ori",
+    "examples/traits.ori",
+    "examples/closures.ori",
+    "examples/comptime.ori",
+    "examples/modules/main.ori",
+    "examples/modules/math.ori",
+    "examples/modules/math/geometry.ori",
+];
+
+#[test]
+fn examples_roundtrip() {
+    for path in EXAMPLES {
+        let src = std::fs::read_to_string(path).unwrap();
+        assert_roundtrip(&src);
+    }
+}
+
+#[test]
+fn parentheses_only_where_needed() {
+    let cases = [
+        ("let a = (1 + 2) * 3;", "let a := (1 + 2) * 3;\n"),
+        ("let a = 1 + (2 * 3);", "let a := 1 + 2 * 3;\n"),
+        ("let a = (1 - 2) - 3;", "let a := 1 - 2 - 3;\n"),
+        ("let a = 1 - (2 - 3);", "let a := 1 - (2 - 3);\n"),
+        ("let a = (a & b) == c;", "let a := a & b == c;\n"),
+        ("let a = ((x));", "let a := x;\n"),
+        ("let a = -(x++);", "let a := -x++;\n"),
+        ("let a = -(-x);", "let a := - -x;\n"),
+        ("let a = -(1 + 2);", "let a := -(1 + 2);\n"),
+        ("let a = (-1) + 2;", "let a := -1 + 2;\n"),
+        ("let a = (*x) as u8;", "let a := *x as u8;\n"),
+        ("let a = -(x as u8);", "let a := -(x as u8);\n"),
+        ("let a = (x + y) as u8;", "let a := (x + y) as u8;\n"),
+        ("let a = (x as u8) as &u16;", "let a := x as u8 as &u16;\n"),
+        ("let a = x * (y as f64);", "let a := x * y as f64;\n"),
+        ("let a = (b) * a;", "let a := b * a;\n"),
+        ("let a = (-f)(x);", "let a := (-f)(x);\n"),
+        ("let a = (x as u8)(y);", "let a := (x as u8)(y);\n"),
+        (
+            "let a = (x as P::<u8>) < y;",
+            "let a := x as P::<u8> < y;\n",
+        ),
+        ("let a : (u8) = x;", "let a : u8 = x;\n"),
+        ("let a : (u8,) = x;", "let a : (u8,) = x;\n"),
+        ("let a = (a + b)[0];", "let a := (a + b)[0];\n"),
+        ("let a = -(a[0]);", "let a := -a[0];\n"),
+        ("let a = (-a)[0];", "let a := (-a)[0];\n"),
+        ("let a = (a[1..2])[0];", "let a := a[1..2][0];\n"),
+        ("let a = a[(1 + 2)..];", "let a := a[1 + 2..];\n"),
+        ("let a = a[..];", "let a := a[..];\n"),
+        ("let a = [[1, 2,], [3; 4]];", "let a := [[1, 2], [3; 4]];\n"),
+        ("let a = (f(x))[0].y;", "let a := f(x)[0].y;\n"),
+        ("let a = (E::A)(x);", "let a := E::A(x);\n"),
+        (
+            "(match x { _ => 1 }) + 1;",
+            "(match x {\n    _ => 1,\n} + 1);\n",
+        ),
+        (
+            "match (P {}) { P => {} }",
+            "match (P {}) {\n    P => {\n    }\n}\n",
+        ),
+        (
+            "(match x { -1 => a, _ => b })[0] = 2;",
+            "(match x {\n    -1 => a,\n    _ => b,\n}[0]) = 2;\n",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        assert_eq!(print_program(&parse(src)), expected);
+    }
+}
+
+#[test]
+fn modules_and_visibility_print() {
+    let cases = [
+        ("pub  mod  shapes ;", "pub mod shapes;\n"),
+        ("import shapes :: Point;", "import shapes::Point;\n"),
+        (
+            "pub func f() = {} pub struct P {} pub enum E {}",
+            "pub func f() = {\n}\npub struct P {\n}\npub enum E {\n}\n",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        assert_eq!(print_program(&parse(src)), expected);
+    }
+}
+
+#[test]
+fn items_are_only_pub_at_the_top_level() {
+    for src in [
+        "func f() : = { pub func g() : = {} }",
+        "func f() : = { mod m; }",
+        "pub let a := 1;",
+    ] {
+        assert!(Parser::new(Lexer::new(src)).parse().is_err(), "{src}");
+        assert!(!orion::syntax::parse(src).errors.is_empty(), "{src}");
+    }
+}
+
+#[test]
+fn generics_print() {
+    let cases = [
+        (
+            "func  max < T >(a : T, b : T) : T = {}",
+            "func max<T>(a : T, b : T) : T = {\n}\n",
+        ),
+        (
+            "struct Pair<A,B> { first : A, second : B }",
+            "struct Pair<A, B> {\n    first : A,\n    second : B,\n}\n",
+        ),
+        (
+            "let p : Pair<u8, Pair<T, U>> = q;",
+            "let p : Pair<u8, Pair<T, U>> = q;\n",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        assert_eq!(print_program(&parse(src)), expected);
+        assert_roundtrip(src);
+    }
+
+    for src in ["func f<T() : = {}", "struct S<1> {}"] {
+        assert!(Parser::new(Lexer::new(src)).parse().is_err(), "{src}");
+    }
+}
+
+#[test]
+fn traits_print() {
+    let cases = [
+        (
+            "trait Show{func show(self):&str; func eq(self, other : &Self) : bool;}",
+            "trait Show {\n    func show(self) : &str;\n    func eq(self, other : &Self) : bool;\n}\n",
+        ),
+        (
+            "impl  fmt::Show for Point { func show(self) : &str = { return \"p\"; } }",
+            "impl fmt::Show for Point {\n    func show(self) : &str = {\n        return \"p\";\n    }\n}\n",
+        ),
+        (
+            "func f<T:Show+fmt::Eq, U>(t:T):={ (p.f)(x); p.m(1).n(); }",
+            "func f<T : Show + fmt::Eq, U>(t : T) = {\n    (p.f)(x);\n    p.m(1).n();\n}\n",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        assert_eq!(print_program(&parse(src)), expected);
+        assert_roundtrip(src);
+    }
+
+    // Methods take `self` first and have no type parameters, and traits and
+    // impls are only declared at the top level
+    for src in [
+        "impl Show for P { func m() : = {} }",
+        "impl Show for P { func m<T>(self) : = {} }",
+        "trait Show { func m(self) : u8 = {} }",
+        "func f() : = { trait T {} }",
+    ] {
+        assert!(Parser::new(Lexer::new(src)).parse().is_err(), "{src}");
+    }
+}
+
+#[test]
+fn closures_print() {
+    let cases = [
+        (
+            "let f := |x:u32,y|->u32=x+y;",
+            "let f := |x : u32, y| -> u32 = x + y;\n",
+        ),
+        (
+            "let g := ||={ count++; };",
+            "let g := || = {\n    count++;\n};\n",
+        ),
+        // The body takes everything after it, so a closure is put in
+        // parentheses anywhere but on its own
+        (
+            "let h := (|x| = x)(1) + (|| = 2 as u8);",
+            "let h := (|x| = x)(1) + (|| = 2 as u8);\n",
+        ),
+        (
+            "apply(|f : func(u8) -> u8| = f(1), |x| = |y| = x * y);",
+            "apply(|f : func(u8) -> u8| = f(1), |x| = |y| = x * y);\n",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        assert_eq!(print_program(&parse(src)), expected);
+        assert_roundtrip(src);
+    }
+
+    for src in [
+        "let f := |x| x;",
+        "let f := |x : | = x;",
+        "let f := |x, = x;",
+    ] {
+        assert!(Parser::new(Lexer::new(src)).parse().is_err(), "{src}");
+    }
+}
+
+#[test]
+fn comptime_prints() {
+    let cases = [
+        (
+            "let SIZE:=$( (4*1024) );",
+            "let SIZE := $(4 * 1024);\n",
+        ),
+        (
+            "let t := ${ return [1, 2]; }[0] + $(f(1));",
+            "let t := ${\n    return [1, 2];\n}[0] + $(f(1));\n",
+        ),
+        (
+            "$if target_os==\"linux\"{func f():={}}else $if debug{}else{let a:=1;}",
+            "$if target_os == \"linux\" {\n    func f() = {\n    }\n} else $if debug {\n} else {\n    let a := 1;\n}\n",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        assert_eq!(print_program(&parse(src)), expected);
+        assert_roundtrip(src);
+    }
+
+    // The branches of a `$if` at the top level declare items like the top
+    // level does
+    assert_roundtrip("$if a { pub func f() : = {} impl Show for P {} mod m; }");
+
+    for src in [
+        "let a := $b;",
+        "let a := $[1];",
+        "$if a {} else if b {}",
+        "func f() : = { $if a { pub func g() : = {} } }",
+    ] {
+        assert!(Parser::new(Lexer::new(src)).parse().is_err(), "{src}");
+    }
+}
+
+#[test]
+fn mutation_prints() {
+    let cases = [
+        (
+            "func f() : = { let mut a:=1; a+=2; a.b[0]<<=1; *r^=a; a=a%2; }",
+            "func f() = {\n    let mut a := 1;\n    a += 2;\n    a.b[0] <<= 1;\n    *r ^= a;\n    a = a % 2;\n}\n",
+        ),
+        // A glued `|=` or `>>=` may close a closure or generic arguments
+        (
+            "let f := |x|= x; let p : P<Q<u8>>= v;",
+            "let f := |x| = x;\nlet p : P<Q<u8>> = v;\n",
+        ),
+        // Only a variable with a type can be declared without a value
+        (
+            "func f() : = { let mut a : u8 ; a = 1; }",
+            "func f() = {\n    let mut a : u8;\n    a = 1;\n}\n",
+        ),
+        (
+            "func f(r:&mut&u8) : = { let p := &mut*r; }",
+            "func f(r : &mut &u8) = {\n    let p := &mut *r;\n}\n",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        assert_eq!(print_program(&parse(src)), expected);
+        assert_roundtrip(src);
+    }
+
+    for src in [
+        "func f() : = { 1 += 2; }",
+        "func f() : = { f() -= 1; }",
+        "func f() : = { let r := &mut 1; }",
+        "func f() : = { a == b += 1; }",
+        "let mut := 1;",
+        "let a;",
+        "let a := ;",
+    ] {
+        assert!(Parser::new(Lexer::new(src)).parse().is_err(), "{src}");
+    }
+}
+
+#[test]
+fn left_arrows_are_rejected() {
+    for (src, loc) in [
+        ("func f() : = { a <- 1; }", "L1:C18"),
+        ("func f() : = { if a<-1 {} }", "L1:C20"),
+        ("let b := a.x <- 2 + 3;", "L1:C14"),
+    ] {
+        let error = Parser::new(Lexer::new(src)).parse().unwrap_err();
+        assert_eq!(
+            error.message(),
+            format!("`<-` is not an operator at {loc}, write `place = value` to assign or `a < -b` to compare."),
+        );
+    }
+}
+
+#[test]
+fn c_style_casts_are_rejected() {
+    for src in [
+        "let c := (u64)*a;",
+        "let c := (u8) x;",
+        "let c := (bool)(x);",
+    ] {
+        let error = Parser::new(Lexer::new(src)).parse().unwrap_err();
+        assert!(
+            matches!(error, orion::error::OrionError::CStyleCast { .. }),
+            "{src}: {error:?}"
+        );
+    }
+}
+
+#[test]
+fn numbers_are_exact() {
+    let program =
+        parse("let a := 18_446_744_073_709_551_615; let b := 2.0; let c := 9007199254740993;");
+    assert_eq!(
+        sexpr(&program),
+        "(program (let a false (number 18446744073709551615)) (let b false (number 2.0)) (let c false (number 9007199254740993)))",
+    );
+    assert_roundtrip("let b := 2.0; let c := 1.5;");
+
+    // Larger than any integer type
+    let error = Parser::new(Lexer::new("let a := 18446744073709551616;"))
+        .parse()
+        .unwrap_err();
+    assert_eq!(
+        error.message(),
+        "invalid number `18446744073709551616` at L1:C10."
+    );
+}
+
+const OPERATORS: &[BinaryOperator] = &[
+    BinaryOperator::Add,
+    BinaryOperator::Sub,
+    BinaryOperator::Mul,
+    BinaryOperator::Div,
+    BinaryOperator::Rem,
+    BinaryOperator::BitAnd,
+    BinaryOperator::BitOr,
+    BinaryOperator::BitXor,
+    BinaryOperator::Shl,
+    BinaryOperator::Shr,
+    BinaryOperator::Eq,
+    BinaryOperator::NotEq,
+    BinaryOperator::Less,
+    BinaryOperator::LessEq,
+    BinaryOperator::Greater,
+    BinaryOperator::GreaterEq,
+];
+
+const UNARY_OPERATORS: &[UnaryOperator] = &[
+    UnaryOperator::Neg,
+    UnaryOperator::Not,
+    UnaryOperator::BitNot,
+    UnaryOperator::Ref,
+    UnaryOperator::RefMut,
+    UnaryOperator::Deref,
+    UnaryOperator::PreIncrement,
+    UnaryOperator::PreDecrement,
+    UnaryOperator::PostIncrement,
+    UnaryOperator::PostDecrement,
+];
+
+/// A whole number, the largest `u64` included, or a float. Multiples of
+/// 1/8 print exactly.
+fn random_number(rng: &mut Rng) -> Number {
+    match rng.below(3) {
+        0 => Number::Integer(rng.below(100_000) as i128),
+        1 => Number::Integer(u64::MAX as i128 - rng.below(3) as i128),
+        _ => Number::Float(rng.below(100_000) as f64 / 8.0),
+    }
+}
+
+fn random_expression(rng: &mut Rng, depth: u32) -> Expression {
+    let kind = if depth == 0 || rng.below(3) == 0 {
+        match rng.below(5) {
+            0 => ExpressionKind::Number(random_number(rng)),
+            1 => ExpressionKind::Bool(rng.one_in(2)),
+            2 => ExpressionKind::String(rng.pick(&["", "a\"b", "tab\t\\"]).to_string()),
+            3 => ExpressionKind::Char(rng.pick(&['x', '\'', '\n', '\0'])),
+            _ => ExpressionKind::Identifier(format!("v{}", rng.below(10))),
+        }
+    } else if rng.one_in(4) {
+        let op = rng.pick(UNARY_OPERATORS);
+
+        // Mutating operators need a variable to work on
+        let operand = if op.is_mutating() {
+            Expression::new(ExpressionKind::Identifier("v".to_string()), Span::default())
+        } else {
+            random_expression(rng, depth - 1)
+        };
+
+        ExpressionKind::Unary {
+            op,
+            operand: Box::new(operand),
+        }
+    } else if rng.one_in(6) {
+        ExpressionKind::Field {
+            expression: Box::new(random_expression(rng, depth - 1)),
+            field: format!("f{}", rng.below(3)),
+        }
+    } else if rng.one_in(8) {
+        ExpressionKind::MethodCall {
+            receiver: Box::new(random_expression(rng, depth - 1)),
+            method: format!("m{}", rng.below(3)),
+            arguments: (0..rng.below(3))
+                .map(|_| random_expression(rng, depth - 1))
+                .collect(),
+        }
+    } else if rng.one_in(6) {
+        ExpressionKind::StructLiteral {
+            name: "Point".to_string(),
+            fields: (0..rng.below(3))
+                .map(|i| FieldInit {
+                    name: format!("f{i}"),
+                    value: random_expression(rng, depth - 1),
+                    span: Span::default(),
+                })
+                .collect(),
+        }
+    } else if rng.one_in(6) {
+        match rng.below(4) {
+            0 => ExpressionKind::Array(
+                (0..rng.below(4))
+                    .map(|_| random_expression(rng, depth - 1))
+                    .collect(),
+            ),
+            1 => ExpressionKind::ArrayRepeat {
+                value: Box::new(random_expression(rng, depth - 1)),
+                length: Box::new(random_expression(rng, depth - 1)),
+            },
+            2 => ExpressionKind::Index {
+                expression: Box::new(random_expression(rng, depth - 1)),
+                index: Box::new(random_expression(rng, depth - 1)),
+            },
+            _ => ExpressionKind::Slice {
+                expression: Box::new(random_expression(rng, depth - 1)),
+                start: rng
+                    .one_in(2)
+                    .then(|| Box::new(random_expression(rng, depth - 1))),
+                end: rng
+                    .one_in(2)
+                    .then(|| Box::new(random_expression(rng, depth - 1))),
+            },
+        }
+    } else if rng.one_in(8) {
+        ExpressionKind::Path(vec!["Shape".to_string(), format!("V{}", rng.below(3))])
+    } else if rng.one_in(8) {
+        random_match(rng, depth - 1)
+    } else if rng.one_in(8) {
+        random_closure(rng, depth - 1)
+    } else if rng.one_in(10) {
+        let inner = if rng.one_in(3) {
+            ExpressionKind::Block(random_block(rng, 0))
+        } else {
+            random_expression(rng, depth - 1).kind
+        };
+
+        ExpressionKind::Comptime(Box::new(Expression::new(inner, Span::default())))
+    } else if rng.one_in(5) {
+        ExpressionKind::Cast {
+            expression: Box::new(random_expression(rng, depth - 1)),
+            ty: random_type(rng, 2),
+        }
+    } else {
+        ExpressionKind::Binary {
+            op: rng.pick(OPERATORS),
+            lhs: Box::new(random_expression(rng, depth - 1)),
+            rhs: Box::new(random_expression(rng, depth - 1)),
+        }
+    };
+
+    Expression::new(kind, Span::default())
+}
+
+fn random_match(rng: &mut Rng, depth: u32) -> ExpressionKind {
+    let arms = (0..rng.below(4))
+        .map(|_| {
+            let body = if rng.one_in(3) {
+                ExpressionKind::Block(random_block(rng, 0))
+            } else {
+                random_expression(rng, depth).kind
+            };
+
+            MatchArm {
+                pattern: random_pattern(rng, 2),
+                body: Expression::new(body, Span::default()),
+                span: Span::default(),
+            }
+        })
+        .collect();
+
+    ExpressionKind::Match {
+        scrutinee: Box::new(random_expression(rng, depth)),
+        arms,
+    }
+}
+
+fn random_closure(rng: &mut Rng, depth: u32) -> ExpressionKind {
+    let parameters = (0..rng.below(3))
+        .map(|i| ClosureParameter {
+            name: format!("c{i}"),
+            ty: rng.one_in(2).then(|| random_type(rng, 2)),
+            span: Span::default(),
+        })
+        .collect();
+
+    let result = rng.one_in(3).then(|| Box::new(random_type(rng, 2)));
+
+    let body = if rng.one_in(4) {
+        ExpressionKind::Block(random_block(rng, 0))
+    } else {
+        random_expression(rng, depth).kind
+    };
+
+    ExpressionKind::Closure(Closure {
+        parameters,
+        result,
+        body: Box::new(Expression::new(body, Span::default())),
+    })
+}
+
+fn random_pattern(rng: &mut Rng, depth: u32) -> Pattern {
+    let kind = match rng.below(6) {
+        0 => PatternKind::Wildcard,
+        1 => PatternKind::Binding(format!("b{}", rng.below(3))),
+        // Zero isn't negated so it doesn't become `-0`
+        2 => match random_number(rng) {
+            number if rng.one_in(2) && number != Number::Integer(0) => PatternKind::Number(-number),
+            number => PatternKind::Number(number),
+        },
+        3 => match rng.below(2) {
+            0 => PatternKind::Bool(rng.one_in(2)),
+            _ => PatternKind::Char(rng.pick(&['x', '\'', '\n'])),
+        },
+        _ => PatternKind::Variant {
+            path: vec!["Shape".to_string(), format!("V{}", rng.below(3))],
+            fields: match depth {
+                0 => vec![],
+                _ => (0..rng.below(3))
+                    .map(|_| random_pattern(rng, depth - 1))
+                    .collect(),
+            },
+        },
+    };
+
+    Pattern {
+        kind,
+        span: Span::default(),
+    }
+}
+
+fn random_type(rng: &mut Rng, depth: u32) -> Type {
+    let named = |segments: &[&str], arguments| {
+        TypeKind::Named(Path {
+            segments: segments.iter().map(|segment| segment.to_string()).collect(),
+            arguments,
+        })
+    };
+
+    let kind = if depth == 0 || rng.one_in(2) {
+        named(&[rng.pick(PRIMITIVE_TYPES)], vec![])
+    } else {
+        match rng.below(6) {
+            0 => TypeKind::Reference {
+                inner: Box::new(random_type(rng, depth - 1)),
+                mutable: rng.one_in(2),
+            },
+            1 => TypeKind::Array {
+                element: Box::new(random_type(rng, depth - 1)),
+                length: Box::new(random_expression(rng, 1)),
+            },
+            2 => TypeKind::Slice(Box::new(random_type(rng, depth - 1))),
+            3 => TypeKind::Tuple(random_types(rng, 4, depth - 1)),
+            4 => TypeKind::Function {
+                parameters: random_types(rng, 3, depth - 1),
+                result: rng.one_in(2).then(|| Box::new(random_type(rng, depth - 1))),
+            },
+            _ => named(&["shapes", "Pair"], random_types(rng, 3, depth - 1)),
+        }
+    };
+
+    Type::new(kind, Span::default())
+}
+
+/// Fewer than `max` random types
+fn random_types(rng: &mut Rng, max: u64, depth: u32) -> Vec<Type> {
+    (0..rng.below(max))
+        .map(|_| random_type(rng, depth))
+        .collect()
+}
+
+fn random_statement(rng: &mut Rng, name: String, depth: u32) -> Statement {
+    let kind = match rng.below(10) {
+        0 if depth > 0 => StatementKind::Function(random_function(rng, name, false, depth)),
+        1 => StatementKind::Return(rng.one_in(2).then(|| random_expression(rng, 3))),
+        2 if depth > 0 => {
+            let comptime = rng.one_in(4);
+            random_if(rng, depth - 1, comptime)
+        }
+        3 => StatementKind::Expression(Expression::new(
+            ExpressionKind::Call {
+                callee: Box::new(random_expression(rng, 1)),
+                arguments: (0..rng.below(3))
+                    .map(|_| random_expression(rng, 3))
+                    .collect(),
+            },
+            Span::default(),
+        )),
+        4 => StatementKind::Struct(Struct {
+            name,
+            public: false,
+            generics: random_generics(rng)
+                .into_iter()
+                .map(|generic| Generic {
+                    bounds: vec![],
+                    ..generic
+                })
+                .collect(),
+            fields: (0..rng.below(3))
+                .map(|i| Field {
+                    name: format!("f{i}"),
+                    ty: random_type(rng, 2),
+                    span: Span::default(),
+                })
+                .collect(),
+        }),
+        5 => StatementKind::Assign {
+            place: Expression::new(
+                ExpressionKind::Field {
+                    expression: Box::new(Expression::new(
+                        ExpressionKind::Identifier("v".to_string()),
+                        Span::default(),
+                    )),
+                    field: "f0".to_string(),
+                },
+                Span::default(),
+            ),
+            // The operators before the comparisons have compound
+            // assignments
+            operator: rng.one_in(2).then(|| rng.pick(&OPERATORS[..10])),
+            value: random_expression(rng, 3),
+        },
+        6 => StatementKind::Enum(Enum {
+            name,
+            public: false,
+            variants: (0..rng.below(3))
+                .map(|i| Variant {
+                    name: format!("V{i}"),
+                    fields: random_types(rng, 3, 2),
+                    span: Span::default(),
+                })
+                .collect(),
+        }),
+        7 => StatementKind::Expression(Expression::new(random_match(rng, 2), Span::default())),
+        _ => {
+            let ty = rng.one_in(3).then(|| random_type(rng, 3));
+
+            // Only a variable with a type can be declared without a value
+            let initial = match ty.is_some() && rng.one_in(4) {
+                true => None,
+                false => Some(random_expression(rng, 5)),
+            };
+
+            StatementKind::Let {
+                name,
+                mutable: rng.one_in(2),
+                ty,
+                initial,
+            }
+        }
+    };
+
+    Statement {
+        kind,
+        span: Span::default(),
+    }
+}
+
+/// A function, or a method of an impl, which takes `self` first and has
+/// no type parameters
+fn random_function(rng: &mut Rng, name: String, method: bool, depth: u32) -> Function {
+    Function {
+        name,
+        public: false,
+        generics: match method {
+            true => vec![],
+            false => random_generics(rng),
+        },
+        parameters: random_parameters(rng, method),
+        result: rng.one_in(2).then(|| random_type(rng, 2)),
+        body: random_block(rng, depth - 1),
+    }
+}
+
+fn random_parameters(rng: &mut Rng, method: bool) -> Vec<Parameter> {
+    let receiver = method.then(|| Parameter {
+        name: "self".to_string(),
+        ty: Type::new(
+            TypeKind::Named(Path {
+                segments: vec!["Self".to_string()],
+                arguments: vec![],
+            }),
+            Span::default(),
+        ),
+        span: Span::default(),
+    });
+
+    receiver
+        .into_iter()
+        .chain((0..rng.below(3)).map(|i| Parameter {
+            name: format!("p{i}"),
+            ty: random_type(rng, 2),
+            span: Span::default(),
+        }))
+        .collect()
+}
+
+fn random_generics(rng: &mut Rng) -> Vec<Generic> {
+    (0..rng.below(3))
+        .map(|i| Generic {
+            name: format!("T{i}"),
+            bounds: (0..rng.below(3))
+                .map(|j| match j {
+                    0 => vec!["Show".to_string()],
+                    _ => vec!["fmt".to_string(), format!("Eq{j}")],
+                })
+                .collect(),
+            span: Span::default(),
+        })
+        .collect()
+}
+
+/// A trait or an impl of one
+fn random_trait(rng: &mut Rng, name: String) -> StatementKind {
+    if rng.one_in(2) {
+        return StatementKind::Trait(Trait {
+            name,
+            public: rng.one_in(2),
+            methods: (0..rng.below(3))
+                .map(|i| Method {
+                    name: format!("m{i}"),
+                    parameters: random_parameters(rng, true),
+                    result: rng.one_in(2).then(|| random_type(rng, 2)),
+                    span: Span::default(),
+                })
+                .collect(),
+        });
+    }
+
+    StatementKind::Impl(Impl {
+        trait_path: vec!["fmt".to_string(), "Show".to_string()],
+        ty: random_type(rng, 2),
+        methods: (0..rng.below(3))
+            .map(|i| Statement {
+                kind: StatementKind::Function(random_function(rng, format!("m{i}"), true, 2)),
+                span: Span::default(),
+            })
+            .collect(),
+    })
+}
+
+/// A statement of the top level, where items can be `pub` and modules can
+/// be declared and imported from
+fn random_top_level(rng: &mut Rng, name: String) -> Statement {
+    let mut stmt = random_statement(rng, name.clone(), 2);
+
+    match &mut stmt.kind {
+        StatementKind::Function(Function { public, .. })
+        | StatementKind::Struct(Struct { public, .. })
+        | StatementKind::Enum(Enum { public, .. }) => *public = rng.one_in(2),
+        _ if rng.one_in(4) => stmt.kind = random_trait(rng, name),
+        _ if rng.one_in(4) => {
+            stmt.kind = StatementKind::Module(Module {
+                name,
+                public: rng.one_in(2),
+            })
+        }
+        _ if rng.one_in(4) => {
+            stmt.kind = StatementKind::Import(
+                (0..rng.below(2) + 1)
+                    .map(|i| format!("m{i}"))
+                    .chain([name])
+                    .collect(),
+            )
+        }
+        _ => {}
+    }
+
+    stmt
+}
+
+/// An `if`, or a `$if` if `comptime` is set
+fn random_if(rng: &mut Rng, depth: u32, comptime: bool) -> StatementKind {
+    // `else` is followed by a block or another `if`, a `$if` after a `$if`
+    let otherwise = match rng.below(3) {
+        0 => None,
+        1 if depth > 0 => Some(random_if(rng, depth - 1, comptime)),
+        _ => Some(StatementKind::Block(random_block(rng, depth))),
+    };
+
+    let condition = random_expression(rng, 3);
+    let then = random_block(rng, depth);
+    let otherwise = otherwise.map(|kind| {
+        Box::new(Statement {
+            kind,
+            span: Span::default(),
+        })
+    });
+
+    match comptime {
+        false => StatementKind::If {
+            condition,
+            then,
+            otherwise,
+        },
+        true => StatementKind::ComptimeIf {
+            condition,
+            then,
+            otherwise,
+        },
+    }
+}
+
+fn random_block(rng: &mut Rng, depth: u32) -> Block {
+    Block {
+        statements: (0..rng.below(4))
+            .map(|i| random_statement(rng, format!("y{i}"), depth))
+            .collect(),
+        span: Span::default(),
+    }
+}
+
+#[test]
+fn random_programs_roundtrip() {
+    let mut rng = Rng::new(0x2545_f491_4f6c_dd1d);
+
+    for _ in 0..500 {
+        let program: Program = (0..rng.below(5) + 1)
+            .map(|i| random_top_level(&mut rng, format!("x{i}")))
+            .collect();
+
+        let printed = print_program(&program);
+        assert_eq!(
+            sexpr(&parse(&printed)),
+            sexpr(&program),
+            "printed as:\n{printed}"
+        );
+        assert_roundtrip(&printed);
+    }
+}
+
+#[test]
+fn generated_sources_roundtrip() {
+    let mut generator = Generator::new(0x0123_4567);
+
+    for _ in 0..500 {
+        assert_roundtrip(&generator.program());
+    }
+}
@@ -0,0 +1,1358 @@
+use orion::error::OrionError;
+use orion::lexer::state::Lexer;
+use orion::lexer::tokens::Span;
+use orion::parser::ast::{Statement, StatementKind};
+use orion::parser::printer::print_program;
+use orion::parser::state::Parser;
+use orion::sema;
+use orion::sema::layout::{Layout, Layouts, Target};
+use orion::sema::names::Definition;
+use orion::sema::structs::Structs;
+use orion::sema::ty::{Primitive, Ty};
+use orion::sema::Diagnostics;
+
+fn check(src: &str) -> Vec<OrionError> {
+    let program = Parser::new(Lexer::new(src)).parse().unwrap();
+    sema::check(&program, src)
+}
+
+fn messages(src: &str) -> Vec<String> {
+    check(src).iter().map(|error| error.message()).collect()
+}
+
+#[test]
+fn examples_check() {
+    for path in [
+        "examples/structs.ori",
+        "examples/arrays.ori",
+        "examples/enums.ori",
+        "examples/generics.ori",
+        "examples/traits.ori",
+        "examples/closures.ori",
+        "examples/adding_variables.ori",
+        "examples/main.ori",
+        "examples/functions.ori",
+        "examples/types.ori",
+    ] {
+        let src = std::fs::read_to_string(path).unwrap();
+        assert_eq!(messages(&src), Vec::<String>::new(), "{path}");
+    }
+}
+
+#[test]
+fn struct_errors() {
+    let cases = [
+        (
+            "struct P { x : u8 } struct P { y : u8 }",
+            "`P` is defined more than once at L1:C21.",
+        ),
+        (
+            "struct P { x : u8, x : u16 }",
+            "field `x` of `P` appears more than once at L1:C20.",
+        ),
+        (
+            "struct P { x : Nope }",
+            "unknown type `Nope` at L1:C16.",
+        ),
+        (
+            "struct P { name : str }",
+            "the size of `str` isn't known at compile time at L1:C19, put it behind a reference like `&str`.",
+        ),
+        (
+            "struct List { next : List }",
+            "`List` contains itself and would be infinitely large at L1:C1, put it behind a reference like `&List`.",
+        ),
+        (
+            "struct A { pair : (u8, [A; 2]) }",
+            "`A` contains itself and would be infinitely large at L1:C1, put it behind a reference like `&A`.",
+        ),
+        (
+            "struct P { x : u8 } let p := P { x: 1, y: 2 };",
+            "`P` has no field `y` at L1:C40.",
+        ),
+        (
+            "struct P { x : u8, y : u8 } let p := P { x: 1 };",
+            "missing `y` in `P` at L1:C38.",
+        ),
+        (
+            "struct P { x : u8 } let p := P { x: 1, x: 2 };",
+            "field `x` of `P` appears more than once at L1:C40.",
+        ),
+        (
+            "let p := u8 { };",
+            "`u8` is not a struct at L1:C10.",
+        ),
+        (
+            "struct P { x : u8 } let p := P { x: true };",
+            "expected `u8` because of L1:C12, found `bool` at L1:C37.",
+        ),
+        (
+            "struct P { x : u8 } let p := P { x: 1 }; let z := p.z;",
+            "`P` has no field `z` at L1:C51.",
+        ),
+        (
+            "struct P { x : u8 } func f(p : &mut P) : = { p.x = 'a'; }",
+            "expected `u8` because of L1:C46, found `char` at L1:C52.",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        assert_eq!(messages(src), vec![expected.to_string()], "{src}");
+    }
+}
+
+#[test]
+fn array_errors() {
+    let cases = [
+        (
+            "let a : [u8; 2] = [1, 2, 3];",
+            "expected an array of 2 elements, found one of 3 at L1:C19.",
+        ),
+        (
+            "let a : [u8; 2] = [0; 4];",
+            "expected an array of 2 elements, found one of 4 at L1:C19.",
+        ),
+        (
+            "let a : [bool; 2] = [true, 'b'];",
+            "expected `bool` because of L1:C9, found `char` at L1:C28.",
+        ),
+        (
+            "let a := [true, 1];",
+            "expected `bool` because of L1:C11, found `number` at L1:C17.",
+        ),
+        (
+            "let a := [0; 1.5];",
+            "array lengths must be whole numbers at L1:C14.",
+        ),
+        ("let a := true[0];", "`bool` can't be indexed at L1:C10."),
+        (
+            "let a := [1, 2]; let b := a[true];",
+            "expected `usize`, found `bool` at L1:C29.",
+        ),
+        (
+            "let a := ['a']; let b : bool = a[0];",
+            "expected `bool` because of L1:C25, found `char` at L1:C32.",
+        ),
+        (
+            "let a := len('a');",
+            "`char` doesn't have a length at L1:C14.",
+        ),
+        (
+            "let a := ['a']; let b : &[bool] = a[..];",
+            "expected `&[bool]` because of L1:C25, found `&[char]` at L1:C35.",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        assert_eq!(messages(src), vec![expected.to_string()], "{src}");
+    }
+}
+
+#[test]
+fn arrays_check() {
+    let src = "
+        func first(values : &[char]) : char = {
+            return values[0];
+        }
+
+        let grid : [[u8; 2]; 2] = [[1, 2], [3, 4]];
+        let row : [u8; 2] = grid[1];
+        let letters := ['a', 'b'];
+        let a : char = first(&letters);
+        let b : char = first(letters[1..]);
+        let n : usize = len(letters) + len(\"text\");
+    ";
+    assert_eq!(messages(src), Vec::<String>::new());
+}
+
+#[test]
+fn every_struct_of_a_cycle_is_reported() {
+    let src = "struct A { b : B } struct B { a : A }";
+    assert_eq!(messages(src).len(), 2);
+}
+
+#[test]
+fn recursion_behind_references_is_allowed() {
+    let src = "struct Node { value : i32, next : &Node, children : &[Node] }";
+    assert_eq!(messages(src), Vec::<String>::new());
+}
+
+#[test]
+fn structs_can_be_used_before_they_are_declared() {
+    let src = "
+        func origin() : Point = {
+            return Point { x: 0, y: 0 };
+        }
+
+        struct Point { x : i32, y : i32 }
+    ";
+    assert_eq!(messages(src), Vec::<String>::new());
+}
+
+const SHAPE: &str = "enum Shape { Circle(f64), Rect(f64, f64), Empty }";
+
+#[test]
+fn enum_errors() {
+    let cases = [
+        (
+            "enum E { A, A }",
+            "variant `A` of `E` appears more than once at L2:C13.",
+        ),
+        (
+            "enum E { A } struct E { }",
+            "`E` is defined more than once at L2:C14.",
+        ),
+        (
+            "enum List { Node(u8, List), End }",
+            "`List` contains itself and would be infinitely large at L2:C1, put it behind a reference like `&List`.",
+        ),
+        ("let s := Nope::A;", "unknown type `Nope` at L2:C10."),
+        (
+            "struct P { } let s := P::A;",
+            "`P` is not an enum at L2:C23.",
+        ),
+        (
+            "let s := Shape::Square;",
+            "`Shape` has no variant `Square` at L2:C10.",
+        ),
+        (
+            "let s : Shape = Shape::Circle(true);",
+            "expected `f64`, found `bool` at L2:C31.",
+        ),
+        (
+            "let s : Shape = Shape::Circle;",
+            "expected `Shape` because of L2:C9, found `func(f64) -> Shape` at L2:C17.",
+        ),
+        (
+            "let a : f64 = match Shape::Empty { Shape::Rect(w) => w, _ => 0 };",
+            "`Shape::Rect` has 2 fields, but the pattern has 1 at L2:C36.",
+        ),
+        (
+            "let a : f64 = match Shape::Empty { true => 1, _ => 0 };",
+            "expected `Shape` because of L2:C21, found `bool` at L2:C36.",
+        ),
+        (
+            "let a : f64 = match 'c' { 1 => 1, _ => 0 };",
+            "expected `char` because of L2:C21, found `number` at L2:C27.",
+        ),
+        (
+            "let a : bool = match Shape::Empty { Shape::Empty => 'a', _ => true };",
+            "expected `bool` because of L2:C9, found `char` at L2:C53.",
+        ),
+    ];
+
+    // The enum is declared on the line before each case
+    for (src, expected) in cases {
+        let src = format!("{SHAPE}\n{src}");
+        assert_eq!(messages(&src), vec![expected.to_string()], "{src}");
+    }
+}
+
+#[test]
+fn matches_must_be_exhaustive() {
+    let cases = [
+        (
+            "let a : f64 = match Shape::Empty { Shape::Circle(r) => r, Shape::Rect(w, h) => w };",
+            "`match` doesn't cover `Shape::Empty` at L2:C15.",
+        ),
+        (
+            "let a : u8 = match true { true => 1 };",
+            "`match` doesn't cover `false` at L2:C14.",
+        ),
+        (
+            "let a : u8 = match 3 { 1 => 1, 2 => 2 };",
+            "`match` doesn't cover `_` at L2:C14.",
+        ),
+        (
+            "let a : u8 = match true { _ => 1, true => 2 };",
+            "unreachable pattern at L2:C35, the arms before it match everything it does.",
+        ),
+        (
+            "let a : u8 = match Shape::Empty { Shape::Rect(_, _) => 1, Shape::Rect(1, _) => 2, _ => 3 };",
+            "unreachable pattern at L2:C59, the arms before it match everything it does.",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        let src = format!("{SHAPE}\n{src}");
+        assert_eq!(messages(&src), vec![expected.to_string()], "{src}");
+    }
+}
+
+#[test]
+fn exhaustive_matches_check() {
+    let src = format!(
+        "{SHAPE}
+        enum Option {{ Some(Shape), None }}
+
+        func describe(o : Option) : u8 = {{
+            return match o {{
+                Option::Some(Shape::Circle(_)) => 1,
+                Option::Some(Shape::Rect(w, 2)) => 2,
+                Option::Some(_) => 3,
+                Option::None => 4,
+            }};
+        }}
+
+        let b : bool = match false {{ true => false, false => true }};
+        let s := Shape::Circle;
+        let c : Shape = s(1);
+    "
+    );
+    assert_eq!(messages(&src), Vec::<String>::new());
+}
+
+fn layouts(src: &str, f: impl FnOnce(&mut Layouts)) {
+    let program = Parser::new(Lexer::new(src)).parse().unwrap();
+    let mut diagnostics = Diagnostics::new(src);
+    let structs = Structs::collect(&program, &mut diagnostics);
+    assert!(diagnostics.errors().is_empty());
+
+    f(&mut Layouts::new(&structs, Target::default()));
+}
+
+#[test]
+fn struct_layouts() {
+    let src = "
+        struct Padded { a : u8, b : u32, c : u16 }
+        struct Outer { flag : bool, inner : Padded, name : &str, items : [u16; 3] }
+        struct Empty { }
+    ";
+
+    layouts(src, |layouts| {
+        let padded = layouts.of_struct("Padded").unwrap();
+        assert_eq!(padded.layout, Layout::new(12, 4));
+        assert_eq!(padded.offsets, vec![0, 4, 8]);
+
+        let outer = layouts.of_struct("Outer").unwrap();
+        assert_eq!(outer.layout, Layout::new(40, 8));
+        assert_eq!(outer.offsets, vec![0, 4, 16, 32]);
+
+        let array = layouts.of(&Ty::Array(
+            Box::new(Ty::Struct("Padded".to_string(), vec![])),
+            3,
+        ));
+        assert_eq!(array, Some(Layout::new(36, 4)));
+
+        let empty = layouts.of_struct("Empty").unwrap();
+        assert_eq!(empty.layout, Layout::new(0, 1));
+        assert!(layouts.of_struct("Missing").is_none());
+    });
+}
+
+#[test]
+fn enum_layouts() {
+    let src = "
+        enum Shape { Circle(f32), Rect(f64, f64), Empty }
+        enum Flag { On, Off }
+        struct Tagged { flag : Flag, value : u16 }
+    ";
+
+    layouts(src, |layouts| {
+        assert_eq!(layouts.of_enum("Shape"), Some(Layout::new(24, 8)));
+        assert_eq!(layouts.of_enum("Flag"), Some(Layout::new(1, 1)));
+        assert_eq!(layouts.of_struct("Tagged").unwrap().offsets, vec![0, 2]);
+    });
+}
+
+const GENERICS: &str = "struct Pair<A, B> { first : A, second : B }
+func max<T>(a : T, b : T) : T = { return a; }
+func zero<T>() : T = { return zero(); }";
+
+#[test]
+fn generic_errors() {
+    let cases = [
+        (
+            "let p : Pair<u8> = 0;",
+            "`Pair` has 2 type parameters, but 1 type arguments were given at L4:C9.",
+        ),
+        // The second type parameter of a name is the one reported
+        (
+            "func f<T, T>() : = {}",
+            "`T` is defined more than once at L4:C11.",
+        ),
+        (
+            "struct S<T, U, T> { a : T }",
+            "`T` is defined more than once at L4:C16.",
+        ),
+        (
+            "func f() : u8 = { return zero(); } let a := zero();",
+            "can't infer the type `T` of `zero` at L4:C45, give a type to the variable it is assigned to.",
+        ),
+        (
+            "func f(a : u8) : u8 = { return max(a, true); }",
+            "expected `u8` because of L2:C24, found `bool` at L4:C39.",
+        ),
+        (
+            "func f<T>(a : T) : u8 = { return a.size; }",
+            "`T` has no field `size` at L4:C34.",
+        ),
+        (
+            "func add<T>(a : T, b : T) : T = { return a + b; }",
+            "`+` can't be applied to `T` at L4:C42, only to numbers.",
+        ),
+        (
+            "func f<T>(a : T) : bool = { return -a == a; }",
+            "`-` can't be applied to `T` at L4:C37, only to signed integers and floats.",
+        ),
+        (
+            "func f<T>(a : T, b : T) : bool = { return a < b; }",
+            "`<` can't be applied to `T` at L4:C43, only to numbers and `char`.",
+        ),
+        (
+            "func f(a : u8) : bool = { let p : Pair<u8, bool> = Pair { first: true, second: a }; return p.second; }",
+            "expected `Pair<u8, bool>` because of L4:C35, found `Pair<bool, u8>` at L4:C52.",
+        ),
+        (
+            "func f<T>(x : T) : = { f(&x); } func main() : = { f(1 as u8); }",
+            "instantiating `f` never ends at L4:C24, it needs instances of itself with ever larger type arguments.",
+        ),
+    ];
+
+    // The generic items are declared on the lines before each case
+    for (src, expected) in cases {
+        let src = format!("{GENERICS}\n{src}");
+        assert_eq!(messages(&src), vec![expected.to_string()], "{src}");
+    }
+}
+
+#[test]
+fn generic_instances() {
+    let src = format!(
+        "{GENERICS}
+        func larger<T>(pair : Pair<T, T>) : T = {{ return max(pair.first, pair.second); }}
+        func main() : bool = {{
+            let a : u8 = 1;
+            let p : Pair<u8, u8> = Pair {{ first: a, second: 2 }};
+            let b : bool = max(true, false);
+            let c : u8 = max(a, larger(p));
+            return b;
+        }}"
+    );
+
+    let program = Parser::new(Lexer::new(&src)).parse().unwrap();
+    let analysis = sema::analyze(&program, &src);
+    assert_eq!(analysis.errors.len(), 0, "{:?}", analysis.errors);
+
+    // `max<u8>` is needed directly and through `larger<u8>`, but is instantiated once
+    let instances: Vec<String> = analysis.instances.iter().map(ToString::to_string).collect();
+    assert_eq!(instances, ["max<bool>", "larger<u8>", "max<u8>"]);
+}
+
+const TRAITS: &str = "trait Show { func show(self) : &str; }
+trait Size { func size(self, unit : u8) : u32; }
+struct Point { x : u8 }
+impl Show for Point { func show(self) : &str = { return \"p\"; } }";
+
+#[test]
+fn trait_errors() {
+    let cases = [
+        (
+            "struct Q { y : u8 } func print<T : Show>(t : T) : = {} func main() : = { print(Q { y: 1 }); }",
+            "`Q` doesn't implement `Show` at L5:C74.",
+        ),
+        (
+            "impl Show for Point { func show(self) : &str = { return \"q\"; } }",
+            "conflicting implementations of `Show` for `Point` at L5:C1, it is first implemented at L4:C1.",
+        ),
+        (
+            "struct Q { y : u8 } impl Size for Q {}",
+            "missing `size` in the implementation of `Size` for `Q` at L5:C21.",
+        ),
+        (
+            "impl Size for Point { func size(self, unit : u8) : u32 = { return 1; } func area(self) : u32 = { return 1; } }",
+            "`area` is not a method of `Size` at L5:C72.",
+        ),
+        (
+            "impl Size for Point { func size(self) : u32 = { return 1; } }",
+            "expected `func(Point, u8) -> u32` because of L2:C14, found `func(Point) -> u32` at L5:C23.",
+        ),
+        (
+            "func f(p : &Point) : u32 = { return p.size(1); }",
+            "`Point` has no method `size` at L5:C37.",
+        ),
+        (
+            "impl Size for Point { func size(self, unit : u8) : u32 = { return 1; } } func f(p : Point) : u32 = { return p.size(true); }",
+            "expected `u8`, found `bool` at L5:C116.",
+        ),
+        (
+            "trait Debug { func show(self) : &str; } impl Debug for Point { func show(self) : &str = { return \"d\"; } }",
+            "`Point` has a method `show` from both `Show` and `Debug` at L5:C41.",
+        ),
+        (
+            "trait Debug { func show(self) : &str; } func f<T : Show + Debug>(t : T) : &str = { return t.show(); }",
+            "`T` has a method `show` from both `Show` and `Debug` at L5:C91.",
+        ),
+        (
+            "func f<T : Show>(t : T) : u32 = { return t.size(1); }",
+            "`T` has no method `size` at L5:C42.",
+        ),
+        (
+            "impl Show for u8 { func show(self) : &str = { return \"u\"; } }",
+            "`u8` can't implement a trait at L5:C15, only structs and enums without type parameters can.",
+        ),
+        ("impl Display for Point {}", "unknown trait `Display` at L5:C1."),
+        (
+            "func f<T : Display>(t : T) : = {}",
+            "unknown trait `Display` at L5:C8.",
+        ),
+        // `Ord` is built in, for the types whose values can be compared
+        (
+            "struct Q { y : u8 } func max<T : Ord>(a : T, b : T) : T = { return a; } func main() : = { let q : Q = max(Q { y: 1 }, Q { y: 2 }); }",
+            "`Q` doesn't implement `Ord` at L5:C103.",
+        ),
+        (
+            "func max<T : Ord>(a : T, b : T) : T = { return a; } func main() : = { let b := max(true, false); }",
+            "`bool` doesn't implement `Ord` at L5:C80.",
+        ),
+        (
+            "impl Ord for Point {}",
+            "`Ord` can't be implemented at L5:C1, it is built in and only numbers and characters implement it.",
+        ),
+        ("trait Ord {}", "`Ord` is defined more than once at L5:C1."),
+        (
+            "struct Q { y : u8 } impl Show for Q { func show(self) : &str = { let q : Self = self; return q.y; } }",
+            "expected `&str` because of L5:C57, found `u8` at L5:C94.",
+        ),
+    ];
+
+    // The traits are declared on the lines before each case
+    for (src, expected) in cases {
+        let src = format!("{TRAITS}\n{src}");
+        assert_eq!(messages(&src), vec![expected.to_string()], "{src}");
+    }
+
+    // Values of a type parameter bound to `Ord` can be compared, and any
+    // number or character given for it
+    let src = format!(
+        "{TRAITS}
+        func max<T : Ord>(a : T, b : T) : T = {{ if a >= b {{ return a; }} return b; }}
+        func larger<T : Ord + Show>(a : T, b : T) : &str = {{ return max(a, b).show(); }}
+        func main() : bool = {{
+            let a : u8 = max(1, 2);
+            let b : f32 = max(1.5, 2.5);
+            return max('a', 'b') < 'c';
+        }}"
+    );
+    assert!(check(&src).is_empty(), "{:?}", messages(&src));
+}
+
+#[test]
+fn generic_struct_layouts() {
+    let src = "struct Pair<A, B> { first : A, second : B }";
+
+    layouts(src, |layouts| {
+        let pair = layouts
+            .of_instance(
+                "Pair",
+                &[Ty::Primitive(Primitive::U8), Ty::Primitive(Primitive::U32)],
+            )
+            .unwrap();
+        assert_eq!(pair.layout, Layout::new(8, 4));
+        assert_eq!(pair.offsets, vec![0, 4]);
+
+        let nested = Ty::Struct(
+            "Pair".to_string(),
+            vec![Ty::Primitive(Primitive::U8), Ty::Primitive(Primitive::U8)],
+        );
+        let outer = layouts
+            .of_instance("Pair", &[nested, Ty::Primitive(Primitive::U16)])
+            .unwrap();
+        assert_eq!(outer.layout, Layout::new(4, 2));
+
+        // A parameter has no layout until it is substituted
+        let param = Ty::Param("T".to_string());
+        assert!(layouts
+            .of_instance("Pair", &[param, Ty::Primitive(Primitive::U8)])
+            .is_none());
+    });
+}
+
+#[test]
+fn closure_errors() {
+    let cases = [
+        (
+            "func main() : = { let f := |x| = x; }",
+            "can't infer the type of the closure parameter `x` at L1:C29, write it as `x : T`.",
+        ),
+        (
+            "func apply(f : func(u8) -> u8) : u8 = { return f(1); } func main() : = { let a := apply(|x : bool| = 1); }",
+            "expected `func(u8) -> u8` because of L1:C16, found `func(bool) -> u8` at L1:C89.",
+        ),
+        (
+            "func main() : = { let f : func(u8) -> bool = |x| = x + 1; }",
+            "expected `bool`, found `u8` at L1:C52.",
+        ),
+        // A block returns `()` unless the result type is written
+        (
+            "func main() : = { let f := |x : u8| = { return x; }; }",
+            "expected `()`, found `u8` at L1:C48.",
+        ),
+        (
+            "func main() : = { let f := |x : u8| -> u8 = x; let b : bool = f(1); }",
+            "expected `bool` because of L1:C56, found `u8` at L1:C63.",
+        ),
+        // The result type of `map` is known from the variable, so the body
+        // of the closure is checked against it
+        (
+            "func map<T, U>(x : T, f : func(T) -> U) : U = { return f(x); } func main() : = { let b : bool = map(1 as u8, |x| = x * 2); }",
+            "expected `bool`, found `u8` at L1:C116.",
+        ),
+        // A closure capturing a variable by reference can't outlive it
+        (
+            "func make() : func(i32) -> i32 = { let mut c : i32 = 0; return |x : i32| -> i32 = { c = c + x; return c; }; }",
+            "the closure at L1:C64 can't be returned, it captures `c` by reference and would outlive it.",
+        ),
+        (
+            "func make() : func() -> &u8 = { let c : u8 = 1; let f := || -> &u8 = &c; return f; }",
+            "the closure at L1:C81 can't be returned, it captures `c` by reference and would outlive it.",
+        ),
+        (
+            "func main() : = { let g := |a : u8| -> func() -> &u8 = || -> &u8 = &a; }",
+            "the closure at L1:C56 can't be returned, it captures `a` by reference and would outlive it.",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        assert_eq!(messages(src), vec![expected.to_string()], "{src}");
+    }
+
+    // Capturing by value, or calling a closure capturing by reference where
+    // the variable is, is fine
+    let src = "
+        func make() : func(i32) -> i32 = { let c : i32 = 3; return |x : i32| -> i32 = c + x; }
+        func main() : i32 = {
+            let mut total : i32 = 0;
+            let add := |x : i32| = { total = total + x; };
+            add(2);
+            return make()(1) + total;
+        }
+    ";
+    assert!(check(src).is_empty(), "{:?}", messages(src));
+}
+
+#[test]
+fn closure_conversion() {
+    let src = "struct Counter { n : u32 }
+        func main() : u32 = {
+            let mut total : u32 = 0;
+            let mut c : Counter = Counter { n: 0 };
+            let step : u32 = 2;
+            let f := |x : u32| = {
+                c.n = c.n + step;
+                total++;
+                let inner := |y : u32| -> u32 = y + x + step;
+            };
+            let r := || -> &u32 = &total;
+            return total;
+        }";
+
+    let program = Parser::new(Lexer::new(src)).parse().unwrap();
+    let analysis = sema::analyze(&program, src);
+    assert_eq!(analysis.errors.len(), 0, "{:?}", analysis.errors);
+
+    // A closure is numbered before the closures in it, which are checked
+    // first
+    let closures: Vec<String> = analysis.closures.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        closures,
+        [
+            "main::closure#1 : func(&(u32, u32), u32) -> u32 capturing x, step",
+            "main::closure#0 : func(&(&mut Counter, u32, &mut u32), u32) capturing &mut c, step, &mut total",
+            "main::closure#2 : func(&(&u32,)) -> &u32 capturing &total",
+        ]
+    );
+
+    // The lifted functions read the captured variables from their
+    // environment, the closures in them too
+    let lifted: Vec<String> = analysis
+        .closures
+        .into_iter()
+        .map(|closure| {
+            let span = closure.function.body.span;
+            let function = StatementKind::Function(closure.function);
+            print_program(&vec![Statement {
+                kind: function,
+                span,
+            }])
+        })
+        .collect();
+    assert_eq!(
+        lifted,
+        [
+            "func main::closure#1(environment# : &(u32, u32), y : u32) : u32 = {
+    return y + (*environment#).0 + (*environment#).1;
+}
+",
+            "func main::closure#0(environment# : &(&mut Counter, u32, &mut u32), x : u32) = {
+    (*(*environment#).0).n = (*(*environment#).0).n + (*environment#).1;
+    (*(*environment#).2)++;
+    let inner := |y : u32| -> u32 = y + x + (*environment#).1;
+}
+",
+            "func main::closure#2(environment# : &(&u32,)) : &u32 = {
+    return &*(*environment#).0;
+}
+",
+        ]
+    );
+}
+
+#[test]
+fn mutation_errors() {
+    let cases = [
+        (
+            "func f() : = { let a := 1; a = 2; }",
+            "`a` can't be assigned to at L1:C28, it is declared without `mut` at L1:C16.",
+        ),
+        (
+            "func f() : = { let a : u8 = 1; a += 2; }",
+            "`a` can't be assigned to at L1:C32, it is declared without `mut` at L1:C16.",
+        ),
+        (
+            "func f() : = { let a : [u8; 2] = [1, 2]; a[0]++; }",
+            "`a` can't be assigned to at L1:C42, it is declared without `mut` at L1:C16.",
+        ),
+        (
+            "struct P { x : u8 } func f() : = { let p := P { x: 1 }; p.x >>= 1; }",
+            "`p` can't be assigned to at L1:C57, it is declared without `mut` at L1:C36.",
+        ),
+        (
+            "func f(x : u8) : = { x = 2; }",
+            "`x` can't be assigned to at L1:C22, it is declared without `mut` at L1:C8.",
+        ),
+        (
+            "func f() : = { let a := 1; let g := || = { --a; }; }",
+            "`a` can't be assigned to at L1:C46, it is declared without `mut` at L1:C16.",
+        ),
+        (
+            "enum E { A(u8) } func f(e : E) : = { match e { E::A(n) => { n = 1; } } }",
+            "`n` can't be assigned to at L1:C61, it is declared without `mut` at L1:C53.",
+        ),
+        (
+            "func g() : = {} func f() : = { g = f; }",
+            "`g` can't be assigned to at L1:C32, it isn't declared `mut`.",
+        ),
+        (
+            "func f() : = { let x := 1; *(&x) = 5; }",
+            "can't write through `&_` at L1:C29, it isn't a `&mut` reference.",
+        ),
+        (
+            "func f(r : &u8) : = { *r += 1; }",
+            "can't write through `&u8` at L1:C24, it isn't a `&mut` reference.",
+        ),
+        (
+            "struct P { x : u8 } func f(p : &P) : = { p.x++; }",
+            "can't write through `&P` at L1:C42, it isn't a `&mut` reference.",
+        ),
+        (
+            "func f(r : &mut &u8) : = { **r = 1; }",
+            "can't write through `&u8` at L1:C30, it isn't a `&mut` reference.",
+        ),
+        (
+            "func set(r : &mut u8) : = { *r = 5; } func f() : = { let x : u8 = 1; set(&x); }",
+            "expected `&mut u8` because of L1:C14, found `&u8` at L1:C74.",
+        ),
+        (
+            "func f() : = { let x : u8 = 1; let r := &mut x; }",
+            "`x` can't be assigned to at L1:C46, it is declared without `mut` at L1:C16.",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        assert_eq!(messages(src), vec![expected.to_string()], "{src}");
+    }
+}
+
+#[test]
+fn mutation_checks() {
+    // What a `&mut` reference refers to can be written through it, whether
+    // the reference is in a variable declared `mut` or not, and it is also
+    // a `&` reference
+    let src = "
+        struct P { x : u8, r : &mut P }
+
+        func scale(p : &mut P, by : u8) : = {
+            p.x *= by;
+            p.r.x = 0;
+            *p.r = P { x: 1, r: p };
+        }
+
+        func update(o : &mut P) : = {
+            let mut a := [1, 2];
+            a[0] += 1;
+            a[1]--;
+
+            let r := &mut a;
+            r[0] = 3;
+            let s : &mut [u8] = &mut a;
+            s[1] |= 4;
+            let t : &[u8] = s;
+
+            let mut p := P { x: 1, r: o };
+            p = P { x: 2, r: &mut p };
+            p.x <<= 1;
+
+            let mut n : u8 = 0;
+            let count := || = { n++; };
+        }
+    ";
+    assert_eq!(messages(src), Vec::<String>::new());
+}
+
+#[test]
+fn name_errors() {
+    let cases = [
+        (
+            "func f() : u8 = { return x; }",
+            "`x` is not defined at L1:C26.",
+        ),
+        (
+            "func f() : = { let a := b; let b := 1; }",
+            "`b` is not defined at L1:C25.",
+        ),
+        ("let a := b; let b := 1;", "`b` is not defined at L1:C10."),
+        (
+            "func f() : = { { let a := 1; } let b := a; }",
+            "`a` is not defined at L1:C41.",
+        ),
+        (
+            "func f() : = { let a : u8 = 1; func g() : u8 = { return a; } }",
+            "`a` is not defined at L1:C57.",
+        ),
+        (
+            "func f() : = { let l := len; }",
+            "`len` is not defined at L1:C25.",
+        ),
+        (
+            "func f() : = {} func f() : = {}",
+            "`f` is defined more than once at L1:C17.",
+        ),
+        (
+            "func f(a : u8, a : u8) : = {}",
+            "`a` is defined more than once at L1:C16.",
+        ),
+        (
+            "let c := |a : u8, a : u8| = a;",
+            "`a` is defined more than once at L1:C19.",
+        ),
+        (
+            "enum E { P(u8, u8) } func f(e : E) : u8 = { return match e { E::P(a, a) => a }; }",
+            "`a` is defined more than once at L1:C70.",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        assert_eq!(messages(src), vec![expected.to_string()], "{src}");
+    }
+}
+
+#[test]
+fn names_resolve_to_their_declarations() {
+    let src = "let x : u8 = 1;
+func f(x : u8) : u8 = {
+    let y := x;
+    let x := x + y;
+    return match x { n => n + g(x) };
+}
+func g(a : u8) : u8 = { return len([a]) as u8 + x; }";
+
+    let program = Parser::new(Lexer::new(src)).parse().unwrap();
+    let analysis = sema::analyze(&program, src);
+    assert!(analysis.errors.is_empty(), "{:?}", analysis.errors);
+
+    // The text a name is used at the start of, and the declaration it
+    // refers to
+    let declaration = |used: &str| {
+        let start = src.find(used).unwrap();
+        let definition = analysis.names.get(0, Span::new(start, start + 1));
+
+        match definition.unwrap() {
+            Definition::Function { span, .. }
+            | Definition::Global { span, .. }
+            | Definition::Local(span)
+            | Definition::Parameter(span)
+            | Definition::Binding(span) => src[span.start..span.end].to_string(),
+            Definition::Builtin => "builtin".to_string(),
+        }
+    };
+
+    // A variable shadows the parameter once declared, its initializer
+    // still seeing the parameter
+    assert_eq!(declaration("x + y"), "x : u8");
+    assert_eq!(declaration("y;"), "let y := x;");
+    assert_eq!(declaration("x { n"), "let x := x + y;");
+    assert_eq!(declaration("n + g"), "n");
+    assert!(declaration("g(x)").starts_with("func g"));
+    assert_eq!(declaration("len("), "builtin");
+    assert_eq!(declaration("x; }"), "let x : u8 = 1;");
+}
+
+#[test]
+fn type_errors() {
+    let cases = [
+        (
+            "func f(a : u8) : = {} func g() : = { f(true); }",
+            "expected `u8` because of L1:C12, found `bool` at L1:C40.",
+        ),
+        (
+            "func f() : u8 = { return; }",
+            "expected `u8` because of L1:C12, found `()` at L1:C19.",
+        ),
+        (
+            "func f() : u8 = { return 'a'; }",
+            "expected `u8` because of L1:C12, found `char` at L1:C26.",
+        ),
+        (
+            "let a : u8 = 1; let b := a + true;",
+            "expected `u8` because of L1:C26, found `bool` at L1:C30.",
+        ),
+        // A number literal takes the type of the other operand
+        (
+            "let a : u8 = 1; let b := 1 < a; let c := 'c' == a;",
+            "expected `char` because of L1:C42, found `u8` at L1:C49.",
+        ),
+        // A shift can be by an amount of another type
+        (
+            "let mut a : u8 = 1; let b : u32 = 2; a <<= b; a = 'c';",
+            "expected `u8` because of L1:C1, found `char` at L1:C51.",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        assert_eq!(messages(src), vec![expected.to_string()], "{src}");
+    }
+}
+
+#[test]
+fn literals_fit_their_type() {
+    let cases = [
+        (
+            "let x : u8 = 300;",
+            "`300` doesn't fit in `u8`, whose values go from 0 to 255 at L1:C14.",
+        ),
+        (
+            "let x : u32 = -1;",
+            "`-1` doesn't fit in `u32`, whose values go from 0 to 4294967295 at L1:C15.",
+        ),
+        (
+            "let x : i8 = -129;",
+            "`-129` doesn't fit in `i8`, whose values go from -128 to 127 at L1:C14.",
+        ),
+        (
+            "let x : u16 = 1.5;",
+            "expected `u16` because of L1:C9, found `float` at L1:C15.",
+        ),
+        (
+            "let x : f32 = -350000000000000000000000000000000000000000.0;",
+            "`-350000000000000000000000000000000000000000.0` doesn't fit in `f32` at L1:C15, where it would be infinite.",
+        ),
+        (
+            "let a : u8 = 1; let b := a + 256;",
+            "`256` doesn't fit in `u8`, whose values go from 0 to 255 at L1:C30.",
+        ),
+        (
+            "func f(x : i16) : = { match x { -32769 => {}, _ => {} }; }",
+            "`-32769` doesn't fit in `i16`, whose values go from -32768 to 32767 at L1:C33.",
+        ),
+        // A variable declared from literals has them checked once its type
+        // is known
+        (
+            "let b := 1000; let c := b + 1; let x : i8 = c;",
+            "`1000` doesn't fit in `i8`, whose values go from -128 to 127 at L1:C10.",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        assert_eq!(messages(src), vec![expected.to_string()], "{src}");
+    }
+
+    let src = "
+        let a : u64 = 18446744073709551615;
+        let b : i64 = -9223372036854775808;
+        let c : f32 = 1;
+        let g : f32 = 340282350000000000000000000000000000000.0;
+        let h : f64 = 350000000000000000000000000000000000000000.0;
+        let d : i8 = -128 + 127;
+        let e := 255; let f : u8 = e;
+    ";
+    assert!(check(src).is_empty(), "{:?}", messages(src));
+}
+
+#[test]
+fn arithmetic_on_literals_is_checked() {
+    let cases = [
+        (
+            "let a : u8 = 200 + 100;",
+            "`200 + 100` overflows `u8` at L1:C14.",
+        ),
+        (
+            "let a : i8 = -100 - 100;",
+            "`-100 - 100` overflows `i8` at L1:C14.",
+        ),
+        (
+            "let a : u8 = 16 * 16 - 1;",
+            "`16 * 16` overflows `u8` at L1:C14.",
+        ),
+        ("let a : u8 = 1 << 8;", "`1 << 8` overflows `u8` at L1:C14."),
+        (
+            "func f(x : u16) : u16 = { return x + 65535 * 2; }",
+            "`65535 * 2` overflows `u16` at L1:C38.",
+        ),
+        (
+            "let a : u16 = 1; let b : u8 = wrapping_add(a, 1);",
+            "expected `u8` because of L1:C26, found `u16` at L1:C31, which has values `u8` doesn't: write `wrapping_add(a, 1) as u8` to convert it.",
+        ),
+        (
+            "let a : f32 = 1; let b := saturating_mul(a, 2);",
+            "expected `integer`, found `f32` at L1:C42.",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        assert_eq!(messages(src), vec![expected.to_string()], "{src}");
+    }
+
+    let src = "
+        let a : i8 = -128 + 127;
+        let b : u8 = 255 - 1 + 1;
+        let c : u64 = 1 << 63;
+        let d : u8 = wrapping_add(b, 1);
+        func wrapping_mul(x : bool) : bool = { return x; }
+        let e : bool = wrapping_mul(true);
+    ";
+    assert!(check(src).is_empty(), "{:?}", messages(src));
+}
+
+#[test]
+fn variables_from_number_literals_are_inferred() {
+    let cases = [
+        // `b` is a `u32` once added to `a`, and so is `c`
+        (
+            "let a : u32 = 5; let b := 10; let c := a + b; let d : u8 = c;",
+            "expected `u8` because of L1:C55, found `u32` at L1:C60, which has values `u8` doesn't: write `c as u8` to convert it.",
+        ),
+        (
+            "let a : u32 = 5; let b := 10; let c := a + b; let d : u8 = b;",
+            "expected `u8` because of L1:C55, found `u32` at L1:C60, which has values `u8` doesn't: write `b as u8` to convert it.",
+        ),
+        // `c` is declared from `b`, so they have the same type
+        (
+            "let b := 10; let c := -b * 2; let a : u16 = c; let d : bool = b;",
+            "expected `bool` because of L1:C56, found `u16` at L1:C63.",
+        ),
+        // The first use telling a type decides it
+        (
+            "let b := 10; let x : u16 = b; let y : u8 = b;",
+            "expected `u8` because of L1:C39, found `u16` at L1:C44, which has values `u8` doesn't: write `b as u8` to convert it.",
+        ),
+        (
+            "let b := 10; let x : bool = b;",
+            "expected `bool` because of L1:C22, found `number` at L1:C29.",
+        ),
+        (
+            "func f(x : i64) : = {} func g() : = { let n := 1; n == 'a'; f(n); }",
+            "expected `char` because of L1:C56, found `number` at L1:C51.",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        assert_eq!(messages(src), vec![expected.to_string()], "{src}");
+    }
+
+    let src = "
+        func scale(x : u64) : u64 = { return x * 2; }
+        func main() : u64 = {
+            let mut total := 0;
+            let step := 3;
+            total += scale(step);
+            return total + step;
+        }
+    ";
+    assert_eq!(messages(src), Vec::<String>::new());
+}
+
+#[test]
+fn numbers_widen_or_need_a_cast() {
+    let cases = [
+        (
+            "func f(a : u32, b : i32) : i32 = { return b + a; }",
+            "expected `i32` because of L1:C43, found `u32` at L1:C47, which has values `i32` doesn't: write `a as i32` to convert it.",
+        ),
+        (
+            "func f(a : u32, b : i32) : usize = { return b + a as i32; }",
+            "expected `usize` because of L1:C28, found `i32` at L1:C45, which has values `usize` doesn't: write `(b + a as i32) as usize` to convert it.",
+        ),
+        (
+            "func f(a : i8) : u64 = { return a; }",
+            "expected `u64` because of L1:C18, found `i8` at L1:C33, which has values `u64` doesn't: write `a as u64` to convert it.",
+        ),
+        (
+            "func f(a : u64) : f64 = { return a; }",
+            "expected `f64` because of L1:C19, found `u64` at L1:C34, which has values `f64` doesn't: write `a as f64` to convert it.",
+        ),
+        (
+            "func f(a : [u8; 2], i : i32) : u8 = { return a[i]; }",
+            "expected `usize`, found `i32` at L1:C48, which has values `usize` doesn't: write `i as usize` to convert it.",
+        ),
+        (
+            "func f(a : u32) : bool = { return a; }",
+            "expected `bool` because of L1:C19, found `u32` at L1:C35.",
+        ),
+        // Operands of types neither widens to are reported once, and not
+        // where their result is used
+        (
+            "func f(a : u64, b : f64) : f64 = { return a + b; }",
+            "expected `u64` because of L1:C43, found `f64` at L1:C47, which has values `u64` doesn't: write `b as u64` to convert it.",
+        ),
+        (
+            "func f(a : u64) : usize = { return a; }",
+            "expected `usize` because of L1:C19, found `u64` at L1:C36, which has values `usize` doesn't: write `a as usize` to convert it.",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        assert_eq!(messages(src), vec![expected.to_string()], "{src}");
+    }
+
+    // A number widens to the types holding all its values, on either side
+    // of an operator
+    let src = "
+        func f(a : u8, b : u32, c : i16, d : f32) : f64 = {
+            let e : u32 = a + b;
+            let g : i64 = c * a;
+            let h : i32 = a - c;
+            let i : [u8; 4] = [1, 2, 3, 4];
+            let j : u8 = i[a];
+            let k : usize = b;
+            let l : isize = b;
+            return d + c;
+        }
+    ";
+    assert!(check(src).is_empty(), "{:?}", messages(src));
+}
+
+#[test]
+fn every_path_returns() {
+    let cases = [
+        (
+            "func f(a : bool) : u8 = { if a { return 1; } }",
+            "`f` must return `u8`, but can reach the end of its body at L1:C46.",
+        ),
+        (
+            "func f(a : bool) : u8 = { if a { return 1; } else if !a { return 2; } }",
+            "`f` must return `u8`, but can reach the end of its body at L1:C71.",
+        ),
+        (
+            "func f() : = { let g := || -> u8 = { let x := 1; }; }",
+            "the closure must return `u8`, but can reach the end of its body at L1:C50.",
+        ),
+        (
+            "enum E { A, B } func f(e : E) : u8 = { match e { E::A => { return 1; }, E::B => 2 }; }",
+            "`f` must return `u8`, but can reach the end of its body at L1:C86.",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        assert_eq!(messages(src), vec![expected.to_string()], "{src}");
+    }
+
+    // Both branches of an `if`, or every arm of a `match`, returning is
+    // enough, and a statement after every path returned is only warned
+    // about, once for all of those following it
+    let src = "
+        enum E { A, B }
+        func f(a : bool) : u8 = {
+            if a { return 1; } else { return 2; }
+            let x := 3;
+            let y := 4;
+        }
+        func g(e : E) : u8 = {
+            match e { E::A => { return 1; }, E::B => { return 2; } };
+        }
+        func h() : = {
+            return;
+        }
+    ";
+    let program = Parser::new(Lexer::new(src)).parse().unwrap();
+    let analysis = sema::analyze(&program, src);
+    assert!(analysis.errors.is_empty(), "{:?}", analysis.errors);
+
+    let warnings: Vec<String> = analysis.warnings.iter().map(|w| w.message()).collect();
+    assert_eq!(
+        warnings,
+        ["unreachable statement at L5:C13, every path before it returns."]
+    );
+    assert!(analysis.warnings[0].is_warning());
+}
+
+#[test]
+fn variables_are_assigned_before_they_are_read() {
+    let cases = [
+        (
+            "func f(a : bool) : u8 = { let x : u8; if a { x = 1; } return x; }",
+            "`x` is read at L1:C62 before it is assigned on every path, the one through L1:C39 doesn't assign it.",
+        ),
+        (
+            "func f(a : bool) : u8 = { let x : u8; if a { x = 1; } else { let y := 2; } return x; }",
+            "`x` is read at L1:C83 before it is assigned on every path, the one through L1:C60 doesn't assign it.",
+        ),
+        (
+            "func f() : u8 = { let x : u8; return x + 1; }",
+            "`x` is read at L1:C38 before it is assigned.",
+        ),
+        (
+            "func f() : = { let x : u8; let g := |a : u8| -> u8 = a + x; x = 1; }",
+            "`x` is read at L1:C37 before it is assigned.",
+        ),
+        (
+            "func f() : = { let x : u8; x = 1; x = 2; }",
+            "`x` can't be assigned to at L1:C35, it is declared without `mut` at L1:C16.",
+        ),
+        (
+            "func f() : = { let mut x : u8; x += 1; }",
+            "`x` is read at L1:C32 before it is assigned.",
+        ),
+        (
+            "let x : u8;",
+            "top-level variable `x` needs an initializer at L1:C1, as functions can read it at any time.",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        assert_eq!(messages(src), vec![expected.to_string()], "{src}");
+    }
+
+    // Assigned on every path, once unless it is `mut`, before the reads
+    let src = "
+        enum E { A, B }
+        func f(a : bool, e : E) : u8 = {
+            let x : u8;
+            if a { x = 1; } else { x = 2; }
+            let y : u8;
+            match e { E::A => { y = 3; }, E::B => { return x; } };
+            let mut z : u8;
+            z = x + y;
+            z += 1;
+            let g := || -> u8 = z;
+            return g();
+        }
+    ";
+    assert!(check(src).is_empty(), "{:?}", messages(src));
+}
+
+#[test]
+fn operators_apply_to_their_operand_types() {
+    let cases = [
+        (
+            "func f() : = { let a := true + false; }",
+            "`+` can't be applied to `bool` at L1:C25, only to numbers.",
+        ),
+        (
+            "struct P { x : u8 } func f(p : P) : = { let a := p * p; }",
+            "`*` can't be applied to `P` at L1:C50, only to numbers.",
+        ),
+        (
+            "func f() : = { let a := \"a\" < \"b\"; }",
+            "`<` can't be applied to `&str` at L1:C25, only to numbers and `char`.",
+        ),
+        (
+            "func f() : = { let a := -true; }",
+            "`-` can't be applied to `bool` at L1:C26, only to signed integers and floats.",
+        ),
+        (
+            "func f() : = { let a := !5; }",
+            "`!` can't be applied to `integer` at L1:C26, only to `bool`.",
+        ),
+        (
+            "func f(x : f64) : = { let a := x & x; }",
+            "`&` can't be applied to `f64` at L1:C32, only to integers and `bool`.",
+        ),
+        (
+            "func f(x : f32) : = { let a := x << 1; }",
+            "`<<` can't be applied to `f32` at L1:C32, only to integers.",
+        ),
+        (
+            "func f() : = { let a := ~1.5; }",
+            "`~` can't be applied to `float` at L1:C26, only to integers.",
+        ),
+        (
+            "func f() : = { let mut c := 'a'; c += 'b'; }",
+            "`+` can't be applied to `char` at L1:C34, only to numbers.",
+        ),
+        (
+            "func f() : = { let mut b := true; b++; }",
+            "`++` can't be applied to `bool` at L1:C35, only to numbers.",
+        ),
+        // An unsigned integer has no negative values
+        (
+            "func f(a : u8) : = { let b := -a; }",
+            "`-` can't be applied to `u8` at L1:C32, only to signed integers and floats.",
+        ),
+        (
+            "func f(a : usize) : usize = { return 1 - -a; }",
+            "`-` can't be applied to `usize` at L1:C43, only to signed integers and floats.",
+        ),
+        (
+            "func f() : = { let a := 1; let b : u16 = 2 * -a; }",
+            "`-` can't be applied to `u16` at L1:C47, only to signed integers and floats.",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        assert_eq!(messages(src), vec![expected.to_string()], "{src}");
+    }
+
+    // Bitwise operators apply to bools, equality to any type, ordering to
+    // chars and negation to signed integers and floats
+    let src = "
+        struct P { x : u8 }
+        func f(a : bool, p : P, c : char, n : u8) : bool = {
+            let b := a & !a | a ^ true;
+            let m := ~n << 2 >> n;
+            return (b == (p == p)) & (c < 'z') & (-1.5 < 2);
+        }
+        func g(i : i8, x : f32) : i64 = {
+            let a := 1;
+            let y := -x;
+            return -(i as i64) - -a;
+        }
+    ";
+    assert!(check(src).is_empty(), "{:?}", messages(src));
+}
+
+#[test]
+fn calls_give_as_many_arguments_as_parameters() {
+    let cases = [
+        (
+            "func g(a : u8) : = {} func f() : = { g(1, 2); }",
+            "`g` takes 1 arguments, but 2 were given at L1:C38.",
+        ),
+        (
+            "func g<T>(a : T) : T = { return a; } func f() : = { let a : u8 = g(); }",
+            "`g` takes 1 arguments, but 0 were given at L1:C66.",
+        ),
+        (
+            "enum S { C(f64), N } func f() : = { let s := S::C(1.0, 2.0); }",
+            "`S::C` takes 1 arguments, but 2 were given at L1:C46.",
+        ),
+        (
+            "trait T { func t(self, a : u8) : u8; } struct P { x : u8 }
+impl T for P { func t(self, a : u8) : u8 = { return a; } }
+func f(p : P) : = { let a := p.t(); }",
+            "`t` takes 1 arguments, but 0 were given at L3:C30.",
+        ),
+        (
+            "func f() : = { let a : u8 = 5; a(1); }",
+            "a value of type `u8` can't be called at L1:C32, it isn't a function.",
+        ),
+        (
+            "enum S { C(f64), N } func f() : = { let s := S::N(); }",
+            "a value of type `S` can't be called at L1:C46, it isn't a function.",
+        ),
+    ];
+
+    for (src, expected) in cases {
+        assert_eq!(messages(src), vec![expected.to_string()], "{src}");
+    }
+}
@@ -0,0 +1,97 @@
+//! Tests for the token stream the parser reads from: lookahead, consuming
+//! tokens of an expected kind and going back to a checkpoint.
+use orion::lexer::state::Lexer;
+use orion::lexer::tokens::TokenKind;
+use orion::parser::stream::TokenStream;
+
+fn stream(src: &str) -> TokenStream<'_> {
+    TokenStream::new(Lexer::new(src))
+}
+
+#[test]
+fn tokens_are_looked_ahead_at_without_consuming_them() {
+    let mut tokens = stream("let x := 1;");
+
+    assert_eq!(tokens.peek_nth(2).kind, TokenKind::UntypedAssignment);
+    assert_eq!(tokens.peek_nth(1).kind, TokenKind::Identifier("x"));
+    assert_eq!(tokens.peek().kind, TokenKind::Let);
+    // Looking past the end gives the end of the input
+    assert_eq!(tokens.peek_nth(100).kind, TokenKind::Eof);
+
+    assert_eq!(tokens.advance().kind, TokenKind::Let);
+    assert_eq!(tokens.peek().kind, TokenKind::Identifier("x"));
+}
+
+#[test]
+fn comments_are_skipped() {
+    let mut tokens = stream("// a comment\nlet /* inside */ x");
+
+    assert_eq!(tokens.advance().kind, TokenKind::Let);
+    assert_eq!(tokens.advance().kind, TokenKind::Identifier("x"));
+    assert_eq!(tokens.advance().kind, TokenKind::Eof);
+    // The end of the input is never consumed
+    assert_eq!(tokens.advance().kind, TokenKind::Eof);
+}
+
+#[test]
+fn tokens_of_a_kind_are_eaten_or_expected() {
+    let mut tokens = stream("let x = 1;");
+
+    // Payloads are ignored when matching kinds
+    assert!(tokens.at(&TokenKind::Let));
+    assert!(tokens.eat_if(&TokenKind::Func).is_none());
+    assert_eq!(tokens.eat_if(&TokenKind::Let).unwrap().kind, TokenKind::Let);
+    assert_eq!(
+        tokens.expect(&TokenKind::Identifier("")).unwrap().kind,
+        TokenKind::Identifier("x")
+    );
+
+    let error = tokens.expect(&TokenKind::UntypedAssignment).unwrap_err();
+    assert_eq!(error.message(), "expected `:=`, found `=` at L1:C7.");
+    // A token that isn't the one expected stays in front
+    assert!(tokens.eat_if(&TokenKind::Eq).is_some());
+}
+
+#[test]
+fn rewinding_reads_tokens_again() {
+    let mut tokens = stream("a :: b + c");
+
+    let start = tokens.checkpoint();
+    assert_eq!(tokens.advance().kind, TokenKind::Identifier("a"));
+    assert!(tokens.eat_if(&TokenKind::ColonColon).is_some());
+    assert_eq!(tokens.last_end(), 4);
+
+    // Going back to where the path started, as when it isn't one
+    tokens.rewind(start);
+    assert_eq!(tokens.last_end(), 0);
+    assert_eq!(tokens.advance().kind, TokenKind::Identifier("a"));
+
+    let middle = tokens.checkpoint();
+    assert_eq!(tokens.advance().kind, TokenKind::ColonColon);
+    assert_eq!(tokens.advance().kind, TokenKind::Identifier("b"));
+    assert_eq!(tokens.advance().kind, TokenKind::Plus);
+
+    tokens.rewind(middle);
+    assert_eq!(tokens.peek().kind, TokenKind::ColonColon);
+    assert_eq!(tokens.peek_nth(3).kind, TokenKind::Identifier("c"));
+}
+
+#[test]
+fn closing_tokens_are_split() {
+    let mut tokens = stream("Pair<A, Pair<B, C>>");
+
+    for _ in 0..9 {
+        tokens.advance();
+    }
+
+    let at = tokens.checkpoint();
+    assert_eq!(tokens.peek().kind, TokenKind::GreaterGreater);
+    tokens.split_closing(&TokenKind::Greater);
+    assert!(tokens.eat_if(&TokenKind::Greater).is_some());
+    assert!(tokens.eat_if(&TokenKind::Greater).is_some());
+    assert!(tokens.at(&TokenKind::Eof));
+
+    // Rewinding goes back to the split tokens
+    tokens.rewind(at);
+    assert_eq!(tokens.advance().kind, TokenKind::Greater);
+}
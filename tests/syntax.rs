@@ -0,0 +1,473 @@
+//! Tests for the typed view over the concrete syntax tree: the accessors
+//! find the parts of complete code, and what there is of incomplete code,
+//! next to the `Error` nodes holding what couldn't be parsed.
+use orion::syntax;
+use orion::syntax::kind::SyntaxKind;
+use orion::syntax::red::SyntaxToken;
+use orion::syntax::view::*;
+
+fn file(src: &str) -> SourceFile {
+    SourceFile::cast(syntax::parse(src).root).unwrap()
+}
+
+fn statements(src: &str) -> Vec<Stmt> {
+    file(src).statements().collect()
+}
+
+fn text(token: Option<SyntaxToken>) -> Option<String> {
+    token.map(|token| token.text().to_string())
+}
+
+fn texts(tokens: impl IntoIterator<Item = SyntaxToken>) -> Vec<String> {
+    tokens
+        .into_iter()
+        .map(|token| token.text().to_string())
+        .collect()
+}
+
+/// The source of a node, trimmed of the trivia at its ends
+fn source(node: &impl AstNode) -> String {
+    node.syntax().text().trim().to_string()
+}
+
+#[test]
+fn lets() {
+    let [Stmt::Let(typed), Stmt::Let(inferred)] =
+        &statements("let mut x : u8 = 1 + 2; let y := x;")[..]
+    else {
+        panic!("expected two lets");
+    };
+
+    assert_eq!(text(typed.mutability()).as_deref(), Some("mut"));
+    assert_eq!(text(typed.name()).as_deref(), Some("x"));
+    assert_eq!(typed.ty().map(|ty| source(&ty)).as_deref(), Some("u8"));
+    assert_eq!(text(typed.assignment()).as_deref(), Some("="));
+
+    let Some(Expr::Binary(initial)) = typed.initial() else {
+        panic!("expected a binary initializer");
+    };
+    assert_eq!(initial.lhs().map(|lhs| source(&lhs)).as_deref(), Some("1"));
+    assert_eq!(text(initial.operator()).as_deref(), Some("+"));
+    assert_eq!(initial.rhs().map(|rhs| source(&rhs)).as_deref(), Some("2"));
+
+    assert!(inferred.mutability().is_none());
+    assert!(inferred.ty().is_none());
+    assert_eq!(text(inferred.assignment()).as_deref(), Some(":="));
+    assert!(matches!(inferred.initial(), Some(Expr::Identifier(_))));
+}
+
+#[test]
+fn functions() {
+    let [Stmt::Function(function)] =
+        &statements("pub func f<T : Ord + a::Show, U>(x : T, y : &mut U) : T = { return x; }")[..]
+    else {
+        panic!("expected a function");
+    };
+
+    assert_eq!(text(function.visibility()).as_deref(), Some("pub"));
+    assert_eq!(text(function.name()).as_deref(), Some("f"));
+
+    let generics: Vec<_> = function.generics().collect();
+    assert_eq!(
+        generics
+            .iter()
+            .map(|generic| text(generic.name()).unwrap())
+            .collect::<Vec<_>>(),
+        ["T", "U"]
+    );
+    assert_eq!(
+        generics[0]
+            .bounds()
+            .into_iter()
+            .map(texts)
+            .collect::<Vec<_>>(),
+        [vec!["Ord"], vec!["a", "Show"]]
+    );
+    assert!(generics[1].bounds().is_empty());
+
+    let parameters: Vec<_> = function.parameters().collect();
+    assert_eq!(parameters.len(), 2);
+    assert_eq!(text(parameters[1].name()).as_deref(), Some("y"));
+
+    let Some(Type::Reference(reference)) = parameters[1].ty() else {
+        panic!("expected a reference type");
+    };
+    assert_eq!(text(reference.mutability()).as_deref(), Some("mut"));
+    assert_eq!(
+        reference.inner().map(|ty| source(&ty)).as_deref(),
+        Some("U")
+    );
+
+    assert_eq!(
+        function.result().map(|ty| source(&ty)).as_deref(),
+        Some("T")
+    );
+
+    let statements: Vec<_> = function.body().unwrap().statements().collect();
+    let [Stmt::Return(ret)] = &statements[..] else {
+        panic!("expected a return");
+    };
+    assert_eq!(
+        ret.value().map(|value| source(&value)).as_deref(),
+        Some("x")
+    );
+}
+
+#[test]
+fn control_flow_and_assignments() {
+    let src = "
+        if a < 1 { a += 2; } else if a > 1 { a--; } else { a = a[1..]; }
+        $if debug { f(1, 2); }
+    ";
+    let [Stmt::If(first), Stmt::ComptimeIf(comptime)] = &statements(src)[..] else {
+        panic!("expected an if and a comptime if");
+    };
+
+    assert_eq!(
+        first.condition().map(|cond| source(&cond)).as_deref(),
+        Some("a < 1")
+    );
+
+    let then: Vec<_> = first.then().unwrap().statements().collect();
+    let [Stmt::Assign(assign)] = &then[..] else {
+        panic!("expected an assignment");
+    };
+    assert_eq!(
+        assign.place().map(|place| source(&place)).as_deref(),
+        Some("a")
+    );
+    assert_eq!(text(assign.operator()).as_deref(), Some("+="));
+    assert_eq!(
+        assign.value().map(|value| source(&value)).as_deref(),
+        Some("2")
+    );
+
+    // `else if` is an if in the `else`, the last `else` a block
+    let Some(Stmt::If(second)) = first.otherwise() else {
+        panic!("expected an else if");
+    };
+    let then: Vec<_> = second.then().unwrap().statements().collect();
+    let [Stmt::Expr(decrement)] = &then[..] else {
+        panic!("expected an expression statement");
+    };
+    let Some(Expr::Postfix(postfix)) = decrement.expression() else {
+        panic!("expected a postfix expression");
+    };
+    assert_eq!(text(postfix.operator()).as_deref(), Some("--"));
+    assert!(matches!(postfix.operand(), Some(Expr::Identifier(_))));
+
+    let Some(Stmt::Block(otherwise)) = second.otherwise() else {
+        panic!("expected an else block");
+    };
+    let statements: Vec<_> = otherwise.statements().collect();
+    let [Stmt::Assign(assign)] = &statements[..] else {
+        panic!("expected an assignment");
+    };
+    let Some(Expr::Slice(slice)) = assign.value() else {
+        panic!("expected a slice");
+    };
+    assert_eq!(
+        slice.start().map(|start| source(&start)).as_deref(),
+        Some("1")
+    );
+    assert!(slice.end().is_none());
+    assert_eq!(text(slice.range()).as_deref(), Some(".."));
+
+    assert_eq!(
+        comptime.condition().map(|cond| source(&cond)).as_deref(),
+        Some("debug")
+    );
+    assert!(comptime.otherwise().is_none());
+    let then: Vec<_> = comptime.then().unwrap().statements().collect();
+    let [Stmt::Expr(call)] = &then[..] else {
+        panic!("expected a call");
+    };
+    let Some(Expr::Call(call)) = call.expression() else {
+        panic!("expected a call");
+    };
+    assert_eq!(
+        call.callee().map(|callee| source(&callee)).as_deref(),
+        Some("f")
+    );
+    assert_eq!(
+        call.arguments().map(|arg| source(&arg)).collect::<Vec<_>>(),
+        ["1", "2"]
+    );
+}
+
+#[test]
+fn items() {
+    let src = "
+        mod math;
+        import math::geometry::Point;
+        pub struct Pair<T> { first : T, second : [u8; 4] }
+        enum Shape { Circle(f64), Empty }
+        trait Area { func area(self) : f64; }
+        impl math::Area for Pair<u8> { func area(self) : f64 = { return 0; } }
+    ";
+    let [Stmt::Module(module), Stmt::Import(import), Stmt::Struct(pair), Stmt::Enum(shape), Stmt::Trait(area), Stmt::Impl(implementation)] =
+        &statements(src)[..]
+    else {
+        panic!("expected every kind of item");
+    };
+
+    assert!(module.visibility().is_none());
+    assert_eq!(text(module.name()).as_deref(), Some("math"));
+    assert_eq!(texts(import.segments()), ["math", "geometry", "Point"]);
+
+    assert_eq!(text(pair.visibility()).as_deref(), Some("pub"));
+    assert_eq!(text(pair.name()).as_deref(), Some("Pair"));
+    assert_eq!(pair.generics().count(), 1);
+    let fields: Vec<_> = pair.fields().collect();
+    assert_eq!(text(fields[1].name()).as_deref(), Some("second"));
+    let Some(Type::Array(array)) = fields[1].ty() else {
+        panic!("expected an array type");
+    };
+    assert_eq!(array.element().map(|ty| source(&ty)).as_deref(), Some("u8"));
+    assert_eq!(array.length().map(|len| source(&len)).as_deref(), Some("4"));
+
+    assert_eq!(text(shape.name()).as_deref(), Some("Shape"));
+    let variants: Vec<_> = shape.variants().collect();
+    assert_eq!(text(variants[0].name()).as_deref(), Some("Circle"));
+    assert_eq!(
+        variants[0]
+            .fields()
+            .map(|ty| source(&ty))
+            .collect::<Vec<_>>(),
+        ["f64"]
+    );
+    assert_eq!(variants[1].fields().count(), 0);
+
+    assert_eq!(text(area.name()).as_deref(), Some("Area"));
+    let methods: Vec<_> = area.methods().collect();
+    assert_eq!(text(methods[0].name()).as_deref(), Some("area"));
+    assert_eq!(methods[0].parameters().count(), 1);
+    assert_eq!(
+        methods[0].result().map(|ty| source(&ty)).as_deref(),
+        Some("f64")
+    );
+
+    assert_eq!(texts(implementation.trait_path()), ["math", "Area"]);
+    let Some(Type::Named(named)) = implementation.ty() else {
+        panic!("expected a named type");
+    };
+    assert_eq!(texts(named.segments()), ["Pair"]);
+    assert_eq!(
+        named.arguments().map(|ty| source(&ty)).collect::<Vec<_>>(),
+        ["u8"]
+    );
+    assert_eq!(implementation.methods().count(), 1);
+}
+
+#[test]
+fn expressions() {
+    let src = "
+        let a := -(x as u32);
+        let b := p.q.len(1);
+        let c := Point { x: 1, y: 2 };
+        let d := [[0; 3], [1, 2]];
+        let e := match s { Shape::Circle(r) => r, -1 => { return 0; }, _ => 2 };
+        let f := |x : u8| -> u8 = x * 2;
+        let g := $(4 * 1024);
+    ";
+    let initials: Vec<Expr> = statements(src)
+        .into_iter()
+        .map(|stmt| match stmt {
+            Stmt::Let(stmt) => stmt.initial().unwrap(),
+            stmt => panic!("expected a let, found {stmt:?}"),
+        })
+        .collect();
+    let [Expr::Prefix(prefix), Expr::MethodCall(call), Expr::StructLit(point), Expr::Array(array), Expr::Match(matched), Expr::Closure(closure), Expr::Comptime(comptime)] =
+        &initials[..]
+    else {
+        panic!("expected every kind of expression, found {initials:?}");
+    };
+
+    assert_eq!(text(prefix.operator()).as_deref(), Some("-"));
+    let Some(Expr::Paren(paren)) = prefix.operand() else {
+        panic!("expected parentheses");
+    };
+    let Some(Expr::Cast(cast)) = paren.inner() else {
+        panic!("expected a cast");
+    };
+    assert!(matches!(cast.expression(), Some(Expr::Identifier(_))));
+    assert_eq!(cast.ty().map(|ty| source(&ty)).as_deref(), Some("u32"));
+
+    assert_eq!(text(call.method()).as_deref(), Some("len"));
+    assert_eq!(call.arguments().count(), 1);
+    let Some(Expr::Field(field)) = call.receiver() else {
+        panic!("expected a field access");
+    };
+    assert_eq!(text(field.field()).as_deref(), Some("q"));
+    assert_eq!(
+        field.expression().map(|expr| source(&expr)).as_deref(),
+        Some("p")
+    );
+
+    assert_eq!(text(point.name()).as_deref(), Some("Point"));
+    let fields: Vec<_> = point.fields().collect();
+    assert_eq!(text(fields[1].name()).as_deref(), Some("y"));
+    assert_eq!(fields[1].value().map(|v| source(&v)).as_deref(), Some("2"));
+
+    let elements: Vec<_> = array.elements().collect();
+    let [Expr::ArrayRepeat(repeat), Expr::Array(_)] = &elements[..] else {
+        panic!("expected arrays");
+    };
+    assert_eq!(repeat.value().map(|v| source(&v)).as_deref(), Some("0"));
+    assert_eq!(repeat.length().map(|v| source(&v)).as_deref(), Some("3"));
+
+    assert_eq!(
+        matched.scrutinee().map(|s| source(&s)).as_deref(),
+        Some("s")
+    );
+    let arms: Vec<_> = matched.arms().collect();
+    let Some(Pattern::Variant(variant)) = arms[0].pattern() else {
+        panic!("expected a variant pattern");
+    };
+    assert_eq!(texts(variant.segments()), ["Shape", "Circle"]);
+    let [Pattern::Binding(binding)] = &variant.fields().collect::<Vec<_>>()[..] else {
+        panic!("expected a binding");
+    };
+    assert_eq!(text(binding.name()).as_deref(), Some("r"));
+    assert!(arms[0].value().is_some() && arms[0].block().is_none());
+
+    let Some(Pattern::Literal(literal)) = arms[1].pattern() else {
+        panic!("expected a literal pattern");
+    };
+    assert_eq!(text(literal.minus()).as_deref(), Some("-"));
+    assert_eq!(text(literal.literal()).as_deref(), Some("1"));
+    assert!(arms[1].value().is_none() && arms[1].block().is_some());
+    assert!(matches!(arms[2].pattern(), Some(Pattern::Wildcard(_))));
+
+    let parameters: Vec<_> = closure.parameters().collect();
+    assert_eq!(text(parameters[0].name()).as_deref(), Some("x"));
+    assert_eq!(
+        parameters[0].ty().map(|ty| source(&ty)).as_deref(),
+        Some("u8")
+    );
+    assert_eq!(
+        closure.result().map(|ty| source(&ty)).as_deref(),
+        Some("u8")
+    );
+    assert_eq!(
+        closure.value().map(|v| source(&v)).as_deref(),
+        Some("x * 2")
+    );
+    assert!(closure.block().is_none());
+
+    assert_eq!(
+        comptime.value().map(|v| source(&v)).as_deref(),
+        Some("4 * 1024")
+    );
+    assert!(comptime.block().is_none());
+}
+
+#[test]
+fn types() {
+    let [Stmt::Let(slice), Stmt::Let(tuple), Stmt::Let(function)] =
+        &statements("let a : &[u8] = b; let c : (u8, (bool)) = d; let e : func(u8) -> u8 = f;")[..]
+    else {
+        panic!("expected three lets");
+    };
+
+    let Some(Type::Reference(reference)) = slice.ty() else {
+        panic!("expected a reference");
+    };
+    assert!(reference.mutability().is_none());
+    let Some(Type::Slice(slice)) = reference.inner() else {
+        panic!("expected a slice type");
+    };
+    assert_eq!(slice.element().map(|ty| source(&ty)).as_deref(), Some("u8"));
+
+    let Some(Type::Tuple(tuple)) = tuple.ty() else {
+        panic!("expected a tuple type");
+    };
+    let elements: Vec<_> = tuple.elements().collect();
+    let [Type::Named(_), Type::Paren(paren)] = &elements[..] else {
+        panic!("expected a parenthesized type");
+    };
+    assert_eq!(paren.inner().map(|ty| source(&ty)).as_deref(), Some("bool"));
+
+    let Some(Type::Function(function)) = function.ty() else {
+        panic!("expected a function type");
+    };
+    assert_eq!(
+        function.types().map(|ty| source(&ty)).collect::<Vec<_>>(),
+        ["u8", "u8"]
+    );
+    assert_eq!(text(function.arrow()).as_deref(), Some("->"));
+}
+
+#[test]
+fn incomplete_code_keeps_what_was_parsed() {
+    // A let missing its initializer still has a name and a type
+    let [Stmt::Let(stmt)] = &statements("let x : u8 = ;")[..] else {
+        panic!("expected a let");
+    };
+    assert_eq!(text(stmt.name()).as_deref(), Some("x"));
+    assert_eq!(stmt.ty().map(|ty| source(&ty)).as_deref(), Some("u8"));
+    assert_eq!(text(stmt.assignment()).as_deref(), Some("="));
+    assert!(stmt.initial().is_none());
+
+    // A binary expression missing its right operand
+    let [Stmt::Let(stmt), Stmt::Function(function)] =
+        &statements("let y := 1 +; func f(a : u8, b) : u8 = { return a; }")[..]
+    else {
+        panic!("expected a let and a function");
+    };
+    let Some(Expr::Binary(binary)) = stmt.initial() else {
+        panic!("expected a binary expression");
+    };
+    assert_eq!(binary.lhs().map(|lhs| source(&lhs)).as_deref(), Some("1"));
+    assert_eq!(text(binary.operator()).as_deref(), Some("+"));
+    assert!(binary.rhs().is_none());
+
+    // A function cut short in its parameters has no result or body
+    assert_eq!(text(function.name()).as_deref(), Some("f"));
+    let parameters: Vec<_> = function.parameters().collect();
+    assert_eq!(parameters.len(), 2);
+    assert_eq!(text(parameters[1].name()).as_deref(), Some("b"));
+    assert!(parameters[1].ty().is_none());
+    assert!(function.result().is_none());
+    assert!(function.body().is_none());
+
+    // A let with nothing but its keyword
+    let [Stmt::Let(stmt), Stmt::Let(next)] = &statements("let = 3 4; let z := 2;")[..] else {
+        panic!("expected two lets");
+    };
+    assert!(stmt.name().is_none() && stmt.ty().is_none() && stmt.initial().is_none());
+    assert_eq!(text(next.name()).as_deref(), Some("z"));
+}
+
+#[test]
+fn error_nodes_hold_what_was_skipped() {
+    let src = "let y := 1 +; func f(a : u8, b) : u8 = { return a; }";
+    let root = file(src);
+
+    // The statements leave the error nodes out, which are next to them in
+    // the tree
+    assert_eq!(root.statements().count(), 2);
+
+    let errors: Vec<String> = root
+        .syntax()
+        .children()
+        .into_iter()
+        .filter(|node| node.kind() == SyntaxKind::Error)
+        .map(|node| node.text())
+        .collect();
+    assert_eq!(errors, [";", ") : u8 = { return a;", "}"]);
+
+    // None of the views are error nodes
+    assert!(root
+        .syntax()
+        .descendants()
+        .into_iter()
+        .filter(|node| node.kind() == SyntaxKind::Error)
+        .all(|node| Stmt::cast(node.clone()).is_none()
+            && Expr::cast(node.clone()).is_none()
+            && Type::cast(node.clone()).is_none()
+            && Pattern::cast(node).is_none()));
+
+    // The whole input is in the tree, the skipped code too
+    assert_eq!(root.syntax().text(), src);
+}
@@ -0,0 +1,120 @@
+//! Tests for the AST visitors: every node kind is reached, by the read-only
+//! and the mutable traversal alike.
+use orion::lexer::state::Lexer;
+use orion::parser::ast::*;
+use orion::parser::printer::print_program;
+use orion::parser::state::Parser;
+use orion::parser::visit::{walk_match_arm, walk_pattern, walk_pattern_mut, Visitor, VisitorMut};
+
+const SRC: &str = "func largest<T : Ord + math::Show, U>(shape : Shape, t : T) : u8 = {
+    return match shape {
+        Shape::Pair(Shape::Circle(r), _) => r,
+        Shape::Circle(-1) => 0,
+        other => match t { _ => 1 },
+    };
+}";
+
+fn parse(src: &str) -> Program {
+    Parser::new(Lexer::new(src)).parse().unwrap()
+}
+
+/// What a read-only visitor reached, in order
+#[derive(Default)]
+struct Reached {
+    generics: Vec<String>,
+    arms: usize,
+    patterns: Vec<String>,
+}
+
+impl<'ast> Visitor<'ast> for Reached {
+    fn visit_generic(&mut self, generic: &'ast Generic) {
+        let bounds: Vec<String> = generic
+            .bounds
+            .iter()
+            .map(|bound| bound.join("::"))
+            .collect();
+        self.generics
+            .push(format!("{}: {}", generic.name, bounds.join(" + ")));
+    }
+
+    fn visit_match_arm(&mut self, arm: &'ast MatchArm) {
+        self.arms += 1;
+        walk_match_arm(self, arm);
+    }
+
+    fn visit_pattern(&mut self, pattern: &'ast Pattern) {
+        let kind = match &pattern.kind {
+            PatternKind::Wildcard => "_".to_string(),
+            PatternKind::Binding(name) => name.clone(),
+            PatternKind::Number(number) => number.to_string(),
+            PatternKind::Bool(value) => value.to_string(),
+            PatternKind::Char(chr) => format!("{chr:?}"),
+            PatternKind::Variant { path, .. } => path.join("::"),
+        };
+
+        self.patterns.push(kind);
+        walk_pattern(self, pattern);
+    }
+}
+
+#[test]
+fn generics_arms_and_patterns_are_visited() {
+    let program = parse(SRC);
+    let mut reached = Reached::default();
+    reached.visit_program(&program);
+
+    assert_eq!(reached.generics, ["T: Ord + math::Show", "U: "]);
+    // The arms of the inner match are reached through the outer one
+    assert_eq!(reached.arms, 4);
+    assert_eq!(
+        reached.patterns,
+        [
+            "Shape::Pair",
+            "Shape::Circle",
+            "r",
+            "_",
+            "Shape::Circle",
+            "-1",
+            "other",
+            "_"
+        ]
+    );
+}
+
+/// Renames the type parameters and the names bound by patterns
+struct Renamer;
+
+impl VisitorMut for Renamer {
+    fn visit_generic_mut(&mut self, generic: &mut Generic) {
+        generic.name = generic.name.to_lowercase();
+        generic.bounds.retain(|bound| bound.len() == 1);
+    }
+
+    fn visit_pattern_mut(&mut self, pattern: &mut Pattern) {
+        if let PatternKind::Binding(name) = &mut pattern.kind {
+            name.push('2');
+        }
+
+        walk_pattern_mut(self, pattern);
+    }
+}
+
+#[test]
+fn generics_and_patterns_are_rewritten() {
+    let mut program = parse(SRC);
+    Renamer.visit_program_mut(&mut program);
+
+    assert_eq!(
+        print_program(&program),
+        "func largest<t : Ord, u>(shape : Shape, t : T) : u8 = {
+    return match shape {
+        Shape::Pair(Shape::Circle(r2), _) => r,
+        Shape::Circle(-1) => 0,
+        other2 => match t {
+            _ => 1,
+        },
+    };
+}
+"
+    );
+}